@@ -1,14 +1,19 @@
 use crate::historical_data::serialization::HistoricalDataSerialization;
 use crate::{HistoricalData, StrategyInitConfig};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use base::entities::candle::BasicCandleProperties;
 use base::entities::tick::{HistoricalTickPrice, TickPrice};
-use base::entities::{BasicTickProperties, StrategyTimeframes};
+use base::entities::{BasicTickProperties, StrategyTimeframes, Timeframe};
+use chrono::{DateTime, Utc};
 use std::path::PathBuf;
 use trading_apis::MarketDataApi;
 
+pub mod aggregation;
+pub mod quality;
 pub mod serialization;
 pub mod synchronization;
+pub mod synthetic_ticks;
+pub mod validation;
 
 /// Tries to deserialize historical data if it exists. Otherwise, requests a market data api
 /// and serializes the got data for caching purposes.
@@ -78,6 +83,42 @@ where
     Ok(historical_data)
 }
 
+/// Fetches only the candles newer than the last known candle of the historical data
+/// and appends them to it. The fetched candles overlapping the known ones
+/// are deduped on the candle time.
+pub fn append_new_candles<T, M>(
+    historical_data: &mut HistoricalData<BasicCandleProperties, T>,
+    market_data_api: &M,
+    symbol: &str,
+    timeframe: Timeframe,
+) -> Result<()>
+where
+    M: MarketDataApi<CandleProperties = BasicCandleProperties>,
+{
+    let last_known_candle_time = match historical_data.candles.iter().rev().flatten().next() {
+        Some(candle) => candle.time,
+        None => bail!("there are no known candles to append the new ones to"),
+    };
+
+    let new_candles = market_data_api.get_historical_candles_since(
+        symbol,
+        timeframe,
+        DateTime::from_utc(last_known_candle_time, Utc),
+    )?;
+
+    let first_new_candle_index = new_candles
+        .iter()
+        .rposition(|candle| matches!(candle, Some(candle) if candle.time <= last_known_candle_time))
+        .map(|boundary_candle_index| boundary_candle_index + 1)
+        .unwrap_or_default();
+
+    historical_data
+        .candles
+        .extend(new_candles.into_iter().skip(first_new_candle_index));
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,13 +127,20 @@ mod tests {
     use chrono::{DateTime, Duration, NaiveDateTime, Utc};
     use std::cell::RefCell;
 
-    struct MarketDataTestApi;
+    #[derive(Default)]
+    struct MarketDataTestApi {
+        symbols: Vec<String>,
+    }
 
     impl MarketDataApi for MarketDataTestApi {
         type RealTickProperties = BasicTickProperties<TickPrice>;
         type HistoricalTickProperties = BasicTickProperties<HistoricalTickPrice>;
         type CandleProperties = BasicCandleProperties;
 
+        fn get_symbols(&self) -> Result<Vec<String>> {
+            Ok(self.symbols.clone())
+        }
+
         fn get_current_tick(&self, _symbol: &str) -> Result<Self::RealTickProperties> {
             todo!()
         }
@@ -127,6 +175,15 @@ mod tests {
             ])
         }
 
+        fn get_historical_candles_since(
+            &self,
+            symbol: &str,
+            timeframe: Timeframe,
+            since: DateTime<Utc>,
+        ) -> Result<Vec<Option<Self::CandleProperties>>> {
+            self.get_historical_candles(symbol, timeframe, since, Duration::zero())
+        }
+
         fn get_historical_ticks(
             &self,
             _symbol: &str,
@@ -258,7 +315,7 @@ mod tests {
         let historical_data_serialization: HistoricalDataTestSerializationDataExists =
             Default::default();
 
-        let market_data_api = MarketDataTestApi {};
+        let market_data_api = MarketDataTestApi::default();
 
         let expected_historical_data = HistoricalData {
             candles: vec![
@@ -334,7 +391,7 @@ mod tests {
         let historical_data_serialization: HistoricalDataTestSerializationDataDoesNotExist =
             Default::default();
 
-        let market_data_api = MarketDataTestApi {};
+        let market_data_api = MarketDataTestApi::default();
 
         let expected_historical_data = HistoricalData {
             candles: vec![
@@ -392,4 +449,61 @@ mod tests {
             .serialization_is_called
             .borrow());
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn append_new_candles__fetched_candles_overlap_known_ones__should_append_only_new_candles() {
+        let known_candle = Some(BasicCandleProperties {
+            time: NaiveDateTime::parse_from_str("19-05-2022 18:00", "%d-%m-%Y %H:%M").unwrap(),
+            ..Default::default()
+        });
+
+        let mut historical_data: HistoricalData<_, BasicTickProperties<HistoricalTickPrice>> =
+            HistoricalData {
+                candles: vec![None, known_candle.clone()],
+                ticks: vec![],
+            };
+
+        append_new_candles(
+            &mut historical_data,
+            &MarketDataTestApi::default(),
+            "GBPUSDm",
+            Timeframe::Hour,
+        )
+        .unwrap();
+
+        assert_eq!(
+            historical_data.candles,
+            vec![
+                None,
+                known_candle,
+                None,
+                Some(BasicCandleProperties {
+                    time: NaiveDateTime::parse_from_str("19-05-2022 19:00", "%d-%m-%Y %H:%M")
+                        .unwrap(),
+                    ..Default::default()
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn append_new_candles__no_known_candles__should_return_error() {
+        let mut historical_data: HistoricalData<
+            BasicCandleProperties,
+            BasicTickProperties<HistoricalTickPrice>,
+        > = HistoricalData {
+            candles: vec![None],
+            ticks: vec![],
+        };
+
+        assert!(append_new_candles(
+            &mut historical_data,
+            &MarketDataTestApi::default(),
+            "GBPUSDm",
+            Timeframe::Hour,
+        )
+        .is_err());
+    }
 }
@@ -0,0 +1,167 @@
+use anyhow::{bail, Result};
+use base::entities::candle::BasicCandleProperties;
+use base::entities::{CandlePrices, Timeframe};
+use base::helpers::{compute_volatility, price_to_points};
+use chrono::NaiveDateTime;
+use trading_apis::metaapi_market_data_api::{DAYS_FOR_VOLATILITY, HOURS_IN_DAY};
+
+/// Groups the chronologically ordered candles of the lower timeframe into the candles
+/// of the higher timeframe aligned to its boundaries, e.g. 15m candles open at :00, :15, :30, :45.
+/// The groups with missing lower candles are aggregated from the existing ones.
+/// The volatility of the aggregated candle is computed over the aggregated candles ending with it
+/// the same way as for the candles got from the market data api.
+/// Returns an error if the higher timeframe is not a multiple of the lower one.
+pub fn aggregate_candles(
+    candles: &[BasicCandleProperties],
+    from: Timeframe,
+    to: Timeframe,
+) -> Result<Vec<BasicCandleProperties>> {
+    let from_minutes = from as i64;
+    let to_minutes = to as i64;
+
+    if to_minutes < from_minutes || to_minutes % from_minutes != 0 {
+        bail!(
+            "the timeframe {} is not a multiple of the timeframe {}",
+            to,
+            from
+        );
+    }
+
+    let to_seconds = to.duration().num_seconds();
+    let open_time_of = |candle: &BasicCandleProperties| {
+        NaiveDateTime::from_timestamp(
+            candle.time.timestamp().div_euclid(to_seconds) * to_seconds,
+            0,
+        )
+    };
+
+    let mut aggregated_candles: Vec<BasicCandleProperties> = Vec::new();
+
+    for candle in candles {
+        let open_time = open_time_of(candle);
+
+        match aggregated_candles.last_mut() {
+            Some(aggregated_candle) if aggregated_candle.time == open_time => {
+                let prices = &mut aggregated_candle.prices;
+                prices.high = prices.high.max(candle.prices.high);
+                prices.low = prices.low.min(candle.prices.low);
+                prices.close = candle.prices.close;
+            }
+            _ => aggregated_candles.push(BasicCandleProperties {
+                time: open_time,
+                prices: candle.prices.clone(),
+                ..candle.clone()
+            }),
+        }
+    }
+
+    let volatility_window = (DAYS_FOR_VOLATILITY * HOURS_IN_DAY) as usize;
+
+    for i in 0..aggregated_candles.len() {
+        let volatility = compute_volatility(&aggregated_candles[..=i], volatility_window);
+
+        let aggregated_candle = &mut aggregated_candles[i];
+        let CandlePrices { high, low, .. } = aggregated_candle.prices;
+
+        aggregated_candle.size = price_to_points(high - low);
+        aggregated_candle.r#type = aggregated_candle.prices.candle_type();
+        aggregated_candle.volatility = volatility;
+    }
+
+    Ok(aggregated_candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::entities::candle::CandleType;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn candle(hour: u32, minute: u32, prices: [Decimal; 4]) -> BasicCandleProperties {
+        BasicCandleProperties {
+            time: NaiveDate::from_ymd(2022, 5, 25).and_hms(hour, minute, 0),
+            prices: CandlePrices {
+                open: prices[0],
+                high: prices[1],
+                low: prices[2],
+                close: prices[3],
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn aggregate_candles__five_minute_candles_to_fifteen_minutes__should_group_them_by_boundaries()
+    {
+        let candles = vec![
+            candle(
+                10,
+                10,
+                [dec!(1.30000), dec!(1.30100), dec!(1.29950), dec!(1.30050)],
+            ),
+            candle(
+                10,
+                15,
+                [dec!(1.30050), dec!(1.30200), dec!(1.30000), dec!(1.30150)],
+            ),
+            candle(
+                10,
+                20,
+                [dec!(1.30150), dec!(1.30300), dec!(1.29900), dec!(1.30100)],
+            ),
+            candle(
+                10,
+                25,
+                [dec!(1.30100), dec!(1.30120), dec!(1.29980), dec!(1.30020)],
+            ),
+            candle(
+                10,
+                30,
+                [dec!(1.30020), dec!(1.30030), dec!(1.29800), dec!(1.29850)],
+            ),
+        ];
+
+        let aggregated_candles =
+            aggregate_candles(&candles, Timeframe::FiveMin, Timeframe::FifteenMin).unwrap();
+
+        assert_eq!(aggregated_candles.len(), 3);
+
+        assert_eq!(
+            aggregated_candles[0].time,
+            NaiveDate::from_ymd(2022, 5, 25).and_hms(10, 0, 0)
+        );
+        assert_eq!(aggregated_candles[0].prices, candles[0].prices);
+
+        assert_eq!(
+            aggregated_candles[1].time,
+            NaiveDate::from_ymd(2022, 5, 25).and_hms(10, 15, 0)
+        );
+        assert_eq!(
+            aggregated_candles[1].prices,
+            CandlePrices {
+                open: dec!(1.30050),
+                high: dec!(1.30300),
+                low: dec!(1.29900),
+                close: dec!(1.30020),
+            }
+        );
+        assert_eq!(aggregated_candles[1].size, dec!(400));
+        assert_eq!(aggregated_candles[1].r#type, CandleType::Red);
+
+        assert_eq!(
+            aggregated_candles[2].time,
+            NaiveDate::from_ymd(2022, 5, 25).and_hms(10, 30, 0)
+        );
+        assert_eq!(aggregated_candles[2].prices, candles[4].prices);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn aggregate_candles__higher_timeframe_is_not_multiple__should_return_error() {
+        assert!(aggregate_candles(&[], Timeframe::FifteenMin, Timeframe::FiveMin).is_err());
+        assert!(aggregate_candles(&[], Timeframe::FifteenMin, Timeframe::Hour).is_ok());
+    }
+}
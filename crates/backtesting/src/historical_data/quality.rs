@@ -0,0 +1,92 @@
+use crate::historical_data::serialization::TIME_PATTERN_FOR_SERIALIZATION;
+use anyhow::{Context, Result};
+use base::entities::candle::BasicCandleProperties;
+use chrono::NaiveDateTime;
+use csv::Reader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The score of the quality of the candle data, e.g. lower for the interpolated candles.
+pub type CandleQuality = u8;
+
+#[derive(Deserialize, Debug)]
+struct CandleQualityRecord {
+    time: String,
+    quality: CandleQuality,
+}
+
+/// Loads the quality scores from the csv file with the `time` and `quality` columns
+/// and aligns them with the candles by time. The returned vector is parallel to the candles.
+/// The gaps and the candles without a score get `None`.
+pub fn load_candle_qualities<P: AsRef<Path>>(
+    path: P,
+    candles: &[Option<BasicCandleProperties>],
+) -> Result<Vec<Option<CandleQuality>>> {
+    let mut reader = Reader::from_path(path.as_ref())
+        .with_context(|| format!("failed to open candle qualities {:?}", path.as_ref()))?;
+
+    let mut qualities = HashMap::new();
+
+    for record in reader.deserialize() {
+        let record: CandleQualityRecord = record?;
+
+        qualities.insert(
+            NaiveDateTime::parse_from_str(&record.time, TIME_PATTERN_FOR_SERIALIZATION)?,
+            record.quality,
+        );
+    }
+
+    Ok(candles
+        .iter()
+        .map(|candle| {
+            candle
+                .as_ref()
+                .and_then(|candle| qualities.get(&candle.time).copied())
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn load_candle_qualities__scores_of_some_candles__should_align_scores_with_candles_by_time() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("qualities.csv");
+
+        fs::write(
+            &path,
+            "time,quality\n2022-05-17 20:00,20\n2022-05-17 18:00,100\n2022-05-17 23:00,50\n",
+        )
+        .unwrap();
+
+        let candle = |time| {
+            Some(BasicCandleProperties {
+                time: NaiveDateTime::parse_from_str(time, TIME_PATTERN_FOR_SERIALIZATION).unwrap(),
+                ..Default::default()
+            })
+        };
+
+        let candles = vec![
+            candle("2022-05-17 18:00"),
+            candle("2022-05-17 19:00"),
+            None,
+            candle("2022-05-17 21:00"),
+            candle("2022-05-17 22:00"),
+        ];
+
+        assert_eq!(
+            load_candle_qualities(&path, &candles).unwrap(),
+            vec![Some(100), None, None, None, None]
+        );
+
+        let mut candles = candles;
+        candles[2] = candle("2022-05-17 20:00");
+
+        assert_eq!(load_candle_qualities(&path, &candles).unwrap()[2], Some(20));
+    }
+}
@@ -9,7 +9,7 @@ use std::ffi::OsStr;
 use std::fs;
 use std::path::PathBuf;
 
-const TIME_PATTERN_FOR_SERIALIZATION: &str = "%Y-%m-%d %H:%M";
+pub(crate) const TIME_PATTERN_FOR_SERIALIZATION: &str = "%Y-%m-%d %H:%M";
 
 const CANDLES_CSV_FILE_NAME: &str = "candles.csv";
 const TICKS_CSV_FILE_NAME: &str = "ticks.csv";
@@ -42,10 +42,10 @@ struct Tick {
 fn get_paths_for_historical_data<P: Into<PathBuf>>(
     directory: P,
     strategy_config: &StrategyInitConfig,
-) -> HistoricalDataPaths {
+) -> anyhow::Result<HistoricalDataPaths> {
     let mut directory = directory.into();
 
-    let directory_for_candles_and_ticks = get_path_name_for_data_config(strategy_config);
+    let directory_for_candles_and_ticks = get_path_name_for_data_config(strategy_config)?;
     directory.push(directory_for_candles_and_ticks);
 
     let mut candles_file_path = directory.clone();
@@ -54,10 +54,10 @@ fn get_paths_for_historical_data<P: Into<PathBuf>>(
     let mut ticks_file_path = directory;
     ticks_file_path.push(TICKS_CSV_FILE_NAME);
 
-    HistoricalDataPaths {
+    Ok(HistoricalDataPaths {
         candles_file_path,
         ticks_file_path,
-    }
+    })
 }
 
 fn historical_data_files_exist(historical_data_paths: &HistoricalDataPaths) -> bool {
@@ -250,7 +250,7 @@ impl HistoricalDataSerialization for HistoricalDataCsvSerialization {
         strategy_config: &StrategyInitConfig,
         directory: P,
     ) -> anyhow::Result<()> {
-        let historical_data_paths = get_paths_for_historical_data(directory, strategy_config);
+        let historical_data_paths = get_paths_for_historical_data(directory, strategy_config)?;
         Self::serialize(historical_data, &historical_data_paths)
     }
 
@@ -261,7 +261,7 @@ impl HistoricalDataSerialization for HistoricalDataCsvSerialization {
     ) -> anyhow::Result<
         Option<HistoricalData<BasicCandleProperties, BasicTickProperties<HistoricalTickPrice>>>,
     > {
-        let historical_data_paths = get_paths_for_historical_data(directory, strategy_config);
+        let historical_data_paths = get_paths_for_historical_data(directory, strategy_config)?;
 
         if historical_data_files_exist(&historical_data_paths) {
             let historical_data = Self::deserialize(&historical_data_paths)?;
@@ -0,0 +1,115 @@
+use base::entities::candle::BasicCandleProperties;
+use base::entities::tick::{HistoricalTickPrice, TickPrice};
+use base::entities::{BasicTickProperties, Timeframe};
+
+/// The assumed order of the extremes within the candle.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum CandlePath {
+    /// Open, high, low, close.
+    Ohlc,
+    /// Open, low, high, close. The conservative worst case for the buy orders,
+    /// since the stop loss is reached before the take profit.
+    #[default]
+    Olhc,
+}
+
+impl CandlePath {
+    fn prices(&self, candle: &BasicCandleProperties) -> [TickPrice; 4] {
+        let prices = &candle.prices;
+
+        match self {
+            CandlePath::Ohlc => [prices.open, prices.high, prices.low, prices.close],
+            CandlePath::Olhc => [prices.open, prices.low, prices.high, prices.close],
+        }
+    }
+}
+
+/// Approximates the price movement within the candle by the ticks going through
+/// the open, the extremes in the order of the path and the close price.
+/// Used for the backtests of the historical data having candles only.
+///
+/// The ticks are evenly spread over the candle period, and each tick covers the movement
+/// from the previous path price, so the orders between the two prices are filled on it.
+pub fn ticks_from_candle(
+    candle: &BasicCandleProperties,
+    timeframe: Timeframe,
+    path: CandlePath,
+) -> Vec<BasicTickProperties<HistoricalTickPrice>> {
+    let prices = path.prices(candle);
+    let step = timeframe.duration() / prices.len() as i32;
+
+    let mut previous_price = prices[0];
+
+    prices
+        .iter()
+        .enumerate()
+        .map(|(i, &price)| {
+            let tick_price = HistoricalTickPrice {
+                high: previous_price.max(price),
+                low: previous_price.min(price),
+                close: price,
+            };
+
+            previous_price = price;
+
+            BasicTickProperties {
+                time: candle.time + step * i as i32,
+                ask: tick_price,
+                bid: tick_price,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::entities::CandlePrices;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn ticks_from_candle__different_paths__should_return_ticks_in_order_of_path() {
+        let candle = BasicCandleProperties {
+            time: NaiveDate::from_ymd(2022, 4, 1).and_hms(18, 0, 0),
+            prices: CandlePrices {
+                open: dec!(1.38050),
+                high: dec!(1.38200),
+                low: dec!(1.38000),
+                close: dec!(1.38100),
+            },
+            ..Default::default()
+        };
+
+        let tick = |minute, high, low, close| {
+            let price = HistoricalTickPrice { high, low, close };
+
+            BasicTickProperties {
+                time: NaiveDate::from_ymd(2022, 4, 1).and_hms(18, minute, 0),
+                ask: price,
+                bid: price,
+            }
+        };
+
+        assert_eq!(
+            ticks_from_candle(&candle, Timeframe::Hour, Default::default()),
+            vec![
+                tick(0, dec!(1.38050), dec!(1.38050), dec!(1.38050)),
+                tick(15, dec!(1.38050), dec!(1.38000), dec!(1.38000)),
+                tick(30, dec!(1.38200), dec!(1.38000), dec!(1.38200)),
+                tick(45, dec!(1.38200), dec!(1.38100), dec!(1.38100)),
+            ]
+        );
+
+        assert_eq!(
+            ticks_from_candle(&candle, Timeframe::Hour, CandlePath::Ohlc),
+            vec![
+                tick(0, dec!(1.38050), dec!(1.38050), dec!(1.38050)),
+                tick(15, dec!(1.38200), dec!(1.38050), dec!(1.38200)),
+                tick(30, dec!(1.38200), dec!(1.38000), dec!(1.38000)),
+                tick(45, dec!(1.38100), dec!(1.38000), dec!(1.38100)),
+            ]
+        );
+    }
+}
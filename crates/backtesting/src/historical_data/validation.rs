@@ -0,0 +1,407 @@
+use anyhow::{bail, Context, Result};
+use base::entities::candle::{BasicCandleProperties, CandleTime};
+use base::entities::Timeframe;
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::str::FromStr;
+
+pub const CANDLE_ALIGNMENT_POLICY_ENV: &str = "CANDLE_ALIGNMENT_POLICY";
+pub const DUPLICATE_CANDLES_POLICY_ENV: &str = "DUPLICATE_CANDLES_POLICY";
+
+/// Defines what to do with the candles having the same time.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum DuplicateCandlesPolicy {
+    KeepFirst,
+    KeepLast,
+    Error,
+    /// The duplicates are kept as they are.
+    #[default]
+    Accept,
+}
+
+impl FromStr for DuplicateCandlesPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep_first" => Ok(Self::KeepFirst),
+            "keep_last" => Ok(Self::KeepLast),
+            "error" => Ok(Self::Error),
+            "accept" => Ok(Self::Accept),
+            _ => bail!("Invalid duplicate candles policy: {}", s),
+        }
+    }
+}
+
+/// Detects the candles with the same time and resolves them according to the policy.
+/// The rejected duplicates are removed from the collection.
+pub fn handle_duplicate_candles<C>(
+    candles: Vec<Option<C>>,
+    policy: DuplicateCandlesPolicy,
+) -> Result<Vec<Option<C>>>
+where
+    C: AsRef<BasicCandleProperties>,
+{
+    let mut last_index_by_time = HashMap::new();
+    let mut duplicate_indexes = HashSet::new();
+
+    for (i, candle) in candles.iter().enumerate() {
+        if let Some(candle) = candle {
+            let time = candle.as_ref().time;
+
+            if let Some(previous_index) = last_index_by_time.insert(time, i) {
+                log::debug!(
+                    "duplicate candle time {} at indexes {} and {}",
+                    time,
+                    previous_index,
+                    i
+                );
+
+                match policy {
+                    DuplicateCandlesPolicy::KeepFirst => {
+                        last_index_by_time.insert(time, previous_index);
+                        duplicate_indexes.insert(i);
+                    }
+                    DuplicateCandlesPolicy::KeepLast => {
+                        duplicate_indexes.insert(previous_index);
+                    }
+                    DuplicateCandlesPolicy::Error => bail!(
+                        "duplicate candle time {} at indexes {} and {}",
+                        time,
+                        previous_index,
+                        i
+                    ),
+                    DuplicateCandlesPolicy::Accept => {}
+                }
+            }
+        }
+    }
+
+    Ok(candles
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !duplicate_indexes.contains(i))
+        .map(|(_, candle)| candle)
+        .collect())
+}
+
+/// Defines what to do with the candles whose time isn't aligned to the timeframe boundary,
+/// e.g. an hour candle opening at 10:03.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum CandleAlignmentPolicy {
+    /// The time of the candle is moved back to the nearest boundary.
+    Snap,
+    /// The misaligned candles are replaced with gaps, so the following candles keep their places.
+    Reject,
+    /// The misaligned candles are kept as they are.
+    #[default]
+    Accept,
+}
+
+impl FromStr for CandleAlignmentPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "snap" => Ok(Self::Snap),
+            "reject" => Ok(Self::Reject),
+            "accept" => Ok(Self::Accept),
+            _ => bail!("Invalid candle alignment policy: {}", s),
+        }
+    }
+}
+
+/// Detects the candles whose time isn't aligned to the timeframe boundary and resolves them
+/// according to the policy. Snapping can produce the candles with the same time,
+/// so the duplicates should be handled after it.
+pub fn handle_misaligned_candles<C>(
+    candles: Vec<Option<C>>,
+    timeframe: Timeframe,
+    policy: CandleAlignmentPolicy,
+) -> Vec<Option<C>>
+where
+    C: AsRef<BasicCandleProperties> + AsMut<BasicCandleProperties>,
+{
+    let timeframe_seconds = timeframe.duration().num_seconds();
+    let aligned_time_of = |time: CandleTime| {
+        NaiveDateTime::from_timestamp(
+            time.timestamp().div_euclid(timeframe_seconds) * timeframe_seconds,
+            0,
+        )
+    };
+
+    candles
+        .into_iter()
+        .enumerate()
+        .map(|(i, candle)| {
+            let mut candle = candle?;
+
+            let time = candle.as_ref().time;
+            let aligned_time = aligned_time_of(time);
+
+            if time != aligned_time {
+                log::debug!(
+                    "candle time {} at index {} is not aligned to the timeframe {}",
+                    time,
+                    i,
+                    timeframe
+                );
+
+                match policy {
+                    CandleAlignmentPolicy::Snap => candle.as_mut().time = aligned_time,
+                    CandleAlignmentPolicy::Reject => return None,
+                    CandleAlignmentPolicy::Accept => {}
+                }
+            }
+
+            Some(candle)
+        })
+        .collect()
+}
+
+/// The policies of handling the invalid historical candles.
+/// By default, the candles are accepted as they are.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct CandleValidationPolicies {
+    pub alignment: CandleAlignmentPolicy,
+    pub duplicates: DuplicateCandlesPolicy,
+}
+
+impl CandleValidationPolicies {
+    /// Reads the policies from the `CANDLE_ALIGNMENT_POLICY`
+    /// and `DUPLICATE_CANDLES_POLICY` environment variables.
+    /// The missing variables are replaced with the default policies.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars(get_var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let default = Self::default();
+
+        Ok(Self {
+            alignment: match get_var(CANDLE_ALIGNMENT_POLICY_ENV) {
+                Some(value) => value.parse().context(format!(
+                    "invalid {} environment variable",
+                    CANDLE_ALIGNMENT_POLICY_ENV
+                ))?,
+                None => default.alignment,
+            },
+            duplicates: match get_var(DUPLICATE_CANDLES_POLICY_ENV) {
+                Some(value) => value.parse().context(format!(
+                    "invalid {} environment variable",
+                    DUPLICATE_CANDLES_POLICY_ENV
+                ))?,
+                None => default.duplicates,
+            },
+        })
+    }
+}
+
+/// Handles the misaligned candles and then the duplicates according to the policies.
+pub fn validate_candles<C>(
+    candles: Vec<Option<C>>,
+    timeframe: Timeframe,
+    policies: CandleValidationPolicies,
+) -> Result<Vec<Option<C>>>
+where
+    C: AsRef<BasicCandleProperties> + AsMut<BasicCandleProperties>,
+{
+    handle_duplicate_candles(
+        handle_misaligned_candles(candles, timeframe, policies.alignment),
+        policies.duplicates,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn test_candles() -> Vec<Option<BasicCandleProperties>> {
+        vec![
+            Some(BasicCandleProperties {
+                time: NaiveDateTime::parse_from_str("17-05-2022 18:00", "%d-%m-%Y %H:%M").unwrap(),
+                size: dec!(1),
+                ..Default::default()
+            }),
+            None,
+            Some(BasicCandleProperties {
+                time: NaiveDateTime::parse_from_str("17-05-2022 20:00", "%d-%m-%Y %H:%M").unwrap(),
+                size: dec!(2),
+                ..Default::default()
+            }),
+            Some(BasicCandleProperties {
+                time: NaiveDateTime::parse_from_str("17-05-2022 20:00", "%d-%m-%Y %H:%M").unwrap(),
+                size: dec!(3),
+                ..Default::default()
+            }),
+        ]
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn handle_duplicate_candles__keep_first_policy__should_remove_later_duplicate() {
+        let candles = test_candles();
+        let expected_candles = candles[..3].to_vec();
+
+        assert_eq!(
+            handle_duplicate_candles(candles, DuplicateCandlesPolicy::KeepFirst).unwrap(),
+            expected_candles
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn handle_duplicate_candles__keep_last_policy__should_remove_earlier_duplicate() {
+        let candles = test_candles();
+        let expected_candles = vec![candles[0].clone(), None, candles[3].clone()];
+
+        assert_eq!(
+            handle_duplicate_candles(candles, DuplicateCandlesPolicy::KeepLast).unwrap(),
+            expected_candles
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn handle_duplicate_candles__error_policy__should_return_error() {
+        assert!(
+            handle_duplicate_candles(test_candles(), DuplicateCandlesPolicy::Error)
+                .unwrap_err()
+                .to_string()
+                .contains("duplicate candle time")
+        );
+    }
+
+    fn candle_at(time: &str) -> Option<BasicCandleProperties> {
+        Some(BasicCandleProperties {
+            time: NaiveDateTime::parse_from_str(time, "%d-%m-%Y %H:%M").unwrap(),
+            ..Default::default()
+        })
+    }
+
+    fn aligned_and_misaligned_candles() -> Vec<Option<BasicCandleProperties>> {
+        vec![
+            candle_at("17-05-2022 09:00"),
+            None,
+            candle_at("17-05-2022 10:03"),
+        ]
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn handle_misaligned_candles__snap_policy__should_move_misaligned_candle_to_boundary() {
+        assert_eq!(
+            handle_misaligned_candles(
+                aligned_and_misaligned_candles(),
+                Timeframe::Hour,
+                CandleAlignmentPolicy::Snap
+            ),
+            vec![
+                candle_at("17-05-2022 09:00"),
+                None,
+                candle_at("17-05-2022 10:00"),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn handle_misaligned_candles__reject_policy__should_replace_misaligned_candle_with_gap() {
+        assert_eq!(
+            handle_misaligned_candles(
+                aligned_and_misaligned_candles(),
+                Timeframe::Hour,
+                CandleAlignmentPolicy::Reject
+            ),
+            vec![candle_at("17-05-2022 09:00"), None, None]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn handle_misaligned_candles__accept_policy__should_keep_all_candles() {
+        assert_eq!(
+            handle_misaligned_candles(
+                aligned_and_misaligned_candles(),
+                Timeframe::Hour,
+                CandleAlignmentPolicy::Accept
+            ),
+            aligned_and_misaligned_candles()
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn handle_duplicate_candles__accept_policy__should_keep_all_candles() {
+        assert_eq!(
+            handle_duplicate_candles(test_candles(), DuplicateCandlesPolicy::Accept).unwrap(),
+            test_candles()
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn validate_candles__snap_and_keep_first_policies__should_remove_snapped_duplicate() {
+        let candles = vec![
+            candle_at("17-05-2022 09:00"),
+            candle_at("17-05-2022 10:00"),
+            candle_at("17-05-2022 10:03"),
+        ];
+
+        assert_eq!(
+            validate_candles(
+                candles,
+                Timeframe::Hour,
+                CandleValidationPolicies {
+                    alignment: CandleAlignmentPolicy::Snap,
+                    duplicates: DuplicateCandlesPolicy::KeepFirst,
+                }
+            )
+            .unwrap(),
+            vec![candle_at("17-05-2022 09:00"), candle_at("17-05-2022 10:00")]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_validation_policies_from_vars__no_vars__should_accept_candles() {
+        assert_eq!(
+            CandleValidationPolicies::from_vars(|_| None).unwrap(),
+            CandleValidationPolicies {
+                alignment: CandleAlignmentPolicy::Accept,
+                duplicates: DuplicateCandlesPolicy::Accept,
+            }
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_validation_policies_from_vars__vars_are_set__should_parse_policies() {
+        let policies = CandleValidationPolicies::from_vars(|name| match name {
+            CANDLE_ALIGNMENT_POLICY_ENV => Some(String::from("snap")),
+            DUPLICATE_CANDLES_POLICY_ENV => Some(String::from("keep_last")),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(
+            policies,
+            CandleValidationPolicies {
+                alignment: CandleAlignmentPolicy::Snap,
+                duplicates: DuplicateCandlesPolicy::KeepLast,
+            }
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_validation_policies_from_vars__invalid_policy__should_return_error() {
+        assert!(CandleValidationPolicies::from_vars(|name| match name {
+            CANDLE_ALIGNMENT_POLICY_ENV => Some(String::from("round")),
+            _ => None,
+        })
+        .is_err());
+    }
+}
@@ -1,23 +1,45 @@
+use anyhow::{bail, Result};
 use base::entities::candle::BasicCandleProperties;
-use base::entities::tick::{TickPrice, TickTime};
-use base::entities::{BasicTickProperties, StrategyTimeframes};
-use chrono::{DateTime, Duration, Utc};
+use base::entities::order::{BasicOrderProperties, OrderId, OrderPrice, OrderType, OrderVolume};
+use base::entities::tick::{BidAskPrices, TickPrice, TickTime, UniversalTickPrice};
+use base::entities::{BasicTickProperties, StrategyTimeframes, LOT};
+use base::helpers::{mean, price_to_points, LotRounding, PointValue, PriceRounding};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use crate::metrics::StreamingStats;
 
 pub mod historical_data;
+pub mod metrics;
+pub mod replay;
 pub mod trading_engine;
 
 const DEFAULT_INITIAL_BALANCE_BACKTESTING: Balance = dec!(10_000);
 const DEFAULT_LEVERAGE_BACKTESTING: Leverage = dec!(0.01);
 const DEFAULT_SPREAD_BACKTESTING: Spread = dec!(0.00010);
+const DEFAULT_REJECTION_SEED_BACKTESTING: u64 = 0;
+const DEFAULT_CONTRACT_SIZE_BACKTESTING: ContractSize = dec!(1);
+const DEFAULT_ACCOUNT_CURRENCY_BACKTESTING: &str = "USD";
 
 const TIME_PATTERN_FOR_PATH: &str = "%Y-%m-%d_%H-%M";
 
+const UNSAFE_SYMBOL_CHARACTERS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
 #[derive(Debug)]
 pub enum OpenPositionBy {
     OpenPrice,
     CurrentTickPrice(TickPrice),
+    /// Buys by the real ask price and sells by the real bid price without adding the spread.
+    CurrentBidAsk(BidAskPrices),
 }
 
 #[derive(Debug)]
@@ -25,11 +47,56 @@ pub enum ClosePositionBy {
     TakeProfit,
     StopLoss,
     CurrentTickPrice(TickPrice),
+    /// Buys by the real ask price and sells by the real bid price without adding the spread.
+    CurrentBidAsk(BidAskPrices),
+    /// Closes by the price of the last tick when the historical data ends.
+    EndOfData(TickPrice),
+    /// Force-closes by the current tick price when the equity drops below the margin call level.
+    MarginCall(TickPrice),
+}
+
+impl ClosePositionBy {
+    pub fn reason(&self) -> CloseReason {
+        match self {
+            ClosePositionBy::TakeProfit => CloseReason::TakeProfit,
+            ClosePositionBy::StopLoss => CloseReason::StopLoss,
+            ClosePositionBy::CurrentTickPrice(_) | ClosePositionBy::CurrentBidAsk(_) => {
+                CloseReason::Manual
+            }
+            ClosePositionBy::EndOfData(_) => CloseReason::EndOfData,
+            ClosePositionBy::MarginCall(_) => CloseReason::MarginCall,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CloseReason {
+    TakeProfit,
+    StopLoss,
+    /// The position is closed by the current price on the decision of the strategy.
+    Manual,
+    EndOfData,
+    MarginCall,
+}
+
+/// The reason of the broker refusing to open the position.
+/// The order stays pending, so the opening can be retried.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OrderRejectionReason {
+    #[error("order is rejected by requote")]
+    Requote,
+    #[error("order is rejected by exceeding the max total risk")]
+    MaxTotalRisk,
 }
 
 pub type Balance = Decimal;
 
-#[derive(Debug)]
+/// The real balance has dropped to zero or below, so the trading can't go on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("real balance is depleted: {0}")]
+pub struct BalanceIsDepleted(pub Balance);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BacktestingBalances {
     pub initial: Balance,
     pub processing: Balance,
@@ -56,11 +123,207 @@ impl Default for BacktestingBalances {
     }
 }
 
-pub type Units = i32;
-pub type Trades = i32;
+pub type Units = i64;
+/// Fractional amount of units used for the computation of trade values.
+pub type Quantity = Decimal;
+pub type Trades = i64;
+
+/// Units of the currently opened positions split by direction.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Exposure {
+    pub buy: Units,
+    pub sell: Units,
+}
+
+impl Exposure {
+    /// Buy units minus sell units.
+    pub fn net(&self) -> Units {
+        self.buy - self.sell
+    }
+
+    /// Buy units plus sell units.
+    pub fn gross(&self) -> Units {
+        self.buy + self.sell
+    }
+}
+
+/// The worst price movement against the trade and the best price movement
+/// in its favor during the trade lifetime.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradeExcursions {
+    pub max_adverse: OrderPrice,
+    pub max_favorable: OrderPrice,
+}
+
+impl TradeExcursions {
+    fn update(
+        &mut self,
+        r#type: OrderType,
+        open_price: OrderPrice,
+        low: TickPrice,
+        high: TickPrice,
+    ) {
+        let (adverse, favorable) = match r#type {
+            OrderType::Buy => (open_price - low, high - open_price),
+            OrderType::Sell => (high - open_price, open_price - low),
+        };
+
+        self.max_adverse = cmp::max(self.max_adverse, adverse);
+        self.max_favorable = cmp::max(self.max_favorable, favorable);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenTrade {
+    pub r#type: OrderType,
+    pub volume: OrderVolume,
+    pub open_price: OrderPrice,
+    pub open_time: Option<DateTime<Utc>>,
+    pub open_candle_index: Option<CandleIndex>,
+    pub excursions: TradeExcursions,
+    /// The difference between the requested and the fill prices of the opening against the trade.
+    pub open_fill_slippage: OrderPrice,
+    /// The part of the slippage of the opening caused by the spread.
+    pub open_spread_cost: OrderPrice,
+}
+
+impl OpenTrade {
+    /// Returns the price at which the opening of the trade was filled.
+    pub fn open_fill_price(&self) -> OrderPrice {
+        match self.r#type {
+            OrderType::Buy => self.open_price + self.open_fill_slippage,
+            OrderType::Sell => self.open_price - self.open_fill_slippage,
+        }
+    }
+}
+
+/// The execution costs of the trades in points.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TradeCosts {
+    pub spread: PointValue,
+    /// The movement of the price against the trades during the fill latency.
+    pub slippage: PointValue,
+}
+
+impl TradeCosts {
+    pub fn total(&self) -> PointValue {
+        self.spread + self.slippage
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClosedTrade {
+    pub order_id: OrderId,
+    pub r#type: OrderType,
+    pub volume: OrderVolume,
+    pub open_price: OrderPrice,
+    pub close_price: OrderPrice,
+    pub open_time: Option<DateTime<Utc>>,
+    pub close_time: Option<DateTime<Utc>>,
+    pub open_candle_index: Option<CandleIndex>,
+    pub close_candle_index: Option<CandleIndex>,
+    pub excursions: TradeExcursions,
+    pub close_reason: CloseReason,
+    /// The total difference between the requested and the fill prices
+    /// of the opening and the closing against the trade.
+    pub fill_slippage: OrderPrice,
+    /// The part of the fill slippage caused by the spread.
+    /// The rest of it is caused by the fill latency.
+    pub spread_cost: OrderPrice,
+}
+
+impl ClosedTrade {
+    /// Returns the profit of the trade per unit in the price points.
+    pub fn profit(&self) -> OrderPrice {
+        match self.r#type {
+            OrderType::Buy => self.close_price - self.open_price,
+            OrderType::Sell => self.open_price - self.close_price,
+        }
+    }
+}
+
+/// The execution of the order waiting for the fill latency to pass.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelayedFill {
+    pub order_id: OrderId,
+    pub r#type: OrderType,
+    pub volume: OrderVolume,
+    /// The price of the order at the time of the signal.
+    pub requested_price: OrderPrice,
+    pub fill_time: DateTime<Utc>,
+    /// It's `None` for the fills opening the position.
+    pub close_reason: Option<CloseReason>,
+}
 
 pub type Leverage = Decimal;
 pub type Spread = Decimal;
+pub type CandleIndex = usize;
+
+pub type SpreadWindow = (DateTime<Utc>, DateTime<Utc>, Spread);
+
+/// The rate of the quote currency of a symbol to the account currency.
+pub type ConversionRate = Decimal;
+
+/// The amount of the instrument in one unit of the position.
+pub type ContractSize = Decimal;
+
+/// The number of consecutive closed trades with the same outcome:
+/// positive for the profitable trades and negative for the losing ones.
+pub type Streak = i64;
+
+/// Provides the rates to convert the profit of the trades of the particular symbol
+/// into the account currency. The rate of the symbols quoted in the account currency is 1.
+pub trait ConversionRateProvider {
+    fn get_rate_to_account_currency(&self, symbol: &str) -> Result<ConversionRate>;
+}
+
+impl<F> ConversionRateProvider for F
+where
+    F: Fn(&str) -> Result<ConversionRate>,
+{
+    fn get_rate_to_account_currency(&self, symbol: &str) -> Result<ConversionRate> {
+        self(symbol)
+    }
+}
+
+/// Defines what to do with the positions left open when the historical data ends.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum EndOfDataPolicy {
+    /// The positions are closed at the close price of the last tick.
+    CloseAtLastPrice,
+    /// The positions stay open and don't affect the final balance.
+    #[default]
+    LeaveOpen,
+    /// The backtest fails, because the strategy ended with exposure.
+    Error,
+}
+
+/// Defines how the configured spread is charged across the opening and the closing of the position.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum ApplySpread {
+    /// The whole spread is charged on the opening of the position.
+    EntryOnly,
+    /// The whole spread is charged on both the opening and the closing of the position.
+    EntryAndExit,
+    /// Half of the spread is charged on each side of the trade.
+    #[default]
+    Split,
+}
+
+/// Defines which price the incoming prices of the historical data are.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum SpreadModel {
+    /// The spread is charged against the position on top of the incoming price
+    /// according to the mode of applying the spread.
+    #[default]
+    Charged,
+    /// The incoming price is the bid price, so the sells are filled at it and the buys
+    /// are filled the whole spread above it. The mode of applying the spread is ignored.
+    OneSided,
+    /// The incoming price is the mid price, so the bid and ask prices are half of the spread
+    /// away from it on each side. The mode of applying the spread is ignored.
+    SymmetricAroundMid,
+}
 
 #[derive(Debug)]
 pub struct BacktestingTradingEngineConfig {
@@ -69,7 +332,576 @@ pub struct BacktestingTradingEngineConfig {
     pub trades: Trades,
     pub leverage: Leverage,
     pub spread: Spread,
+    /// Time windows (e.g. around high-impact news) during which
+    /// the base spread is overridden with the specified one.
+    pub spread_schedule: Vec<SpreadWindow>,
+    /// Time of the current tick to look up the spread schedule.
+    pub current_time: Option<DateTime<Utc>>,
+    /// Index of the current candle to record the candles of the opening and closing of the trades.
+    pub current_candle_index: Option<CandleIndex>,
     pub use_spread: bool,
+    pub apply_spread: ApplySpread,
+    pub spread_model: SpreadModel,
+    /// The delay between the signal and the fill of the order. The order is filled
+    /// at the price of the first tick at or after the signal time plus the delay.
+    /// If it's `None`, the orders are filled instantly.
+    pub fill_latency: Option<Duration>,
+    pub delayed_fills: Vec<DelayedFill>,
+    /// The max lifetime of the pending orders. The expired pending orders are cancelled
+    /// instead of being filled. If it's `None`, the pending orders never expire.
+    pub order_expiration: Option<Duration>,
+    /// The probability (from 0 to 1) of the opening of the position being rejected by requote.
+    pub reject_probability: Decimal,
+    /// The seeded generator of the rejections to make the backtests reproducible.
+    pub rejection_rng: Xoshiro256PlusPlus,
+    /// The max fraction of the real balance that can be lost by the open positions
+    /// if all of them are closed by stop loss. The opening of the position exceeding it
+    /// is rejected. If it's `None`, the total risk isn't limited.
+    pub max_total_risk_fraction: Option<Decimal>,
+    /// The min ratio of the equity to the used margin. When the equity drops below it,
+    /// the positions with the largest loss are force-closed until the ratio is restored.
+    /// If it's `None`, the positions are never force-closed.
+    pub margin_call_level: Option<Decimal>,
+    /// Rounding of the fill prices, trade values and balances.
+    pub price_rounding: PriceRounding,
+    /// Rounding of the computed volumes of the orders.
+    pub lot_rounding: LotRounding,
+    pub end_of_data_policy: EndOfDataPolicy,
+    /// The rate to convert the profit of the closed trades into the account currency
+    /// before adding it to the real balance.
+    pub conversion_rate: ConversionRate,
+    /// The amount of the instrument in one unit of the position. The notional value
+    /// of the trades and therefore their profit and margin are multiplied by it.
+    pub contract_size: ContractSize,
+    /// The currency of the balances.
+    pub account_currency: String,
+    pub open_trades: HashMap<OrderId, OpenTrade>,
+    /// Whether to keep every closed trade. The metrics of the closed trades need them,
+    /// so they can be dropped only if the streaming stats are enough.
+    pub keep_closed_trades: bool,
+    pub closed_trades: Vec<ClosedTrade>,
+    pub streaming_stats: StreamingStats,
+    /// The trades closed since the last taking of the events. They're recorded regardless
+    /// of `keep_closed_trades`, so the consumers reacting to the closings don't depend on it.
+    pub closed_trade_events: Vec<ClosedTrade>,
+}
+
+/// The part of the trading engine config changed by the trading. It's saved
+/// to resume the trading with the same balances and positions after a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradingEngineState {
+    pub balances: BacktestingBalances,
+    pub units: Units,
+    pub trades: Trades,
+    pub open_trades: HashMap<OrderId, OpenTrade>,
+    pub delayed_fills: Vec<DelayedFill>,
+}
+
+impl BacktestingTradingEngineConfig {
+    pub fn builder() -> BacktestingTradingEngineConfigBuilder {
+        BacktestingTradingEngineConfigBuilder::default()
+    }
+
+    pub fn state(&self) -> TradingEngineState {
+        TradingEngineState {
+            balances: self.balances.clone(),
+            units: self.units,
+            trades: self.trades,
+            open_trades: self.open_trades.clone(),
+            delayed_fills: self.delayed_fills.clone(),
+        }
+    }
+
+    /// Replaces the balances and positions with the saved ones. The settings
+    /// of the engine are kept.
+    pub fn restore_state(&mut self, state: TradingEngineState) {
+        self.balances = state.balances;
+        self.units = state.units;
+        self.trades = state.trades;
+        self.open_trades = state.open_trades;
+        self.delayed_fills = state.delayed_fills;
+    }
+
+    /// Updates the rate to convert the profit of the trades of the particular symbol
+    /// into the account currency.
+    pub fn update_conversion_rate(
+        &mut self,
+        symbol: &str,
+        conversion_rate_provider: &impl ConversionRateProvider,
+    ) -> Result<()> {
+        self.conversion_rate = conversion_rate_provider.get_rate_to_account_currency(symbol)?;
+        Ok(())
+    }
+
+    /// Returns the spread for the current time. If the current time falls into
+    /// several overlapping windows of the spread schedule, the widest spread wins.
+    pub fn current_spread(&self) -> Spread {
+        let current_time = match self.current_time {
+            Some(current_time) => current_time,
+            None => return self.spread,
+        };
+
+        self.spread_schedule
+            .iter()
+            .filter(|(start, end, _)| *start <= current_time && current_time < *end)
+            .map(|(_, _, spread)| *spread)
+            .max()
+            .unwrap_or(self.spread)
+    }
+
+    /// Returns the part of the current spread by which the fill price of the market order
+    /// of the particular type opening or closing the position is moved against the position.
+    pub fn spread_to_apply(&self, r#type: OrderType, closes_position: bool) -> Spread {
+        if !self.use_spread {
+            return dec!(0);
+        }
+
+        match self.spread_model {
+            SpreadModel::Charged => match (self.apply_spread, closes_position) {
+                (ApplySpread::EntryOnly, false) | (ApplySpread::EntryAndExit, _) => {
+                    self.current_spread()
+                }
+                (ApplySpread::EntryOnly, true) => dec!(0),
+                (ApplySpread::Split, _) => self.current_spread() / dec!(2),
+            },
+            SpreadModel::OneSided => match r#type {
+                OrderType::Buy => self.current_spread(),
+                OrderType::Sell => dec!(0),
+            },
+            SpreadModel::SymmetricAroundMid => self.current_spread() / dec!(2),
+        }
+    }
+
+    /// Returns the value of the particular quantity of units at the price in the quote currency.
+    pub fn notional_value(&self, quantity: Quantity, price: OrderPrice) -> Balance {
+        quantity * self.contract_size * price
+    }
+
+    /// Returns the margin required to open the position of the particular quantity of units
+    /// at the price. The leverage is the share of the notional value reserved as the margin.
+    pub fn required_margin(&self, quantity: Quantity, price: OrderPrice) -> Balance {
+        self.notional_value(quantity, price) * self.leverage
+    }
+
+    /// Returns the loss of the position in the account currency if it's closed by stop loss.
+    pub fn position_risk(&self, order: &BasicOrderProperties) -> Balance {
+        let quantity = order.volume * Decimal::from(LOT);
+        let stop_loss_distance = (order.prices.open - order.prices.stop_loss).abs();
+
+        self.notional_value(quantity, stop_loss_distance) * self.conversion_rate
+    }
+
+    /// Checks whether the equity is below the margin call level of the used margin.
+    pub fn margin_call_is_triggered(&self, equity: Balance, used_margin: Balance) -> bool {
+        match self.margin_call_level {
+            Some(margin_call_level) => equity < used_margin * margin_call_level,
+            None => false,
+        }
+    }
+
+    /// Runs the action with the fill latency disabled, so that the orders are filled instantly,
+    /// and restores the latency afterwards whether the action has succeeded or not.
+    pub fn without_fill_latency<T>(&mut self, action: impl FnOnce(&mut Self) -> T) -> T {
+        let fill_latency = self.fill_latency.take();
+        let result = action(self);
+        self.fill_latency = fill_latency;
+
+        result
+    }
+
+    /// Returns the time of the fill of the order signaled at the current time
+    /// if the fill latency is set.
+    pub(crate) fn delayed_fill_time(&self) -> Option<DateTime<Utc>> {
+        Some(self.current_time? + self.fill_latency?)
+    }
+
+    /// Decides whether the opening of the position is rejected by requote.
+    /// The generator of the rejections is advanced only if the reject probability is positive.
+    pub(crate) fn opening_is_requoted(&mut self) -> bool {
+        if self.reject_probability <= dec!(0) {
+            return false;
+        }
+
+        self.rejection_rng.gen::<f64>() < self.reject_probability.to_f64().unwrap_or_default()
+    }
+
+    /// Updates the max adverse and max favorable excursions of the open trades
+    /// by the range of the current tick.
+    pub fn update_trade_excursions(&mut self, current_tick_price: UniversalTickPrice) {
+        let (low, high) = match current_tick_price {
+            UniversalTickPrice::Historical(current_tick_price) => {
+                (current_tick_price.low, current_tick_price.high)
+            }
+            UniversalTickPrice::Realtime(current_tick_price) => {
+                (current_tick_price, current_tick_price)
+            }
+        };
+
+        // the excursions are measured from the fill price to agree with the realized profit
+        for trade in self.open_trades.values_mut() {
+            let open_fill_price = trade.open_fill_price();
+
+            trade
+                .excursions
+                .update(trade.r#type, open_fill_price, low, high);
+        }
+    }
+
+    pub(crate) fn open_trade(
+        &mut self,
+        order_id: OrderId,
+        r#type: OrderType,
+        volume: OrderVolume,
+        open_price: OrderPrice,
+        fill_slippage: OrderPrice,
+        spread_cost: OrderPrice,
+    ) {
+        self.open_trades.insert(
+            order_id,
+            OpenTrade {
+                r#type,
+                volume,
+                open_price,
+                open_time: self.current_time,
+                open_candle_index: self.current_candle_index,
+                excursions: Default::default(),
+                open_fill_slippage: fill_slippage,
+                open_spread_cost: spread_cost,
+            },
+        );
+    }
+
+    pub(crate) fn close_trade(
+        &mut self,
+        order_id: &str,
+        close_price: OrderPrice,
+        close_reason: CloseReason,
+        fill_slippage: OrderPrice,
+        spread_cost: OrderPrice,
+    ) {
+        if let Some(mut trade) = self.open_trades.remove(order_id) {
+            let close_fill_price = match trade.r#type {
+                OrderType::Buy => close_price - fill_slippage,
+                OrderType::Sell => close_price + fill_slippage,
+            };
+
+            trade.excursions.update(
+                trade.r#type,
+                trade.open_fill_price(),
+                close_fill_price,
+                close_fill_price,
+            );
+
+            let closed_trade = ClosedTrade {
+                order_id: order_id.to_string(),
+                r#type: trade.r#type,
+                volume: trade.volume,
+                open_price: trade.open_price,
+                close_price,
+                open_time: trade.open_time,
+                close_time: self.current_time,
+                open_candle_index: trade.open_candle_index,
+                close_candle_index: self.current_candle_index,
+                excursions: trade.excursions,
+                close_reason,
+                fill_slippage: trade.open_fill_slippage + fill_slippage,
+                spread_cost: trade.open_spread_cost + spread_cost,
+            };
+
+            self.streaming_stats.update(&closed_trade);
+            self.closed_trade_events.push(closed_trade.clone());
+
+            if self.keep_closed_trades {
+                self.closed_trades.push(closed_trade);
+            }
+        }
+    }
+
+    /// Returns the trades closed since the previous call.
+    pub fn take_closed_trade_events(&mut self) -> Vec<ClosedTrade> {
+        mem::take(&mut self.closed_trade_events)
+    }
+
+    /// Returns the percentage of the closed trades with profit.
+    pub fn win_rate(&self) -> Option<Decimal> {
+        if self.closed_trades.is_empty() {
+            return None;
+        }
+
+        let winning_trades = self
+            .closed_trades
+            .iter()
+            .filter(|trade| trade.profit() > dec!(0))
+            .count();
+
+        Some(Decimal::from(winning_trades) / Decimal::from(self.closed_trades.len()) * dec!(100))
+    }
+
+    /// Returns the length of the longest series of consecutive profitable closed trades.
+    pub fn max_win_streak(&self) -> Streak {
+        self.streaks().max().unwrap_or_default().max(0)
+    }
+
+    /// Returns the length of the longest series of consecutive losing closed trades.
+    pub fn max_loss_streak(&self) -> Streak {
+        -self.streaks().min().unwrap_or_default().min(0)
+    }
+
+    /// Returns the streak of the last closed trades.
+    pub fn current_streak(&self) -> Streak {
+        self.streaks().last().unwrap_or_default()
+    }
+
+    /// Returns the streak after every closed trade in the chronological order.
+    /// The break-even trades are skipped, so they don't interrupt the streaks.
+    fn streaks(&self) -> impl Iterator<Item = Streak> + '_ {
+        self.closed_trades
+            .iter()
+            .map(|trade| trade.profit())
+            .filter(|profit| *profit != dec!(0))
+            .scan(0, |streak: &mut Streak, profit| {
+                *streak = match (profit > dec!(0), *streak > 0) {
+                    (true, true) => *streak + 1,
+                    (true, false) => 1,
+                    (false, true) => -1,
+                    (false, false) => *streak - 1,
+                };
+
+                Some(*streak)
+            })
+    }
+
+    /// Returns the average number of candles between the opening and closing of the closed trades.
+    /// The trades without the recorded candles are skipped.
+    pub fn average_bars_in_trade(&self) -> Option<Decimal> {
+        let bars_in_trades: Vec<_> = self
+            .closed_trades
+            .iter()
+            .filter_map(|trade| {
+                Some(Decimal::from(
+                    trade.close_candle_index? - trade.open_candle_index?,
+                ))
+            })
+            .collect();
+
+        if bars_in_trades.is_empty() {
+            return None;
+        }
+
+        Some(mean(&bars_in_trades))
+    }
+
+    /// Returns the profit of the closed trade in the account currency.
+    pub fn trade_pnl(&self, trade: &ClosedTrade) -> Balance {
+        let quantity = trade.volume * Decimal::from(LOT);
+
+        self.notional_value(quantity, trade.profit()) * self.conversion_rate
+    }
+
+    /// Returns the number of the closed trades and their total profit in the account currency
+    /// for every reason of closing the trades.
+    pub fn trades_by_close_reason(&self) -> HashMap<CloseReason, (usize, Decimal)> {
+        self.group_closed_trades(|trade| Some(trade.close_reason))
+    }
+
+    /// Returns the number of the closed trades and their total profit in the account currency
+    /// for every hour of the day (UTC) the trades were opened at. The trades without the open time are skipped.
+    pub fn performance_by_hour(&self) -> HashMap<u32, (usize, Decimal)> {
+        self.group_closed_trades(|trade| trade.open_time.map(|time| time.hour()))
+    }
+
+    /// Returns the number of the closed trades and their total profit in the account currency
+    /// for every day of the week (UTC) the trades were opened on. The trades without the open time are skipped.
+    pub fn performance_by_weekday(&self) -> HashMap<Weekday, (usize, Decimal)> {
+        self.group_closed_trades(|trade| trade.open_time.map(|time| time.weekday()))
+    }
+
+    fn group_closed_trades<K>(
+        &self,
+        get_key: impl Fn(&ClosedTrade) -> Option<K>,
+    ) -> HashMap<K, (usize, Decimal)>
+    where
+        K: Eq + Hash,
+    {
+        let mut grouped_trades = HashMap::new();
+
+        for trade in self.closed_trades.iter() {
+            if let Some(key) = get_key(trade) {
+                let (number_of_trades, total_profit) =
+                    grouped_trades.entry(key).or_insert((0, dec!(0)));
+
+                *number_of_trades += 1;
+                *total_profit += self.trade_pnl(trade);
+            }
+        }
+
+        grouped_trades
+    }
+
+    /// Returns the expected profit per trade in the price points:
+    /// `win_rate * average_win - loss_rate * average_loss`.
+    pub fn expectancy(&self) -> Option<OrderPrice> {
+        if self.closed_trades.is_empty() {
+            return None;
+        }
+
+        let profits: Vec<_> = self.closed_trades.iter().map(ClosedTrade::profit).collect();
+
+        let wins: Vec<_> = profits
+            .iter()
+            .copied()
+            .filter(|profit| *profit > dec!(0))
+            .collect();
+        let losses: Vec<_> = profits
+            .iter()
+            .filter(|profit| **profit < dec!(0))
+            .map(|profit| profit.abs())
+            .collect();
+
+        let number_of_trades = Decimal::from(profits.len());
+
+        let expected_win = if wins.is_empty() {
+            dec!(0)
+        } else {
+            Decimal::from(wins.len()) / number_of_trades * mean(&wins)
+        };
+
+        let expected_loss = if losses.is_empty() {
+            dec!(0)
+        } else {
+            Decimal::from(losses.len()) / number_of_trades * mean(&losses)
+        };
+
+        Some(expected_win - expected_loss)
+    }
+
+    /// Returns the sum of the relative returns of the closed trades. Unlike the time-weighted
+    /// return of the equity, it doesn't depend on how long the capital stays out of the market.
+    pub fn trade_weighted_return(&self) -> Option<Decimal> {
+        if self.closed_trades.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.closed_trades
+                .iter()
+                .filter(|trade| trade.open_price != dec!(0))
+                .map(|trade| trade.profit() / trade.open_price)
+                .sum(),
+        )
+    }
+
+    /// Returns the Kelly fraction of the balance to risk per trade:
+    /// `W - (1 - W) / R`, where `W` is the win rate and `R` is the ratio
+    /// of the average win to the average loss.
+    ///
+    /// The formula assumes that the trades are independent and the future trades
+    /// have the same distribution of outcomes as the closed ones, and that the whole risked
+    /// amount is lost on a losing trade. The break-even trades are skipped, because they don't
+    /// change the balance. Returns `None` if there are no profitable or no losing trades,
+    /// or the edge is non-positive.
+    pub fn kelly_fraction(&self) -> Option<Decimal> {
+        let (wins, losses): (Vec<_>, Vec<_>) = self
+            .closed_trades
+            .iter()
+            .map(ClosedTrade::profit)
+            .filter(|profit| *profit != dec!(0))
+            .partition(|profit| *profit > dec!(0));
+
+        if wins.is_empty() || losses.is_empty() {
+            return None;
+        }
+
+        let losses: Vec<_> = losses.iter().map(|loss| loss.abs()).collect();
+
+        let win_rate = Decimal::from(wins.len()) / Decimal::from(wins.len() + losses.len());
+        let win_loss_ratio = mean(&wins) / mean(&losses);
+
+        let kelly_fraction = win_rate - (dec!(1) - win_rate) / win_loss_ratio;
+
+        if kelly_fraction <= dec!(0) {
+            return None;
+        }
+
+        Some(kelly_fraction)
+    }
+
+    /// Returns half of the Kelly fraction. It's the common more conservative choice,
+    /// because the full Kelly fraction is very sensitive to the errors of the estimates.
+    pub fn half_kelly(&self) -> Option<Decimal> {
+        self.kelly_fraction()
+            .map(|kelly_fraction| kelly_fraction / dec!(2))
+    }
+
+    /// Returns the average max adverse excursion of the closed trades.
+    pub fn average_max_adverse_excursion(&self) -> Option<OrderPrice> {
+        self.average_excursion(|excursions| excursions.max_adverse)
+    }
+
+    /// Returns the average max favorable excursion of the closed trades.
+    pub fn average_max_favorable_excursion(&self) -> Option<OrderPrice> {
+        self.average_excursion(|excursions| excursions.max_favorable)
+    }
+
+    /// Returns the average difference in points between the requested and the fill prices
+    /// of the closed trades. It's the execution cost of the spread and the fill latency.
+    pub fn average_fill_slippage(&self) -> Option<PointValue> {
+        if self.closed_trades.is_empty() {
+            return None;
+        }
+
+        let fill_slippages: Vec<_> = self
+            .closed_trades
+            .iter()
+            .map(|trade| price_to_points(trade.fill_slippage))
+            .collect();
+
+        Some(mean(&fill_slippages))
+    }
+
+    /// Returns the execution costs of all the closed trades in points broken down by component.
+    pub fn total_costs(&self) -> TradeCosts {
+        self.closed_trades
+            .iter()
+            .fold(TradeCosts::default(), |costs, trade| TradeCosts {
+                spread: costs.spread + price_to_points(trade.spread_cost),
+                slippage: costs.slippage + price_to_points(trade.fill_slippage - trade.spread_cost),
+            })
+    }
+
+    /// Returns the average total execution cost of the closed trades in points.
+    pub fn total_cost_per_trade(&self) -> Option<PointValue> {
+        if self.closed_trades.is_empty() {
+            return None;
+        }
+
+        Some(self.total_costs().total() / Decimal::from(self.closed_trades.len()))
+    }
+
+    /// Returns the largest total execution cost of the closed trades in points.
+    pub fn max_cost_per_trade(&self) -> Option<PointValue> {
+        self.closed_trades
+            .iter()
+            .map(|trade| price_to_points(trade.fill_slippage))
+            .max()
+    }
+
+    fn average_excursion(
+        &self,
+        get_excursion: impl Fn(&TradeExcursions) -> OrderPrice,
+    ) -> Option<OrderPrice> {
+        if self.closed_trades.is_empty() {
+            return None;
+        }
+
+        let excursions: Vec<_> = self
+            .closed_trades
+            .iter()
+            .map(|trade| get_excursion(&trade.excursions))
+            .collect();
+
+        Some(mean(&excursions))
+    }
 }
 
 impl Default for BacktestingTradingEngineConfig {
@@ -80,8 +912,270 @@ impl Default for BacktestingTradingEngineConfig {
             trades: 0,
             leverage: DEFAULT_LEVERAGE_BACKTESTING,
             spread: DEFAULT_SPREAD_BACKTESTING,
+            spread_schedule: Vec::new(),
+            current_time: None,
+            current_candle_index: None,
             use_spread: true,
+            apply_spread: ApplySpread::default(),
+            spread_model: SpreadModel::default(),
+            fill_latency: None,
+            delayed_fills: Vec::new(),
+            order_expiration: None,
+            reject_probability: dec!(0),
+            rejection_rng: Xoshiro256PlusPlus::seed_from_u64(DEFAULT_REJECTION_SEED_BACKTESTING),
+            max_total_risk_fraction: None,
+            margin_call_level: None,
+            price_rounding: PriceRounding::default(),
+            lot_rounding: LotRounding::default(),
+            end_of_data_policy: EndOfDataPolicy::default(),
+            conversion_rate: dec!(1),
+            contract_size: DEFAULT_CONTRACT_SIZE_BACKTESTING,
+            account_currency: String::from(DEFAULT_ACCOUNT_CURRENCY_BACKTESTING),
+            open_trades: HashMap::new(),
+            keep_closed_trades: true,
+            closed_trades: Vec::new(),
+            streaming_stats: StreamingStats::default(),
+            closed_trade_events: Vec::new(),
+        }
+    }
+}
+
+/// Builds the trading engine config. The fields that aren't set are taken from the default config.
+#[derive(Debug, Default)]
+pub struct BacktestingTradingEngineConfigBuilder {
+    initial_balance: Option<Balance>,
+    leverage: Option<Leverage>,
+    spread: Option<Spread>,
+    spread_schedule: Option<Vec<SpreadWindow>>,
+    use_spread: Option<bool>,
+    apply_spread: Option<ApplySpread>,
+    spread_model: Option<SpreadModel>,
+    fill_latency: Option<Duration>,
+    order_expiration: Option<Duration>,
+    reject_probability: Option<Decimal>,
+    rejection_seed: Option<u64>,
+    max_total_risk_fraction: Option<Decimal>,
+    margin_call_level: Option<Decimal>,
+    price_rounding: Option<PriceRounding>,
+    lot_rounding: Option<LotRounding>,
+    end_of_data_policy: Option<EndOfDataPolicy>,
+    conversion_rate: Option<ConversionRate>,
+    contract_size: Option<ContractSize>,
+    account_currency: Option<String>,
+    keep_closed_trades: Option<bool>,
+}
+
+impl BacktestingTradingEngineConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn initial_balance(mut self, initial_balance: Balance) -> Self {
+        self.initial_balance = Some(initial_balance);
+        self
+    }
+
+    pub fn leverage(mut self, leverage: Leverage) -> Self {
+        self.leverage = Some(leverage);
+        self
+    }
+
+    pub fn spread(mut self, spread: Spread) -> Self {
+        self.spread = Some(spread);
+        self
+    }
+
+    pub fn spread_schedule(mut self, spread_schedule: Vec<SpreadWindow>) -> Self {
+        self.spread_schedule = Some(spread_schedule);
+        self
+    }
+
+    pub fn use_spread(mut self, use_spread: bool) -> Self {
+        self.use_spread = Some(use_spread);
+        self
+    }
+
+    pub fn apply_spread(mut self, apply_spread: ApplySpread) -> Self {
+        self.apply_spread = Some(apply_spread);
+        self
+    }
+
+    pub fn spread_model(mut self, spread_model: SpreadModel) -> Self {
+        self.spread_model = Some(spread_model);
+        self
+    }
+
+    pub fn fill_latency(mut self, fill_latency: Duration) -> Self {
+        self.fill_latency = Some(fill_latency);
+        self
+    }
+
+    pub fn order_expiration(mut self, order_expiration: Duration) -> Self {
+        self.order_expiration = Some(order_expiration);
+        self
+    }
+
+    pub fn reject_probability(mut self, reject_probability: Decimal) -> Self {
+        self.reject_probability = Some(reject_probability);
+        self
+    }
+
+    pub fn rejection_seed(mut self, rejection_seed: u64) -> Self {
+        self.rejection_seed = Some(rejection_seed);
+        self
+    }
+
+    pub fn max_total_risk_fraction(mut self, max_total_risk_fraction: Decimal) -> Self {
+        self.max_total_risk_fraction = Some(max_total_risk_fraction);
+        self
+    }
+
+    pub fn margin_call_level(mut self, margin_call_level: Decimal) -> Self {
+        self.margin_call_level = Some(margin_call_level);
+        self
+    }
+
+    pub fn price_rounding(mut self, price_rounding: PriceRounding) -> Self {
+        self.price_rounding = Some(price_rounding);
+        self
+    }
+
+    pub fn lot_rounding(mut self, lot_rounding: LotRounding) -> Self {
+        self.lot_rounding = Some(lot_rounding);
+        self
+    }
+
+    pub fn keep_closed_trades(mut self, keep_closed_trades: bool) -> Self {
+        self.keep_closed_trades = Some(keep_closed_trades);
+        self
+    }
+
+    pub fn end_of_data_policy(mut self, end_of_data_policy: EndOfDataPolicy) -> Self {
+        self.end_of_data_policy = Some(end_of_data_policy);
+        self
+    }
+
+    pub fn conversion_rate(mut self, conversion_rate: ConversionRate) -> Self {
+        self.conversion_rate = Some(conversion_rate);
+        self
+    }
+
+    pub fn contract_size(mut self, contract_size: ContractSize) -> Self {
+        self.contract_size = Some(contract_size);
+        self
+    }
+
+    pub fn account_currency(mut self, account_currency: impl Into<String>) -> Self {
+        self.account_currency = Some(account_currency.into());
+        self
+    }
+
+    /// Returns an error if the leverage or any of the spreads is negative,
+    /// the reject probability is out of the range from 0 to 1
+    /// or the contract size isn't positive.
+    pub fn build(self) -> Result<BacktestingTradingEngineConfig> {
+        let default = BacktestingTradingEngineConfig::default();
+
+        let leverage = self.leverage.unwrap_or(default.leverage);
+        if leverage < dec!(0) {
+            bail!("leverage ({}) is negative", leverage);
+        }
+
+        let spread = self.spread.unwrap_or(default.spread);
+        if spread < dec!(0) {
+            bail!("spread ({}) is negative", spread);
+        }
+
+        let spread_schedule = self.spread_schedule.unwrap_or(default.spread_schedule);
+        if let Some((start, end, spread)) = spread_schedule
+            .iter()
+            .find(|(_, _, spread)| *spread < dec!(0))
+        {
+            bail!(
+                "spread ({}) of the schedule window {} - {} is negative",
+                spread,
+                start,
+                end
+            );
         }
+
+        let reject_probability = self
+            .reject_probability
+            .unwrap_or(default.reject_probability);
+        if reject_probability < dec!(0) || reject_probability > dec!(1) {
+            bail!(
+                "reject probability ({}) is out of the range from 0 to 1",
+                reject_probability
+            );
+        }
+
+        let max_total_risk_fraction = self
+            .max_total_risk_fraction
+            .or(default.max_total_risk_fraction);
+        if let Some(max_total_risk_fraction) = max_total_risk_fraction {
+            if max_total_risk_fraction <= dec!(0) {
+                bail!(
+                    "max total risk fraction ({}) is not positive",
+                    max_total_risk_fraction
+                );
+            }
+        }
+
+        let margin_call_level = self.margin_call_level.or(default.margin_call_level);
+        if let Some(margin_call_level) = margin_call_level {
+            if margin_call_level <= dec!(0) {
+                bail!("margin call level ({}) is not positive", margin_call_level);
+            }
+        }
+
+        let lot_rounding = self.lot_rounding.unwrap_or(default.lot_rounding);
+        if lot_rounding.step <= dec!(0) {
+            bail!("lot step ({}) is not positive", lot_rounding.step);
+        }
+        if lot_rounding.min < dec!(0) {
+            bail!("min lot ({}) is negative", lot_rounding.min);
+        }
+
+        let contract_size = self.contract_size.unwrap_or(default.contract_size);
+        if contract_size <= dec!(0) {
+            bail!("contract size ({}) is not positive", contract_size);
+        }
+
+        Ok(BacktestingTradingEngineConfig {
+            balances: self
+                .initial_balance
+                .map(BacktestingBalances::new)
+                .unwrap_or(default.balances),
+            leverage,
+            spread,
+            spread_schedule,
+            use_spread: self.use_spread.unwrap_or(default.use_spread),
+            apply_spread: self.apply_spread.unwrap_or(default.apply_spread),
+            spread_model: self.spread_model.unwrap_or(default.spread_model),
+            fill_latency: self.fill_latency.or(default.fill_latency),
+            order_expiration: self.order_expiration.or(default.order_expiration),
+            reject_probability,
+            rejection_rng: self
+                .rejection_seed
+                .map(Xoshiro256PlusPlus::seed_from_u64)
+                .unwrap_or(default.rejection_rng),
+            max_total_risk_fraction,
+            margin_call_level,
+            price_rounding: self.price_rounding.unwrap_or(default.price_rounding),
+            lot_rounding,
+            end_of_data_policy: self
+                .end_of_data_policy
+                .unwrap_or(default.end_of_data_policy),
+            conversion_rate: self.conversion_rate.unwrap_or(default.conversion_rate),
+            contract_size,
+            account_currency: self
+                .account_currency
+                .unwrap_or_else(|| default.account_currency.clone()),
+            keep_closed_trades: self
+                .keep_closed_trades
+                .unwrap_or(default.keep_closed_trades),
+            ..default
+        })
     }
 }
 
@@ -99,7 +1193,20 @@ pub struct StrategyInitConfig {
     pub duration: Duration,
 }
 
-pub fn get_path_name_for_data_config(strategy_config: &StrategyInitConfig) -> String {
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum DataConfigPathError {
+    #[error("symbol {0:?} can't be used in a path")]
+    UnsafeSymbol(String),
+    #[error("duration of {0} minutes isn't positive")]
+    NonPositiveDuration(i64),
+}
+
+/// Returns the name of the directory for the historical data of the config.
+/// The symbol is rejected if it's empty, a relative path component,
+/// or contains path separators or other characters unsafe for the filesystem.
+pub fn get_path_name_for_data_config(
+    strategy_config: &StrategyInitConfig,
+) -> Result<String, DataConfigPathError> {
     let StrategyInitConfig {
         symbol,
         timeframes:
@@ -111,7 +1218,23 @@ pub fn get_path_name_for_data_config(strategy_config: &StrategyInitConfig) -> St
         duration,
     } = strategy_config;
 
-    format!(
+    if symbol.is_empty()
+        || symbol == "."
+        || symbol == ".."
+        || symbol
+            .chars()
+            .any(|c| c.is_control() || UNSAFE_SYMBOL_CHARACTERS.contains(&c))
+    {
+        return Err(DataConfigPathError::UnsafeSymbol(symbol.clone()));
+    }
+
+    if *duration <= Duration::zero() {
+        return Err(DataConfigPathError::NonPositiveDuration(
+            duration.num_minutes(),
+        ));
+    }
+
+    Ok(format!(
         "{}_{}_{}_{}_{}_({}_weeks)",
         symbol,
         candle_timeframe,
@@ -119,5 +1242,160 @@ pub fn get_path_name_for_data_config(strategy_config: &StrategyInitConfig) -> St
         end_time.format(TIME_PATTERN_FOR_PATH),
         duration.num_minutes(),
         duration.num_weeks()
-    )
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::entities::Timeframe;
+    use chrono::TimeZone;
+
+    fn strategy_config(symbol: &str, duration: Duration) -> StrategyInitConfig {
+        StrategyInitConfig {
+            symbol: symbol.to_string(),
+            timeframes: StrategyTimeframes {
+                candle: Timeframe::Hour,
+                tick: Timeframe::OneMin,
+            },
+            end_time: Utc.ymd(2022, 5, 17).and_hms(13, 0, 0),
+            duration,
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn build__some_fields_are_set__should_take_other_fields_from_default_config() {
+        let config = BacktestingTradingEngineConfig::builder()
+            .initial_balance(dec!(5_000))
+            .leverage(dec!(0.02))
+            .use_spread(false)
+            .build()
+            .unwrap();
+
+        let default = BacktestingTradingEngineConfig::default();
+
+        assert_eq!(config.balances.initial, dec!(5_000));
+        assert_eq!(config.balances.processing, dec!(5_000));
+        assert_eq!(config.balances.real, dec!(5_000));
+        assert_eq!(config.leverage, dec!(0.02));
+        assert!(!config.use_spread);
+        assert_eq!(config.spread, default.spread);
+        assert_eq!(config.fill_latency, default.fill_latency);
+        assert_eq!(config.order_expiration, default.order_expiration);
+        assert_eq!(config.end_of_data_policy, default.end_of_data_policy);
+        assert_eq!(config.conversion_rate, default.conversion_rate);
+        assert_eq!(config.contract_size, default.contract_size);
+        assert_eq!(config.account_currency, default.account_currency);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn build__negative_leverage_or_spread__should_return_error() {
+        assert!(BacktestingTradingEngineConfig::builder()
+            .leverage(dec!(-0.01))
+            .build()
+            .is_err());
+
+        assert!(BacktestingTradingEngineConfig::builder()
+            .spread(dec!(-0.0001))
+            .build()
+            .is_err());
+
+        assert!(BacktestingTradingEngineConfig::builder()
+            .spread_schedule(vec![(
+                Utc.ymd(2022, 5, 17).and_hms(13, 0, 0),
+                Utc.ymd(2022, 5, 17).and_hms(14, 0, 0),
+                dec!(-0.0001),
+            )])
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn build__reject_probability_out_of_range__should_return_error() {
+        for reject_probability in [dec!(-0.1), dec!(1.1)] {
+            assert!(BacktestingTradingEngineConfig::builder()
+                .reject_probability(reject_probability)
+                .build()
+                .is_err());
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn build__non_positive_contract_size__should_return_error() {
+        for contract_size in [dec!(0), dec!(-1)] {
+            assert!(BacktestingTradingEngineConfig::builder()
+                .contract_size(contract_size)
+                .build()
+                .is_err());
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn required_margin__contract_size_is_set__should_multiply_notional_value_by_contract_size() {
+        let config = BacktestingTradingEngineConfig::builder()
+            .contract_size(dec!(10))
+            .leverage(dec!(0.01))
+            .account_currency("EUR")
+            .build()
+            .unwrap();
+
+        assert_eq!(config.account_currency, "EUR");
+        assert_eq!(config.notional_value(dec!(1_000), dec!(1.5)), dec!(15_000));
+        assert_eq!(config.required_margin(dec!(1_000), dec!(1.5)), dec!(150));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn without_fill_latency__action_fails__should_restore_fill_latency() {
+        let mut config = BacktestingTradingEngineConfig {
+            fill_latency: Some(Duration::seconds(5)),
+            ..Default::default()
+        };
+
+        let result: Result<()> = config.without_fill_latency(|config| {
+            assert_eq!(config.fill_latency, None);
+            bail!("closing failed")
+        });
+
+        assert!(result.is_err());
+        assert_eq!(config.fill_latency, Some(Duration::seconds(5)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_path_name_for_data_config__valid_config__should_return_path_name() {
+        assert_eq!(
+            get_path_name_for_data_config(&strategy_config("GBPUSD", Duration::weeks(2))).unwrap(),
+            "GBPUSD_1h_1m_2022-05-17_13-00_20160_(2_weeks)"
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_path_name_for_data_config__symbols_with_unsafe_characters__should_return_error() {
+        for symbol in ["EUR/USD", "EUR\\USD", "..", "", "EUR:USD"] {
+            assert_eq!(
+                get_path_name_for_data_config(&strategy_config(symbol, Duration::weeks(2))),
+                Err(DataConfigPathError::UnsafeSymbol(symbol.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_path_name_for_data_config__non_positive_duration__should_return_error() {
+        assert_eq!(
+            get_path_name_for_data_config(&strategy_config("GBPUSD", Duration::zero())),
+            Err(DataConfigPathError::NonPositiveDuration(0))
+        );
+        assert_eq!(
+            get_path_name_for_data_config(&strategy_config("GBPUSD", Duration::minutes(-60))),
+            Err(DataConfigPathError::NonPositiveDuration(-60))
+        );
+    }
 }
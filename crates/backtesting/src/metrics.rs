@@ -0,0 +1,542 @@
+use base::entities::Timeframe;
+use base::helpers::mean;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+use crate::{Balance, ClosedTrade};
+
+const TRADING_DAYS_PER_YEAR: u32 = 252;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+pub type EquityReturn = Decimal;
+pub type RiskAdjustedRatio = Decimal;
+/// Decline of the equity from its peak in percents.
+pub type Drawdown = Decimal;
+pub type Correlation = Decimal;
+
+/// Returns the relative change of the equity for every period of the equity curve.
+/// The periods starting with a zero equity are skipped.
+pub fn equity_returns(equity_curve: &[Balance]) -> Vec<EquityReturn> {
+    equity_curve
+        .windows(2)
+        .filter(|pair| pair[0] != dec!(0))
+        .map(|pair| pair[1] / pair[0] - dec!(1))
+        .collect()
+}
+
+/// Annualized Sharpe ratio of the per-period returns with the zero risk-free rate.
+/// Returns `None` if there are less than two returns or the returns have no variance.
+pub fn sharpe_ratio(returns: &[EquityReturn], timeframe: Timeframe) -> Option<RiskAdjustedRatio> {
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let mean_return = mean(returns);
+
+    let variance = mean(
+        &returns
+            .iter()
+            .map(|r| (r - mean_return) * (r - mean_return))
+            .collect::<Vec<_>>(),
+    );
+
+    annualized_ratio(mean_return, variance, timeframe)
+}
+
+/// Annualized Sortino ratio of the per-period returns with the zero target return.
+/// Returns `None` if there are less than two returns or there is no downside deviation.
+pub fn sortino_ratio(returns: &[EquityReturn], timeframe: Timeframe) -> Option<RiskAdjustedRatio> {
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let downside_variance = mean(
+        &returns
+            .iter()
+            .map(|&r| {
+                let downside = r.min(dec!(0));
+                downside * downside
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    annualized_ratio(mean(returns), downside_variance, timeframe)
+}
+
+fn annualized_ratio(
+    mean_return: EquityReturn,
+    variance: Decimal,
+    timeframe: Timeframe,
+) -> Option<RiskAdjustedRatio> {
+    if variance == dec!(0) {
+        return None;
+    }
+
+    let periods_per_year = (TRADING_DAYS_PER_YEAR * MINUTES_PER_DAY) as f64
+        / timeframe.duration().num_minutes() as f64;
+
+    let deviation = variance.to_f64()?.sqrt();
+
+    Decimal::from_f64(mean_return.to_f64()? / deviation * periods_per_year.sqrt())
+}
+
+/// Pearson correlation of the per-period returns of two equity curves.
+/// The curves are aligned by timestamps, so only the timestamps present in both of them are used,
+/// and the periods starting with a zero equity in any of the curves are skipped.
+/// Returns `None` if there are less than two common returns or the returns of any curve have no variance.
+pub fn returns_correlation(
+    a: &[(DateTime<Utc>, Balance)],
+    b: &[(DateTime<Utc>, Balance)],
+) -> Option<Correlation> {
+    let b_equity_by_time: HashMap<_, _> = b.iter().cloned().collect();
+
+    let mut aligned_equities = a
+        .iter()
+        .filter_map(|(time, a_equity)| {
+            b_equity_by_time
+                .get(time)
+                .map(|b_equity| (*time, *a_equity, *b_equity))
+        })
+        .collect::<Vec<_>>();
+
+    aligned_equities.sort_by_key(|(time, _, _)| *time);
+
+    let (a_returns, b_returns): (Vec<_>, Vec<_>) = aligned_equities
+        .windows(2)
+        .filter(|pair| pair[0].1 != dec!(0) && pair[0].2 != dec!(0))
+        .map(|pair| {
+            (
+                pair[1].1 / pair[0].1 - dec!(1),
+                pair[1].2 / pair[0].2 - dec!(1),
+            )
+        })
+        .unzip();
+
+    if a_returns.len() < 2 {
+        return None;
+    }
+
+    let a_mean = mean(&a_returns);
+    let b_mean = mean(&b_returns);
+
+    let mut covariance = dec!(0);
+    let mut a_variance = dec!(0);
+    let mut b_variance = dec!(0);
+
+    for (a_return, b_return) in a_returns.iter().zip(b_returns.iter()) {
+        let a_deviation = a_return - a_mean;
+        let b_deviation = b_return - b_mean;
+
+        covariance += a_deviation * b_deviation;
+        a_variance += a_deviation * a_deviation;
+        b_variance += b_deviation * b_deviation;
+    }
+
+    if a_variance == dec!(0) || b_variance == dec!(0) {
+        return None;
+    }
+
+    Decimal::from_f64(covariance.to_f64()? / (a_variance * b_variance).to_f64()?.sqrt())
+}
+
+/// Tracks the largest decline of the equity from its running peak.
+#[derive(Debug, Default)]
+pub struct DrawdownTracker {
+    peak: Balance,
+    max_drawdown: Drawdown,
+}
+
+impl DrawdownTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, equity: Balance) {
+        if equity > self.peak {
+            self.peak = equity;
+        } else if self.peak > dec!(0) {
+            self.max_drawdown = self
+                .max_drawdown
+                .max((self.peak - equity) / self.peak * dec!(100));
+        }
+    }
+
+    pub fn max_drawdown(&self) -> Drawdown {
+        self.max_drawdown
+    }
+}
+
+/// Links the per-period returns of the equity geometrically, so the return doesn't depend
+/// on how much of the capital is in the open positions in every period.
+///
+/// The equity updated at the same time as the previous one doesn't start a zero-length period,
+/// its change is linked into the previous period. The periods starting with a non-positive
+/// equity are skipped.
+#[derive(Debug)]
+pub struct TimeWeightedReturnTracker {
+    last_update: Option<(DateTime<Utc>, Balance)>,
+    growth: Decimal,
+    number_of_periods: u64,
+}
+
+impl Default for TimeWeightedReturnTracker {
+    fn default() -> Self {
+        Self {
+            last_update: None,
+            growth: dec!(1),
+            number_of_periods: 0,
+        }
+    }
+}
+
+impl TimeWeightedReturnTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, time: DateTime<Utc>, equity: Balance) {
+        let (last_time, last_equity) = match self.last_update {
+            Some(last_update) => last_update,
+            None => {
+                self.last_update = Some((time, equity));
+                return;
+            }
+        };
+
+        if last_equity > dec!(0) {
+            self.growth *= equity / last_equity;
+        }
+
+        if time > last_time {
+            self.number_of_periods += 1;
+        }
+
+        self.last_update = Some((time.max(last_time), equity));
+    }
+
+    /// Returns `None` if there are no periods of non-zero length.
+    pub fn time_weighted_return(&self) -> Option<EquityReturn> {
+        if self.number_of_periods == 0 {
+            return None;
+        }
+
+        Some(self.growth - dec!(1))
+    }
+}
+
+/// Running mean and variance updated with the Welford's algorithm.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RunningMoments {
+    count: u64,
+    mean: Decimal,
+    sum_of_squared_deviations: Decimal,
+}
+
+impl RunningMoments {
+    pub fn update(&mut self, value: Decimal) {
+        self.count += 1;
+
+        let deviation = value - self.mean;
+        self.mean += deviation / Decimal::from(self.count);
+        self.sum_of_squared_deviations += deviation * (value - self.mean);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<Decimal> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// The population variance, the same as for the Sharpe ratio of the returns.
+    pub fn variance(&self) -> Option<Decimal> {
+        (self.count > 0).then(|| self.sum_of_squared_deviations / Decimal::from(self.count))
+    }
+}
+
+/// Statistics of the closed trades updated as each trade closes, so they can be computed
+/// without keeping every closed trade. The profits are per unit in the price points.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StreamingStats {
+    winning_trades: u64,
+    gross_profit: Decimal,
+    gross_loss: Decimal,
+    profit: RunningMoments,
+    /// The trades without the recorded candles are skipped.
+    bars_in_trade: RunningMoments,
+}
+
+impl StreamingStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, trade: &ClosedTrade) {
+        let profit = trade.profit();
+
+        if profit > dec!(0) {
+            self.winning_trades += 1;
+            self.gross_profit += profit;
+        } else {
+            self.gross_loss -= profit;
+        }
+
+        self.profit.update(profit);
+
+        if let (Some(open_candle_index), Some(close_candle_index)) =
+            (trade.open_candle_index, trade.close_candle_index)
+        {
+            self.bars_in_trade
+                .update(Decimal::from(close_candle_index - open_candle_index));
+        }
+    }
+
+    pub fn number_of_trades(&self) -> u64 {
+        self.profit.count()
+    }
+
+    /// Returns the percentage of the trades with profit.
+    pub fn win_rate(&self) -> Option<Decimal> {
+        if self.number_of_trades() == 0 {
+            return None;
+        }
+
+        Some(
+            Decimal::from(self.winning_trades) / Decimal::from(self.number_of_trades()) * dec!(100),
+        )
+    }
+
+    /// Returns the ratio of the gross profit to the gross loss.
+    /// Returns `None` if there are no losses.
+    pub fn profit_factor(&self) -> Option<Decimal> {
+        if self.gross_loss == dec!(0) {
+            return None;
+        }
+
+        Some(self.gross_profit / self.gross_loss)
+    }
+
+    pub fn average_profit(&self) -> Option<Decimal> {
+        self.profit.mean()
+    }
+
+    /// Per-trade Sharpe ratio of the profits with the zero risk-free rate.
+    /// Returns `None` if there are less than two trades or the profits have no variance.
+    pub fn sharpe_ratio(&self) -> Option<RiskAdjustedRatio> {
+        if self.number_of_trades() < 2 {
+            return None;
+        }
+
+        let variance = self.profit.variance()?;
+        if variance == dec!(0) {
+            return None;
+        }
+
+        Decimal::from_f64(self.profit.mean()?.to_f64()? / variance.to_f64()?.sqrt())
+    }
+
+    pub fn average_bars_in_trade(&self) -> Option<Decimal> {
+        self.bars_in_trade.mean()
+    }
+
+    pub fn bars_in_trade_variance(&self) -> Option<Decimal> {
+        self.bars_in_trade.variance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CloseReason;
+    use base::entities::order::OrderType;
+    use chrono::TimeZone;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn equity_returns__equity_curve__should_return_relative_changes() {
+        assert_eq!(
+            equity_returns(&[dec!(100), dec!(110), dec!(99)]),
+            vec![dec!(0.1), dec!(-0.1)]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn sharpe_ratio__returns_with_variance__should_return_annualized_ratio() {
+        let returns = [dec!(0.02), dec!(0), dec!(0.02), dec!(0)];
+
+        let ratio = sharpe_ratio(&returns, Timeframe::Hour).unwrap();
+
+        // mean 0.01, deviation 0.01, 252 * 24 periods per year
+        assert_eq!(ratio.round_dp(4), dec!(77.7689));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn sharpe_ratio__zero_variance__should_return_none() {
+        assert_eq!(
+            sharpe_ratio(&[dec!(0.01), dec!(0.01), dec!(0.01)], Timeframe::Hour),
+            None
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn sharpe_ratio__single_return__should_return_none() {
+        assert_eq!(sharpe_ratio(&[dec!(0.01)], Timeframe::Hour), None);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn sortino_ratio__returns_with_losses__should_return_annualized_ratio() {
+        let returns = [dec!(0.03), dec!(-0.02), dec!(0.03), dec!(-0.02)];
+
+        let ratio = sortino_ratio(&returns, Timeframe::Hour).unwrap();
+
+        // mean 0.005, downside deviation sqrt(0.0002), 252 * 24 periods per year
+        assert_eq!(ratio.round_dp(4), dec!(27.4955));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn sortino_ratio__no_losses__should_return_none() {
+        assert_eq!(
+            sortino_ratio(&[dec!(0.01), dec!(0.02), dec!(0)], Timeframe::Hour),
+            None
+        );
+    }
+
+    fn equity_curve(equities: &[(u32, Balance)]) -> Vec<(DateTime<Utc>, Balance)> {
+        equities
+            .iter()
+            .map(|(hour, equity)| (Utc.ymd(2022, 4, 1).and_hms(*hour, 0, 0), *equity))
+            .collect()
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn returns_correlation__curves_with_different_timestamps__should_use_common_timestamps_only() {
+        let a = equity_curve(&[
+            (0, dec!(100)),
+            (1, dec!(110)),
+            (2, dec!(99)),
+            (3, dec!(108.9)),
+            (4, dec!(50)),
+        ]);
+
+        // the opposite moves on the common timestamps
+        let b = equity_curve(&[
+            (0, dec!(200)),
+            (1, dec!(180)),
+            (2, dec!(198)),
+            (3, dec!(178.2)),
+            (5, dec!(500)),
+        ]);
+
+        assert_eq!(returns_correlation(&a, &b).unwrap().round_dp(6), dec!(-1));
+        assert_eq!(returns_correlation(&a, &a).unwrap().round_dp(6), dec!(1));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn returns_correlation__insufficient_overlap__should_return_none() {
+        let a = equity_curve(&[(0, dec!(100)), (1, dec!(110)), (2, dec!(99))]);
+        let b = equity_curve(&[(1, dec!(200)), (2, dec!(180)), (3, dec!(198))]);
+
+        assert_eq!(returns_correlation(&a, &b), None);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn drawdown_tracker__several_declines__should_return_largest_decline_from_peak() {
+        let mut tracker = DrawdownTracker::new();
+
+        for equity in [
+            dec!(10_000),
+            dec!(9_500),
+            dec!(11_000),
+            dec!(9_900),
+            dec!(10_500),
+        ] {
+            tracker.update(equity);
+        }
+
+        assert_eq!(tracker.max_drawdown(), dec!(10));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn time_weighted_return_tracker__periods_with_zero_length__should_link_returns_geometrically() {
+        let mut tracker = TimeWeightedReturnTracker::new();
+        let time = |hour| Utc.ymd(2022, 4, 1).and_hms(hour, 0, 0);
+
+        tracker.update(time(0), dec!(10_000));
+        assert_eq!(tracker.time_weighted_return(), None);
+
+        // the zero-length period doesn't produce a separate return
+        tracker.update(time(0), dec!(11_000));
+        assert_eq!(tracker.time_weighted_return(), None);
+
+        tracker.update(time(1), dec!(11_000));
+        tracker.update(time(2), dec!(9_900));
+        tracker.update(time(2), dec!(10_890));
+
+        // 1.1 * 0.9 * 1.1 - 1
+        assert_eq!(tracker.time_weighted_return(), Some(dec!(0.089)));
+    }
+
+    fn closed_trade(close_price: Decimal, bars_in_trade: Option<usize>) -> ClosedTrade {
+        ClosedTrade {
+            order_id: String::from("1"),
+            r#type: OrderType::Buy,
+            volume: dec!(0.03),
+            open_price: dec!(1.38000),
+            close_price,
+            open_time: None,
+            close_time: None,
+            open_candle_index: bars_in_trade.map(|_| 10),
+            close_candle_index: bars_in_trade.map(|bars| 10 + bars),
+            excursions: Default::default(),
+            close_reason: CloseReason::TakeProfit,
+            fill_slippage: dec!(0),
+            spread_cost: dec!(0),
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn streaming_stats__several_trades__should_return_same_stats_as_full_ledger() {
+        let mut stats = StreamingStats::new();
+
+        assert_eq!(stats.win_rate(), None);
+        assert_eq!(stats.sharpe_ratio(), None);
+
+        for trade in [
+            closed_trade(dec!(1.38300), Some(2)),
+            closed_trade(dec!(1.37900), Some(4)),
+            closed_trade(dec!(1.38100), None),
+            closed_trade(dec!(1.37800), Some(6)),
+        ] {
+            stats.update(&trade);
+        }
+
+        assert_eq!(stats.number_of_trades(), 4);
+        assert_eq!(stats.win_rate(), Some(dec!(50)));
+        // 0.004 / 0.003
+        assert_eq!(stats.profit_factor().unwrap().round_dp(4), dec!(1.3333));
+        assert_eq!(stats.average_profit(), Some(dec!(0.00025)));
+
+        // the population deviation of the profits is 0.00192029
+        assert_eq!(stats.sharpe_ratio().unwrap().round_dp(4), dec!(0.1302));
+
+        assert_eq!(stats.average_bars_in_trade(), Some(dec!(4)));
+        assert_eq!(
+            stats.bars_in_trade_variance().unwrap().round_dp(4),
+            dec!(2.6667)
+        );
+    }
+}
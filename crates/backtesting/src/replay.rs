@@ -0,0 +1,109 @@
+use base::entities::StrategyTimeframes;
+
+use crate::HistoricalData;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ReplayEvent<'a, C, T> {
+    /// The candle is completed. It's passed right before the first tick of the next candle.
+    Candle(&'a C),
+    Tick(&'a T),
+}
+
+/// Feeds the synchronized historical data to the callback in the chronological order.
+/// The ticks are interleaved with the candles they belong to, and every candle is passed
+/// only after all its ticks. The last candle isn't passed, because it's not completed
+/// within the historical data. Gaps in the data are skipped.
+pub fn replay<'a, C, T>(
+    historical_data: &'a HistoricalData<C, T>,
+    timeframes: StrategyTimeframes,
+    mut on_event: impl FnMut(ReplayEvent<'a, C, T>),
+) {
+    let number_of_ticks_per_candle = timeframes.candle as usize / timeframes.tick as usize;
+
+    for (i, tick) in historical_data.ticks.iter().enumerate() {
+        if i != 0 && i % number_of_ticks_per_candle == 0 {
+            if let Some(Some(candle)) = historical_data
+                .candles
+                .get(i / number_of_ticks_per_candle - 1)
+            {
+                on_event(ReplayEvent::Candle(candle));
+            }
+        }
+
+        if let Some(tick) = tick {
+            on_event(ReplayEvent::Tick(tick));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::entities::Timeframe;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn replay__hour_candles_and_thirty_minute_ticks__should_interleave_ticks_and_candles() {
+        let historical_data = HistoricalData {
+            candles: vec![Some("18:00"), None, Some("20:00")],
+            ticks: vec![
+                Some("18:00"),
+                Some("18:30"),
+                Some("19:00"),
+                None,
+                Some("20:00"),
+            ],
+        };
+
+        let mut events = Vec::new();
+
+        replay(
+            &historical_data,
+            StrategyTimeframes {
+                candle: Timeframe::Hour,
+                tick: Timeframe::ThirtyMin,
+            },
+            |event| events.push(event),
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                ReplayEvent::Tick(&"18:00"),
+                ReplayEvent::Tick(&"18:30"),
+                ReplayEvent::Candle(&"18:00"),
+                ReplayEvent::Tick(&"19:00"),
+                ReplayEvent::Tick(&"20:00"),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn replay__equal_timeframes__should_pass_candle_before_each_next_tick() {
+        let historical_data = HistoricalData {
+            candles: vec![Some(1), Some(2)],
+            ticks: vec![Some(10), Some(20)],
+        };
+
+        let mut events = Vec::new();
+
+        replay(
+            &historical_data,
+            StrategyTimeframes {
+                candle: Timeframe::Hour,
+                tick: Timeframe::Hour,
+            },
+            |event| events.push(event),
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                ReplayEvent::Tick(&10),
+                ReplayEvent::Candle(&1),
+                ReplayEvent::Tick(&20),
+            ]
+        );
+    }
+}
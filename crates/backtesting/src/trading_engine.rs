@@ -1,8 +1,13 @@
-use crate::{BacktestingTradingEngineConfig, ClosePositionBy, OpenPositionBy, Units};
+use crate::{
+    BacktestingTradingEngineConfig, BalanceIsDepleted, ClosePositionBy, DelayedFill, Exposure,
+    OpenPositionBy, OrderRejectionReason, Quantity, Units,
+};
 use base::entities::order::{
     BasicOrderProperties, OrderId, OrderPrice, OrderStatus, OrderType, OrderVolume,
 };
-use base::entities::{Item, CANDLE_PRICE_DECIMAL_PLACES, LOT, SIGNIFICANT_DECIMAL_PLACES};
+use base::entities::tick::TickPrice;
+use base::entities::{Item, LOT};
+use chrono::{DateTime, Utc};
 use std::fmt::Debug;
 
 use anyhow::Result;
@@ -11,6 +16,8 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
 pub trait TradingEngine {
+    /// Returns the `OrderRejectionReason` error if the opening of the position is rejected.
+    /// The order stays pending in this case.
     fn open_position<O>(
         &self,
         order: &Item<OrderId, O>,
@@ -30,6 +37,46 @@ pub trait TradingEngine {
     ) -> Result<()>
     where
         O: Into<BasicOrderProperties> + Clone + Debug;
+
+    /// Returns the buy and sell units of the currently opened positions.
+    /// The backtesting engine trades a single symbol, so all the opened orders
+    /// of the store are taken into account.
+    fn exposure_by_direction<O>(
+        &self,
+        order_store: &impl BasicOrderStore<OrderProperties = O>,
+    ) -> Result<Exposure>
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug;
+
+    /// Executes the delayed fills that are due by the current time at the current tick price.
+    fn fill_delayed_orders(
+        &self,
+        current_tick_price: TickPrice,
+        trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<()>;
+
+    /// Registers the position opened outside of the backtest, e.g. before the restart of the bot,
+    /// as filled at its open price without the spread. The order should already be opened in the store.
+    fn import_position<O>(
+        &self,
+        order: &Item<OrderId, O>,
+        open_time: DateTime<Utc>,
+        trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<()>
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug;
+
+    /// Force-closes the opened positions with the largest loss at the current tick price
+    /// while the equity is below the margin call level of the used margin.
+    /// Returns the number of the closed positions.
+    fn close_positions_by_margin_call<O>(
+        &self,
+        current_tick_price: TickPrice,
+        order_store: &mut impl BasicOrderStore<OrderProperties = O>,
+        trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<usize>
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug;
 }
 
 #[derive(Default)]
@@ -46,65 +93,201 @@ impl BacktestingTradingEngine {
             .all(|status| status != &OrderStatus::Opened)
     }
 
-    /// Executes a buy market order.
+    fn volume_to_quantity(volume: OrderVolume) -> Quantity {
+        volume * Decimal::from(LOT)
+    }
+
+    fn volume_to_units(volume: OrderVolume) -> Result<Units> {
+        Ok(Self::volume_to_quantity(volume)
+            .trunc()
+            .to_string()
+            .parse::<Units>()?)
+    }
+
+    /// Checks whether the risk of the opened positions together with the risk
+    /// of the position being opened exceeds the max total risk.
+    fn max_total_risk_is_exceeded<O>(
+        order_props: &BasicOrderProperties,
+        order_store: &impl BasicOrderStore<OrderProperties = O>,
+        trading_config: &BacktestingTradingEngineConfig,
+    ) -> Result<bool>
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug,
+    {
+        let max_total_risk_fraction = match trading_config.max_total_risk_fraction {
+            Some(max_total_risk_fraction) => max_total_risk_fraction,
+            None => return Ok(false),
+        };
+
+        let mut total_risk = trading_config.position_risk(order_props);
+
+        for order in order_store.get_all_orders()? {
+            let opened_order_props = order.props.into();
+
+            if opened_order_props.status == OrderStatus::Opened {
+                total_risk += trading_config.position_risk(&opened_order_props);
+            }
+        }
+
+        // the processing balance is reduced by the notional value of the opened buy positions,
+        // so the risk is measured against the real balance
+        Ok(total_risk > trading_config.balances.real * max_total_risk_fraction)
+    }
+
+    /// Returns the unrealized profit of the opened position at the current price in the quote currency.
+    fn unrealized_profit(
+        order_props: &BasicOrderProperties,
+        open_price: OrderPrice,
+        current_tick_price: TickPrice,
+        trading_config: &BacktestingTradingEngineConfig,
+    ) -> Decimal {
+        let profit_per_unit = match order_props.r#type {
+            OrderType::Buy => current_tick_price - open_price,
+            OrderType::Sell => open_price - current_tick_price,
+        };
+
+        trading_config.notional_value(
+            Self::volume_to_quantity(order_props.volume),
+            profit_per_unit,
+        )
+    }
+
+    /// Returns the equity and the used margin of the opened positions in the account currency.
+    ///
+    /// The processing balance already includes the profit of the trades closed since
+    /// the last update of the real balance and the fill values of the opened positions,
+    /// so the opened positions are valued at the current price on top of it.
+    /// The positions waiting for the delayed fill don't affect the balances yet and are skipped.
+    fn equity_and_used_margin<O>(
+        opened_orders: &[Item<OrderId, O>],
+        current_tick_price: TickPrice,
+        trading_config: &BacktestingTradingEngineConfig,
+    ) -> (Decimal, Decimal)
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug,
+    {
+        let mut equity = trading_config.balances.processing;
+        let mut used_margin = dec!(0);
+
+        for order in opened_orders {
+            let trade = match trading_config.open_trades.get(&order.id) {
+                Some(trade) => trade,
+                None => continue,
+            };
+
+            let order_props = order.props.clone().into();
+            let quantity = Self::volume_to_quantity(order_props.volume);
+            let current_value = trading_config.notional_value(quantity, current_tick_price);
+
+            match order_props.r#type {
+                OrderType::Buy => equity += current_value,
+                OrderType::Sell => equity -= current_value,
+            }
+
+            used_margin += trading_config.required_margin(quantity, trade.open_price);
+        }
+
+        let real_balance = trading_config.balances.real;
+
+        (
+            real_balance + (equity - real_balance) * trading_config.conversion_rate,
+            used_margin * trading_config.conversion_rate,
+        )
+    }
+
+    /// Returns the difference between the requested and the fill prices against the trade
+    /// of the particular type for the opening or the closing of the position.
+    fn get_fill_slippage(
+        r#type: OrderType,
+        closes_position: bool,
+        requested_price: OrderPrice,
+        fill_price: OrderPrice,
+    ) -> OrderPrice {
+        match (r#type, closes_position) {
+            (OrderType::Buy, false) | (OrderType::Sell, true) => fill_price - requested_price,
+            (OrderType::Sell, false) | (OrderType::Buy, true) => requested_price - fill_price,
+        }
+    }
+
+    /// Executes a buy market order and returns the fill price.
+    /// The spread isn't applied to the real ask price.
     fn buy_instrument(
         mut price: OrderPrice,
         volume: OrderVolume,
+        price_is_real_quote: bool,
+        closes_position: bool,
         trading_config: &mut BacktestingTradingEngineConfig,
-    ) -> Result<()> {
-        if trading_config.use_spread {
+    ) -> Result<OrderPrice> {
+        if trading_config.use_spread && !price_is_real_quote {
             // ask price
-            price += trading_config.spread / dec!(2);
-            price = price.round_dp(CANDLE_PRICE_DECIMAL_PLACES);
+            price += trading_config.spread_to_apply(OrderType::Buy, closes_position);
+            price = trading_config.price_rounding.round_price(price);
         }
 
-        let units = (volume * Decimal::from(LOT))
-            .trunc()
-            .to_string()
-            .parse::<Units>()?;
+        let units = Self::volume_to_units(volume)?;
 
-        let trade_value = (Decimal::from(units) * price).round_dp(SIGNIFICANT_DECIMAL_PLACES);
+        let trade_value = trading_config
+            .price_rounding
+            .round_value(trading_config.notional_value(Self::volume_to_quantity(volume), price));
 
         trading_config.balances.processing -= trade_value;
         trading_config.balances.processing = trading_config
-            .balances
-            .processing
-            .round_dp(SIGNIFICANT_DECIMAL_PLACES);
+            .price_rounding
+            .round_value(trading_config.balances.processing);
 
         trading_config.units += units;
         trading_config.trades += 1;
 
-        Ok(())
+        Ok(price)
     }
 
-    /// Executes a sell market order.
+    /// Executes a sell market order and returns the fill price.
+    /// The spread isn't applied to the real bid price.
     fn sell_instrument(
         mut price: OrderPrice,
         volume: OrderVolume,
+        price_is_real_quote: bool,
+        closes_position: bool,
         trading_config: &mut BacktestingTradingEngineConfig,
-    ) -> Result<()> {
-        if trading_config.use_spread {
+    ) -> Result<OrderPrice> {
+        if trading_config.use_spread && !price_is_real_quote {
             // bid price
-            price -= trading_config.spread / dec!(2);
-            price = price.round_dp(CANDLE_PRICE_DECIMAL_PLACES);
+            price -= trading_config.spread_to_apply(OrderType::Sell, closes_position);
+            price = trading_config.price_rounding.round_price(price);
         }
 
-        let units = (volume * Decimal::from(LOT))
-            .trunc()
-            .to_string()
-            .parse::<Units>()?;
+        let units = Self::volume_to_units(volume)?;
 
-        let trade_value = (Decimal::from(units) * price).round_dp(SIGNIFICANT_DECIMAL_PLACES);
+        let trade_value = trading_config
+            .price_rounding
+            .round_value(trading_config.notional_value(Self::volume_to_quantity(volume), price));
 
         trading_config.balances.processing += trade_value;
         trading_config.balances.processing = trading_config
-            .balances
-            .processing
-            .round_dp(SIGNIFICANT_DECIMAL_PLACES);
+            .price_rounding
+            .round_value(trading_config.balances.processing);
 
         trading_config.units -= units;
         trading_config.trades += 1;
 
+        Ok(price)
+    }
+
+    /// Adds the profit of the closed trades converted into the account currency
+    /// to the real balance. Returns the `BalanceIsDepleted` error if the real balance
+    /// drops to zero or below.
+    fn update_real_balance(trading_config: &mut BacktestingTradingEngineConfig) -> Result<()> {
+        let profit = trading_config.balances.processing - trading_config.balances.real;
+
+        trading_config.balances.real += trading_config
+            .price_rounding
+            .round_value(profit * trading_config.conversion_rate);
+        trading_config.balances.processing = trading_config.balances.real;
+
+        if trading_config.balances.real <= dec!(0) {
+            return Err(BalanceIsDepleted(trading_config.balances.real).into());
+        }
+
         Ok(())
     }
 }
@@ -126,16 +309,65 @@ impl TradingEngine for BacktestingTradingEngine {
             anyhow::bail!("order status is not pending: {:?}", order_props);
         }
 
-        let price = match by {
-            OpenPositionBy::OpenPrice => order_props.prices.open,
-            OpenPositionBy::CurrentTickPrice(current_tick_price) => current_tick_price,
+        if trading_config.opening_is_requoted() {
+            return Err(OrderRejectionReason::Requote.into());
+        }
+
+        if Self::max_total_risk_is_exceeded(&order_props, order_store, trading_config)? {
+            return Err(OrderRejectionReason::MaxTotalRisk.into());
+        }
+
+        let (price, price_is_real_quote) = match by {
+            OpenPositionBy::OpenPrice => (order_props.prices.open, false),
+            OpenPositionBy::CurrentTickPrice(current_tick_price) => (current_tick_price, false),
+            OpenPositionBy::CurrentBidAsk(prices) => match order_props.r#type {
+                OrderType::Buy => (prices.ask, true),
+                OrderType::Sell => (prices.bid, true),
+            },
         };
 
-        match order_props.r#type {
-            OrderType::Buy => Self::buy_instrument(price, order_props.volume, trading_config)?,
-            OrderType::Sell => Self::sell_instrument(price, order_props.volume, trading_config)?,
+        if let Some(fill_time) = trading_config.delayed_fill_time() {
+            trading_config.delayed_fills.push(DelayedFill {
+                order_id: order.id.clone(),
+                r#type: order_props.r#type,
+                volume: order_props.volume,
+                requested_price: price,
+                fill_time,
+                close_reason: None,
+            });
+
+            return order_store.update_order_status(&order.id, OrderStatus::Opened);
         }
 
+        let fill_price = match order_props.r#type {
+            OrderType::Buy => Self::buy_instrument(
+                price,
+                order_props.volume,
+                price_is_real_quote,
+                false,
+                trading_config,
+            )?,
+            OrderType::Sell => Self::sell_instrument(
+                price,
+                order_props.volume,
+                price_is_real_quote,
+                false,
+                trading_config,
+            )?,
+        };
+
+        let fill_slippage = Self::get_fill_slippage(order_props.r#type, false, price, fill_price);
+
+        // the instant fill slippage is caused by the spread only
+        trading_config.open_trade(
+            order.id.clone(),
+            order_props.r#type,
+            order_props.volume,
+            price,
+            fill_slippage,
+            fill_slippage,
+        );
+
         order_store.update_order_status(&order.id, OrderStatus::Opened)
     }
 
@@ -155,17 +387,54 @@ impl TradingEngine for BacktestingTradingEngine {
             anyhow::bail!("order status is not opened: {:?}", order_props);
         }
 
-        let price = match by {
-            ClosePositionBy::TakeProfit => order_props.prices.take_profit,
-            ClosePositionBy::StopLoss => order_props.prices.stop_loss,
-            ClosePositionBy::CurrentTickPrice(current_tick_price) => current_tick_price,
+        let (price, price_is_real_quote) = match by {
+            ClosePositionBy::TakeProfit => (order_props.prices.take_profit, false),
+            ClosePositionBy::StopLoss => (order_props.prices.stop_loss, false),
+            ClosePositionBy::CurrentTickPrice(current_tick_price)
+            | ClosePositionBy::EndOfData(current_tick_price)
+            | ClosePositionBy::MarginCall(current_tick_price) => (current_tick_price, false),
+            ClosePositionBy::CurrentBidAsk(prices) => match order_props.r#type {
+                OrderType::Buy => (prices.bid, true),
+                OrderType::Sell => (prices.ask, true),
+            },
         };
 
-        match order_props.r#type {
-            OrderType::Buy => Self::sell_instrument(price, order_props.volume, trading_config)?,
-            OrderType::Sell => Self::buy_instrument(price, order_props.volume, trading_config)?,
+        if let Some(fill_time) = trading_config.delayed_fill_time() {
+            trading_config.delayed_fills.push(DelayedFill {
+                order_id: order.id.clone(),
+                r#type: order_props.r#type,
+                volume: order_props.volume,
+                requested_price: price,
+                fill_time,
+                close_reason: Some(by.reason()),
+            });
+
+            // the real balance is updated when the delayed fill is executed
+            return order_store.update_order_status(&order.id, OrderStatus::Closed);
         }
 
+        let fill_price = match order_props.r#type {
+            OrderType::Buy => Self::sell_instrument(
+                price,
+                order_props.volume,
+                price_is_real_quote,
+                true,
+                trading_config,
+            )?,
+            OrderType::Sell => Self::buy_instrument(
+                price,
+                order_props.volume,
+                price_is_real_quote,
+                true,
+                trading_config,
+            )?,
+        };
+
+        let fill_slippage = Self::get_fill_slippage(order_props.r#type, true, price, fill_price);
+
+        // the instant fill slippage is caused by the spread only
+        trading_config.close_trade(&order.id, price, by.reason(), fill_slippage, fill_slippage);
+
         order_store.update_order_status(&order.id, OrderStatus::Closed)?;
 
         let order_statuses: Vec<_> = order_store
@@ -175,17 +444,240 @@ impl TradingEngine for BacktestingTradingEngine {
             .collect();
 
         if Self::no_opened_orders(&order_statuses) {
-            trading_config.balances.real = trading_config.balances.processing;
-            if trading_config.balances.real <= dec!(0) {
-                anyhow::bail!(
-                    "real balance is less than or equal to zero: {:?}",
-                    trading_config.balances.real
-                );
+            Self::update_real_balance(trading_config)?;
+        }
+
+        Ok(())
+    }
+
+    fn exposure_by_direction<O>(
+        &self,
+        order_store: &impl BasicOrderStore<OrderProperties = O>,
+    ) -> Result<Exposure>
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug,
+    {
+        let mut exposure = Exposure::default();
+
+        for order in order_store.get_all_orders()? {
+            let order_props = order.props.into();
+
+            if order_props.status != OrderStatus::Opened {
+                continue;
+            }
+
+            let units = Self::volume_to_units(order_props.volume)?;
+
+            match order_props.r#type {
+                OrderType::Buy => exposure.buy += units,
+                OrderType::Sell => exposure.sell += units,
             }
         }
 
+        Ok(exposure)
+    }
+
+    fn fill_delayed_orders(
+        &self,
+        current_tick_price: TickPrice,
+        trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<()> {
+        let current_time = match trading_config.current_time {
+            Some(current_time) => current_time,
+            None => return Ok(()),
+        };
+
+        let (due_fills, delayed_fills): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut trading_config.delayed_fills)
+                .into_iter()
+                .partition(|fill| fill.fill_time <= current_time);
+
+        trading_config.delayed_fills = delayed_fills;
+
+        if due_fills.is_empty() {
+            return Ok(());
+        }
+
+        for fill in due_fills {
+            let closes_position = fill.close_reason.is_some();
+
+            let fill_price = match (fill.r#type, closes_position) {
+                (OrderType::Buy, false) | (OrderType::Sell, true) => Self::buy_instrument(
+                    current_tick_price,
+                    fill.volume,
+                    false,
+                    closes_position,
+                    trading_config,
+                )?,
+                (OrderType::Sell, false) | (OrderType::Buy, true) => Self::sell_instrument(
+                    current_tick_price,
+                    fill.volume,
+                    false,
+                    closes_position,
+                    trading_config,
+                )?,
+            };
+
+            let fill_slippage = Self::get_fill_slippage(
+                fill.r#type,
+                closes_position,
+                fill.requested_price,
+                fill_price,
+            );
+
+            // the rest of the slippage is the movement of the price during the fill latency
+            let spread_cost = Self::get_fill_slippage(
+                fill.r#type,
+                closes_position,
+                current_tick_price,
+                fill_price,
+            );
+
+            // the trades are recorded at the requested prices like the instant fills,
+            // so the fill prices are derived from them by the measured slippage
+            match fill.close_reason {
+                Some(close_reason) => trading_config.close_trade(
+                    &fill.order_id,
+                    fill.requested_price,
+                    close_reason,
+                    fill_slippage,
+                    spread_cost,
+                ),
+                None => trading_config.open_trade(
+                    fill.order_id,
+                    fill.r#type,
+                    fill.volume,
+                    fill.requested_price,
+                    fill_slippage,
+                    spread_cost,
+                ),
+            }
+        }
+
+        if trading_config.open_trades.is_empty() && trading_config.delayed_fills.is_empty() {
+            Self::update_real_balance(trading_config)?;
+        }
+
         Ok(())
     }
+
+    fn import_position<O>(
+        &self,
+        order: &Item<OrderId, O>,
+        open_time: DateTime<Utc>,
+        trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<()>
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug,
+    {
+        let order_props = order.props.clone().into();
+
+        if order_props.status != OrderStatus::Opened {
+            anyhow::bail!("order status is not opened: {:?}", order_props);
+        }
+
+        if trading_config.open_trades.contains_key(&order.id) {
+            anyhow::bail!(
+                "a trade of the order with an id {} is already open",
+                order.id
+            );
+        }
+
+        let open_price = order_props.prices.open;
+
+        match order_props.r#type {
+            OrderType::Buy => {
+                Self::buy_instrument(open_price, order_props.volume, true, false, trading_config)?
+            }
+            OrderType::Sell => {
+                Self::sell_instrument(open_price, order_props.volume, true, false, trading_config)?
+            }
+        };
+
+        trading_config.open_trade(
+            order.id.clone(),
+            order_props.r#type,
+            order_props.volume,
+            open_price,
+            dec!(0),
+            dec!(0),
+        );
+
+        if let Some(trade) = trading_config.open_trades.get_mut(&order.id) {
+            trade.open_time = Some(open_time);
+        }
+
+        Ok(())
+    }
+
+    fn close_positions_by_margin_call<O>(
+        &self,
+        current_tick_price: TickPrice,
+        order_store: &mut impl BasicOrderStore<OrderProperties = O>,
+        trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<usize>
+    where
+        O: Into<BasicOrderProperties> + Clone + Debug,
+    {
+        if trading_config.margin_call_level.is_none() {
+            return Ok(0);
+        }
+
+        // the broker closes the positions instantly on the margin call
+        trading_config.without_fill_latency(|trading_config| {
+            let mut number_of_closed_positions = 0;
+
+            loop {
+                let opened_orders: Vec<_> = order_store
+                    .get_all_orders()?
+                    .into_iter()
+                    .filter(|order| order.props.clone().into().status == OrderStatus::Opened)
+                    .collect();
+
+                let (equity, used_margin) = Self::equity_and_used_margin(
+                    &opened_orders,
+                    current_tick_price,
+                    trading_config,
+                );
+
+                if !trading_config.margin_call_is_triggered(equity, used_margin) {
+                    break;
+                }
+
+                let worst_order = opened_orders
+                    .iter()
+                    .filter_map(|order| {
+                        let trade = trading_config.open_trades.get(&order.id)?;
+                        let unrealized_profit = Self::unrealized_profit(
+                            &order.props.clone().into(),
+                            trade.open_fill_price(),
+                            current_tick_price,
+                            trading_config,
+                        );
+
+                        Some((order, unrealized_profit))
+                    })
+                    .min_by_key(|(_, unrealized_profit)| *unrealized_profit)
+                    .map(|(order, _)| order.clone());
+
+                let worst_order = match worst_order {
+                    Some(worst_order) => worst_order,
+                    None => break,
+                };
+
+                self.close_position(
+                    &worst_order,
+                    ClosePositionBy::MarginCall(current_tick_price),
+                    order_store,
+                    trading_config,
+                )?;
+
+                number_of_closed_positions += 1;
+            }
+
+            Ok(number_of_closed_positions)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,12 @@
 use super::*;
-use crate::{trading_engine, BacktestingBalances};
+use crate::{
+    trading_engine, ApplySpread, BacktestingBalances, BalanceIsDepleted, CloseReason, ClosedTrade,
+    ConversionRate, Exposure, OpenTrade, OrderRejectionReason, SpreadModel, TradeCosts,
+    TradeExcursions, Trades,
+};
 use base::entities::order::BasicOrderPrices;
+use base::entities::tick::{BidAskPrices, HistoricalTickPrice, UniversalTickPrice};
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc, Weekday};
 use std::collections::HashMap;
 
 #[derive(Default)]
@@ -215,6 +221,43 @@ fn open_position__buy_order_by_current_tick_price_without_spread__should_success
     assert_eq!(trading_config.trades, 1);
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn open_position__counters_at_i32_limit__should_not_overflow() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        units: i32::MAX as Units,
+        trades: i32::MAX as Trades,
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.20586)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.units, i32::MAX as Units + 3000);
+    assert_eq!(trading_config.trades, i32::MAX as Trades + 1);
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn open_position__sell_order_by_open_price_with_spread__should_successfully_open_position() {
@@ -292,6 +335,106 @@ fn open_position__sell_order_by_current_tick_price_with_spread__should_successfu
     assert_eq!(trading_config.trades, 1);
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn close_position__cross_pair_with_conversion_rate__should_convert_profit_to_account_currency() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        ..Default::default()
+    };
+
+    let gbpusd_rate = |symbol: &str| -> Result<ConversionRate> {
+        assert_eq!(symbol, "EURGBP");
+        Ok(dec!(1.25))
+    };
+
+    trading_config
+        .update_conversion_rate("EURGBP", &gbpusd_rate)
+        .unwrap();
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let order = order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order,
+            OpenPositionBy::CurrentTickPrice(dec!(0.85)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(0.86)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    // 100 GBP of profit converted at the GBPUSD rate
+    assert_eq!(trading_config.balances.real, dec!(10_125));
+    assert_eq!(trading_config.balances.processing, dec!(10_125));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_position__contract_size_is_set__should_multiply_profit_by_contract_size() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        contract_size: dec!(10),
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let order = order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order,
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.38100)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    // 10 points on 10_000 units of 10 contracts each
+    assert_eq!(trading_config.balances.real, dec!(10_100));
+    assert_eq!(trading_config.units, 0);
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn close_position__order_status_is_different_from_opened__should_return_error() {
@@ -368,16 +511,19 @@ fn close_position__all_positions_become_closed_with_zero_balance__should_return_
         )
         .unwrap();
 
-    assert!(trading_engine
+    let error = trading_engine
         .close_position(
             &order_store.get_order_by_id("1").unwrap().unwrap(),
             ClosePositionBy::StopLoss,
             &mut order_store,
             &mut trading_config,
         )
-        .unwrap_err()
-        .to_string()
-        .contains("real balance is less than or equal to zero: 0"))
+        .unwrap_err();
+
+    assert_eq!(
+        error.downcast_ref::<BalanceIsDepleted>(),
+        Some(&BalanceIsDepleted(dec!(0)))
+    );
 }
 
 #[test]
@@ -541,6 +687,90 @@ fn close_position__buy_order_by_current_tick_price_with_spread__should_successfu
     assert_eq!(trading_config.trades, 1);
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn open_and_close_position__buy_order_by_bid_ask__should_use_real_quotes_without_spread() {
+    let mut trading_config = BacktestingTradingEngineConfig::default();
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentBidAsk(BidAskPrices {
+                bid: dec!(1.20580),
+                ask: dec!(1.20600),
+            }),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(6382.00));
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentBidAsk(BidAskPrices {
+                bid: dec!(1.20700),
+                ask: dec!(1.20720),
+            }),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(10_003.00));
+    assert_eq!(trading_config.balances.real, dec!(10_003.00));
+    assert_eq!(trading_config.units, 0);
+    assert_eq!(trading_config.trades, 2);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn open_position__sell_order_by_bid_ask__should_use_real_bid_price() {
+    let mut trading_config = BacktestingTradingEngineConfig::default();
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Sell,
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentBidAsk(BidAskPrices {
+                bid: dec!(1.20580),
+                ask: dec!(1.20600),
+            }),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(13_617.40));
+    assert_eq!(trading_config.units, -3000);
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn close_position__sell_order_by_take_profit_with_spread__should_successfully_close_position() {
@@ -707,3 +937,1630 @@ fn close_position__there_are_still_opened_orders__should_not_update_real_balance
     assert_eq!(trading_config.units, 3000);
     assert_eq!(trading_config.trades, 1);
 }
+
+#[test]
+#[allow(non_snake_case)]
+fn exposure_by_direction__opened_pending_and_closed_orders__should_count_only_opened_orders() {
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let orders = [
+        ("1", OrderType::Buy, dec!(0.03), OrderStatus::Opened),
+        ("2", OrderType::Buy, dec!(0.02), OrderStatus::Opened),
+        ("3", OrderType::Sell, dec!(0.01), OrderStatus::Opened),
+        ("4", OrderType::Buy, dec!(0.05), OrderStatus::Pending),
+        ("5", OrderType::Sell, dec!(0.04), OrderStatus::Closed),
+    ];
+
+    for (id, r#type, volume, status) in orders {
+        order_store
+            .create_order(
+                String::from(id),
+                BasicOrderProperties {
+                    r#type,
+                    volume,
+                    status,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+
+    let exposure = trading_engine.exposure_by_direction(&order_store).unwrap();
+
+    assert_eq!(
+        exposure,
+        Exposure {
+            buy: 5000,
+            sell: 1000
+        }
+    );
+    assert_eq!(exposure.net(), 4000);
+    assert_eq!(exposure.gross(), 6000);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn open_position__current_time_is_inside_overlapping_spread_windows__should_use_widest_spread() {
+    let window_start = DateTime::from_utc(
+        NaiveDateTime::parse_from_str("03-06-2022 12:00", "%d-%m-%Y %H:%M").unwrap(),
+        Utc,
+    );
+
+    let mut trading_config = BacktestingTradingEngineConfig {
+        spread_schedule: vec![
+            (
+                window_start,
+                window_start + Duration::minutes(30),
+                dec!(0.00030),
+            ),
+            (
+                window_start + Duration::minutes(10),
+                window_start + Duration::minutes(20),
+                dec!(0.00060),
+            ),
+        ],
+        current_time: Some(window_start + Duration::minutes(15)),
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.20586)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(6381.52));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn current_spread__current_time_is_outside_spread_windows__should_return_base_spread() {
+    let window_start = DateTime::from_utc(
+        NaiveDateTime::parse_from_str("03-06-2022 12:00", "%d-%m-%Y %H:%M").unwrap(),
+        Utc,
+    );
+
+    let trading_config = BacktestingTradingEngineConfig {
+        spread_schedule: vec![(
+            window_start,
+            window_start + Duration::minutes(30),
+            dec!(0.00030),
+        )],
+        current_time: Some(window_start + Duration::minutes(30)),
+        ..Default::default()
+    };
+
+    assert_eq!(trading_config.current_spread(), trading_config.spread);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_position__excursions_are_updated_by_ticks__should_add_closed_trade_with_excursions() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Sell,
+                volume: dec!(0.03),
+                prices: BasicOrderPrices {
+                    open: dec!(1.38000),
+                    stop_loss: dec!(1.38500),
+                    take_profit: dec!(1.37000),
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::OpenPrice,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_config.update_trade_excursions(UniversalTickPrice::Historical(HistoricalTickPrice {
+        high: dec!(1.38200),
+        low: dec!(1.37900),
+        close: dec!(1.38100),
+    }));
+    trading_config.update_trade_excursions(UniversalTickPrice::Realtime(dec!(1.38300)));
+    trading_config.update_trade_excursions(UniversalTickPrice::Historical(HistoricalTickPrice {
+        high: dec!(1.37800),
+        low: dec!(1.37500),
+        close: dec!(1.37600),
+    }));
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.37400)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert!(trading_config.open_trades.is_empty());
+    assert_eq!(
+        trading_config.closed_trades,
+        vec![ClosedTrade {
+            order_id: String::from("1"),
+            r#type: OrderType::Sell,
+            volume: dec!(0.03),
+            open_price: dec!(1.38000),
+            close_price: dec!(1.37400),
+            open_time: None,
+            close_time: None,
+            open_candle_index: None,
+            close_candle_index: None,
+            excursions: TradeExcursions {
+                max_adverse: dec!(0.00300),
+                max_favorable: dec!(0.00600),
+            },
+            close_reason: CloseReason::Manual,
+            fill_slippage: dec!(0),
+            spread_cost: dec!(0),
+        }]
+    );
+    assert_eq!(
+        trading_config.average_max_adverse_excursion(),
+        Some(dec!(0.00300))
+    );
+    assert_eq!(
+        trading_config.average_max_favorable_excursion(),
+        Some(dec!(0.00600))
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_position__spread_is_applied_on_opening__should_measure_excursions_from_fill_price() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        spread: dec!(0.00010),
+        apply_spread: ApplySpread::EntryOnly,
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Sell,
+                volume: dec!(0.03),
+                prices: BasicOrderPrices {
+                    open: dec!(1.38000),
+                    stop_loss: dec!(1.38500),
+                    take_profit: dec!(1.37000),
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::OpenPrice,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(
+        trading_config.open_trades["1"].open_fill_price(),
+        dec!(1.37990)
+    );
+
+    trading_config.update_trade_excursions(UniversalTickPrice::Realtime(dec!(1.38300)));
+    trading_config.update_trade_excursions(UniversalTickPrice::Historical(HistoricalTickPrice {
+        high: dec!(1.37800),
+        low: dec!(1.37500),
+        close: dec!(1.37600),
+    }));
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.37400)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(
+        trading_config.closed_trades[0].excursions,
+        TradeExcursions {
+            max_adverse: dec!(0.00310),
+            max_favorable: dec!(0.00590),
+        }
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_position__closed_trades_are_not_kept__should_record_closed_trade_events() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        keep_closed_trades: false,
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                prices: BasicOrderPrices {
+                    open: dec!(1.38000),
+                    stop_loss: dec!(1.37900),
+                    take_profit: dec!(1.38500),
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::OpenPrice,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::StopLoss,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert!(trading_config.closed_trades.is_empty());
+
+    let closed_trade_events = trading_config.take_closed_trade_events();
+
+    assert_eq!(closed_trade_events.len(), 1);
+    assert_eq!(closed_trade_events[0].order_id, "1");
+    assert_eq!(closed_trade_events[0].close_reason, CloseReason::StopLoss);
+
+    assert!(trading_config.take_closed_trade_events().is_empty());
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn open_position__fractional_amount_of_units__should_compute_trade_value_by_fractional_quantity() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.000015),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38134)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(9997.93));
+    assert_eq!(trading_config.units, 1);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn open_position__fill_latency_is_set__should_fill_at_price_of_first_tick_after_latency() {
+    let signal_time = DateTime::<Utc>::from_utc(
+        NaiveDateTime::parse_from_str("17-05-2022 13:00", "%d-%m-%Y %H:%M").unwrap(),
+        Utc,
+    );
+
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        fill_latency: Some(Duration::minutes(1)),
+        current_time: Some(signal_time),
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(
+        order_store
+            .get_order_by_id("1")
+            .unwrap()
+            .unwrap()
+            .props
+            .status,
+        OrderStatus::Opened
+    );
+    assert_eq!(trading_config.balances.processing, dec!(10_000));
+    assert_eq!(trading_config.units, 0);
+
+    trading_config.current_time = Some(signal_time + Duration::seconds(30));
+    trading_engine
+        .fill_delayed_orders(dec!(1.38100), &mut trading_config)
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(10_000));
+
+    trading_config.current_time = Some(signal_time + Duration::minutes(1));
+    trading_engine
+        .fill_delayed_orders(dec!(1.38200), &mut trading_config)
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(5854));
+    assert_eq!(trading_config.units, 3000);
+    assert_eq!(trading_config.open_trades["1"].open_price, dec!(1.38000));
+    assert_eq!(
+        trading_config.open_trades["1"].open_fill_price(),
+        dec!(1.38200)
+    );
+    assert!(trading_config.delayed_fills.is_empty());
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_position__fill_latency_is_set__should_update_real_balance_after_delayed_fill() {
+    let mut trading_config = BacktestingTradingEngineConfig {
+        use_spread: false,
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    let signal_time = DateTime::<Utc>::from_utc(
+        NaiveDateTime::parse_from_str("17-05-2022 13:00", "%d-%m-%Y %H:%M").unwrap(),
+        Utc,
+    );
+
+    trading_config.fill_latency = Some(Duration::minutes(1));
+    trading_config.current_time = Some(signal_time);
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.38100)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.balances.real, dec!(10_000));
+
+    trading_config.current_time = Some(signal_time + Duration::minutes(2));
+    trading_engine
+        .fill_delayed_orders(dec!(1.38050), &mut trading_config)
+        .unwrap();
+
+    assert_eq!(trading_config.balances.processing, dec!(10_001.50));
+    assert_eq!(trading_config.balances.real, dec!(10_001.50));
+    assert_eq!(trading_config.closed_trades[0].close_price, dec!(1.38100));
+    assert_eq!(trading_config.closed_trades[0].fill_slippage, dec!(0.00050));
+    assert_eq!(trading_config.units, 0);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn fill_delayed_orders__fill_latency_with_spread__should_record_fill_prices_once() {
+    let signal_time = DateTime::<Utc>::from_utc(
+        NaiveDateTime::parse_from_str("17-05-2022 13:00", "%d-%m-%Y %H:%M").unwrap(),
+        Utc,
+    );
+
+    let mut trading_config = BacktestingTradingEngineConfig {
+        fill_latency: Some(Duration::minutes(1)),
+        current_time: Some(signal_time),
+        ..Default::default()
+    };
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    // the position is filled at the tick after the latency plus the half spread
+    trading_config.current_time = Some(signal_time + Duration::minutes(1));
+    trading_engine
+        .fill_delayed_orders(dec!(1.37950), &mut trading_config)
+        .unwrap();
+
+    assert_eq!(
+        trading_config.open_trades["1"].open_fill_price(),
+        dec!(1.37955)
+    );
+
+    trading_config.update_trade_excursions(UniversalTickPrice::Historical(HistoricalTickPrice {
+        high: dec!(1.38100),
+        low: dec!(1.37900),
+        close: dec!(1.38050),
+    }));
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.38050)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    // the position is closed at the tick after the latency minus the half spread
+    trading_config.current_time = Some(signal_time + Duration::minutes(2));
+    trading_engine
+        .fill_delayed_orders(dec!(1.38200), &mut trading_config)
+        .unwrap();
+
+    let closed_trade = &trading_config.closed_trades[0];
+
+    // the slippage of the opening is 1.37955 - 1.38000 in favor of the trade
+    let close_fill_slippage = closed_trade.fill_slippage - dec!(-0.00045);
+
+    assert_eq!(closed_trade.open_price, dec!(1.38000));
+    assert_eq!(closed_trade.close_price, dec!(1.38050));
+    assert_eq!(
+        closed_trade.close_price - close_fill_slippage,
+        dec!(1.38195)
+    );
+    assert_eq!(
+        closed_trade.excursions,
+        TradeExcursions {
+            max_adverse: dec!(0.00055),
+            max_favorable: dec!(0.00240),
+        }
+    );
+    assert_eq!(trading_config.balances.real, dec!(10_007.20));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn win_rate__profitable_and_losing_trades_of_both_types__should_return_share_of_profitable() {
+    let closed_trade = |r#type, open_price, close_price| ClosedTrade {
+        order_id: String::from("1"),
+        r#type,
+        volume: dec!(0.03),
+        open_price,
+        close_price,
+        open_time: None,
+        close_time: None,
+        open_candle_index: None,
+        close_candle_index: None,
+        excursions: Default::default(),
+        close_reason: CloseReason::Manual,
+        fill_slippage: dec!(0),
+        spread_cost: dec!(0),
+    };
+
+    let trading_config = BacktestingTradingEngineConfig {
+        closed_trades: vec![
+            closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.38100)),
+            closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.37900)),
+            closed_trade(OrderType::Sell, dec!(1.38000), dec!(1.37900)),
+            closed_trade(OrderType::Sell, dec!(1.38000), dec!(1.38100)),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(trading_config.win_rate(), Some(dec!(50)));
+    assert_eq!(BacktestingTradingEngineConfig::default().win_rate(), None);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn streaks__profitable_losing_and_break_even_trades__should_return_longest_and_current_streaks() {
+    let closed_trade = |close_price| ClosedTrade {
+        order_id: String::from("1"),
+        r#type: OrderType::Buy,
+        volume: dec!(0.03),
+        open_price: dec!(1.38000),
+        close_price,
+        open_time: None,
+        close_time: None,
+        open_candle_index: None,
+        close_candle_index: None,
+        excursions: Default::default(),
+        close_reason: CloseReason::Manual,
+        fill_slippage: dec!(0),
+        spread_cost: dec!(0),
+    };
+
+    let mut trading_config = BacktestingTradingEngineConfig {
+        closed_trades: vec![
+            closed_trade(dec!(1.38100)),
+            closed_trade(dec!(1.38100)),
+            closed_trade(dec!(1.37900)),
+            closed_trade(dec!(1.37900)),
+            closed_trade(dec!(1.38000)),
+            closed_trade(dec!(1.37900)),
+            closed_trade(dec!(1.38100)),
+            closed_trade(dec!(1.38100)),
+            closed_trade(dec!(1.38100)),
+            closed_trade(dec!(1.37900)),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(trading_config.max_win_streak(), 3);
+    // the break-even trade doesn't interrupt the streak
+    assert_eq!(trading_config.max_loss_streak(), 3);
+    assert_eq!(trading_config.current_streak(), -1);
+
+    trading_config.closed_trades.pop();
+
+    assert_eq!(trading_config.current_streak(), 3);
+
+    let trading_config = BacktestingTradingEngineConfig::default();
+
+    assert_eq!(trading_config.max_win_streak(), 0);
+    assert_eq!(trading_config.max_loss_streak(), 0);
+    assert_eq!(trading_config.current_streak(), 0);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn average_bars_in_trade__trades_opened_and_closed_on_different_candles__should_return_average_number_of_candles(
+) {
+    let mut trading_config = BacktestingTradingEngineConfig::default();
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    for (id, open_candle_index, close_candle_index) in [("1", 2, 5), ("2", 4, 12)] {
+        let order = order_store
+            .create_order(
+                String::from(id),
+                BasicOrderProperties {
+                    r#type: OrderType::Buy,
+                    volume: dec!(0.01),
+                    prices: BasicOrderPrices {
+                        open: dec!(1.38000),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        trading_config.current_candle_index = Some(open_candle_index);
+        trading_engine
+            .open_position(
+                &order,
+                OpenPositionBy::OpenPrice,
+                &mut order_store,
+                &mut trading_config,
+            )
+            .unwrap();
+
+        trading_config.current_candle_index = Some(close_candle_index);
+        trading_engine
+            .close_position(
+                &order_store.get_order_by_id(id).unwrap().unwrap(),
+                ClosePositionBy::CurrentTickPrice(dec!(1.38100)),
+                &mut order_store,
+                &mut trading_config,
+            )
+            .unwrap();
+    }
+
+    assert_eq!(trading_config.average_bars_in_trade(), Some(dec!(5.5)));
+    assert_eq!(
+        BacktestingTradingEngineConfig::default().average_bars_in_trade(),
+        None
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn expectancy__profitable_and_losing_trades__should_return_expected_profit_per_trade() {
+    let closed_trade = |r#type, open_price, close_price| ClosedTrade {
+        order_id: String::from("1"),
+        r#type,
+        volume: dec!(0.03),
+        open_price,
+        close_price,
+        open_time: None,
+        close_time: None,
+        open_candle_index: None,
+        close_candle_index: None,
+        excursions: Default::default(),
+        close_reason: CloseReason::Manual,
+        fill_slippage: dec!(0),
+        spread_cost: dec!(0),
+    };
+
+    let trading_config = BacktestingTradingEngineConfig {
+        closed_trades: vec![
+            closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.38300)),
+            closed_trade(OrderType::Sell, dec!(1.38000), dec!(1.37900)),
+            closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.37800)),
+            closed_trade(OrderType::Sell, dec!(1.38000), dec!(1.38000)),
+        ],
+        ..Default::default()
+    };
+
+    // win rate 0.5, average win 0.002, loss rate 0.25, average loss 0.002
+    assert_eq!(trading_config.expectancy(), Some(dec!(0.0005)));
+    assert_eq!(BacktestingTradingEngineConfig::default().expectancy(), None);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn trade_weighted_return__profitable_and_losing_trades__should_return_sum_of_trade_returns() {
+    let closed_trade = |r#type, open_price, close_price| ClosedTrade {
+        order_id: String::from("1"),
+        r#type,
+        volume: dec!(0.03),
+        open_price,
+        close_price,
+        open_time: None,
+        close_time: None,
+        open_candle_index: None,
+        close_candle_index: None,
+        excursions: Default::default(),
+        close_reason: CloseReason::Manual,
+        fill_slippage: dec!(0),
+        spread_cost: dec!(0),
+    };
+
+    let trading_config = BacktestingTradingEngineConfig {
+        closed_trades: vec![
+            closed_trade(OrderType::Buy, dec!(1.25000), dec!(1.30000)),
+            closed_trade(OrderType::Sell, dec!(1.25000), dec!(1.27500)),
+        ],
+        ..Default::default()
+    };
+
+    // 0.04 - 0.02
+    assert_eq!(trading_config.trade_weighted_return(), Some(dec!(0.02)));
+    assert_eq!(
+        BacktestingTradingEngineConfig::default().trade_weighted_return(),
+        None
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn kelly_fraction__profitable_and_losing_trades__should_return_fraction_by_win_rate_and_win_loss_ratio(
+) {
+    let closed_trade = |r#type, open_price, close_price| ClosedTrade {
+        order_id: String::from("1"),
+        r#type,
+        volume: dec!(0.03),
+        open_price,
+        close_price,
+        open_time: None,
+        close_time: None,
+        open_candle_index: None,
+        close_candle_index: None,
+        excursions: Default::default(),
+        close_reason: CloseReason::Manual,
+        fill_slippage: dec!(0),
+        spread_cost: dec!(0),
+    };
+
+    let mut trading_config = BacktestingTradingEngineConfig {
+        closed_trades: vec![
+            closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.38300)),
+            closed_trade(OrderType::Sell, dec!(1.38000), dec!(1.37900)),
+            closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.37900)),
+            closed_trade(OrderType::Sell, dec!(1.38000), dec!(1.38000)),
+        ],
+        ..Default::default()
+    };
+
+    // win rate 2/3 without the break-even trade, average win 0.002, average loss 0.001
+    assert_eq!(
+        trading_config.kelly_fraction().unwrap().round_dp(6),
+        dec!(0.5)
+    );
+    assert_eq!(trading_config.half_kelly().unwrap().round_dp(6), dec!(0.25));
+
+    // win rate 1/3, the win equals the loss, so there is no edge
+    trading_config.closed_trades = vec![
+        closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.38100)),
+        closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.37900)),
+        closed_trade(OrderType::Sell, dec!(1.38000), dec!(1.38100)),
+    ];
+
+    assert_eq!(trading_config.kelly_fraction(), None);
+    assert_eq!(trading_config.half_kelly(), None);
+
+    // no losing trades
+    trading_config.closed_trades = vec![closed_trade(OrderType::Buy, dec!(1.38000), dec!(1.38100))];
+
+    assert_eq!(trading_config.kelly_fraction(), None);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_position__different_modes_of_applying_spread__should_charge_corresponding_round_trip_cost()
+{
+    // the spread is 0.0001 and the quantity is 10_000, so the whole spread costs 1
+    for (apply_spread, open_cost, round_trip_cost) in [
+        (ApplySpread::EntryOnly, dec!(13801), dec!(1)),
+        (ApplySpread::EntryAndExit, dec!(13801), dec!(2)),
+        (ApplySpread::Split, dec!(13800.5), dec!(1)),
+    ] {
+        let mut trading_config = BacktestingTradingEngineConfig::builder()
+            .spread(dec!(0.0001))
+            .apply_spread(apply_spread)
+            .build()
+            .unwrap();
+
+        let initial_balance = trading_config.balances.initial;
+
+        let mut order_store = TestOrderStore::new();
+        let trading_engine = BacktestingTradingEngine::new();
+
+        order_store
+            .create_order(
+                String::from("1"),
+                BasicOrderProperties {
+                    r#type: OrderType::Buy,
+                    volume: dec!(0.1),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        trading_engine
+            .open_position(
+                &order_store.get_order_by_id("1").unwrap().unwrap(),
+                OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+                &mut order_store,
+                &mut trading_config,
+            )
+            .unwrap();
+
+        assert_eq!(
+            trading_config.balances.processing,
+            initial_balance - open_cost
+        );
+
+        trading_engine
+            .close_position(
+                &order_store.get_order_by_id("1").unwrap().unwrap(),
+                ClosePositionBy::CurrentTickPrice(dec!(1.38000)),
+                &mut order_store,
+                &mut trading_config,
+            )
+            .unwrap();
+
+        assert_eq!(
+            trading_config.balances.real,
+            initial_balance - round_trip_cost
+        );
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn open_and_close_position__different_spread_models__should_fill_by_corresponding_bid_and_ask() {
+    // the whole spread of 0.0001 is charged on both sides in the charged model,
+    // only the buys pay the spread in the one-sided model,
+    // and the fills are half of the spread away from the price in the symmetric model
+    for (spread_model, ask, bid) in [
+        (SpreadModel::Charged, dec!(1.38010), dec!(1.37990)),
+        (SpreadModel::OneSided, dec!(1.38010), dec!(1.38000)),
+        (
+            SpreadModel::SymmetricAroundMid,
+            dec!(1.38005),
+            dec!(1.37995),
+        ),
+    ] {
+        let mut trading_config = BacktestingTradingEngineConfig::builder()
+            .spread(dec!(0.0001))
+            .apply_spread(ApplySpread::EntryAndExit)
+            .spread_model(spread_model)
+            .build()
+            .unwrap();
+
+        let mut order_store = TestOrderStore::new();
+        let trading_engine = BacktestingTradingEngine::new();
+
+        // the quantity is 10_000, so the fill price is the change of the processing balance
+        // divided by 10_000
+        for (id, r#type, open_price, close_price) in [
+            ("1", OrderType::Buy, ask, bid),
+            ("2", OrderType::Sell, bid, ask),
+        ] {
+            order_store
+                .create_order(
+                    String::from(id),
+                    BasicOrderProperties {
+                        r#type,
+                        volume: dec!(0.1),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+
+            let balance_before_opening = trading_config.balances.processing;
+
+            trading_engine
+                .open_position(
+                    &order_store.get_order_by_id(id).unwrap().unwrap(),
+                    OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+                    &mut order_store,
+                    &mut trading_config,
+                )
+                .unwrap();
+
+            assert_eq!(
+                (trading_config.balances.processing - balance_before_opening).abs(),
+                open_price * dec!(10_000)
+            );
+
+            let balance_before_closing = trading_config.balances.processing;
+
+            trading_engine
+                .close_position(
+                    &order_store.get_order_by_id(id).unwrap().unwrap(),
+                    ClosePositionBy::CurrentTickPrice(dec!(1.38000)),
+                    &mut order_store,
+                    &mut trading_config,
+                )
+                .unwrap();
+
+            assert_eq!(
+                (trading_config.balances.processing - balance_before_closing).abs(),
+                close_price * dec!(10_000)
+            );
+        }
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn trades_by_close_reason__trades_closed_by_different_reasons__should_group_counts_and_profits() {
+    let closed_trade = |volume, close_price, close_reason| ClosedTrade {
+        order_id: String::from("1"),
+        r#type: OrderType::Buy,
+        volume,
+        open_price: dec!(1.38000),
+        close_price,
+        open_time: None,
+        close_time: None,
+        open_candle_index: None,
+        close_candle_index: None,
+        excursions: Default::default(),
+        close_reason,
+        fill_slippage: dec!(0),
+        spread_cost: dec!(0),
+    };
+
+    let trading_config = BacktestingTradingEngineConfig {
+        closed_trades: vec![
+            closed_trade(dec!(0.03), dec!(1.38300), CloseReason::TakeProfit),
+            closed_trade(dec!(0.01), dec!(1.38200), CloseReason::TakeProfit),
+            closed_trade(dec!(0.02), dec!(1.37900), CloseReason::StopLoss),
+            closed_trade(dec!(0.03), dec!(1.38050), CloseReason::EndOfData),
+        ],
+        conversion_rate: dec!(2),
+        ..Default::default()
+    };
+
+    // TakeProfit: (0.003 * 3000 + 0.002 * 1000) * 2, StopLoss: -0.001 * 2000 * 2,
+    // EndOfData: 0.0005 * 3000 * 2
+    assert_eq!(
+        trading_config.trades_by_close_reason(),
+        HashMap::from([
+            (CloseReason::TakeProfit, (2, dec!(22))),
+            (CloseReason::StopLoss, (1, dec!(-4))),
+            (CloseReason::EndOfData, (1, dec!(3))),
+        ])
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn performance_by_hour_and_weekday__trades_of_different_volumes__should_group_profits_by_open_time()
+{
+    let closed_trade = |open_time, volume, close_price| ClosedTrade {
+        order_id: String::from("1"),
+        r#type: OrderType::Buy,
+        volume,
+        open_price: dec!(1.38000),
+        close_price,
+        open_time,
+        close_time: None,
+        open_candle_index: None,
+        close_candle_index: None,
+        excursions: Default::default(),
+        close_reason: CloseReason::TakeProfit,
+        fill_slippage: dec!(0),
+        spread_cost: dec!(0),
+    };
+
+    // 2022-04-04 is Monday
+    let trading_config = BacktestingTradingEngineConfig {
+        closed_trades: vec![
+            closed_trade(
+                Some(Utc.ymd(2022, 4, 4).and_hms(9, 15, 0)),
+                dec!(0.01),
+                dec!(1.38300),
+            ),
+            closed_trade(
+                Some(Utc.ymd(2022, 4, 5).and_hms(9, 45, 0)),
+                dec!(0.05),
+                dec!(1.37900),
+            ),
+            closed_trade(
+                Some(Utc.ymd(2022, 4, 4).and_hms(14, 0, 0)),
+                dec!(0.02),
+                dec!(1.38200),
+            ),
+            closed_trade(None, dec!(0.03), dec!(1.38500)),
+        ],
+        ..Default::default()
+    };
+
+    // the larger losing trade outweighs the smaller winning one of the same hour:
+    // 0.003 * 1000 - 0.001 * 5000
+    assert_eq!(
+        trading_config.performance_by_hour(),
+        HashMap::from([(9, (2, dec!(-2))), (14, (1, dec!(4)))])
+    );
+
+    assert_eq!(
+        trading_config.performance_by_weekday(),
+        HashMap::from([(Weekday::Mon, (2, dec!(7))), (Weekday::Tue, (1, dec!(-5)))])
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn open_position__reject_probability__should_reject_orders_reproducibly_and_keep_them_pending() {
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let mut order_store = TestOrderStore::new();
+    let order = order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                volume: dec!(0.03),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let mut trading_config = BacktestingTradingEngineConfig::builder()
+        .reject_probability(dec!(1))
+        .build()
+        .unwrap();
+
+    let error = trading_engine
+        .open_position(
+            &order,
+            OpenPositionBy::OpenPrice,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        error.downcast_ref::<OrderRejectionReason>(),
+        Some(&OrderRejectionReason::Requote)
+    );
+    assert_eq!(
+        order_store
+            .get_order_by_id("1")
+            .unwrap()
+            .unwrap()
+            .props
+            .status,
+        OrderStatus::Pending
+    );
+    assert_eq!(trading_config.trades, 0);
+    assert!(trading_config.open_trades.is_empty());
+
+    let get_outcomes = |seed| {
+        let mut trading_config = BacktestingTradingEngineConfig::builder()
+            .reject_probability(dec!(0.5))
+            .rejection_seed(seed)
+            .build()
+            .unwrap();
+
+        (0..20)
+            .map(|_| {
+                let mut order_store = TestOrderStore::new();
+                let order = order_store
+                    .create_order(String::from("1"), order.props.clone())
+                    .unwrap();
+
+                trading_engine
+                    .open_position(
+                        &order,
+                        OpenPositionBy::OpenPrice,
+                        &mut order_store,
+                        &mut trading_config,
+                    )
+                    .is_ok()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let outcomes = get_outcomes(42);
+
+    assert_eq!(outcomes, get_outcomes(42));
+    assert!(outcomes.contains(&true));
+    assert!(outcomes.contains(&false));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn average_fill_slippage__instant_and_delayed_fills_with_spread__should_return_average_execution_cost_in_points(
+) {
+    let signal_time = DateTime::<Utc>::from_utc(
+        NaiveDateTime::parse_from_str("17-05-2022 13:00", "%d-%m-%Y %H:%M").unwrap(),
+        Utc,
+    );
+
+    let mut trading_config = BacktestingTradingEngineConfig::default();
+
+    assert_eq!(trading_config.average_fill_slippage(), None);
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    for (order_id, r#type) in [("1", OrderType::Buy), ("2", OrderType::Sell)] {
+        order_store
+            .create_order(
+                String::from(order_id),
+                BasicOrderProperties {
+                    r#type,
+                    volume: dec!(0.03),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+
+    // half of the spread is charged on each side of the instant fills
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.38200)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(trading_config.closed_trades[0].fill_slippage, dec!(0.00010));
+
+    // the price moves against the trade during the fill latency
+    trading_config.fill_latency = Some(Duration::minutes(1));
+    trading_config.current_time = Some(signal_time);
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("2").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_config.current_time = Some(signal_time + Duration::minutes(1));
+    trading_engine
+        .fill_delayed_orders(dec!(1.37950), &mut trading_config)
+        .unwrap();
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("2").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.37900)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_config.current_time = Some(signal_time + Duration::minutes(2));
+    trading_engine
+        .fill_delayed_orders(dec!(1.37920), &mut trading_config)
+        .unwrap();
+
+    assert_eq!(trading_config.closed_trades[1].fill_slippage, dec!(0.00080));
+    assert_eq!(trading_config.average_fill_slippage(), Some(dec!(45)));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn total_costs__instant_and_delayed_fills_with_spread__should_split_execution_costs_into_spread_and_slippage(
+) {
+    let signal_time = DateTime::<Utc>::from_utc(
+        NaiveDateTime::parse_from_str("17-05-2022 13:00", "%d-%m-%Y %H:%M").unwrap(),
+        Utc,
+    );
+
+    let mut trading_config = BacktestingTradingEngineConfig::default();
+
+    assert_eq!(trading_config.total_costs(), TradeCosts::default());
+    assert_eq!(trading_config.total_cost_per_trade(), None);
+    assert_eq!(trading_config.max_cost_per_trade(), None);
+
+    let mut order_store = TestOrderStore::new();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    for (order_id, r#type) in [("1", OrderType::Buy), ("2", OrderType::Sell)] {
+        order_store
+            .create_order(
+                String::from(order_id),
+                BasicOrderProperties {
+                    r#type,
+                    volume: dec!(0.03),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+
+    // half of the spread is charged on each side of the instant fills
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("1").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.38200)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    // the price moves against the trade during the fill latency
+    trading_config.fill_latency = Some(Duration::minutes(1));
+    trading_config.current_time = Some(signal_time);
+
+    trading_engine
+        .open_position(
+            &order_store.get_order_by_id("2").unwrap().unwrap(),
+            OpenPositionBy::CurrentTickPrice(dec!(1.38000)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_config.current_time = Some(signal_time + Duration::minutes(1));
+    trading_engine
+        .fill_delayed_orders(dec!(1.37950), &mut trading_config)
+        .unwrap();
+
+    trading_engine
+        .close_position(
+            &order_store.get_order_by_id("2").unwrap().unwrap(),
+            ClosePositionBy::CurrentTickPrice(dec!(1.37900)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_config.current_time = Some(signal_time + Duration::minutes(2));
+    trading_engine
+        .fill_delayed_orders(dec!(1.37920), &mut trading_config)
+        .unwrap();
+
+    // the half spread of 5 points is charged on each side of both trades,
+    // and the price moves against the second trade by 70 points during the fill latency
+    assert_eq!(
+        trading_config.total_costs(),
+        TradeCosts {
+            spread: dec!(20),
+            slippage: dec!(70),
+        }
+    );
+    assert_eq!(trading_config.total_cost_per_trade(), Some(dec!(45)));
+    assert_eq!(trading_config.max_cost_per_trade(), Some(dec!(80)));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn open_position__max_total_risk_fraction__should_reject_orders_until_opened_position_is_closed() {
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let mut order_store = TestOrderStore::new();
+
+    // the risk of each order is 0.01 * 3000 = 30
+    let first_order = order_store
+        .create_order(String::from("1"), Default::default())
+        .unwrap();
+    let second_order = order_store
+        .create_order(String::from("2"), Default::default())
+        .unwrap();
+
+    let mut trading_config = BacktestingTradingEngineConfig::builder()
+        .initial_balance(dec!(1_000))
+        .max_total_risk_fraction(dec!(0.05))
+        .use_spread(false)
+        .build()
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &first_order,
+            OpenPositionBy::OpenPrice,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    let error = trading_engine
+        .open_position(
+            &second_order,
+            OpenPositionBy::OpenPrice,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        error.downcast_ref::<OrderRejectionReason>(),
+        Some(&OrderRejectionReason::MaxTotalRisk)
+    );
+    assert_eq!(
+        order_store
+            .get_order_by_id("2")
+            .unwrap()
+            .unwrap()
+            .props
+            .status,
+        OrderStatus::Pending
+    );
+
+    let first_order = order_store.get_order_by_id("1").unwrap().unwrap();
+    trading_engine
+        .close_position(
+            &first_order,
+            ClosePositionBy::StopLoss,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &second_order,
+            OpenPositionBy::OpenPrice,
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    assert_eq!(
+        order_store
+            .get_order_by_id("2")
+            .unwrap()
+            .unwrap()
+            .props
+            .status,
+        OrderStatus::Opened
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_positions_by_margin_call__sharp_move_against_positions__should_close_worst_positions_until_margin_is_restored(
+) {
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let mut order_store = TestOrderStore::new();
+
+    let big_order = order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                volume: dec!(0.1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let small_order = order_store
+        .create_order(
+            String::from("2"),
+            BasicOrderProperties {
+                volume: dec!(0.01),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let mut trading_config = BacktestingTradingEngineConfig::builder()
+        .initial_balance(dec!(1_000))
+        .margin_call_level(dec!(1))
+        .use_spread(false)
+        .build()
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &big_order,
+            OpenPositionBy::CurrentTickPrice(dec!(1.38)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+    trading_engine
+        .open_position(
+            &small_order,
+            OpenPositionBy::CurrentTickPrice(dec!(1.30)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    // the equity is 1000 - 100 + 70 = 970, the used margin is 138 + 13 = 151
+    assert_eq!(
+        trading_engine
+            .close_positions_by_margin_call(dec!(1.37), &mut order_store, &mut trading_config)
+            .unwrap(),
+        0
+    );
+
+    // the equity is 1000 - 900 - 10 = 90, the used margin is 151,
+    // after closing the big order the used margin is 13
+    assert_eq!(
+        trading_engine
+            .close_positions_by_margin_call(dec!(1.29), &mut order_store, &mut trading_config)
+            .unwrap(),
+        1
+    );
+
+    let order_status = |id| {
+        order_store
+            .get_order_by_id(id)
+            .unwrap()
+            .unwrap()
+            .props
+            .status
+    };
+
+    assert_eq!(order_status("1"), OrderStatus::Closed);
+    assert_eq!(order_status("2"), OrderStatus::Opened);
+
+    assert_eq!(trading_config.closed_trades.len(), 1);
+    assert_eq!(trading_config.closed_trades[0].order_id, "1");
+    assert_eq!(
+        trading_config.closed_trades[0].close_reason,
+        CloseReason::MarginCall
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_positions_by_margin_call__requested_and_fill_prices_differ__should_close_worst_position_by_fill_price(
+) {
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let mut order_store = TestOrderStore::new();
+
+    for id in ["1", "2"] {
+        order_store
+            .create_order(
+                id.to_string(),
+                BasicOrderProperties {
+                    volume: dec!(0.01),
+                    status: OrderStatus::Opened,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+
+    let mut trading_config = BacktestingTradingEngineConfig::builder()
+        .initial_balance(dec!(2_000))
+        .margin_call_level(dec!(100))
+        .use_spread(false)
+        .build()
+        .unwrap();
+
+    let open_trade = |open_price, open_fill_slippage| OpenTrade {
+        r#type: OrderType::Buy,
+        volume: dec!(0.01),
+        open_price,
+        open_time: None,
+        open_candle_index: None,
+        excursions: Default::default(),
+        open_fill_slippage,
+        open_spread_cost: dec!(0),
+    };
+
+    // the first position is requested at the better price, but filled at the worse one
+    trading_config
+        .open_trades
+        .insert(String::from("1"), open_trade(dec!(1.30), dec!(0.02)));
+    trading_config
+        .open_trades
+        .insert(String::from("2"), open_trade(dec!(1.31), dec!(0)));
+
+    // the fill values are 1320 and 1310
+    trading_config.balances.processing = dec!(-630);
+
+    // the equity is -630 + 1310 + 1310 = 1990, the used margin is (13 + 13.1) * 100 = 2610,
+    // after closing the first position the equity is 1990 and the used margin is 1310
+    assert_eq!(
+        trading_engine
+            .close_positions_by_margin_call(dec!(1.31), &mut order_store, &mut trading_config)
+            .unwrap(),
+        1
+    );
+
+    assert_eq!(trading_config.closed_trades.len(), 1);
+    assert_eq!(trading_config.closed_trades[0].order_id, "1");
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn close_positions_by_margin_call__closing_depletes_balance__should_return_balance_is_depleted_error_and_restore_fill_latency(
+) {
+    let trading_engine = BacktestingTradingEngine::new();
+
+    let mut order_store = TestOrderStore::new();
+
+    let order = order_store
+        .create_order(
+            String::from("1"),
+            BasicOrderProperties {
+                volume: dec!(0.1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let mut trading_config = BacktestingTradingEngineConfig::builder()
+        .initial_balance(dec!(1_000))
+        .margin_call_level(dec!(1))
+        .use_spread(false)
+        .build()
+        .unwrap();
+
+    trading_engine
+        .open_position(
+            &order,
+            OpenPositionBy::CurrentTickPrice(dec!(1.38)),
+            &mut order_store,
+            &mut trading_config,
+        )
+        .unwrap();
+
+    trading_config.fill_latency = Some(Duration::seconds(5));
+
+    // the loss of the position is 10000 * 0.18 = 1800
+    let error = trading_engine
+        .close_positions_by_margin_call(dec!(1.20), &mut order_store, &mut trading_config)
+        .unwrap_err();
+
+    assert_eq!(
+        error.downcast_ref::<BalanceIsDepleted>(),
+        Some(&BalanceIsDepleted(dec!(-800)))
+    );
+    assert_eq!(trading_config.fill_latency, Some(Duration::seconds(5)));
+    assert_eq!(
+        trading_config.closed_trades[0].close_reason,
+        CloseReason::MarginCall
+    );
+}
@@ -6,7 +6,10 @@ use crate::entities::order::OrderType;
 use crate::helpers::Holiday;
 use anyhow::Result;
 pub use candle::{CandlePrices, CandleType};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 pub use tick::BasicTickProperties;
 
@@ -15,13 +18,13 @@ pub const LOT: u32 = 100_000;
 pub const CANDLE_PRICE_DECIMAL_PLACES: u32 = 5;
 pub const SIGNIFICANT_DECIMAL_PLACES: u32 = 2;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Level {
     Min = -1,
     Max = 1,
 }
 
-#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Tendency {
     #[default]
     Unknown = 0,
@@ -53,8 +56,9 @@ pub enum Edge {
     Low = -1,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Timeframe {
+    FourHours = 240,
     Hour = 60,
     ThirtyMin = 30,
     FifteenMin = 15,
@@ -62,6 +66,14 @@ pub enum Timeframe {
     FiveMin = 5,
 }
 
+impl Timeframe {
+    pub fn duration(&self) -> Duration {
+        Duration::minutes(*self as i64)
+    }
+}
+
+/// The exact inverse of the `Display` implementation, so the timeframes
+/// can be parsed back from the path names of the historical data.
 impl FromStr for Timeframe {
     type Err = anyhow::Error;
 
@@ -72,6 +84,7 @@ impl FromStr for Timeframe {
             "15m" => Ok(Self::FifteenMin),
             "30m" => Ok(Self::ThirtyMin),
             "1h" => Ok(Self::Hour),
+            "4h" => Ok(Self::FourHours),
             _ => anyhow::bail!("Invalid timeframe: {}", input),
         }
     }
@@ -80,6 +93,7 @@ impl FromStr for Timeframe {
 impl Display for Timeframe {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match *self {
+            Timeframe::FourHours => write!(f, "4h"),
             Timeframe::Hour => write!(f, "1h"),
             Timeframe::ThirtyMin => write!(f, "30m"),
             Timeframe::FifteenMin => write!(f, "15m"),
@@ -95,12 +109,28 @@ pub struct StrategyTimeframes {
     pub tick: Timeframe,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// Items are identified only by their ids, so two items with the same id are equal
+/// regardless of their properties.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item<I, P> {
     pub id: I,
     pub props: P,
 }
 
+impl<I: PartialEq, P> PartialEq for Item<I, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<I: Eq, P> Eq for Item<I, P> {}
+
+impl<I: Hash, P> Hash for Item<I, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 pub const CANDLE_TIMEFRAME_ENV: &str = "CANDLE_TIMEFRAME";
 pub const TICK_TIMEFRAME_ENV: &str = "TICK_TIMEFRAME";
 
@@ -123,3 +153,68 @@ where
 pub trait MyInto<T> {
     fn my_into(self) -> T;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn item__same_ids_and_different_props__should_be_equal() {
+        let first_item = Item { id: "1", props: 1 };
+        let second_item = Item { id: "1", props: 2 };
+
+        assert_eq!(first_item, second_item);
+        assert_ne!(first_item, Item { id: "2", props: 1 });
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn item__hash_set_operations__should_be_based_on_ids() {
+        let previous_items: HashSet<_> = [
+            Item { id: "1", props: 1 },
+            Item { id: "2", props: 2 },
+            Item { id: "2", props: 3 },
+        ]
+        .into_iter()
+        .collect();
+
+        let current_items: HashSet<_> = [Item { id: "2", props: 4 }].into_iter().collect();
+
+        assert_eq!(previous_items.len(), 2);
+        assert_eq!(
+            previous_items
+                .difference(&current_items)
+                .map(|item| item.id)
+                .collect::<Vec<_>>(),
+            vec!["1"]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn timeframe__display_and_parse__should_round_trip_every_variant() {
+        for timeframe in [
+            Timeframe::FourHours,
+            Timeframe::Hour,
+            Timeframe::ThirtyMin,
+            Timeframe::FifteenMin,
+            Timeframe::OneMin,
+            Timeframe::FiveMin,
+        ] {
+            assert_eq!(
+                Timeframe::from_str(&timeframe.to_string()).unwrap(),
+                timeframe
+            );
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn timeframe__unknown_string__should_return_error() {
+        for input in ["", "2h", "1H", "60", " 1h"] {
+            assert!(Timeframe::from_str(input).is_err());
+        }
+    }
+}
@@ -31,7 +31,42 @@ pub type CandleTime = NaiveDateTime;
 
 pub type CandlePrice = Decimal;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The max ratio of the candle body to the candle range
+/// for the candle to be considered neutral (doji).
+pub type DojiThreshold = Decimal;
+
+/// Only the candles with equal open and close prices are neutral by default.
+pub const DEFAULT_DOJI_THRESHOLD: DojiThreshold = dec!(0);
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum PriceError {
+    #[error("high price {high} is lower than low price {low}")]
+    HighBelowLow { high: CandlePrice, low: CandlePrice },
+    #[error("open price {open} is out of the range [{low}, {high}]")]
+    OpenOutOfRange {
+        open: CandlePrice,
+        low: CandlePrice,
+        high: CandlePrice,
+    },
+    #[error("close price {close} is out of the range [{low}, {high}]")]
+    CloseOutOfRange {
+        close: CandlePrice,
+        low: CandlePrice,
+        high: CandlePrice,
+    },
+}
+
+/// Defines what to do with the candles having inconsistent prices.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PriceValidationMode {
+    /// The inconsistency is logged, but the candle is accepted.
+    #[default]
+    Warn,
+    /// The candle is rejected with an error.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CandlePrices {
     pub open: CandlePrice,
     pub high: CandlePrice,
@@ -39,6 +74,66 @@ pub struct CandlePrices {
     pub close: CandlePrice,
 }
 
+impl CandlePrices {
+    /// Checks that the low price doesn't exceed the high price
+    /// and the open and close prices are within the range between them.
+    pub fn validate(&self) -> Result<(), PriceError> {
+        let (low, high) = (self.low, self.high);
+
+        if low > high {
+            return Err(PriceError::HighBelowLow { high, low });
+        }
+
+        if self.open < low || self.open > high {
+            return Err(PriceError::OpenOutOfRange {
+                open: self.open,
+                low,
+                high,
+            });
+        }
+
+        if self.close < low || self.close > high {
+            return Err(PriceError::CloseOutOfRange {
+                close: self.close,
+                low,
+                high,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The middle of the candle range: (high + low) / 2.
+    pub fn midpoint(&self) -> CandlePrice {
+        (self.high + self.low) / dec!(2)
+    }
+
+    /// The average of the high, low and close prices: (high + low + close) / 3.
+    pub fn typical_price(&self) -> CandlePrice {
+        (self.high + self.low + self.close) / dec!(3)
+    }
+
+    /// Classifies the candle using the default doji threshold.
+    pub fn candle_type(&self) -> CandleType {
+        self.candle_type_with_doji_threshold(DEFAULT_DOJI_THRESHOLD)
+    }
+
+    /// Classifies the candle by its open and close prices. The candle is neutral
+    /// when the ratio of its body to its range doesn't exceed the doji threshold.
+    pub fn candle_type_with_doji_threshold(&self, doji_threshold: DojiThreshold) -> CandleType {
+        let body = self.close - self.open;
+        let range = self.high - self.low;
+
+        if body == dec!(0) || (range > dec!(0) && body.abs() / range <= doji_threshold) {
+            CandleType::Neutral
+        } else if body > dec!(0) {
+            CandleType::Green
+        } else {
+            CandleType::Red
+        }
+    }
+}
+
 impl Default for CandlePrices {
     fn default() -> Self {
         Self {
@@ -50,7 +145,7 @@ impl Default for CandlePrices {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BasicCandleProperties {
     pub time: CandleTime,
     pub r#type: CandleType,
@@ -65,6 +160,12 @@ impl AsRef<BasicCandleProperties> for BasicCandleProperties {
     }
 }
 
+impl AsMut<BasicCandleProperties> for BasicCandleProperties {
+    fn as_mut(&mut self) -> &mut BasicCandleProperties {
+        self
+    }
+}
+
 impl Default for BasicCandleProperties {
     fn default() -> Self {
         Self {
@@ -81,6 +182,117 @@ impl Default for BasicCandleProperties {
 mod tests {
     use super::*;
 
+    #[test]
+    #[allow(non_snake_case)]
+    fn midpoint__candle_prices__should_return_middle_of_range() {
+        let prices = CandlePrices {
+            open: dec!(1.38050),
+            high: dec!(1.38200),
+            low: dec!(1.38000),
+            close: dec!(1.38100),
+        };
+
+        assert_eq!(prices.midpoint(), dec!(1.38100));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn typical_price__candle_prices__should_return_average_of_high_low_and_close() {
+        let prices = CandlePrices {
+            open: dec!(1.38050),
+            high: dec!(1.38200),
+            low: dec!(1.38000),
+            close: dec!(1.38100),
+        };
+
+        assert_eq!(prices.typical_price(), dec!(1.38100));
+
+        let prices = CandlePrices {
+            close: dec!(1.38160),
+            ..prices
+        };
+
+        assert_eq!(prices.typical_price(), dec!(1.38120));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn validate__consistent_prices__should_return_ok() {
+        assert_eq!(CandlePrices::default().validate(), Ok(()));
+
+        let flat_candle = CandlePrices {
+            open: dec!(1.38),
+            high: dec!(1.38),
+            low: dec!(1.38),
+            close: dec!(1.38),
+        };
+
+        assert_eq!(flat_candle.validate(), Ok(()));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn validate__high_below_low__should_return_error() {
+        let prices = CandlePrices {
+            open: dec!(1.38),
+            high: dec!(1.37),
+            low: dec!(1.39),
+            close: dec!(1.38),
+        };
+
+        assert_eq!(
+            prices.validate(),
+            Err(PriceError::HighBelowLow {
+                high: dec!(1.37),
+                low: dec!(1.39)
+            })
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn validate__open_out_of_range__should_return_error() {
+        for open in [dec!(1.36), dec!(1.40)] {
+            let prices = CandlePrices {
+                open,
+                high: dec!(1.39),
+                low: dec!(1.37),
+                close: dec!(1.38),
+            };
+
+            assert_eq!(
+                prices.validate(),
+                Err(PriceError::OpenOutOfRange {
+                    open,
+                    low: dec!(1.37),
+                    high: dec!(1.39)
+                })
+            );
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn validate__close_out_of_range__should_return_error() {
+        for close in [dec!(1.36), dec!(1.40)] {
+            let prices = CandlePrices {
+                open: dec!(1.38),
+                high: dec!(1.39),
+                low: dec!(1.37),
+                close,
+            };
+
+            assert_eq!(
+                prices.validate(),
+                Err(PriceError::CloseOutOfRange {
+                    close,
+                    low: dec!(1.37),
+                    high: dec!(1.39)
+                })
+            );
+        }
+    }
+
     #[test]
     fn should_covert_candle_to_green_type() {
         let candle_open_close = CandlePrices {
@@ -113,4 +325,80 @@ mod tests {
 
         assert_eq!(CandleType::from(&candle_open_close), CandleType::Red);
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_type__clear_bullish_candle__should_return_green() {
+        let prices = CandlePrices {
+            open: dec!(1.38000),
+            high: dec!(1.38110),
+            low: dec!(1.37990),
+            close: dec!(1.38100),
+        };
+
+        assert_eq!(
+            prices.candle_type_with_doji_threshold(dec!(0.1)),
+            CandleType::Green
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_type__clear_bearish_candle__should_return_red() {
+        let prices = CandlePrices {
+            open: dec!(1.38100),
+            high: dec!(1.38110),
+            low: dec!(1.37990),
+            close: dec!(1.38000),
+        };
+
+        assert_eq!(
+            prices.candle_type_with_doji_threshold(dec!(0.1)),
+            CandleType::Red
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_type__body_to_range_ratio_equals_threshold__should_return_neutral() {
+        let prices = CandlePrices {
+            open: dec!(1.38000),
+            high: dec!(1.38050),
+            low: dec!(1.37950),
+            close: dec!(1.38010),
+        };
+
+        assert_eq!(
+            prices.candle_type_with_doji_threshold(dec!(0.1)),
+            CandleType::Neutral
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_type__body_to_range_ratio_exceeds_threshold__should_return_red() {
+        let prices = CandlePrices {
+            open: dec!(1.38000),
+            high: dec!(1.38050),
+            low: dec!(1.37950),
+            close: dec!(1.37989),
+        };
+
+        assert_eq!(
+            prices.candle_type_with_doji_threshold(dec!(0.1)),
+            CandleType::Red
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_type__default_threshold__should_match_conversion_from_prices() {
+        let prices = CandlePrices {
+            open: dec!(1.38),
+            close: dec!(1.38001),
+            ..Default::default()
+        };
+
+        assert_eq!(prices.candle_type(), CandleType::from(&prices));
+    }
 }
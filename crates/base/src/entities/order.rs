@@ -1,9 +1,10 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 
 pub type OrderId = String;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum OrderType {
     Buy = 1,
     Sell = -1,
@@ -11,7 +12,7 @@ pub enum OrderType {
 
 pub type OrderPrice = Decimal;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BasicOrderPrices {
     pub open: OrderPrice,
     pub stop_loss: OrderPrice,
@@ -28,7 +29,7 @@ impl Default for BasicOrderPrices {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum OrderStatus {
     Pending = 0,
     Opened = 1,
@@ -43,7 +44,7 @@ impl Default for OrderStatus {
 
 pub type OrderVolume = Decimal;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BasicOrderProperties {
     pub r#type: OrderType,
     pub volume: OrderVolume,
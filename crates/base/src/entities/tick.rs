@@ -22,6 +22,63 @@ pub enum UniversalTickPrice {
     Realtime(TickPrice),
 }
 
+impl UniversalTickPrice {
+    /// The high price of the historical tick or the price of the realtime tick.
+    pub fn highest(&self) -> TickPrice {
+        match self {
+            UniversalTickPrice::Historical(price) => price.high,
+            UniversalTickPrice::Realtime(price) => *price,
+        }
+    }
+
+    /// The low price of the historical tick or the price of the realtime tick.
+    pub fn lowest(&self) -> TickPrice {
+        match self {
+            UniversalTickPrice::Historical(price) => price.low,
+            UniversalTickPrice::Realtime(price) => *price,
+        }
+    }
+
+    /// The close price of the historical tick or the price of the realtime tick.
+    pub fn close(&self) -> TickPrice {
+        match self {
+            UniversalTickPrice::Historical(price) => price.close,
+            UniversalTickPrice::Realtime(price) => *price,
+        }
+    }
+}
+
+/// The kind of the tick prices the strategy is run on.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum TickPriceMode {
+    #[default]
+    Historical,
+    Realtime,
+}
+
+impl TickPriceMode {
+    /// Returns an error if the variant of the price doesn't match the mode, because
+    /// the historical and realtime prices are handled differently and mixing them
+    /// makes the results subtly wrong.
+    pub fn ensure_matches(&self, price: &UniversalTickPrice) -> anyhow::Result<()> {
+        let price_mode = match price {
+            UniversalTickPrice::Historical(_) => TickPriceMode::Historical,
+            UniversalTickPrice::Realtime(_) => TickPriceMode::Realtime,
+        };
+
+        if price_mode != *self {
+            anyhow::bail!(
+                "the {:?} tick price {:?} doesn't match the {:?} tick price mode",
+                price_mode,
+                price,
+                self
+            );
+        }
+
+        Ok(())
+    }
+}
+
 impl Default for UniversalTickPrice {
     fn default() -> Self {
         Self::Realtime(dec!(1.38000))
@@ -40,10 +97,26 @@ impl From<TickPrice> for UniversalTickPrice {
     }
 }
 
+/// The real bid and ask prices of the realtime tick.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct BidAskPrices {
+    pub bid: TickPrice,
+    pub ask: TickPrice,
+}
+
+impl From<&BasicTickProperties<TickPrice>> for BidAskPrices {
+    fn from(tick: &BasicTickProperties<TickPrice>) -> Self {
+        Self {
+            bid: tick.bid,
+            ask: tick.ask,
+        }
+    }
+}
+
 pub type TickId = String;
 pub type TickTime = NaiveDateTime;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BasicTickProperties<P> {
     pub time: TickTime,
     pub ask: P,
@@ -1,7 +1,11 @@
-use chrono::{Datelike, Duration, NaiveDateTime, Weekday};
-use rust_decimal::Decimal;
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Utc, Weekday};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
 
-use crate::entities::LOT;
+use crate::entities::candle::{BasicCandleProperties, CandleVolatility};
+use crate::entities::order::{OrderType, OrderVolume};
+use crate::entities::{Timeframe, CANDLE_PRICE_DECIMAL_PLACES, LOT, SIGNIFICANT_DECIMAL_PLACES};
 
 pub type PointValue = Decimal;
 pub type PriceValue = Decimal;
@@ -14,11 +18,128 @@ pub fn price_to_points(price: PriceValue) -> PointValue {
     price * Decimal::from(LOT)
 }
 
+/// Returns the distance in points between two prices. The distance is positive when the price
+/// moved from `from` to `to` in the crossing direction of the level of the given type
+/// (down for buy levels, up for sell levels) and negative otherwise.
+pub fn signed_points(from: PriceValue, to: PriceValue, order_type: OrderType) -> PointValue {
+    match order_type {
+        OrderType::Buy => price_to_points(from - to),
+        OrderType::Sell => price_to_points(to - from),
+    }
+}
+
 pub fn mean(numbers: &[Decimal]) -> Decimal {
     let sum: Decimal = numbers.iter().sum();
     sum / Decimal::from(numbers.len())
 }
 
+pub type RoundingMode = RoundingStrategy;
+pub type DecimalPlaces = u32;
+
+/// Rounding of the computed prices and trade values. The default one rounds prices
+/// to the candle price precision with the banker's rounding.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PriceRounding {
+    pub mode: RoundingMode,
+    pub decimal_places: DecimalPlaces,
+}
+
+impl Default for PriceRounding {
+    fn default() -> Self {
+        Self {
+            mode: RoundingMode::MidpointNearestEven,
+            decimal_places: CANDLE_PRICE_DECIMAL_PLACES,
+        }
+    }
+}
+
+impl PriceRounding {
+    pub fn new(mode: RoundingMode, decimal_places: DecimalPlaces) -> Self {
+        Self {
+            mode,
+            decimal_places,
+        }
+    }
+
+    pub fn round_price(&self, price: PriceValue) -> PriceValue {
+        price.round_dp_with_strategy(self.decimal_places, self.mode)
+    }
+
+    /// Rounds the trade values and balances to the significant decimal places.
+    pub fn round_value(&self, value: Decimal) -> Decimal {
+        value.round_dp_with_strategy(SIGNIFICANT_DECIMAL_PLACES, self.mode)
+    }
+}
+
+/// Rounding of the computed volumes to the lot step accepted by the broker.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LotRounding {
+    pub step: OrderVolume,
+    /// The min volume accepted by the broker.
+    pub min: OrderVolume,
+}
+
+impl Default for LotRounding {
+    fn default() -> Self {
+        Self {
+            step: dec!(0.01),
+            min: dec!(0.01),
+        }
+    }
+}
+
+impl LotRounding {
+    pub fn new(step: OrderVolume, min: OrderVolume) -> Self {
+        Self { step, min }
+    }
+
+    /// Rounds the volume down to the nearest lot step.
+    /// Returns `None` if the rounded volume is zero or less than the min volume.
+    pub fn round_volume(&self, volume: OrderVolume) -> Option<OrderVolume> {
+        let rounded_volume = ((volume / self.step).floor() * self.step).normalize();
+
+        if rounded_volume <= dec!(0) || rounded_volume < self.min {
+            return None;
+        }
+
+        Some(rounded_volume)
+    }
+}
+
+/// Computes the volatility as the average true range of the last `window` candles.
+/// The true range of a candle is the largest of its high-low range and the distances
+/// from the previous close to its high and low. The result is in points rounded
+/// to the nearest integer. Returns 0 if there are no candles.
+pub fn compute_volatility(candles: &[BasicCandleProperties], window: usize) -> CandleVolatility {
+    let start = candles.len().saturating_sub(window);
+
+    let true_ranges: Vec<_> = (start..candles.len())
+        .map(|i| {
+            let prices = &candles[i].prices;
+            let range = prices.high - prices.low;
+
+            let true_range = match i.checked_sub(1) {
+                Some(previous) => {
+                    let previous_close = candles[previous].prices.close;
+
+                    range
+                        .max((prices.high - previous_close).abs())
+                        .max((prices.low - previous_close).abs())
+                }
+                None => range,
+            };
+
+            price_to_points(true_range)
+        })
+        .collect();
+
+    if true_ranges.is_empty() {
+        return 0;
+    }
+
+    mean(&true_ranges).round().to_u32().unwrap()
+}
+
 type Day = u32;
 type Month = u32;
 
@@ -69,11 +190,71 @@ pub fn exclude_weekend_and_holidays(
     days_to_exclude
 }
 
+/// Returns the open times of the candles of the given timeframe within `[start, end)`.
+/// The first open time is aligned to the timeframe boundary, e.g. hour candles open on the hour.
+pub fn candle_open_times(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    timeframe: Timeframe,
+) -> impl Iterator<Item = DateTime<Utc>> {
+    let step = timeframe.duration();
+    let step_in_seconds = step.num_seconds();
+
+    let aligned_start_in_seconds =
+        (start.timestamp() + step_in_seconds - 1).div_euclid(step_in_seconds) * step_in_seconds;
+    let first_open_time = Utc.timestamp(aligned_start_in_seconds, 0);
+
+    std::iter::successors(Some(first_open_time), move |open_time| {
+        Some(*open_time + step)
+    })
+    .take_while(move |open_time| *open_time < end)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::entities::CandlePrices;
     use chrono::NaiveDate;
 
+    #[test]
+    #[allow(non_snake_case)]
+    fn round_price__different_rounding_modes__should_round_midpoint_accordingly() {
+        let price = dec!(1.234565);
+
+        assert_eq!(PriceRounding::default().round_price(price), dec!(1.23456));
+        assert_eq!(
+            PriceRounding::new(RoundingMode::MidpointAwayFromZero, 5).round_price(price),
+            dec!(1.23457)
+        );
+        assert_eq!(
+            PriceRounding::new(RoundingMode::ToZero, 3).round_price(price),
+            dec!(1.234)
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn round_value__away_from_zero_mode__should_round_to_significant_decimal_places() {
+        let rounding = PriceRounding::new(RoundingMode::MidpointAwayFromZero, 5);
+
+        assert_eq!(rounding.round_value(dec!(100.125)), dec!(100.13));
+        assert_eq!(
+            PriceRounding::default().round_value(dec!(100.125)),
+            dec!(100.12)
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn round_volume__different_volumes__should_round_down_to_lot_step() {
+        let rounding = LotRounding::new(dec!(0.01), dec!(0.02));
+
+        assert_eq!(rounding.round_volume(dec!(0.137)), Some(dec!(0.13)));
+        assert_eq!(rounding.round_volume(dec!(0.02)), Some(dec!(0.02)));
+        assert_eq!(rounding.round_volume(dec!(0.019)), None);
+        assert_eq!(LotRounding::default().round_volume(dec!(0.009)), None);
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn exclude_weekend_and_holidays__contains_all_weekend_and_one_holiday__should_return_3() {
@@ -95,4 +276,103 @@ mod tests {
 
         assert_eq!(exclude_weekend_and_holidays(start, end, &holidays), 0);
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_open_times__start_is_not_aligned__should_return_open_times_aligned_to_timeframe() {
+        let start = Utc.ymd(2022, 5, 17).and_hms(18, 20, 0);
+        let end = Utc.ymd(2022, 5, 17).and_hms(21, 0, 0);
+
+        let open_times: Vec<_> = candle_open_times(start, end, Timeframe::Hour).collect();
+
+        assert_eq!(
+            open_times,
+            vec![
+                Utc.ymd(2022, 5, 17).and_hms(19, 0, 0),
+                Utc.ymd(2022, 5, 17).and_hms(20, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn candle_open_times__start_is_aligned__should_include_start() {
+        let start = Utc.ymd(2022, 5, 17).and_hms(18, 15, 0);
+        let end = Utc.ymd(2022, 5, 17).and_hms(19, 0, 0);
+
+        let open_times: Vec<_> = candle_open_times(start, end, Timeframe::FifteenMin).collect();
+
+        assert_eq!(
+            open_times,
+            vec![
+                Utc.ymd(2022, 5, 17).and_hms(18, 15, 0),
+                Utc.ymd(2022, 5, 17).and_hms(18, 30, 0),
+                Utc.ymd(2022, 5, 17).and_hms(18, 45, 0),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn signed_points__price_moved_in_crossing_direction__should_return_positive_points() {
+        assert_eq!(
+            signed_points(dec!(1.38000), dec!(1.37950), OrderType::Buy),
+            dec!(50)
+        );
+        assert_eq!(
+            signed_points(dec!(1.38000), dec!(1.38050), OrderType::Sell),
+            dec!(50)
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn signed_points__price_moved_against_crossing_direction__should_return_negative_points() {
+        assert_eq!(
+            signed_points(dec!(1.38000), dec!(1.38050), OrderType::Buy),
+            dec!(-50)
+        );
+        assert_eq!(
+            signed_points(dec!(1.38000), dec!(1.37950), OrderType::Sell),
+            dec!(-50)
+        );
+    }
+
+    fn candles_with_gap() -> Vec<BasicCandleProperties> {
+        [
+            (dec!(1.10100), dec!(1.10000), dec!(1.10050)),
+            (dec!(1.10300), dec!(1.10200), dec!(1.10250)),
+            (dec!(1.10260), dec!(1.10100), dec!(1.10150)),
+        ]
+        .into_iter()
+        .map(|(high, low, close)| BasicCandleProperties {
+            prices: CandlePrices {
+                open: low,
+                high,
+                low,
+                close,
+            },
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn compute_volatility__candles_with_gap__should_return_average_true_range() {
+        // true ranges: 100, 250 (gap from the previous close), 160
+        assert_eq!(compute_volatility(&candles_with_gap(), 3), 170);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn compute_volatility__window_is_less_than_amount_of_candles__should_use_last_candles() {
+        assert_eq!(compute_volatility(&candles_with_gap(), 2), 205);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn compute_volatility__no_candles__should_return_zero() {
+        assert_eq!(compute_volatility(&[], 3), 0);
+    }
 }
@@ -20,4 +20,12 @@ pub trait BasicCandleStore {
 
     fn get_previous_candle(&self) -> Result<Option<Item<CandleId, Self::CandleProperties>>>;
     fn update_previous_candle(&mut self, candle_id: CandleId) -> Result<()>;
+
+    /// Returns the candle which is `offset` positions back in time from the current candle.
+    /// The offset 0 is the current candle itself. Returns `None` if there is no current candle
+    /// or the offset runs off the beginning of the stored candles.
+    fn get_candle_offset_from_current(
+        &self,
+        offset: usize,
+    ) -> Result<Option<Item<CandleId, Self::CandleProperties>>>;
 }
@@ -6,8 +6,14 @@ use crate::step::utils::backtesting_charts::{
 use crate::step::utils::corridors::{
     Corridors, UpdateCorridorsNearWorkingLevelsUtils, UpdateGeneralCorridorUtils,
 };
+use crate::step::utils::decision_sink::{
+    get_order_events, get_removed_working_level_events, DecisionEvent, DecisionSink,
+};
 use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
-use crate::step::utils::entities::working_levels::{BacktestingWLProperties, BasicWLProperties};
+use crate::step::utils::entities::order::StepOrderProperties;
+use crate::step::utils::entities::working_levels::{
+    BacktestingWLProperties, BasicWLProperties, WLId,
+};
 use crate::step::utils::entities::{
     Diff, FakeBacktestingNotificationQueue, MaxMinAngles, Mode, StatisticsChartsNotifier,
     StatisticsNotifier, StrategySignals, MODE_ENV,
@@ -20,20 +26,112 @@ use crate::step::utils::level_utils::{
 use crate::step::utils::order_utils::{
     OrderUtils, UpdateOrdersBacktestingStores, UpdateOrdersBacktestingUtils,
 };
-use crate::step::utils::stores::{StepBacktestingMainStore, StepBacktestingStores, StepDiffs};
+use crate::step::utils::stores::{
+    StepBacktestingMainStore, StepBacktestingStores, StepDiffs, TendencyChange,
+};
 use crate::step::utils::StepBacktestingUtils;
 use anyhow::Result;
 use backtesting::trading_engine::TradingEngine;
+use backtesting::CloseReason;
 use base::corridor::BasicCorridorUtils;
 use base::entities::candle::CandleId;
-use base::entities::order::OrderType;
+use base::entities::order::{OrderId, OrderType};
 use base::entities::tick::HistoricalTickPrice;
 use base::entities::{BasicTickProperties, Item, MyInto};
 use base::helpers::{Holiday, NumberOfDaysToExclude};
 use base::params::StrategyParams;
 use chrono::{Datelike, NaiveDateTime};
+use rust_decimal_macros::dec;
+use std::mem;
 use std::str::FromStr;
 
+fn get_order_snapshot<T>(
+    store: &T,
+    decision_sink: &dyn DecisionSink,
+) -> Result<Vec<Item<OrderId, StepOrderProperties>>>
+where
+    T: StepBacktestingMainStore,
+{
+    if decision_sink.enabled() {
+        store.get_all_orders()
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+fn get_working_level_snapshot<T>(store: &T, decision_sink: &dyn DecisionSink) -> Result<Vec<WLId>>
+where
+    T: StepBacktestingMainStore,
+{
+    if decision_sink.enabled() {
+        Ok(store
+            .get_all_working_levels()?
+            .into_iter()
+            .map(|level| level.id)
+            .collect())
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+fn record_decisions(
+    decision_sink: &dyn DecisionSink,
+    events: Vec<DecisionEvent>,
+    tick: &BasicTickProperties<HistoricalTickPrice>,
+    candle: Option<&StepBacktestingCandleProperties>,
+) {
+    for event in events {
+        decision_sink.record(event, tick, candle);
+    }
+}
+
+/// Checks whether the loss breaker is tripped at the current time.
+/// The breaker is reset first if its cooldown is over.
+fn loss_breaker_is_tripped<T>(
+    stores: &mut StepBacktestingStores<T>,
+    current_time: NaiveDateTime,
+) -> bool
+where
+    T: StepBacktestingMainStore,
+{
+    if stores.config.loss_breaker.update_cooldown(current_time) {
+        log::debug!("the loss breaker is reset after its cooldown");
+    }
+
+    stores.config.loss_breaker.is_tripped()
+}
+
+/// Passes the trades closed since the previous call to the consumers of the closings:
+/// the loss breaker and the re-arming of the stopped out working levels.
+/// Returns true if the loss breaker has just been tripped.
+fn process_closed_trades<T>(
+    stores: &mut StepBacktestingStores<T>,
+    current_time: NaiveDateTime,
+) -> bool
+where
+    T: StepBacktestingMainStore,
+{
+    let closed_trades = stores.config.trading_engine.take_closed_trade_events();
+
+    stores.config.stopped_out_order_ids.extend(
+        closed_trades
+            .iter()
+            .filter(|trade| trade.close_reason == CloseReason::StopLoss)
+            .map(|trade| trade.order_id.clone()),
+    );
+
+    let tripped = stores
+        .config
+        .loss_breaker
+        .record_closed_trades(&closed_trades, current_time);
+
+    if tripped {
+        stores.statistics.loss_breaker_trips += 1;
+    }
+
+    tripped
+}
+
 pub fn run_iteration<T, Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, E, D, X>(
     new_tick_props: BasicTickProperties<HistoricalTickPrice>,
     new_candle_props: Option<StepBacktestingCandleProperties>,
@@ -84,8 +182,32 @@ where
         None => (stores.main.get_current_candle()?, false),
     };
 
+    let decision_sink = utils.decision_sink.as_ref();
+    let previous_working_levels = get_working_level_snapshot(&stores.main, decision_sink)?;
+
+    if new_candle_appeared {
+        if let (Some(previous_candle), Some(current_candle)) =
+            (stores.main.get_previous_candle()?, &current_candle)
+        {
+            LevUt::remove_working_levels_jumped_over_by_gap(
+                &mut stores.main,
+                &previous_candle.props.step_common.base,
+                &current_candle.props.step_common.base,
+                params.get_ratio_param_value(
+                    StepRatioParam::MinCandleGapForLevelInvalidation,
+                    current_candle.props.step_common.base.volatility,
+                ),
+                StatisticsNotifier::<FakeBacktestingNotificationQueue>::Backtesting(
+                    &mut stores.statistics,
+                ),
+            )?;
+        }
+    }
+
     if let Some(current_candle) = &current_candle {
         if signals.close_all_orders {
+            let previous_orders = get_order_snapshot(&stores.main, decision_sink)?;
+
             OrUt::close_all_orders_backtesting(
                 current_tick.props.bid,
                 current_candle.props.chart_index,
@@ -94,43 +216,115 @@ where
                 &utils.trading_engine,
                 &utils.add_entity_to_chart_traces,
             )?;
+
+            record_decisions(
+                decision_sink,
+                get_order_events(
+                    &previous_orders,
+                    &get_order_snapshot(&stores.main, decision_sink)?,
+                ),
+                &current_tick.props,
+                Some(&current_candle.props),
+            );
         }
     }
 
     let created_working_levels = stores.main.get_created_working_levels()?;
 
-    let crossed_level =
-        LevUt::get_crossed_level(current_tick.props.bid.into(), &created_working_levels);
+    let crossed_level = LevUt::get_crossed_level(
+        current_tick.props.bid.into(),
+        &created_working_levels,
+        stores.config.crossed_level_tie_break,
+    );
 
     if let Some(crossed_level) = crossed_level {
-        if stores
+        if signals.warmup {
+            log::debug!(
+                "the level {:?} is crossed during the warm-up period, the signal is suppressed",
+                crossed_level
+            );
+
+            stores.main.remove_working_level(&crossed_level.id)?;
+            stores.statistics.suppressed_signals_during_warmup += 1;
+        } else if !stores
+            .config
+            .trade_direction_mode
+            .allows(crossed_level.props.base.r#type)
+        {
+            log::debug!(
+                "the level {:?} is crossed, but its direction is not allowed, \
+                the signal is suppressed",
+                crossed_level
+            );
+
+            stores.main.remove_working_level(&crossed_level.id)?;
+            stores.statistics.suppressed_by_trade_direction += 1;
+        } else if signals.out_of_session {
+            log::debug!(
+                "the level {:?} is crossed outside the allowed trading sessions, \
+                the chain of orders is not created",
+                crossed_level
+            );
+        } else if loss_breaker_is_tripped(stores, current_tick.props.time) {
+            log::debug!(
+                "the level {:?} is crossed while the loss breaker is tripped, \
+                the chain of orders is not created",
+                crossed_level
+            );
+
+            stores.statistics.suppressed_by_loss_breaker += 1;
+        } else if stores
             .main
             .get_working_level_chain_of_orders(&crossed_level.id)?
             .is_empty()
         {
+            let current_volatility = stores
+                .main
+                .get_current_candle()?
+                .unwrap()
+                .props
+                .step_common
+                .base
+                .volatility;
+
             let chain_of_orders = OrUt::get_new_chain_of_orders(
                 crossed_level,
                 params,
-                stores
-                    .main
-                    .get_current_candle()?
-                    .unwrap()
-                    .props
-                    .step_common
-                    .base
-                    .volatility,
+                current_volatility,
                 stores.config.trading_engine.balances.real,
+                stores.config.trading_engine.price_rounding,
+                stores.config.trading_engine.lot_rounding,
+            )?
+            .into_iter()
+            .map(|order| StepOrderProperties {
+                creation_time: Some(current_tick.props.time),
+                ..order
+            })
+            .collect();
+
+            OrUt::create_chain_of_orders(
+                chain_of_orders,
+                params,
+                current_volatility,
+                &mut stores.main,
             )?;
-
-            for order_props in chain_of_orders {
-                stores
-                    .main
-                    .create_order(xid::new().to_string(), order_props)?;
-            }
         }
     }
 
-    LevUt::remove_active_working_levels_with_closed_orders(&mut stores.main)?;
+    let stopped_out_order_ids = mem::take(&mut stores.config.stopped_out_order_ids);
+
+    LevUt::remove_active_working_levels_with_closed_orders(
+        &mut stores.main,
+        params.get_point_param_value(StepPointParam::MaxReentries),
+        &|order_id: &str| stopped_out_order_ids.contains(order_id),
+    )?;
+
+    stores
+        .config
+        .trading_engine
+        .update_trade_excursions(current_tick.props.bid.into());
+
+    let previous_orders = get_order_snapshot(&stores.main, decision_sink)?;
 
     if let Some(current_candle) = &current_candle {
         OrUt::update_orders_backtesting(
@@ -153,6 +347,52 @@ where
         )?;
     }
 
+    utils.trading_engine.fill_delayed_orders(
+        current_tick.props.bid.close,
+        &mut stores.config.trading_engine,
+    )?;
+
+    let number_of_closed_positions = utils.trading_engine.close_positions_by_margin_call(
+        current_tick.props.bid.close,
+        &mut stores.main,
+        &mut stores.config.trading_engine,
+    )?;
+
+    if number_of_closed_positions > 0 {
+        log::warn!(
+            "the margin call closed {} positions",
+            number_of_closed_positions
+        );
+
+        stores.statistics.closed_by_margin_call += number_of_closed_positions as u64;
+    }
+
+    record_decisions(
+        decision_sink,
+        get_order_events(
+            &previous_orders,
+            &get_order_snapshot(&stores.main, decision_sink)?,
+        ),
+        &current_tick.props,
+        current_candle.as_ref().map(|candle| &candle.props),
+    );
+
+    if process_closed_trades(stores, current_tick.props.time) {
+        let consecutive_losses = stores.config.loss_breaker.consecutive_losses();
+
+        log::warn!(
+            "the loss breaker is tripped after {} consecutive losses, \
+            no new chains of orders are created until its cooldown is over or it's reset",
+            consecutive_losses
+        );
+
+        decision_sink.record(
+            DecisionEvent::LossBreakerTripped { consecutive_losses },
+            &current_tick.props,
+            current_candle.as_ref().map(|candle| &candle.props),
+        );
+    }
+
     LevUt::update_max_crossing_value_of_working_levels(
         &mut stores.main,
         current_tick.props.bid.into(),
@@ -176,6 +416,11 @@ where
             ),
         )?;
 
+        let min_profit_to_trail = params.get_ratio_param_value(
+            StepRatioParam::MinProfitToTrail,
+            current_candle.props.step_common.base.volatility,
+        );
+
         LevUt::move_take_profits(
             &mut stores.main,
             params.get_ratio_param_value(
@@ -186,6 +431,12 @@ where
                 StepRatioParam::DistanceToMoveTakeProfits,
                 current_candle.props.step_common.base.volatility,
             ),
+            // zero moves the take profits of all orders of the level
+            if min_profit_to_trail == dec!(0) {
+                None
+            } else {
+                Some(min_profit_to_trail)
+            },
             current_tick.props.bid.into(),
         )?;
     }
@@ -241,6 +492,14 @@ where
                                 StepRatioParam::MinDistanceBetweenCurrentMaxAndMinAnglesForNewInnerAngleToAppear,
                                 current_candle.props.step_common.base.volatility,
                             ),
+                            params.get_ratio_param_value(
+                                StepRatioParam::MinAngleStrength,
+                                current_candle.props.step_common.base.volatility,
+                            ),
+                            params.get_ratio_param_value(
+                                StepRatioParam::MinAngleCandleSize,
+                                current_candle.props.step_common.base.volatility,
+                            ),
                         )
                     }
                     None => None
@@ -283,7 +542,9 @@ where
                 crossed_angle_candle_chart_index: crossed_angle.props.candle.props.chart_index,
             };
 
-            let create_new_working_level =
+            let previous_tendency = stores.config.base.tendency;
+
+            let new_working_level_origin =
                 LevUt::update_tendency_and_get_instruction_to_create_new_working_level(
                     &mut stores.config.base,
                     &mut stores.main,
@@ -300,8 +561,43 @@ where
                     params,
                 )?;
 
-            if create_new_working_level {
-                stores.main.create_working_level(
+            if stores.config.base.tendency != previous_tendency {
+                stores.config.base.record_tendency_change(TendencyChange {
+                    time: current_tick.props.time,
+                    tendency: stores.config.base.tendency,
+                    crossed_angle_id: crossed_angle.id.clone(),
+                });
+
+                decision_sink.record(
+                    DecisionEvent::TendencyChanged {
+                        previous: previous_tendency,
+                        current: stores.config.base.tendency,
+                    },
+                    &current_tick.props,
+                    Some(&current_candle.props),
+                );
+            }
+
+            let new_working_level_origin = new_working_level_origin.filter(|origin| {
+                let r#type = OrderType::from(crossed_angle.props.base.r#type);
+                let allowed = stores.config.trade_direction_mode.allows(r#type);
+
+                if !allowed {
+                    log::debug!(
+                        "the creation of the {:?} working level with the origin {:?} \
+                        is suppressed by the trade direction mode",
+                        r#type,
+                        origin
+                    );
+
+                    stores.statistics.suppressed_by_trade_direction += 1;
+                }
+
+                allowed
+            });
+
+            if let Some(origin) = new_working_level_origin {
+                let new_working_level = stores.main.create_working_level(
                     xid::new().to_string(),
                     BacktestingWLProperties {
                         base: BasicWLProperties {
@@ -310,15 +606,30 @@ where
                             time: crossed_angle.props.candle.props.step_common.base.time,
                         },
                         chart_index: crossed_angle.props.candle.props.chart_index,
+                        origin: Some(origin),
                     },
                 )?;
 
+                log::debug!("new working level origin: {:?}", origin);
+
+                decision_sink.record(
+                    DecisionEvent::WorkingLevelCreated {
+                        id: new_working_level.id,
+                        price: new_working_level.props.base.price,
+                        r#type: new_working_level.props.base.r#type,
+                        origin,
+                    },
+                    &current_tick.props,
+                    Some(&current_candle.props),
+                );
+
                 stores.statistics.number_of_working_levels += 1;
 
                 if Mode::from_str(&dotenv::var(MODE_ENV).unwrap()).unwrap() != Mode::Optimization {
                     (utils.add_entity_to_chart_traces)(
                         ChartTraceEntity::WorkingLevel {
                             crossed_angle: &crossed_angle.props,
+                            origin,
                         },
                         &mut stores.config.chart_traces,
                         current_candle.props.chart_index,
@@ -354,5 +665,139 @@ where
         )?;
     }
 
+    record_decisions(
+        decision_sink,
+        get_removed_working_level_events(
+            &previous_working_levels,
+            &get_working_level_snapshot(&stores.main, decision_sink)?,
+        ),
+        &current_tick.props,
+        stores
+            .main
+            .get_current_candle()?
+            .as_ref()
+            .map(|candle| &candle.props),
+    );
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step::utils::entities::working_levels::{
+        BacktestingWLProperties, BasicWLProperties,
+    };
+    use crate::step::utils::loss_breaker::ConsecutiveLossBreaker;
+    use crate::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
+    use crate::step::utils::stores::working_level_store::StepWorkingLevelStore;
+    use crate::step::utils::stores::StepBacktestingConfig;
+    use backtesting::trading_engine::BacktestingTradingEngine;
+    use backtesting::{ClosePositionBy, OpenPositionBy};
+    use base::entities::order::{BasicOrderPrices, BasicOrderProperties, OrderStatus};
+    use base::stores::order_store::BasicOrderStore;
+    use chrono::{DateTime, Duration, NaiveDate, Utc};
+    use rust_decimal_macros::dec;
+    use std::collections::HashSet;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn loss_breaker_is_tripped__max_number_of_stopped_out_trades__should_block_crossed_levels_until_cooldown_is_over(
+    ) {
+        let mut stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        stores.config.loss_breaker = ConsecutiveLossBreaker::new(2).cooldown(Duration::hours(1));
+        // the breaker doesn't depend on the retained closed trades
+        stores.config.trading_engine.keep_closed_trades = false;
+
+        let start_time = NaiveDate::from_ymd(2022, 1, 1).and_hms(10, 0, 0);
+        stores.config.trading_engine.current_time = Some(DateTime::from_utc(start_time, Utc));
+
+        stores
+            .main
+            .create_working_level(
+                String::from("1"),
+                BacktestingWLProperties {
+                    base: BasicWLProperties {
+                        price: dec!(1.38000),
+                        r#type: OrderType::Buy,
+                        time: start_time,
+                    },
+                    chart_index: 0,
+                    origin: None,
+                },
+            )
+            .unwrap();
+
+        let trading_engine = BacktestingTradingEngine::new();
+
+        for id in ["1", "2"] {
+            let order = stores
+                .main
+                .create_order(
+                    String::from(id),
+                    StepOrderProperties {
+                        base: BasicOrderProperties {
+                            status: OrderStatus::Pending,
+                            prices: BasicOrderPrices {
+                                open: dec!(1.38000),
+                                stop_loss: dec!(1.37500),
+                                take_profit: dec!(1.38500),
+                            },
+                            ..Default::default()
+                        },
+                        working_level_id: String::from("1"),
+                        creation_time: None,
+                    },
+                )
+                .unwrap();
+
+            trading_engine
+                .open_position(
+                    &order,
+                    OpenPositionBy::OpenPrice,
+                    &mut stores.main,
+                    &mut stores.config.trading_engine,
+                )
+                .unwrap();
+
+            let order = stores.main.get_order_by_id(id).unwrap().unwrap();
+
+            trading_engine
+                .close_position(
+                    &order,
+                    ClosePositionBy::StopLoss,
+                    &mut stores.main,
+                    &mut stores.config.trading_engine,
+                )
+                .unwrap();
+        }
+
+        assert!(stores.config.trading_engine.closed_trades.is_empty());
+
+        assert!(process_closed_trades(&mut stores, start_time));
+        assert_eq!(stores.statistics.loss_breaker_trips, 1);
+        assert_eq!(
+            stores.config.stopped_out_order_ids,
+            HashSet::from([String::from("1"), String::from("2")])
+        );
+
+        // the closings are consumed only once
+        assert!(!process_closed_trades(&mut stores, start_time));
+        assert_eq!(stores.statistics.loss_breaker_trips, 1);
+
+        assert!(loss_breaker_is_tripped(
+            &mut stores,
+            start_time + Duration::minutes(30)
+        ));
+        assert!(!loss_breaker_is_tripped(
+            &mut stores,
+            start_time + Duration::hours(1)
+        ));
+        assert_eq!(stores.config.loss_breaker.consecutive_losses(), 0);
+    }
+}
@@ -3,6 +3,7 @@ use crate::step::utils::backtesting_charts::{
     ChartIndex, ChartTraceEntity, StepBacktestingChartTraces,
 };
 use crate::step::utils::corridors::Corridors;
+use crate::step::utils::decision_sink::{DecisionSink, NoopDecisionSink};
 use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
 use crate::step::utils::helpers::Helpers;
 use crate::step::utils::level_conditions::LevelConditions;
@@ -20,11 +21,15 @@ use std::marker::PhantomData;
 pub mod angle_utils;
 pub mod backtesting_charts;
 pub mod corridors;
+pub mod decision_sink;
 pub mod entities;
 pub mod helpers;
 pub mod level_conditions;
 pub mod level_utils;
+pub mod look_ahead_guard;
+pub mod loss_breaker;
 pub mod order_utils;
+pub mod session_filter;
 pub mod stores;
 pub mod trading_limiter;
 
@@ -51,6 +56,7 @@ where
     pub trading_engine: E,
     pub add_entity_to_chart_traces: D,
     pub exclude_weekend_and_holidays: X,
+    pub decision_sink: Box<dyn DecisionSink>,
 }
 
 impl<Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, T, D, X>
@@ -83,13 +89,19 @@ where
             trading_engine,
             add_entity_to_chart_traces,
             exclude_weekend_and_holidays,
+            decision_sink: Box::new(NoopDecisionSink),
         }
     }
+
+    pub fn with_decision_sink(mut self, decision_sink: impl DecisionSink + 'static) -> Self {
+        self.decision_sink = Box::new(decision_sink);
+        self
+    }
 }
 
 /// Determines the candle price to use for building the linear trading chart.
 pub fn get_candle_leading_price(candle: &BasicCandleProperties) -> CandlePrice {
-    match candle.r#type {
+    match candle.prices.candle_type() {
         CandleType::Green => candle.prices.high,
         CandleType::Red => candle.prices.low,
         CandleType::Neutral => {
@@ -127,6 +139,12 @@ mod tests {
     fn get_candle_leading_price__red_candle__should_return_low() {
         let candle = BasicCandleProperties {
             r#type: CandleType::Red,
+            prices: CandlePrices {
+                open: dec!(1.38100),
+                high: dec!(1.38150),
+                low: dec!(1.37950),
+                close: dec!(1.38000),
+            },
             ..Default::default()
         };
 
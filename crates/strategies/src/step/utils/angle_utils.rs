@@ -12,6 +12,7 @@ use base::params::ParamOutputValue;
 use std::cmp;
 use std::cmp::Ordering;
 use std::fmt::Debug;
+use std::marker::PhantomData;
 
 #[derive(Debug, Copy, Clone)]
 pub struct ExistingDiffs {
@@ -19,7 +20,8 @@ pub struct ExistingDiffs {
     pub previous: Diff,
 }
 
-pub trait AngleUtils {
+/// Detects the angles of the linear trading chart.
+pub trait AngleDetector {
     /// Calculates the difference between current and previous candle leading prices
     /// to further determine angles.
     fn get_diff_between_current_and_previous_candles<C>(
@@ -30,17 +32,23 @@ pub trait AngleUtils {
         C: AsRef<StepCandleProperties>;
 
     /// Checks if a new angle has appeared and returns such an angle.
+    /// The angles formed by the price move less than `min_angle_strength` are skipped.
+    /// The candles smaller than `min_angle_candle_size` can't become the angles.
     fn get_new_angle<C, A>(
         previous_candle: &Item<CandleId, C>,
         diffs: ExistingDiffs,
         angles: MaxMinAngles<A, C>,
         min_distance_between_new_and_current_max_and_min_angles: ParamOutputValue,
         min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear: ParamOutputValue,
+        min_angle_strength: ParamOutputValue,
+        min_angle_candle_size: ParamOutputValue,
     ) -> Option<FullAngleProperties<BasicAngleProperties, C>>
     where
         C: AsRef<StepCandleProperties> + Debug + Clone,
         A: AsRef<BasicAngleProperties> + Debug + Clone;
+}
 
+pub trait AngleUtils: AngleDetector {
     fn update_angles<A, C>(
         new_angle: Item<AngleId, FullAngleProperties<A, C>>,
         general_corridor: &[Item<CandleId, C>],
@@ -59,8 +67,19 @@ pub trait AngleUtils {
         A: AsRef<BasicAngleProperties> + Debug + Clone;
 }
 
+/// The default angle utils with the detection of the angles by the diffs
+/// between the leading prices of the candles.
 pub struct AngleUtilsImpl;
 
+/// The angle utils with the custom angle detector. The rest of the angle utils
+/// are taken from the default implementation.
+pub struct AngleUtilsWithDetector<D>
+where
+    D: AngleDetector,
+{
+    detector: PhantomData<D>,
+}
+
 impl AngleUtilsImpl {
     /// Checks if the current config matches the particular angle type.
     fn diffs_for_angle_type_exist(
@@ -377,6 +396,74 @@ impl AngleUtilsImpl {
         None
     }
 
+    /// The small candles (e.g. doji) make the angles noisy, so they aren't eligible
+    /// to become the angles.
+    fn candle_is_big_enough_for_angle<C>(
+        candle: &Item<CandleId, C>,
+        min_angle_candle_size: ParamOutputValue,
+    ) -> bool
+    where
+        C: AsRef<StepCandleProperties>,
+    {
+        let candle_size = candle.props.as_ref().base.size;
+
+        if candle_size < min_angle_candle_size {
+            log::debug!(
+                "candle is skipped as the point of the new angle, because its size is less than \
+                 the min angle candle size: ({}) < ({})",
+                candle_size,
+                min_angle_candle_size
+            );
+
+            return false;
+        }
+
+        true
+    }
+
+    /// The strength of the angle is the price move from the current angle of the opposite type
+    /// to the new angle. If there is no angle of the opposite type, the strength can't be measured,
+    /// so the angle is considered strong enough.
+    fn angle_is_strong_enough<A, C>(
+        new_angle: &FullAngleProperties<BasicAngleProperties, C>,
+        angles: MaxMinAngles<A, C>,
+        min_angle_strength: ParamOutputValue,
+    ) -> bool
+    where
+        C: AsRef<StepCandleProperties> + Debug + Clone,
+        A: AsRef<BasicAngleProperties> + Debug + Clone,
+    {
+        let opposite_angle = match new_angle.base.r#type {
+            Level::Max => angles.min_angle,
+            Level::Min => angles.max_angle,
+        };
+
+        let opposite_angle = match opposite_angle {
+            Some(opposite_angle) => opposite_angle,
+            None => return true,
+        };
+
+        let angle_strength = price_to_points(
+            (new_angle.candle.props.as_ref().leading_price
+                - opposite_angle.props.candle.props.as_ref().leading_price)
+                .abs(),
+        );
+
+        if angle_strength < min_angle_strength {
+            log::debug!(
+                "new {:?} angle is skipped, because its strength is less than the min angle strength: \
+                 ({}) < ({})",
+                new_angle.base.r#type,
+                angle_strength,
+                min_angle_strength
+            );
+
+            return false;
+        }
+
+        true
+    }
+
     fn get_angle_before_bargaining_corridor<'a, A, C>(
         new_angle: &FullAngleProperties<A, C>,
         general_corridor: &[Item<CandleId, C>],
@@ -458,7 +545,7 @@ impl AngleUtilsImpl {
     }
 }
 
-impl AngleUtils for AngleUtilsImpl {
+impl AngleDetector for AngleUtilsImpl {
     fn get_diff_between_current_and_previous_candles<C>(
         current_candle_props: &C,
         previous_candle_props: &C,
@@ -491,11 +578,17 @@ impl AngleUtils for AngleUtilsImpl {
         angles: MaxMinAngles<A, C>,
         min_distance_between_new_and_current_angles: ParamOutputValue,
         min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear: ParamOutputValue,
+        min_angle_strength: ParamOutputValue,
+        min_angle_candle_size: ParamOutputValue,
     ) -> Option<FullAngleProperties<BasicAngleProperties, C>>
     where
         C: AsRef<StepCandleProperties> + Debug + Clone,
         A: AsRef<BasicAngleProperties> + Debug + Clone,
     {
+        if !Self::candle_is_big_enough_for_angle(previous_candle, min_angle_candle_size) {
+            return None;
+        }
+
         let new_max_angle = Self::get_new_angle_of_type(
             Level::Max,
             previous_candle,
@@ -505,7 +598,7 @@ impl AngleUtils for AngleUtilsImpl {
             min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
         );
 
-        match new_max_angle {
+        let new_angle = match new_max_angle {
             Some(_) => new_max_angle,
             None => Self::get_new_angle_of_type(
                 Level::Min,
@@ -515,9 +608,14 @@ impl AngleUtils for AngleUtilsImpl {
                 min_distance_between_new_and_current_angles,
                 min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
             ),
-        }
+        };
+
+        new_angle
+            .filter(|new_angle| Self::angle_is_strong_enough(new_angle, angles, min_angle_strength))
     }
+}
 
+impl AngleUtils for AngleUtilsImpl {
     fn update_angles<A, C>(
         new_angle: Item<AngleId, FullAngleProperties<A, C>>,
         general_corridor: &[Item<CandleId, C>],
@@ -616,5 +714,75 @@ impl AngleUtils for AngleUtilsImpl {
     }
 }
 
+impl<D> AngleDetector for AngleUtilsWithDetector<D>
+where
+    D: AngleDetector,
+{
+    fn get_diff_between_current_and_previous_candles<C>(
+        current_candle_props: &C,
+        previous_candle_props: &C,
+    ) -> Diff
+    where
+        C: AsRef<StepCandleProperties>,
+    {
+        D::get_diff_between_current_and_previous_candles(
+            current_candle_props,
+            previous_candle_props,
+        )
+    }
+
+    fn get_new_angle<C, A>(
+        previous_candle: &Item<CandleId, C>,
+        diffs: ExistingDiffs,
+        angles: MaxMinAngles<A, C>,
+        min_distance_between_new_and_current_max_and_min_angles: ParamOutputValue,
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear: ParamOutputValue,
+        min_angle_strength: ParamOutputValue,
+        min_angle_candle_size: ParamOutputValue,
+    ) -> Option<FullAngleProperties<BasicAngleProperties, C>>
+    where
+        C: AsRef<StepCandleProperties> + Debug + Clone,
+        A: AsRef<BasicAngleProperties> + Debug + Clone,
+    {
+        D::get_new_angle(
+            previous_candle,
+            diffs,
+            angles,
+            min_distance_between_new_and_current_max_and_min_angles,
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            min_angle_strength,
+            min_angle_candle_size,
+        )
+    }
+}
+
+impl<D> AngleUtils for AngleUtilsWithDetector<D>
+where
+    D: AngleDetector,
+{
+    fn update_angles<A, C>(
+        new_angle: Item<AngleId, FullAngleProperties<A, C>>,
+        general_corridor: &[Item<CandleId, C>],
+        angle_store: &mut impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
+    ) -> Result<()>
+    where
+        A: AsRef<BasicAngleProperties> + Debug + Clone,
+        C: AsRef<StepCandleProperties> + Debug + Clone + PartialEq,
+    {
+        AngleUtilsImpl::update_angles(new_angle, general_corridor, angle_store)
+    }
+
+    fn get_crossed_angle<'a, A, C>(
+        angles: MaxMinAngles<'a, A, C>,
+        current_candle: &C,
+    ) -> Option<&'a Item<AngleId, FullAngleProperties<A, C>>>
+    where
+        C: AsRef<StepCandleProperties> + Debug + Clone,
+        A: AsRef<BasicAngleProperties> + Debug + Clone,
+    {
+        AngleUtilsImpl::get_crossed_angle(angles, current_candle)
+    }
+}
+
 #[cfg(test)]
 mod tests;
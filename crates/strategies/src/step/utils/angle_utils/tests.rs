@@ -170,7 +170,9 @@ fn get_new_angle__no_new_angle_diffs__should_return_none() {
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -221,7 +223,9 @@ fn get_new_angle__new_max_angle_with_high_leading_price_and_neither_max_nor_min_
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -265,7 +269,9 @@ fn get_new_angle__new_max_angle_with_low_leading_price_and_neither_max_nor_min_a
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -316,7 +322,9 @@ fn get_new_angle__new_min_angle_with_low_leading_price_and_neither_max_nor_min_a
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -360,7 +368,9 @@ fn get_new_angle__new_min_angle_with_high_leading_price_and_neither_max_nor_min_
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -432,7 +442,9 @@ fn get_new_angle__new_max_angle_and_max_angle_exists_and_no_min_angle_and_new_an
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -497,7 +509,9 @@ fn get_new_angle__new_max_angle_and_max_angle_exists_and_no_min_angle_and_new_an
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -569,7 +583,9 @@ fn get_new_angle__new_max_angle_and_min_angle_exists_and_no_max_angle_and_approp
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -634,7 +650,9 @@ fn get_new_angle__new_max_angle_and_min_angle_exists_and_no_max_angle_and_inappr
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -727,7 +745,9 @@ fn get_new_angle__new_max_angle_and_both_min_and_max_angles_exist_and_new_angle_
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -813,7 +833,9 @@ fn get_new_angle__new_max_angle_and_both_min_and_max_angles_exist_and_new_angle_
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -906,7 +928,9 @@ fn get_new_angle__new_max_angle_and_both_min_and_max_angles_exist_and_new_angle_
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -1001,7 +1025,9 @@ fn get_new_angle__new_max_angle_and_both_min_and_max_angles_exist_and_new_angle_
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -1075,7 +1101,9 @@ fn get_new_angle__new_min_angle_and_min_angle_exists_and_no_max_angle_and_new_an
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -1140,7 +1168,9 @@ fn get_new_angle__new_min_angle_and_min_angle_exists_and_no_max_angle_and_new_an
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -1212,13 +1242,208 @@ fn get_new_angle__new_min_angle_and_max_angle_exists_and_no_min_angle_and_approp
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
     );
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn get_new_angle__new_min_angle_and_max_angle_exists_and_price_move_is_less_than_min_angle_strength__should_return_none(
+) {
+    let previous_candle = Item {
+        id: String::from("1"),
+        props: StepCandleProperties {
+            base: BasicCandleProperties {
+                prices: CandlePrices {
+                    low: dec!(1.38000),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            leading_price: dec!(1.38000),
+        },
+    };
+
+    let diffs = ExistingDiffs {
+        current: Diff::Greater,
+        previous: Diff::Less,
+    };
+
+    let max_angle = Some(Item {
+        id: String::from("1"),
+        props: FullAngleProperties {
+            base: BasicAngleProperties {
+                r#type: Level::Max,
+                state: AngleState::Real,
+            },
+            candle: Item {
+                id: String::from("2"),
+                props: StepCandleProperties {
+                    base: BasicCandleProperties {
+                        prices: CandlePrices {
+                            low: dec!(1.39000),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    leading_price: dec!(1.39000),
+                },
+            },
+        },
+    });
+
+    let angles = MaxMinAngles {
+        max_angle: &max_angle,
+        min_angle: &None,
+    };
+
+    let min_distance_between_new_and_current_angles = dec!(1_000);
+    let min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear =
+        dec!(1_000_000);
+
+    // the price move forming the new min angle is 1_000 points
+    assert!(AngleUtilsImpl::get_new_angle(
+        &previous_candle,
+        diffs,
+        angles,
+        min_distance_between_new_and_current_angles,
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(1_001),
+        dec!(0)
+    )
+    .is_none());
+
+    assert!(AngleUtilsImpl::get_new_angle(
+        &previous_candle,
+        diffs,
+        angles,
+        min_distance_between_new_and_current_angles,
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
+    )
+    .is_some());
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn get_new_angle__new_max_angle_on_doji_candle_smaller_than_min_angle_candle_size__should_return_none(
+) {
+    let doji_candle = Item {
+        id: String::from("1"),
+        props: StepCandleProperties {
+            base: BasicCandleProperties {
+                prices: CandlePrices {
+                    open: dec!(1.38000),
+                    high: dec!(1.38020),
+                    low: dec!(1.37980),
+                    close: dec!(1.38000),
+                },
+                size: dec!(4),
+                ..Default::default()
+            },
+            leading_price: dec!(1.38020),
+        },
+    };
+
+    let diffs = ExistingDiffs {
+        current: Diff::Less,
+        previous: Diff::Greater,
+    };
+
+    let angles: MaxMinAngles<BasicAngleProperties, StepCandleProperties> = MaxMinAngles {
+        max_angle: &None,
+        min_angle: &None,
+    };
+
+    let min_distance_between_new_and_current_angles = dec!(1_000_000);
+    let min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear =
+        dec!(1_000_000);
+
+    assert!(AngleUtilsImpl::get_new_angle(
+        &doji_candle,
+        diffs,
+        angles,
+        min_distance_between_new_and_current_angles,
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(10)
+    )
+    .is_none());
+
+    // the zero min angle candle size doesn't filter the candles
+    assert!(AngleUtilsImpl::get_new_angle(
+        &doji_candle,
+        diffs,
+        angles,
+        min_distance_between_new_and_current_angles,
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
+    )
+    .is_some());
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn get_new_angle__new_max_angle_on_full_bodied_candle_bigger_than_min_angle_candle_size__should_return_new_max_angle(
+) {
+    let full_bodied_candle = Item {
+        id: String::from("1"),
+        props: StepCandleProperties {
+            base: BasicCandleProperties {
+                prices: CandlePrices {
+                    open: dec!(1.38000),
+                    high: dec!(1.38210),
+                    low: dec!(1.37990),
+                    close: dec!(1.38200),
+                },
+                size: dec!(22),
+                ..Default::default()
+            },
+            leading_price: dec!(1.38210),
+        },
+    };
+
+    let diffs = ExistingDiffs {
+        current: Diff::Less,
+        previous: Diff::Greater,
+    };
+
+    let angles: MaxMinAngles<BasicAngleProperties, StepCandleProperties> = MaxMinAngles {
+        max_angle: &None,
+        min_angle: &None,
+    };
+
+    let min_distance_between_new_and_current_angles = dec!(1_000_000);
+    let min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear =
+        dec!(1_000_000);
+
+    assert_eq!(
+        AngleUtilsImpl::get_new_angle(
+            &full_bodied_candle,
+            diffs,
+            angles,
+            min_distance_between_new_and_current_angles,
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(10)
+        ),
+        Some(FullAngleProperties {
+            base: BasicAngleProperties {
+                r#type: Level::Max,
+                state: AngleState::Real,
+            },
+            candle: full_bodied_candle.clone(),
+        })
+    );
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn get_new_angle__new_min_angle_and_max_angle_exists_and_no_min_angle_and_inappropriate_distance_between_current_max_and_new_min_angle__should_return_none(
@@ -1277,7 +1502,9 @@ fn get_new_angle__new_min_angle_and_max_angle_exists_and_no_min_angle_and_inappr
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -1370,7 +1597,9 @@ fn get_new_angle__new_min_angle_and_both_min_and_max_angles_exist_and_new_angle_
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -1456,7 +1685,9 @@ fn get_new_angle__new_min_angle_and_both_min_and_max_angles_exist_and_new_angle_
         diffs,
         angles,
         min_distance_between_new_and_current_angles,
-        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+        min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+        dec!(0),
+        dec!(0)
     )
         .is_none());
 }
@@ -1549,7 +1780,9 @@ fn get_new_angle__new_min_angle_and_both_min_and_max_angles_exist_and_new_angle_
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -1644,7 +1877,9 @@ fn get_new_angle__new_min_angle_and_both_min_and_max_angles_exist_and_new_angle_
             diffs,
             angles,
             min_distance_between_new_and_current_angles,
-            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear
+            min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear,
+            dec!(0),
+            dec!(0)
         )
             .unwrap(),
         expected_new_angle
@@ -2591,3 +2826,65 @@ fn get_crossed_angle__max_angle_is_crossed_by_gap__should_return_max_angle() {
         &max_angle
     );
 }
+
+struct AlwaysGreaterDiffDetector;
+
+impl AngleDetector for AlwaysGreaterDiffDetector {
+    fn get_diff_between_current_and_previous_candles<C>(
+        _current_candle_props: &C,
+        _previous_candle_props: &C,
+    ) -> Diff
+    where
+        C: AsRef<StepCandleProperties>,
+    {
+        Diff::Greater
+    }
+
+    fn get_new_angle<C, A>(
+        _previous_candle: &Item<CandleId, C>,
+        _diffs: ExistingDiffs,
+        _angles: MaxMinAngles<A, C>,
+        _min_distance_between_new_and_current_max_and_min_angles: ParamOutputValue,
+        _min_distance_between_current_max_and_min_angles_for_new_inner_angle_to_appear: ParamOutputValue,
+        _min_angle_strength: ParamOutputValue,
+        _min_angle_candle_size: ParamOutputValue,
+    ) -> Option<FullAngleProperties<BasicAngleProperties, C>>
+    where
+        C: AsRef<StepCandleProperties> + Debug + Clone,
+        A: AsRef<BasicAngleProperties> + Debug + Clone,
+    {
+        None
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn get_diff_between_current_and_previous_candles__custom_angle_detector__should_use_custom_detector(
+) {
+    let current_candle_props = StepCandleProperties {
+        leading_price: dec!(1.37950),
+        ..Default::default()
+    };
+    let previous_candle_props = StepCandleProperties {
+        leading_price: dec!(1.38000),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        AngleUtilsImpl::get_diff_between_current_and_previous_candles(
+            &current_candle_props,
+            &previous_candle_props
+        ),
+        Diff::Less
+    );
+
+    type CustomAngleUtils = AngleUtilsWithDetector<AlwaysGreaterDiffDetector>;
+
+    assert_eq!(
+        CustomAngleUtils::get_diff_between_current_and_previous_candles(
+            &current_candle_props,
+            &previous_candle_props
+        ),
+        Diff::Greater
+    );
+}
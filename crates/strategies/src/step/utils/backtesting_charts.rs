@@ -1,5 +1,6 @@
 use crate::step::utils::entities::angle::{BasicAngleProperties, FullAngleProperties};
 use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
+use crate::step::utils::entities::working_levels::LevelOrigin;
 use backtesting::Balance;
 use base::entities::candle::CandlePrice;
 use base::entities::tick::TickPrice;
@@ -16,6 +17,7 @@ pub enum ChartTraceEntity<'a> {
     WorkingLevel {
         crossed_angle:
             &'a FullAngleProperties<BasicAngleProperties, StepBacktestingCandleProperties>,
+        origin: LevelOrigin,
     },
     StopLoss {
         working_level_chart_index: ChartIndex,
@@ -43,6 +45,7 @@ pub struct StepBacktestingChartTraces {
     balance: Vec<Option<AxisValue>>,
 
     working_levels: Vec<Vec<Option<AxisValue>>>,
+    working_level_origins: Vec<LevelOrigin>,
     stop_losses: Vec<Vec<Option<AxisValue>>>,
     take_profits: Vec<Vec<Option<AxisValue>>>,
     close_prices: Vec<Vec<Option<AxisValue>>>,
@@ -58,6 +61,7 @@ impl StepBacktestingChartTraces {
             tendency,
             balance,
             working_levels: vec![],
+            working_level_origins: vec![],
             stop_losses: vec![],
             take_profits: vec![],
             close_prices: vec![],
@@ -84,7 +88,11 @@ impl StepBacktestingChartTraces {
         &self.balance
     }
 
-    pub fn create_new_working_level_trace(&mut self) -> &mut [Option<AxisValue>] {
+    pub fn create_new_working_level_trace(
+        &mut self,
+        origin: LevelOrigin,
+    ) -> &mut [Option<AxisValue>] {
+        self.working_level_origins.push(origin);
         self.working_levels
             .push(vec![None; self.total_amount_of_candles]);
         self.working_levels.last_mut().unwrap()
@@ -94,6 +102,11 @@ impl StepBacktestingChartTraces {
         &self.working_levels
     }
 
+    /// Returns the origins of the working levels in the order of their traces.
+    pub fn get_working_level_origins(&self) -> &[LevelOrigin] {
+        &self.working_level_origins
+    }
+
     pub fn create_new_stop_loss_trace(&mut self) -> &mut [Option<AxisValue>] {
         self.stop_losses
             .push(vec![None; self.total_amount_of_candles]);
@@ -161,6 +174,7 @@ pub fn add_entity_to_chart_traces(
         }
         ChartTraceEntity::WorkingLevel {
             crossed_angle: last_broken_angle,
+            origin,
         } => {
             let price = if last_broken_angle.base.r#type == Level::Max {
                 last_broken_angle.candle.props.step_common.base.prices.high
@@ -168,7 +182,7 @@ pub fn add_entity_to_chart_traces(
                 last_broken_angle.candle.props.step_common.base.prices.low
             };
 
-            let working_level_trace = chart_traces.create_new_working_level_trace();
+            let working_level_trace = chart_traces.create_new_working_level_trace(origin);
 
             for item in working_level_trace
                 .iter_mut()
@@ -343,6 +357,7 @@ mod tests {
                         },
                         ..Default::default()
                     },
+                    ..Default::default()
                 },
             },
         };
@@ -352,6 +367,7 @@ mod tests {
         add_entity_to_chart_traces(
             ChartTraceEntity::WorkingLevel {
                 crossed_angle: &crossed_angle,
+                origin: LevelOrigin::TendencyChange,
             },
             &mut chart_traces,
             current_candle_chart_index,
@@ -389,6 +405,7 @@ mod tests {
                         },
                         ..Default::default()
                     },
+                    ..Default::default()
                 },
             },
         };
@@ -398,6 +415,7 @@ mod tests {
         add_entity_to_chart_traces(
             ChartTraceEntity::WorkingLevel {
                 crossed_angle: &new_crossed_angle,
+                origin: LevelOrigin::CrossingOfTendencyChangeAngle,
             },
             &mut chart_traces,
             new_current_candle_chart_index,
@@ -415,6 +433,14 @@ mod tests {
                 Some(new_expected_working_level_price),
             ]
         );
+
+        assert_eq!(
+            chart_traces.get_working_level_origins(),
+            &[
+                LevelOrigin::TendencyChange,
+                LevelOrigin::CrossingOfTendencyChangeAngle
+            ]
+        );
     }
 
     #[test]
@@ -0,0 +1,222 @@
+use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
+use crate::step::utils::entities::order::StepOrderProperties;
+use crate::step::utils::entities::working_levels::{LevelOrigin, WLId, WLPrice};
+use crate::step::utils::loss_breaker::NumberOfLosses;
+use base::entities::order::{OrderId, OrderStatus, OrderType};
+use base::entities::tick::HistoricalTickPrice;
+use base::entities::{BasicTickProperties, Item, Tendency};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The significant decision of the strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecisionEvent {
+    TendencyChanged {
+        previous: Tendency,
+        current: Tendency,
+    },
+    WorkingLevelCreated {
+        id: WLId,
+        price: WLPrice,
+        r#type: OrderType,
+        origin: LevelOrigin,
+    },
+    WorkingLevelRemoved {
+        id: WLId,
+    },
+    OrderOpened {
+        id: OrderId,
+        working_level_id: WLId,
+    },
+    OrderClosed {
+        id: OrderId,
+        working_level_id: WLId,
+    },
+    LossBreakerTripped {
+        consecutive_losses: NumberOfLosses,
+    },
+}
+
+/// Receives the decisions of the strategy together with the tick and the candle
+/// on which they were made. Used to keep the audit log of the strategy.
+pub trait DecisionSink {
+    /// Tracking of the order and working level changes requires the snapshots
+    /// of the store on every iteration, so they are taken only if the sink is enabled.
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn record(
+        &self,
+        event: DecisionEvent,
+        tick: &BasicTickProperties<HistoricalTickPrice>,
+        candle: Option<&StepBacktestingCandleProperties>,
+    );
+}
+
+#[derive(Default)]
+pub struct NoopDecisionSink;
+
+impl DecisionSink for NoopDecisionSink {
+    fn enabled(&self) -> bool {
+        false
+    }
+
+    fn record(
+        &self,
+        _event: DecisionEvent,
+        _tick: &BasicTickProperties<HistoricalTickPrice>,
+        _candle: Option<&StepBacktestingCandleProperties>,
+    ) {
+    }
+}
+
+/// Keeps the decisions in memory until they are taken. The clones of the sink share
+/// the same decisions, so one clone may be passed to the utils and the other one
+/// may be used to take the recorded decisions.
+#[derive(Debug, Default, Clone)]
+pub struct CollectingDecisionSink {
+    decisions: Rc<RefCell<Vec<DecisionEvent>>>,
+}
+
+impl CollectingDecisionSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the decisions recorded since the previous call.
+    pub fn take_decisions(&self) -> Vec<DecisionEvent> {
+        self.decisions.take()
+    }
+}
+
+impl DecisionSink for CollectingDecisionSink {
+    fn record(
+        &self,
+        event: DecisionEvent,
+        _tick: &BasicTickProperties<HistoricalTickPrice>,
+        _candle: Option<&StepBacktestingCandleProperties>,
+    ) {
+        self.decisions.borrow_mut().push(event);
+    }
+}
+
+/// Returns the events of the orders opened and closed between the two snapshots of the orders.
+pub fn get_order_events(
+    previous_orders: &[Item<OrderId, StepOrderProperties>],
+    current_orders: &[Item<OrderId, StepOrderProperties>],
+) -> Vec<DecisionEvent> {
+    let mut events = Vec::new();
+
+    for order in current_orders {
+        let previous_status = previous_orders
+            .iter()
+            .find(|previous_order| previous_order.id == order.id)
+            .map(|previous_order| previous_order.props.base.status)
+            .unwrap_or(OrderStatus::Pending);
+
+        let order_opened = DecisionEvent::OrderOpened {
+            id: order.id.clone(),
+            working_level_id: order.props.working_level_id.clone(),
+        };
+
+        let order_closed = DecisionEvent::OrderClosed {
+            id: order.id.clone(),
+            working_level_id: order.props.working_level_id.clone(),
+        };
+
+        match (previous_status, order.props.base.status) {
+            (OrderStatus::Pending, OrderStatus::Opened) => events.push(order_opened),
+            (OrderStatus::Pending, OrderStatus::Closed) => {
+                events.push(order_opened);
+                events.push(order_closed);
+            }
+            (OrderStatus::Opened, OrderStatus::Closed) => events.push(order_closed),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Returns the events of the working levels missing in the current snapshot of the levels.
+pub fn get_removed_working_level_events(
+    previous_working_levels: &[WLId],
+    current_working_levels: &[WLId],
+) -> Vec<DecisionEvent> {
+    previous_working_levels
+        .iter()
+        .filter(|id| !current_working_levels.contains(id))
+        .map(|id| DecisionEvent::WorkingLevelRemoved { id: id.clone() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: &str, status: OrderStatus) -> Item<OrderId, StepOrderProperties> {
+        let mut props = StepOrderProperties::default();
+        props.base.status = status;
+
+        Item {
+            id: String::from(id),
+            props,
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_order_events__orders_with_changed_statuses__should_return_opened_and_closed_orders() {
+        let previous_orders = vec![
+            order("1", OrderStatus::Pending),
+            order("2", OrderStatus::Opened),
+            order("3", OrderStatus::Opened),
+        ];
+
+        let current_orders = vec![
+            order("1", OrderStatus::Opened),
+            order("2", OrderStatus::Closed),
+            order("3", OrderStatus::Opened),
+            order("4", OrderStatus::Closed),
+        ];
+
+        let working_level_id = StepOrderProperties::default().working_level_id;
+
+        assert_eq!(
+            get_order_events(&previous_orders, &current_orders),
+            vec![
+                DecisionEvent::OrderOpened {
+                    id: String::from("1"),
+                    working_level_id: working_level_id.clone(),
+                },
+                DecisionEvent::OrderClosed {
+                    id: String::from("2"),
+                    working_level_id: working_level_id.clone(),
+                },
+                DecisionEvent::OrderOpened {
+                    id: String::from("4"),
+                    working_level_id: working_level_id.clone(),
+                },
+                DecisionEvent::OrderClosed {
+                    id: String::from("4"),
+                    working_level_id,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_removed_working_level_events__some_levels_are_missing__should_return_them() {
+        let previous_working_levels = vec![String::from("1"), String::from("2")];
+        let current_working_levels = vec![String::from("2"), String::from("3")];
+
+        assert_eq!(
+            get_removed_working_level_events(&previous_working_levels, &current_working_levels),
+            vec![DecisionEvent::WorkingLevelRemoved {
+                id: String::from("1")
+            }]
+        );
+    }
+}
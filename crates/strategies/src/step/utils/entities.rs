@@ -7,6 +7,7 @@ use crate::step::utils::stores::StepBacktestingStatistics;
 use base::entities::Item;
 use base::notifier::{Message, NotificationQueue};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use std::str::FromStr;
 
@@ -16,16 +17,21 @@ pub mod order;
 pub mod params;
 pub mod working_levels;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Diff {
     Greater = 1,
     Less = -1,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct StrategySignals {
     pub no_trading_mode: bool,
     pub close_all_orders: bool,
+    /// No new chains of orders are created during the warm-up period,
+    /// but the stores are still updated.
+    pub warmup: bool,
+    /// No new chains of orders are created outside the allowed trading sessions.
+    pub out_of_session: bool,
 }
 
 pub type StrategyPerformance = Decimal;
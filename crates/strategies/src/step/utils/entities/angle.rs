@@ -1,14 +1,15 @@
 use base::entities::{candle::CandleId, Item, Level};
+use serde::{Deserialize, Serialize};
 
 pub type AngleId = String;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AngleState {
     Real,
     Virtual,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BasicAngleProperties {
     pub r#type: Level,
     pub state: AngleState,
@@ -1,13 +1,21 @@
 use crate::step::utils::backtesting_charts::ChartIndex;
 use base::entities::candle::{BasicCandleProperties, CandlePrice};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+/// The logical number of the candle. Unlike the chart index, it's never reused,
+/// so it stays stable when the candles are pruned or the gaps are filled.
+pub type CandleSequenceNumber = u64;
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct StepBacktestingCandleProperties {
     pub step_common: StepCandleProperties,
+    /// The position of the candle on the chart. It's used only for plotting.
     pub chart_index: ChartIndex,
+    /// Assigned by the store on the creation of the candle.
+    pub sequence_number: CandleSequenceNumber,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct StepCandleProperties {
     pub base: BasicCandleProperties,
     pub leading_price: CandlePrice,
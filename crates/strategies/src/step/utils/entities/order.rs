@@ -1,11 +1,16 @@
 use base::entities::order::BasicOrderProperties;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
 
 use crate::step::utils::entities::working_levels::WLId;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StepOrderProperties {
     pub base: BasicOrderProperties,
     pub working_level_id: WLId,
+    /// The time of the tick on which the order is created. It's used to cancel
+    /// the expired pending orders. If it's `None`, the order never expires.
+    pub creation_time: Option<NaiveDateTime>,
 }
 
 impl From<StepOrderProperties> for BasicOrderProperties {
@@ -20,11 +25,28 @@ impl AsRef<BasicOrderProperties> for StepOrderProperties {
     }
 }
 
+/// Defines how the opened order is closed when the tick range contains
+/// both the stop loss and the take profit, so it's unknown which of them was reached first.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum AmbiguousFillPolicy {
+    StopFirst,
+    TakeProfitFirst,
+    /// The exit with the lower profit is assumed, which is the stop loss for the regular orders.
+    /// It's the default to avoid the optimistic results.
+    #[default]
+    WorstCase,
+    /// The order is closed at the average of the stop loss and the take profit
+    /// weighted by the probabilities of reaching each of them first. The probability
+    /// of every exit is proportional to the distance from the open price to the other exit.
+    Proportional,
+}
+
 impl Default for StepOrderProperties {
     fn default() -> Self {
         Self {
             base: BasicOrderProperties::default(),
             working_level_id: String::from("1"),
+            creation_time: None,
         }
     }
 }
@@ -9,6 +9,18 @@ pub enum StepPointParam {
     MinAmountOfCandlesInBigCorridorBeforeActivationCrossingOfLevel,
     MinAmountOfCandlesInCorridorDefiningEdgeBargaining,
     MaxLossPerOneChainOfOrdersPctOfBalance,
+    MaxOrdersPerWorkingLevel,
+    /// The max number of the simultaneously active working levels. Zero disables the cap.
+    MaxActiveWorkingLevels,
+    /// The number of the last candles used to compute the volatility of the candle.
+    /// Zero keeps the volatility computed on loading of the historical data.
+    VolatilityWindow,
+    /// The number of ticks the price has to stay beyond the crossed created level
+    /// before the level is activated. Zero activates the level on crossing.
+    MinAmountOfTicksBeyondLevelBeforeActivation,
+    /// The number of times the stopped out active level is re-armed for the re-entry
+    /// before it's removed. Zero removes the level on the first closing of its orders.
+    MaxReentries,
 }
 
 impl Display for StepPointParam {
@@ -40,6 +52,15 @@ impl Display for StepPointParam {
             StepPointParam::MaxLossPerOneChainOfOrdersPctOfBalance => {
                 write!(f, "max_loss_per_one_chain_of_orders_pct_of_balance")
             }
+            StepPointParam::MaxOrdersPerWorkingLevel => {
+                write!(f, "max_orders_per_working_level")
+            }
+            StepPointParam::MaxActiveWorkingLevels => write!(f, "max_active_working_levels"),
+            StepPointParam::VolatilityWindow => write!(f, "volatility_window"),
+            StepPointParam::MinAmountOfTicksBeyondLevelBeforeActivation => {
+                write!(f, "min_amount_of_ticks_beyond_level_before_activation")
+            }
+            StepPointParam::MaxReentries => write!(f, "max_reentries"),
         }
     }
 }
@@ -58,6 +79,13 @@ pub enum StepRatioParam {
     DistanceDefiningNearbyLevelsOfTheSameType,
     MinDistanceOfActivationCrossingOfLevelWhenReturningToLevelForItsDeletion,
     RangeOfBigCorridorNearLevel,
+    MinDistanceBetweenOrdersOfLevel,
+    MinAngleStrength,
+    MinAngleCandleSize,
+    MinCandleGapForLevelInvalidation,
+    /// The min profit of the opened order from its open price for its take profit to be moved.
+    /// Zero moves the take profits of all orders of the level.
+    MinProfitToTrail,
 }
 
 impl Display for StepRatioParam {
@@ -106,6 +134,15 @@ impl Display for StepRatioParam {
             StepRatioParam::RangeOfBigCorridorNearLevel => {
                 write!(f, "range_of_big_corridor_near_level")
             }
+            StepRatioParam::MinDistanceBetweenOrdersOfLevel => {
+                write!(f, "min_distance_between_orders_of_level")
+            }
+            StepRatioParam::MinAngleStrength => write!(f, "min_angle_strength"),
+            StepRatioParam::MinAngleCandleSize => write!(f, "min_angle_candle_size"),
+            StepRatioParam::MinCandleGapForLevelInvalidation => {
+                write!(f, "min_candle_gap_for_level_invalidation")
+            }
+            StepRatioParam::MinProfitToTrail => write!(f, "min_profit_to_trail"),
         }
     }
 }
@@ -3,6 +3,7 @@ use base::entities::order::OrderType;
 use chrono::{NaiveDateTime, Utc};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 
 pub type WLId = String;
 pub type WLPrice = Decimal;
@@ -15,7 +16,7 @@ pub enum WLStatus {
     Active,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BasicWLProperties {
     pub price: WLPrice,
     pub r#type: OrderType,
@@ -28,10 +29,90 @@ impl AsRef<BasicWLProperties> for BasicWLProperties {
     }
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+/// Defines what happens when a created working level is about to become active,
+/// but the max number of active working levels is reached.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ActiveLevelsCapPolicy {
+    /// The level stays created until one of the active levels is removed.
+    #[default]
+    BlockActivation,
+    /// The active level without active orders that is the farthest from the current price
+    /// is removed to make room for the new one. If there is no such level, the activation is blocked.
+    RemoveWeakest,
+    /// The active level without active orders with the lowest score is removed to make room
+    /// for the new one if its score is lower than the score of the new level.
+    /// Otherwise, the activation is blocked.
+    RemoveLowestScored(LevelScoreWeights),
+}
+
+/// The weights of the factors of the score of the working level.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LevelScoreWeights {
+    /// The weight of the max crossing value of the level in volatilities.
+    pub break_distance: Decimal,
+    /// The weight of the number of candles in the small corridor of the level.
+    pub corridor_strength: Decimal,
+    /// The weight of the number of other levels of the same type nearby, it reduces the score.
+    pub nearby_levels: Decimal,
+}
+
+impl Default for LevelScoreWeights {
+    fn default() -> Self {
+        Self {
+            break_distance: dec!(1),
+            corridor_strength: dec!(1),
+            nearby_levels: dec!(1),
+        }
+    }
+}
+
+/// Defines which level is considered crossed when the tick crosses several created levels at once.
+/// The remaining ties are resolved by the level id, so the choice doesn't depend
+/// on the order of the levels.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum CrossedLevelTieBreak {
+    /// The level with the price nearest to the close price of the tick, then the oldest one.
+    Nearest,
+    /// The level created first.
+    #[default]
+    Oldest,
+    /// The level created last.
+    Newest,
+}
+
+/// Defines the working levels of which direction are allowed to be created and activated.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum TradeDirectionMode {
+    #[default]
+    Both,
+    LongOnly,
+    ShortOnly,
+}
+
+impl TradeDirectionMode {
+    pub fn allows(&self, r#type: OrderType) -> bool {
+        matches!(
+            (self, r#type),
+            (TradeDirectionMode::Both, _)
+                | (TradeDirectionMode::LongOnly, OrderType::Buy)
+                | (TradeDirectionMode::ShortOnly, OrderType::Sell)
+        )
+    }
+}
+
+/// The path of the tendency logic on which the working level was created.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LevelOrigin {
+    TendencyChange,
+    SecondLevelAfterBargainingTendencyChange,
+    CrossingOfTendencyChangeAngle,
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BacktestingWLProperties {
     pub base: BasicWLProperties,
     pub chart_index: ChartIndex,
+    pub origin: Option<LevelOrigin>,
 }
 
 impl From<BacktestingWLProperties> for BasicWLProperties {
@@ -58,10 +139,32 @@ impl Default for BasicWLProperties {
 
 pub type WLMaxCrossingValue = Decimal;
 
+pub type NumberOfTicksBeyondLevel = u32;
+
+pub type NumberOfReentries = u32;
+
 pub type WLIndex = u32;
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum CorridorType {
     Small,
     Big,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn allows__different_trade_direction_modes__should_allow_only_corresponding_orders() {
+        assert!(TradeDirectionMode::Both.allows(OrderType::Buy));
+        assert!(TradeDirectionMode::Both.allows(OrderType::Sell));
+
+        assert!(TradeDirectionMode::LongOnly.allows(OrderType::Buy));
+        assert!(!TradeDirectionMode::LongOnly.allows(OrderType::Sell));
+
+        assert!(!TradeDirectionMode::ShortOnly.allows(OrderType::Buy));
+        assert!(TradeDirectionMode::ShortOnly.allows(OrderType::Sell));
+    }
+}
@@ -12,7 +12,7 @@ use base::entities::candle::CandleId;
 use base::entities::order::{BasicOrderProperties, OrderPrice, OrderStatus, OrderType};
 use base::entities::tick::{TickPrice, TickTime, UniversalTickPrice};
 use base::entities::{Item, Level, DEFAULT_HOLIDAYS};
-use base::helpers::{price_to_points, Holiday, NumberOfDaysToExclude};
+use base::helpers::{price_to_points, signed_points, Holiday, NumberOfDaysToExclude};
 use base::params::{ParamOutputValue, StrategyParams};
 use chrono::NaiveDateTime;
 use rust_decimal::Decimal;
@@ -44,6 +44,16 @@ pub trait LevelConditions {
         distance_from_level_for_its_deletion: ParamOutputValue,
     ) -> bool;
 
+    /// Unlike `level_expired_by_distance`, takes into account only the distance
+    /// the price has run away from the level in the direction opposite to the level crossing
+    /// (up for buy levels, down for sell levels).
+    fn level_expired_by_distance_directional(
+        level_price: WLPrice,
+        level_type: OrderType,
+        current_tick_price: UniversalTickPrice,
+        distance_from_level_for_its_deletion: ParamOutputValue,
+    ) -> bool;
+
     fn level_expired_by_time(
         level_time: LevelTime,
         current_tick_time: TickTime,
@@ -76,6 +86,7 @@ pub trait LevelConditions {
         general_corridor: &[Item<CandleId, C>],
         angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -132,17 +143,9 @@ impl LevelConditions for LevelConditionsImpl {
         stop_loss_price: OrderPrice,
         working_level_type: OrderType,
     ) -> bool {
-        let (lowest_tick_price, highest_tick_price) = match current_tick_price {
-            UniversalTickPrice::Historical(current_tick_price) => {
-                (current_tick_price.low, current_tick_price.high)
-            }
-            UniversalTickPrice::Realtime(current_tick_price) => {
-                (current_tick_price, current_tick_price)
-            }
-        };
-
-        (working_level_type == OrderType::Buy && lowest_tick_price <= stop_loss_price)
-            || working_level_type == OrderType::Sell && highest_tick_price >= stop_loss_price
+        (working_level_type == OrderType::Buy && current_tick_price.lowest() <= stop_loss_price)
+            || working_level_type == OrderType::Sell
+                && current_tick_price.highest() >= stop_loss_price
     }
 
     fn level_expired_by_distance(
@@ -158,23 +161,38 @@ impl LevelConditions for LevelConditionsImpl {
             distance_from_level_for_its_deletion
         );
 
-        let (lowest_tick_price, highest_tick_price) = match current_tick_price {
-            UniversalTickPrice::Historical(current_tick_price) => {
-                (current_tick_price.low, current_tick_price.high)
-            }
-            UniversalTickPrice::Realtime(current_tick_price) => {
-                (current_tick_price, current_tick_price)
-            }
-        };
-
         let max_distance = cmp::max(
-            (level_price - lowest_tick_price).abs(),
-            (level_price - highest_tick_price).abs(),
+            (level_price - current_tick_price.lowest()).abs(),
+            (level_price - current_tick_price.highest()).abs(),
         );
 
         price_to_points(max_distance) >= distance_from_level_for_its_deletion
     }
 
+    fn level_expired_by_distance_directional(
+        level_price: WLPrice,
+        level_type: OrderType,
+        current_tick_price: UniversalTickPrice,
+        distance_from_level_for_its_deletion: ParamOutputValue,
+    ) -> bool {
+        log::debug!(
+            "level_expired_by_distance_directional: level price is {}, level type is {:?}, \
+            current tick price is {:?}, distance from level for its deletion is {}",
+            level_price,
+            level_type,
+            current_tick_price,
+            distance_from_level_for_its_deletion
+        );
+
+        let unfavorable_tick_price = match level_type {
+            OrderType::Buy => current_tick_price.highest(),
+            OrderType::Sell => current_tick_price.lowest(),
+        };
+
+        signed_points(unfavorable_tick_price, level_price, level_type)
+            >= distance_from_level_for_its_deletion
+    }
+
     fn level_expired_by_time(
         level_time: LevelTime,
         current_tick_time: TickTime,
@@ -265,6 +283,7 @@ impl LevelConditions for LevelConditionsImpl {
         general_corridor: &[Item<CandleId, C>],
         angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -282,9 +301,17 @@ impl LevelConditions for LevelConditionsImpl {
             let max_angle = angle_store.get_max_angle()?;
             let min_angle = angle_store.get_min_angle()?;
 
+            // only the latest candles are scanned, so the angles beyond the bound are considered not found
+            let scanned_corridor = match max_candles_to_scan {
+                Some(max_candles_to_scan) => {
+                    &general_corridor[general_corridor.len().saturating_sub(max_candles_to_scan)..]
+                }
+                None => general_corridor,
+            };
+
             if let (Some(min_angle), Some(max_angle)) = (&min_angle, &max_angle) {
-                if general_corridor.contains(&min_angle.props.candle)
-                    && general_corridor.contains(&max_angle.props.candle)
+                if scanned_corridor.contains(&min_angle.props.candle)
+                    && scanned_corridor.contains(&max_angle.props.candle)
                 {
                     log::debug!(
                         "min angle and max angle are in corridor defining edge bargaining: min angle — {min_angle:?},\
@@ -270,6 +270,95 @@ fn level_expired_by_distance__current_tick_price_is_beyond_acceptable_range_from
     ));
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn level_expired_by_distance_directional__buy_level_price_runs_away_upward__should_return_true() {
+    assert!(LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Buy,
+        UniversalTickPrice::Realtime(dec!(1.40001)),
+        dec!(2_000)
+    ));
+
+    assert!(LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Buy,
+        UniversalTickPrice::Historical(HistoricalTickPrice {
+            high: dec!(1.40001),
+            low: dec!(1.39000),
+            ..Default::default()
+        }),
+        dec!(2_000)
+    ));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn level_expired_by_distance_directional__buy_level_price_dips_downward__should_return_false() {
+    assert!(!LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Buy,
+        UniversalTickPrice::Realtime(dec!(1.35999)),
+        dec!(2_000)
+    ));
+
+    assert!(!LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Buy,
+        UniversalTickPrice::Historical(HistoricalTickPrice {
+            high: dec!(1.39000),
+            low: dec!(1.35999),
+            ..Default::default()
+        }),
+        dec!(2_000)
+    ));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn level_expired_by_distance_directional__sell_level_price_runs_away_downward__should_return_true()
+{
+    assert!(LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Sell,
+        UniversalTickPrice::Realtime(dec!(1.35999)),
+        dec!(2_000)
+    ));
+
+    assert!(LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Sell,
+        UniversalTickPrice::Historical(HistoricalTickPrice {
+            high: dec!(1.37000),
+            low: dec!(1.35999),
+            ..Default::default()
+        }),
+        dec!(2_000)
+    ));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn level_expired_by_distance_directional__sell_level_price_rises_upward__should_return_false() {
+    assert!(!LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Sell,
+        UniversalTickPrice::Realtime(dec!(1.40001)),
+        dec!(2_000)
+    ));
+
+    assert!(!LevelConditionsImpl::level_expired_by_distance_directional(
+        dec!(1.38000),
+        OrderType::Sell,
+        UniversalTickPrice::Historical(HistoricalTickPrice {
+            high: dec!(1.40001),
+            low: dec!(1.37000),
+            ..Default::default()
+        }),
+        dec!(2_000)
+    ));
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn level_expired_by_time__current_diff_is_greater_than_level_expiration__should_return_true() {
@@ -721,6 +810,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_less_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -778,6 +868,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -835,6 +926,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -904,6 +996,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -973,6 +1066,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -1039,6 +1133,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -1145,6 +1240,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -1250,6 +1346,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
         &general_corridor,
         &store,
         min_amount_of_candles_in_corridor_defining_edge_bargaining,
+        None,
     )
     .unwrap());
 }
@@ -1315,6 +1412,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -1422,6 +1520,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
             &general_corridor,
             &store,
             min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            None,
         )
         .unwrap()
     );
@@ -1528,6 +1627,7 @@ fn level_comes_out_of_bargaining_corridor__len_of_bargaining_corridor_is_more_th
         &general_corridor,
         &store,
         min_amount_of_candles_in_corridor_defining_edge_bargaining,
+        None,
     )
     .unwrap());
 }
@@ -3614,3 +3714,130 @@ fn working_level_is_close_to_another_one__opposite_from_existing_active_sell_lev
     )
     .unwrap());
 }
+
+#[test]
+#[allow(non_snake_case)]
+fn level_comes_out_of_bargaining_corridor__min_and_max_angles_are_beyond_max_candles_to_scan__should_return_false(
+) {
+    let mut store = InMemoryStepBacktestingStore::default();
+
+    let min_angle_candle = store
+        .create_candle(
+            xid::new().to_string(),
+            StepBacktestingCandleProperties::default(),
+        )
+        .unwrap();
+    let min_angle = store
+        .create_angle(
+            xid::new().to_string(),
+            BasicAngleProperties {
+                r#type: Level::Min,
+                ..Default::default()
+            },
+            min_angle_candle.id.clone(),
+        )
+        .unwrap();
+
+    let max_angle_before_bargaining_corridor_candle = store
+        .create_candle(
+            xid::new().to_string(),
+            StepBacktestingCandleProperties {
+                step_common: StepCandleProperties {
+                    leading_price: dec!(1.38000),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let max_angle_before_bargaining_corridor = store
+        .create_angle(
+            xid::new().to_string(),
+            BasicAngleProperties {
+                r#type: Level::Max,
+                ..Default::default()
+            },
+            max_angle_before_bargaining_corridor_candle.id.clone(),
+        )
+        .unwrap();
+
+    store
+        .update_max_angle_before_bargaining_corridor(max_angle_before_bargaining_corridor.id)
+        .unwrap();
+
+    let max_angle_candle = store
+        .create_candle(
+            xid::new().to_string(),
+            StepBacktestingCandleProperties {
+                step_common: StepCandleProperties {
+                    leading_price: dec!(1.39000),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let max_angle = store
+        .create_angle(
+            xid::new().to_string(),
+            BasicAngleProperties {
+                r#type: Level::Max,
+                ..Default::default()
+            },
+            max_angle_candle.id.clone(),
+        )
+        .unwrap();
+
+    store.update_min_angle(min_angle.id).unwrap();
+    store.update_max_angle(max_angle.id).unwrap();
+
+    let crossed_angle = Item {
+        id: String::from("1"),
+        props: FullAngleProperties {
+            candle: Item {
+                id: String::from("1"),
+                props: StepBacktestingCandleProperties::default(),
+            },
+            base: BasicAngleProperties {
+                r#type: Level::Min,
+                ..Default::default()
+            },
+        },
+    };
+
+    let mut general_corridor = vec![min_angle_candle, max_angle_candle];
+
+    for _ in 0..3 {
+        general_corridor.push(
+            store
+                .create_candle(
+                    xid::new().to_string(),
+                    StepBacktestingCandleProperties::default(),
+                )
+                .unwrap(),
+        );
+    }
+
+    let min_amount_of_candles_in_corridor_defining_edge_bargaining = dec!(2);
+
+    assert!(LevelConditionsImpl::level_comes_out_of_bargaining_corridor(
+        &crossed_angle,
+        &general_corridor,
+        &store,
+        min_amount_of_candles_in_corridor_defining_edge_bargaining,
+        Some(5),
+    )
+    .unwrap());
+
+    assert!(
+        !LevelConditionsImpl::level_comes_out_of_bargaining_corridor(
+            &crossed_angle,
+            &general_corridor,
+            &store,
+            min_amount_of_candles_in_corridor_defining_edge_bargaining,
+            Some(3),
+        )
+        .unwrap()
+    );
+}
@@ -6,7 +6,7 @@ use crate::step::utils::entities::candle::{StepBacktestingCandleProperties, Step
 use crate::step::utils::entities::order::StepOrderProperties;
 use crate::step::utils::entities::params::{StepPointParam, StepRatioParam};
 use crate::step::utils::entities::working_levels::{
-    LevelTime, WLMaxCrossingValue, WLPrice, WLStatus,
+    LevelOrigin, LevelTime, WLMaxCrossingValue, WLPrice, WLStatus,
 };
 use crate::step::utils::entities::{Mode, StatisticsChartsNotifier, StatisticsNotifier, MODE_ENV};
 use crate::step::utils::level_conditions::LevelConditions;
@@ -15,36 +15,49 @@ use crate::step::utils::stores::candle_store::StepCandleStore;
 use crate::step::utils::stores::working_level_store::StepWorkingLevelStore;
 use crate::step::utils::stores::StepConfig;
 use anyhow::{Context, Result};
-use base::entities::candle::{CandleId, CandleVolatility};
+use base::entities::candle::{BasicCandleProperties, CandleId, CandleVolatility};
 use base::entities::order::{BasicOrderProperties, OrderStatus, OrderType};
 use base::entities::tick::{TickPrice, TickTime, UniversalTickPrice};
 use base::entities::{BasicTickProperties, Item, Level, Tendency};
-use base::helpers::{price_to_points, Holiday, NumberOfDaysToExclude};
+use base::helpers::{
+    points_to_price, price_to_points, signed_points, Holiday, NumberOfDaysToExclude,
+};
 use base::notifier::NotificationQueue;
 use base::params::{ParamOutputValue, StrategyParams};
 use chrono::NaiveDateTime;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::str::FromStr;
 
-use super::entities::working_levels::{BasicWLProperties, WLId};
+use super::entities::working_levels::{
+    BasicWLProperties, CorridorType, CrossedLevelTieBreak, LevelScoreWeights, WLId,
+};
 
 pub trait LevelUtils {
     /// Checks whether one of the working levels has got crossed and returns such a level.
+    /// If several levels are crossed, the level is chosen according to the tie-break policy.
     fn get_crossed_level<W>(
         current_tick_price: UniversalTickPrice,
         created_working_levels: &[Item<WLId, W>],
+        tie_break: CrossedLevelTieBreak,
     ) -> Option<&Item<WLId, W>>
     where
         W: AsRef<BasicWLProperties>;
 
     /// Moves active working levels to removed if they have closed orders in their chains.
-    fn remove_active_working_levels_with_closed_orders<O>(
+    /// The level whose closed orders were all stopped out is re-armed instead of removing
+    /// until the max number of re-entries of the level is exhausted.
+    fn remove_active_working_levels_with_closed_orders<O, S>(
         working_level_store: &mut impl StepWorkingLevelStore<OrderProperties = O>,
+        max_reentries: ParamOutputValue,
+        order_is_stopped_out: &S,
     ) -> Result<()>
     where
-        O: Into<StepOrderProperties>;
+        O: Into<StepOrderProperties>,
+        S: Fn(&str) -> bool;
 
     /// Updates the activation max crossing distance for active levels.
     /// It's required to delete invalid active levels that crossed particular distance
@@ -74,17 +87,54 @@ pub trait LevelUtils {
         E: Fn(NaiveDateTime, NaiveDateTime, &[Holiday]) -> NumberOfDaysToExclude,
         N: NotificationQueue;
 
+    /// Removes the created working levels jumped over by the gap between the close price
+    /// of the previous candle and the open price of the current candle (e.g. the weekend gap)
+    /// if the gap is not less than the min gap in points. The zero min gap disables the removal.
+    fn remove_working_levels_jumped_over_by_gap<T, N>(
+        working_level_store: &mut impl StepWorkingLevelStore<WorkingLevelProperties = T>,
+        previous_candle: &BasicCandleProperties,
+        current_candle: &BasicCandleProperties,
+        min_gap: ParamOutputValue,
+        entity: StatisticsNotifier<N>,
+    ) -> Result<()>
+    where
+        T: AsRef<BasicWLProperties> + Debug,
+        N: NotificationQueue;
+
     /// Moves take profits of the existing chains of orders when the current tick price
     /// deviates from the active working level on the defined amount of points.
-    fn move_take_profits<W>(
-        working_level_store: &mut impl StepWorkingLevelStore<WorkingLevelProperties = W>,
+    /// If the minimum profit to trail is defined, only the take profits of the opened orders
+    /// that are in profit by at least this amount of points from their open prices are moved.
+    fn move_take_profits<W, O>(
+        working_level_store: &mut impl StepWorkingLevelStore<
+            WorkingLevelProperties = W,
+            OrderProperties = O,
+        >,
         distance_from_level_for_signaling_of_moving_take_profits: ParamOutputValue,
         distance_to_move_take_profits: ParamOutputValue,
+        min_profit_to_trail: Option<ParamOutputValue>,
         current_tick_price: UniversalTickPrice,
     ) -> Result<()>
     where
-        W: Into<BasicWLProperties>;
+        W: Into<BasicWLProperties>,
+        O: AsRef<BasicOrderProperties>;
+
+    /// Moves stop losses of the opened orders of the active working levels to their open prices
+    /// (shifted by the optional buffer in the profit direction) when the current tick price
+    /// advances from the open price on the trigger distance in favor of the order.
+    /// The stop losses that are already at or beyond breakeven are never moved backward.
+    fn move_stops_to_breakeven<W>(
+        working_level_store: &mut impl StepWorkingLevelStore<
+            WorkingLevelProperties = W,
+            OrderProperties = StepOrderProperties,
+        >,
+        trigger_distance: ParamOutputValue,
+        buffer: Option<ParamOutputValue>,
+        current_tick_price: UniversalTickPrice,
+    ) -> Result<()>;
 
+    /// Updates the tendency on crossing the angle and returns the origin of the new working level
+    /// if the crossed angle should become a new working level.
     fn update_tendency_and_get_instruction_to_create_new_working_level<
         S,
         D,
@@ -106,7 +156,7 @@ pub trait LevelUtils {
         crossed_angle: &Item<AngleId, FullAngleProperties<A, C>>,
         current_candle: &Item<CandleId, C>,
         params: &M,
-    ) -> Result<bool>
+    ) -> Result<Option<LevelOrigin>>
     where
         S: StepAngleStore<AngleProperties = A, CandleProperties = C>
             + StepCandleStore<CandleProperties = C>
@@ -121,6 +171,7 @@ pub trait LevelUtils {
             &[Item<CandleId, C>],
             &S,
             ParamOutputValue,
+            Option<usize>,
         ) -> Result<bool>,
         M: StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
         P: Fn(
@@ -165,6 +216,7 @@ where
         &[Item<CandleId, C>],
         &S,
         ParamOutputValue,
+        Option<usize>,
     ) -> Result<bool>,
     M: StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
     P: Fn(&Item<AngleId, FullAngleProperties<A, C>>, &Item<CandleId, C>, &S, &M) -> Result<bool>,
@@ -197,6 +249,7 @@ where
         &[Item<CandleId, C>],
         &S,
         ParamOutputValue,
+        Option<usize>,
     ) -> Result<bool>,
     M: StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
     P: Fn(&Item<AngleId, FullAngleProperties<A, C>>, &Item<CandleId, C>, &S, &M) -> Result<bool>,
@@ -233,65 +286,90 @@ impl LevelUtilsImpl {
     pub fn new() -> Self {
         Self::default()
     }
-
-    fn working_level_has_closed_orders_in_chain(chain_of_orders: &[StepOrderProperties]) -> bool {
-        chain_of_orders
-            .iter()
-            .any(|order| order.base.status == OrderStatus::Closed)
-    }
 }
 
 impl LevelUtils for LevelUtilsImpl {
     fn get_crossed_level<W>(
         current_tick_price: UniversalTickPrice,
         created_working_levels: &[Item<WLId, W>],
+        tie_break: CrossedLevelTieBreak,
     ) -> Option<&Item<WLId, W>>
     where
         W: AsRef<BasicWLProperties>,
     {
-        let (lowest_current_tick_price, highest_current_tick_price) = match current_tick_price {
-            UniversalTickPrice::Historical(current_tick_price) => {
-                (current_tick_price.low, current_tick_price.high)
-            }
-            UniversalTickPrice::Realtime(current_tick_price) => {
-                (current_tick_price, current_tick_price)
-            }
-        };
-
-        for level in created_working_levels {
+        let crossed_levels = created_working_levels.iter().filter(|level| {
             let level_properties = level.props.as_ref();
 
             match level_properties.r#type {
-                OrderType::Buy => {
-                    if lowest_current_tick_price < level_properties.price {
-                        return Some(level);
-                    }
-                }
-                OrderType::Sell => {
-                    if highest_current_tick_price > level_properties.price {
-                        return Some(level);
-                    }
-                }
+                OrderType::Buy => current_tick_price.lowest() < level_properties.price,
+                OrderType::Sell => current_tick_price.highest() > level_properties.price,
             }
-        }
+        });
+
+        let by_time_and_id = |a: &&Item<WLId, W>, b: &&Item<WLId, W>| {
+            a.props
+                .as_ref()
+                .time
+                .cmp(&b.props.as_ref().time)
+                .then_with(|| a.id.cmp(&b.id))
+        };
 
-        None
+        match tie_break {
+            CrossedLevelTieBreak::Nearest => crossed_levels.min_by(|a, b| {
+                let distance = |level: &Item<WLId, W>| {
+                    (level.props.as_ref().price - current_tick_price.close()).abs()
+                };
+
+                distance(a)
+                    .cmp(&distance(b))
+                    .then_with(|| by_time_and_id(a, b))
+            }),
+            CrossedLevelTieBreak::Oldest => crossed_levels.min_by(by_time_and_id),
+            CrossedLevelTieBreak::Newest => crossed_levels.max_by(by_time_and_id),
+        }
     }
 
-    fn remove_active_working_levels_with_closed_orders<O>(
+    fn remove_active_working_levels_with_closed_orders<O, S>(
         working_level_store: &mut impl StepWorkingLevelStore<OrderProperties = O>,
+        max_reentries: ParamOutputValue,
+        order_is_stopped_out: &S,
     ) -> Result<()>
     where
         O: Into<StepOrderProperties>,
+        S: Fn(&str) -> bool,
     {
         for level in working_level_store.get_active_working_levels()? {
             let level_chain_of_orders: Vec<_> = working_level_store
                 .get_working_level_chain_of_orders(&level.id)?
                 .into_iter()
-                .map(|order| order.props.into())
+                .map(|order| Item {
+                    id: order.id,
+                    props: order.props.into(),
+                })
                 .collect();
 
-            if Self::working_level_has_closed_orders_in_chain(&level_chain_of_orders) {
+            let closed_orders: Vec<_> = level_chain_of_orders
+                .iter()
+                .filter(|order| order.props.base.status == OrderStatus::Closed)
+                .collect();
+
+            if closed_orders.is_empty() {
+                continue;
+            }
+
+            let level_is_stopped_out = closed_orders
+                .iter()
+                .all(|order| order_is_stopped_out(&order.id));
+
+            if level_is_stopped_out
+                && Decimal::from(
+                    working_level_store.get_number_of_reentries_of_working_level(&level.id)?,
+                ) < max_reentries
+            {
+                log::debug!("the stopped out working level {} is re-armed", level.id);
+
+                working_level_store.rearm_working_level(&level.id)?;
+            } else {
                 working_level_store.remove_working_level(&level.id)?;
             }
         }
@@ -324,11 +402,14 @@ impl LevelUtils for LevelUtilsImpl {
                 props: level.props.into(),
             })
         {
-            let current_crossing_value = match level.props.r#type {
-                OrderType::Buy => price_to_points(level.props.price - lowest_current_tick_price),
-                OrderType::Sell => price_to_points(highest_current_tick_price - level.props.price),
+            let crossing_tick_price = match level.props.r#type {
+                OrderType::Buy => lowest_current_tick_price,
+                OrderType::Sell => highest_current_tick_price,
             };
 
+            let current_crossing_value =
+                signed_points(level.props.price, crossing_tick_price, level.props.r#type);
+
             log::debug!(
                 "current crossing value of level ({:?}) is {}",
                 level,
@@ -544,24 +625,139 @@ impl LevelUtils for LevelUtilsImpl {
         Ok(())
     }
 
-    fn move_take_profits<W>(
-        working_level_store: &mut impl StepWorkingLevelStore<WorkingLevelProperties = W>,
+    fn remove_working_levels_jumped_over_by_gap<T, N>(
+        working_level_store: &mut impl StepWorkingLevelStore<WorkingLevelProperties = T>,
+        previous_candle: &BasicCandleProperties,
+        current_candle: &BasicCandleProperties,
+        min_gap: ParamOutputValue,
+        mut entity: StatisticsNotifier<N>,
+    ) -> Result<()>
+    where
+        T: AsRef<BasicWLProperties> + Debug,
+        N: NotificationQueue,
+    {
+        if min_gap <= dec!(0) {
+            return Ok(());
+        }
+
+        let gap_start = previous_candle.prices.close;
+        let gap_end = current_candle.prices.open;
+
+        if price_to_points((gap_end - gap_start).abs()) < min_gap {
+            return Ok(());
+        }
+
+        let (lowest_gap_price, highest_gap_price) = if gap_start < gap_end {
+            (gap_start, gap_end)
+        } else {
+            (gap_end, gap_start)
+        };
+
+        for level in working_level_store.get_created_working_levels()? {
+            let level_price = level.props.as_ref().price;
+
+            if level_price < lowest_gap_price || level_price > highest_gap_price {
+                continue;
+            }
+
+            log::debug!(
+                "level ({:?}) is jumped over by the gap from {} to {}",
+                level,
+                gap_start,
+                gap_end
+            );
+
+            match &mut entity {
+                StatisticsNotifier::Backtesting(statistics) => {
+                    statistics.deleted_by_candle_gap += 1;
+                    statistics.number_of_working_levels -= 1;
+                }
+                StatisticsNotifier::Realtime(queue) => {
+                    queue.send_message(format!(
+                        "level ({:?}) is jumped over by the gap from {} to {}",
+                        level, gap_start, gap_end
+                    ))?;
+                }
+            }
+
+            working_level_store.remove_working_level(&level.id)?;
+        }
+
+        Ok(())
+    }
+
+    fn move_stops_to_breakeven<W>(
+        working_level_store: &mut impl StepWorkingLevelStore<
+            WorkingLevelProperties = W,
+            OrderProperties = StepOrderProperties,
+        >,
+        trigger_distance: ParamOutputValue,
+        buffer: Option<ParamOutputValue>,
+        current_tick_price: UniversalTickPrice,
+    ) -> Result<()> {
+        let buffer = points_to_price(buffer.unwrap_or_default());
+
+        for level in working_level_store.get_active_working_levels()? {
+            for order in working_level_store
+                .get_working_level_chain_of_orders(&level.id)?
+                .into_iter()
+                .filter(|order| order.props.base.status == OrderStatus::Opened)
+            {
+                let prices = &order.props.base.prices;
+
+                let (favorable_distance, breakeven_stop_loss, stop_loss_is_behind_breakeven) =
+                    match order.props.base.r#type {
+                        OrderType::Buy => {
+                            let breakeven_stop_loss = prices.open + buffer;
+                            (
+                                price_to_points(current_tick_price.highest() - prices.open),
+                                breakeven_stop_loss,
+                                prices.stop_loss < breakeven_stop_loss,
+                            )
+                        }
+                        OrderType::Sell => {
+                            let breakeven_stop_loss = prices.open - buffer;
+                            (
+                                price_to_points(prices.open - current_tick_price.lowest()),
+                                breakeven_stop_loss,
+                                prices.stop_loss > breakeven_stop_loss,
+                            )
+                        }
+                    };
+
+                if favorable_distance >= trigger_distance && stop_loss_is_behind_breakeven {
+                    log::debug!(
+                        "move stop loss of order ({:?}) to breakeven ({}), because the favorable \
+                        distance ({}) >= trigger distance ({})",
+                        order,
+                        breakeven_stop_loss,
+                        favorable_distance,
+                        trigger_distance
+                    );
+
+                    working_level_store
+                        .update_stop_loss_of_order(&order.id, breakeven_stop_loss)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn move_take_profits<W, O>(
+        working_level_store: &mut impl StepWorkingLevelStore<
+            WorkingLevelProperties = W,
+            OrderProperties = O,
+        >,
         distance_from_level_for_signaling_of_moving_take_profits: ParamOutputValue,
         distance_to_move_take_profits: ParamOutputValue,
+        min_profit_to_trail: Option<ParamOutputValue>,
         current_tick_price: UniversalTickPrice,
     ) -> Result<()>
     where
         W: Into<BasicWLProperties>,
+        O: AsRef<BasicOrderProperties>,
     {
-        let (lowest_current_tick_price, highest_current_tick_price) = match current_tick_price {
-            UniversalTickPrice::Historical(current_tick_price) => {
-                (current_tick_price.low, current_tick_price.high)
-            }
-            UniversalTickPrice::Realtime(current_tick_price) => {
-                (current_tick_price, current_tick_price)
-            }
-        };
-
         for level in working_level_store
             .get_active_working_levels()?
             .into_iter()
@@ -573,10 +769,10 @@ impl LevelUtils for LevelUtilsImpl {
             if !working_level_store.take_profits_of_level_are_moved(&level.id)? {
                 let deviation_distance = match level.props.r#type {
                     OrderType::Buy => {
-                        price_to_points(level.props.price - lowest_current_tick_price)
+                        price_to_points(level.props.price - current_tick_price.lowest())
                     }
                     OrderType::Sell => {
-                        price_to_points(highest_current_tick_price - level.props.price)
+                        price_to_points(current_tick_price.highest() - level.props.price)
                     }
                 };
 
@@ -589,8 +785,42 @@ impl LevelUtils for LevelUtilsImpl {
                         distance_from_level_for_signaling_of_moving_take_profits
                     );
 
-                    working_level_store
-                        .move_take_profits_of_level(&level.id, distance_to_move_take_profits)?;
+                    match min_profit_to_trail {
+                        None => working_level_store
+                            .move_take_profits_of_level(&level.id, distance_to_move_take_profits)?,
+                        Some(min_profit_to_trail) => {
+                            let profitable_orders = working_level_store
+                                .get_working_level_chain_of_orders(&level.id)?
+                                .into_iter()
+                                .filter(|order| {
+                                    let order = order.props.as_ref();
+
+                                    let profit = match order.r#type {
+                                        OrderType::Buy => price_to_points(
+                                            current_tick_price.highest() - order.prices.open,
+                                        ),
+                                        OrderType::Sell => price_to_points(
+                                            order.prices.open - current_tick_price.lowest(),
+                                        ),
+                                    };
+
+                                    order.status == OrderStatus::Opened
+                                        && profit >= min_profit_to_trail
+                                })
+                                .map(|order| order.id)
+                                .collect::<Vec<_>>();
+
+                            // the level is checked again on the next tick
+                            // until at least one of its orders is profitable enough
+                            if !profitable_orders.is_empty() {
+                                working_level_store.move_take_profits_of_orders_of_level(
+                                    &level.id,
+                                    &profitable_orders,
+                                    distance_to_move_take_profits,
+                                )?;
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -619,7 +849,7 @@ impl LevelUtils for LevelUtilsImpl {
         crossed_angle: &Item<AngleId, FullAngleProperties<A, C>>,
         current_candle: &Item<CandleId, C>,
         params: &M,
-    ) -> Result<bool>
+    ) -> Result<Option<LevelOrigin>>
     where
         S: StepAngleStore<AngleProperties = A, CandleProperties = C>
             + StepCandleStore<CandleProperties = C>
@@ -634,6 +864,7 @@ impl LevelUtils for LevelUtilsImpl {
             &[Item<CandleId, C>],
             &S,
             ParamOutputValue,
+            Option<usize>,
         ) -> Result<bool>,
         M: StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
         P: Fn(
@@ -688,6 +919,14 @@ impl LevelUtils for LevelUtilsImpl {
             {
                 let mut skip_creating_new_working_level = false;
 
+                let origin = if config.tendency != crossed_angle.props.base.as_ref().r#type.into() {
+                    LevelOrigin::TendencyChange
+                } else if is_second_level_after_bargaining_tendency_change {
+                    LevelOrigin::SecondLevelAfterBargainingTendencyChange
+                } else {
+                    LevelOrigin::CrossingOfTendencyChangeAngle
+                };
+
                 if config.tendency != crossed_angle.props.base.as_ref().r#type.into() {
                     config.tendency = crossed_angle.props.base.as_ref().r#type.into();
 
@@ -733,13 +972,31 @@ impl LevelUtils for LevelUtilsImpl {
                         "set second_level_after_bargaining_tendency_change_is_created to false"
                     );
 
+                    let min_amount_of_candles_in_corridor_defining_edge_bargaining = params
+                        .get_point_param_value(
+                            StepPointParam::MinAmountOfCandlesInCorridorDefiningEdgeBargaining,
+                        );
+
+                    // fetching not less candles than the min amount keeps the corridor length
+                    // check intact without loading the whole corridor
+                    let general_corridor = match config.max_candles_to_scan_in_general_corridor {
+                        Some(max_candles_to_scan) => store.get_last_candles_of_general_corridor(
+                            max_candles_to_scan.max(
+                                min_amount_of_candles_in_corridor_defining_edge_bargaining
+                                    .ceil()
+                                    .to_usize()
+                                    .unwrap_or_default(),
+                            ),
+                        )?,
+                        None => store.get_candles_of_general_corridor()?,
+                    };
+
                     if !(utils.level_comes_out_of_bargaining_corridor)(
                         crossed_angle,
-                        &store.get_candles_of_general_corridor()?,
+                        &general_corridor,
                         store,
-                        params.get_point_param_value(
-                            StepPointParam::MinAmountOfCandlesInCorridorDefiningEdgeBargaining,
-                        ),
+                        min_amount_of_candles_in_corridor_defining_edge_bargaining,
+                        config.max_candles_to_scan_in_general_corridor,
                     )? {
                         skip_creating_new_working_level = false;
 
@@ -843,14 +1100,70 @@ impl LevelUtils for LevelUtilsImpl {
                         );
                     }
 
-                    return Ok(true);
+                    return Ok(Some(origin));
                 }
             }
         }
 
-        Ok(false)
+        Ok(None)
     }
 }
 
+/// Scores the working level to rank the levels competing for the trading, the higher the better.
+/// The score is the weighted sum of the max crossing value of the level in volatilities,
+/// the number of candles in the small corridor of the level and the negative number
+/// of the other levels of the same type closer than the distance defining nearby levels.
+pub fn score_working_level<W>(
+    level: &Item<WLId, W>,
+    working_level_store: &impl StepWorkingLevelStore<WorkingLevelProperties = W>,
+    params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+    current_volatility: CandleVolatility,
+    weights: LevelScoreWeights,
+) -> Result<Decimal>
+where
+    W: AsRef<BasicWLProperties>,
+{
+    let break_distance =
+        match working_level_store.get_max_crossing_value_of_working_level(&level.id)? {
+            Some(max_crossing_value) if current_volatility > 0 => {
+                max_crossing_value / Decimal::from(current_volatility)
+            }
+            _ => dec!(0),
+        };
+
+    let corridor_strength = Decimal::from(
+        working_level_store
+            .get_candles_of_working_level_corridor(&level.id, CorridorType::Small)?
+            .len(),
+    );
+
+    let distance_defining_nearby_levels = params.get_ratio_param_value(
+        StepRatioParam::DistanceDefiningNearbyLevelsOfTheSameType,
+        current_volatility,
+    );
+
+    let level_props = level.props.as_ref();
+
+    let nearby_levels = Decimal::from(
+        working_level_store
+            .get_all_working_levels()?
+            .iter()
+            .filter(|other_level| {
+                let other_level_props = other_level.props.as_ref();
+
+                other_level.id != level.id
+                    && other_level_props.r#type == level_props.r#type
+                    && price_to_points((other_level_props.price - level_props.price).abs())
+                        <= distance_defining_nearby_levels
+            })
+            .count(),
+    );
+
+    Ok(
+        weights.break_distance * break_distance + weights.corridor_strength * corridor_strength
+            - weights.nearby_levels * nearby_levels,
+    )
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,19 +1,22 @@
 use crate::step::utils::entities::working_levels::{
-    BacktestingWLProperties, CorridorType, LevelTime, WLPrice,
+    BacktestingWLProperties, CorridorType, CrossedLevelTieBreak, LevelScoreWeights, LevelTime,
+    WLPrice, WLStatus,
 };
 use crate::step::utils::entities::FakeBacktestingNotificationQueue;
 use crate::step::utils::level_conditions::{LevelConditionsImpl, MinAmountOfCandles};
 use crate::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
 use crate::step::utils::stores::StepBacktestingStatistics;
-use base::entities::candle::CandleId;
-use base::entities::order::{BasicOrderPrices, BasicOrderProperties, OrderPrice, OrderStatus};
+use base::entities::candle::{CandleId, CandlePrices};
+use base::entities::order::{
+    BasicOrderPrices, BasicOrderProperties, OrderId, OrderPrice, OrderStatus,
+};
 use base::entities::tick::{HistoricalTickPrice, TickTime};
 use base::helpers::points_to_price;
 use base::notifier::Message;
 use base::params::ParamOutputValue;
 use base::stores::candle_store::BasicCandleStore;
 use base::stores::order_store::BasicOrderStore;
-use chrono::{Datelike, NaiveDate, Utc};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use std::cell::RefCell;
@@ -51,13 +54,19 @@ fn get_crossed_level__current_tick_price_is_less_than_buy_level_price__should_re
 
     let realtime_current_tick_price = UniversalTickPrice::Realtime(dec!(8));
 
-    let crossed_level =
-        LevelUtilsImpl::get_crossed_level(historical_current_tick_price, &created_working_levels);
+    let crossed_level = LevelUtilsImpl::get_crossed_level(
+        historical_current_tick_price,
+        &created_working_levels,
+        Default::default(),
+    );
 
     assert_eq!(crossed_level.unwrap().id, "1");
 
-    let crossed_level =
-        LevelUtilsImpl::get_crossed_level(realtime_current_tick_price, &created_working_levels);
+    let crossed_level = LevelUtilsImpl::get_crossed_level(
+        realtime_current_tick_price,
+        &created_working_levels,
+        Default::default(),
+    );
 
     assert_eq!(crossed_level.unwrap().id, "1");
 }
@@ -93,13 +102,19 @@ fn get_crossed_level__current_tick_price_is_greater_than_sell_level_price__shoul
 
     let realtime_current_tick_price = UniversalTickPrice::Realtime(dec!(12));
 
-    let crossed_level =
-        LevelUtilsImpl::get_crossed_level(historical_current_tick_price, &created_working_levels);
+    let crossed_level = LevelUtilsImpl::get_crossed_level(
+        historical_current_tick_price,
+        &created_working_levels,
+        Default::default(),
+    );
 
     assert_eq!(crossed_level.unwrap().id, "2");
 
-    let crossed_level =
-        LevelUtilsImpl::get_crossed_level(realtime_current_tick_price, &created_working_levels);
+    let crossed_level = LevelUtilsImpl::get_crossed_level(
+        realtime_current_tick_price,
+        &created_working_levels,
+        Default::default(),
+    );
 
     assert_eq!(crossed_level.unwrap().id, "2");
 }
@@ -136,17 +151,86 @@ fn get_crossed_level__current_tick_price_is_greater_than_buy_level_price_and_les
     let realtime_current_tick_price = UniversalTickPrice::Realtime(dec!(11));
     let current_tick_price = dec!(11);
 
-    let crossed_level =
-        LevelUtilsImpl::get_crossed_level(historical_current_tick_price, &created_working_levels);
+    let crossed_level = LevelUtilsImpl::get_crossed_level(
+        historical_current_tick_price,
+        &created_working_levels,
+        Default::default(),
+    );
 
     assert!(crossed_level.is_none());
 
-    let crossed_level =
-        LevelUtilsImpl::get_crossed_level(realtime_current_tick_price, &created_working_levels);
+    let crossed_level = LevelUtilsImpl::get_crossed_level(
+        realtime_current_tick_price,
+        &created_working_levels,
+        Default::default(),
+    );
 
     assert!(crossed_level.is_none());
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn get_crossed_level__several_levels_are_crossed__should_choose_level_by_tie_break_policy() {
+    let base_time = NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0);
+
+    let created_working_levels = vec![
+        Item {
+            id: String::from("1"),
+            props: BasicWLProperties {
+                r#type: OrderType::Buy,
+                price: dec!(10),
+                time: base_time + Duration::hours(1),
+            },
+        },
+        Item {
+            id: String::from("2"),
+            props: BasicWLProperties {
+                r#type: OrderType::Sell,
+                price: dec!(5),
+                time: base_time,
+            },
+        },
+        Item {
+            id: String::from("3"),
+            props: BasicWLProperties {
+                r#type: OrderType::Buy,
+                price: dec!(8),
+                time: base_time + Duration::hours(2),
+            },
+        },
+        Item {
+            id: String::from("4"),
+            props: BasicWLProperties {
+                r#type: OrderType::Buy,
+                price: dec!(8),
+                time: base_time + Duration::hours(2),
+            },
+        },
+    ];
+
+    let current_tick_price = UniversalTickPrice::Historical(HistoricalTickPrice {
+        high: dec!(9),
+        low: dec!(4),
+        close: dec!(7),
+    });
+
+    let mut reversed_working_levels = created_working_levels.clone();
+    reversed_working_levels.reverse();
+
+    for (tie_break, expected_id) in [
+        (CrossedLevelTieBreak::Nearest, "3"),
+        (CrossedLevelTieBreak::Oldest, "2"),
+        (CrossedLevelTieBreak::Newest, "4"),
+    ] {
+        for working_levels in [&created_working_levels, &reversed_working_levels] {
+            let crossed_level =
+                LevelUtilsImpl::get_crossed_level(current_tick_price, working_levels, tie_break);
+
+            assert_eq!(crossed_level.unwrap().id, expected_id);
+        }
+    }
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn remove_active_working_levels_with_closed_orders__two_active_working_levels_with_closed_orders_exist__should_remove_these_two_levels(
@@ -181,6 +265,7 @@ fn remove_active_working_levels_with_closed_orders__two_active_working_levels_wi
                             ..Default::default()
                         },
                         working_level_id: working_level_ids[0].clone(),
+                        creation_time: None,
                     },
                 )
                 .unwrap()
@@ -206,6 +291,7 @@ fn remove_active_working_levels_with_closed_orders__two_active_working_levels_wi
                             ..Default::default()
                         },
                         working_level_id: working_level_ids[2].clone(),
+                        creation_time: None,
                     },
                 )
                 .unwrap()
@@ -249,7 +335,12 @@ fn remove_active_working_levels_with_closed_orders__two_active_working_levels_wi
         store.move_working_level_to_active(level_id).unwrap();
     }
 
-    LevelUtilsImpl::remove_active_working_levels_with_closed_orders(&mut store).unwrap();
+    LevelUtilsImpl::remove_active_working_levels_with_closed_orders(
+        &mut store,
+        dec!(0),
+        &|_order_id: &str| true,
+    )
+    .unwrap();
 
     assert!(!store
         .get_active_working_levels()
@@ -258,6 +349,105 @@ fn remove_active_working_levels_with_closed_orders__two_active_working_levels_wi
         .any(|level| { level.id == working_level_ids[0] || level.id == working_level_ids[2] }));
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn remove_active_working_levels_with_closed_orders__stopped_out_level_with_max_reentries__should_rearm_level_until_reentries_are_exhausted(
+) {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let stopped_out_level_id = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap()
+        .id;
+
+    let take_profit_level_id = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap()
+        .id;
+
+    let mut order_ids = Vec::new();
+
+    for level_id in [&stopped_out_level_id, &take_profit_level_id] {
+        order_ids.push(
+            store
+                .create_order(
+                    xid::new().to_string(),
+                    StepOrderProperties {
+                        base: BasicOrderProperties {
+                            status: OrderStatus::Closed,
+                            ..Default::default()
+                        },
+                        working_level_id: level_id.clone(),
+                        creation_time: None,
+                    },
+                )
+                .unwrap()
+                .id,
+        );
+
+        store.move_working_level_to_active(level_id).unwrap();
+    }
+
+    let stopped_out_order_id = order_ids[0].clone();
+    let order_is_stopped_out = |order_id: &str| order_id == stopped_out_order_id;
+
+    let max_reentries = dec!(2);
+
+    for number_of_reentries in 1..=2 {
+        LevelUtilsImpl::remove_active_working_levels_with_closed_orders(
+            &mut store,
+            max_reentries,
+            &order_is_stopped_out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            store
+                .get_working_level_status(&stopped_out_level_id)
+                .unwrap(),
+            Some(WLStatus::Active)
+        );
+        assert_eq!(
+            store
+                .get_number_of_reentries_of_working_level(&stopped_out_level_id)
+                .unwrap(),
+            number_of_reentries
+        );
+        assert_eq!(
+            store
+                .get_order_by_id(&stopped_out_order_id)
+                .unwrap()
+                .unwrap()
+                .props
+                .base
+                .status,
+            OrderStatus::Pending
+        );
+
+        // the level closed by take profit isn't re-armed
+        assert!(store
+            .get_working_level_by_id(&take_profit_level_id)
+            .unwrap()
+            .is_none());
+
+        store
+            .update_order_status(&stopped_out_order_id, OrderStatus::Closed)
+            .unwrap();
+    }
+
+    LevelUtilsImpl::remove_active_working_levels_with_closed_orders(
+        &mut store,
+        max_reentries,
+        &order_is_stopped_out,
+    )
+    .unwrap();
+
+    assert!(store
+        .get_working_level_by_id(&stopped_out_level_id)
+        .unwrap()
+        .is_none());
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn update_max_crossing_value_of_level__buy_level_first_crossing_value__should_set_new_crossing_value(
@@ -273,6 +463,7 @@ fn update_max_crossing_value_of_level__buy_level_first_crossing_value__should_se
             ..Default::default()
         },
         chart_index: 0,
+        ..Default::default()
     };
 
     let level = store
@@ -341,6 +532,7 @@ fn update_max_crossing_value_of_level__sell_level_first_crossing_value__should_s
             ..Default::default()
         },
         chart_index: 0,
+        ..Default::default()
     };
 
     let level = store
@@ -409,6 +601,7 @@ fn update_max_crossing_value_of_level__buy_level_crossing_value_is_negative__sho
             ..Default::default()
         },
         chart_index: 0,
+        ..Default::default()
     };
 
     let level = store
@@ -484,6 +677,7 @@ fn update_max_crossing_value_of_level__sell_level_crossing_value_is_negative__sh
             ..Default::default()
         },
         chart_index: 0,
+        ..Default::default()
     };
 
     let level = store
@@ -544,6 +738,7 @@ fn update_max_crossing_value_of_level__crossing_value_is_greater_than_previous__
             ..Default::default()
         },
         chart_index: 0,
+        ..Default::default()
     };
 
     let level = store
@@ -622,6 +817,7 @@ fn update_max_crossing_value_of_level__crossing_value_is_less_than_previous__sho
             ..Default::default()
         },
         chart_index: 0,
+        ..Default::default()
     };
 
     let level = store
@@ -712,6 +908,15 @@ impl LevelConditions for TestLevelConditionsImpl {
         level_price == dec!(1) || level_price == dec!(5)
     }
 
+    fn level_expired_by_distance_directional(
+        _level_price: WLPrice,
+        _level_type: OrderType,
+        _current_tick_price: UniversalTickPrice,
+        _distance_from_level_for_its_deletion: ParamOutputValue,
+    ) -> bool {
+        unimplemented!()
+    }
+
     fn level_expired_by_time(
         level_time: LevelTime,
         _current_tick_time: TickTime,
@@ -752,6 +957,7 @@ impl LevelConditions for TestLevelConditionsImpl {
         general_corridor: &[Item<CandleId, C>],
         angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -1053,6 +1259,264 @@ fn remove_invalid_working_levels__realtime__should_remove_only_invalid_levels()
     assert_eq!(*notification_queue.number_of_calls.borrow(), 5);
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn remove_working_levels_jumped_over_by_gap__weekend_gap_past_levels__should_remove_created_levels_in_path_of_gap(
+) {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 4,
+        ..Default::default()
+    };
+
+    let mut level_ids = Vec::new();
+
+    // the first level is the active one in the path of the gap, the last one is beyond the gap
+    for price in [dec!(1.38200), dec!(1.38300), dec!(1.38500), dec!(1.38700)] {
+        let level = store
+            .create_working_level(
+                xid::new().to_string(),
+                BacktestingWLProperties {
+                    base: BasicWLProperties {
+                        price,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        level_ids.push(level.id);
+    }
+
+    store.move_working_level_to_active(&level_ids[0]).unwrap();
+
+    let friday_candle = BasicCandleProperties {
+        time: NaiveDate::from_ymd(2022, 5, 20).and_hms(23, 0, 0),
+        prices: CandlePrices {
+            open: dec!(1.38100),
+            high: dec!(1.38150),
+            low: dec!(1.37950),
+            close: dec!(1.38000),
+        },
+        ..Default::default()
+    };
+
+    let monday_candle = BasicCandleProperties {
+        time: NaiveDate::from_ymd(2022, 5, 23).and_hms(0, 0, 0),
+        prices: CandlePrices {
+            open: dec!(1.38500),
+            high: dec!(1.38600),
+            low: dec!(1.38400),
+            close: dec!(1.38550),
+        },
+        ..Default::default()
+    };
+
+    // the gap of 500 points is less than the min gap
+    LevelUtilsImpl::remove_working_levels_jumped_over_by_gap(
+        &mut store,
+        &friday_candle,
+        &monday_candle,
+        dec!(600),
+        StatisticsNotifier::<FakeBacktestingNotificationQueue>::Backtesting(&mut statistics),
+    )
+    .unwrap();
+
+    assert_eq!(store.get_created_working_levels().unwrap().len(), 3);
+
+    LevelUtilsImpl::remove_working_levels_jumped_over_by_gap(
+        &mut store,
+        &friday_candle,
+        &monday_candle,
+        dec!(300),
+        StatisticsNotifier::<FakeBacktestingNotificationQueue>::Backtesting(&mut statistics),
+    )
+    .unwrap();
+
+    assert_eq!(
+        store
+            .get_created_working_levels()
+            .unwrap()
+            .into_iter()
+            .map(|level| level.id)
+            .collect::<Vec<_>>(),
+        vec![level_ids[3].clone()]
+    );
+    assert_eq!(store.get_active_working_levels().unwrap().len(), 1);
+
+    assert_eq!(statistics.deleted_by_candle_gap, 2);
+    assert_eq!(statistics.number_of_working_levels, 2);
+}
+
+fn store_with_opened_order(
+    r#type: OrderType,
+    open: OrderPrice,
+    stop_loss: OrderPrice,
+) -> (InMemoryStepBacktestingStore, OrderId) {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let level = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap();
+
+    store.move_working_level_to_active(&level.id).unwrap();
+
+    let order = store
+        .create_order(
+            xid::new().to_string(),
+            StepOrderProperties {
+                base: BasicOrderProperties {
+                    r#type,
+                    status: OrderStatus::Opened,
+                    prices: BasicOrderPrices {
+                        open,
+                        stop_loss,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                working_level_id: level.id,
+                creation_time: None,
+            },
+        )
+        .unwrap();
+
+    (store, order.id)
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn move_stops_to_breakeven__buy_order_and_realtime_tick__should_move_stop_loss_to_open_with_buffer()
+{
+    let (mut store, order_id) = store_with_opened_order(OrderType::Buy, dec!(1.37), dec!(1.365));
+
+    LevelUtilsImpl::move_stops_to_breakeven(
+        &mut store,
+        dec!(100),
+        Some(dec!(10)),
+        UniversalTickPrice::Realtime(dec!(1.37099)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .prices
+            .stop_loss,
+        dec!(1.365)
+    );
+
+    LevelUtilsImpl::move_stops_to_breakeven(
+        &mut store,
+        dec!(100),
+        Some(dec!(10)),
+        UniversalTickPrice::Realtime(dec!(1.371)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .prices
+            .stop_loss,
+        dec!(1.3701)
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn move_stops_to_breakeven__sell_order_and_historical_tick__should_move_stop_loss_to_open() {
+    let (mut store, order_id) = store_with_opened_order(OrderType::Sell, dec!(1.37), dec!(1.375));
+
+    LevelUtilsImpl::move_stops_to_breakeven(
+        &mut store,
+        dec!(100),
+        None,
+        UniversalTickPrice::Historical(HistoricalTickPrice {
+            high: dec!(1.3705),
+            low: dec!(1.3689),
+            close: dec!(1.3702),
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .prices
+            .stop_loss,
+        dec!(1.37)
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn move_stops_to_breakeven__stop_loss_beyond_breakeven__should_not_move_stop_loss_backward() {
+    let (mut store, buy_order_id) =
+        store_with_opened_order(OrderType::Buy, dec!(1.37), dec!(1.3705));
+
+    LevelUtilsImpl::move_stops_to_breakeven(
+        &mut store,
+        dec!(100),
+        Some(dec!(10)),
+        UniversalTickPrice::Historical(HistoricalTickPrice {
+            high: dec!(1.372),
+            low: dec!(1.37),
+            close: dec!(1.371),
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&buy_order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .prices
+            .stop_loss,
+        dec!(1.3705)
+    );
+
+    let (mut store, sell_order_id) =
+        store_with_opened_order(OrderType::Sell, dec!(1.37), dec!(1.3695));
+
+    LevelUtilsImpl::move_stops_to_breakeven(
+        &mut store,
+        dec!(100),
+        None,
+        UniversalTickPrice::Realtime(dec!(1.368)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&sell_order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .prices
+            .stop_loss,
+        dec!(1.3695)
+    );
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn move_take_profits__realtime_tick__should_successfully_move_take_profits_of_active_levels_only() {
@@ -1097,6 +1561,7 @@ fn move_take_profits__realtime_tick__should_successfully_move_take_profits_of_ac
                             ..Default::default()
                         },
                         working_level_id: level.id.clone(),
+                        creation_time: None,
                     },
                 )
                 .unwrap();
@@ -1114,6 +1579,7 @@ fn move_take_profits__realtime_tick__should_successfully_move_take_profits_of_ac
         &mut store,
         distance_from_level_for_signaling_of_moving_take_profits,
         distance_to_move_take_profits,
+        None,
         current_tick_price,
     )
     .unwrap();
@@ -1191,6 +1657,7 @@ fn move_take_profits__historical_tick__should_successfully_move_take_profits_of_
                             ..Default::default()
                         },
                         working_level_id: level.id.clone(),
+                        creation_time: None,
                     },
                 )
                 .unwrap();
@@ -1208,6 +1675,7 @@ fn move_take_profits__historical_tick__should_successfully_move_take_profits_of_
         &mut store,
         distance_from_level_for_signaling_of_moving_take_profits,
         distance_to_move_take_profits,
+        None,
         current_tick_price,
     )
     .unwrap();
@@ -1234,6 +1702,159 @@ fn move_take_profits__historical_tick__should_successfully_move_take_profits_of_
     }
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn move_take_profits__min_profit_to_trail__should_move_take_profits_of_profitable_orders_only() {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let take_profit = dec!(1.37500);
+
+    let level = store
+        .create_working_level(
+            xid::new().to_string(),
+            BacktestingWLProperties {
+                base: BasicWLProperties {
+                    price: dec!(1.37000),
+                    r#type: OrderType::Buy,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    store.move_working_level_to_active(&level.id).unwrap();
+
+    let mut order_ids = Vec::new();
+
+    // the profit of the first order is 99 points, the second one is underwater,
+    // and the third one is pending
+    for (open_price, status) in [
+        (dec!(1.36700), OrderStatus::Opened),
+        (dec!(1.36900), OrderStatus::Opened),
+        (dec!(1.36600), OrderStatus::Pending),
+    ] {
+        let order = store
+            .create_order(
+                xid::new().to_string(),
+                StepOrderProperties {
+                    base: BasicOrderProperties {
+                        r#type: OrderType::Buy,
+                        status,
+                        prices: BasicOrderPrices {
+                            open: open_price,
+                            take_profit,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    working_level_id: level.id.clone(),
+                    creation_time: None,
+                },
+            )
+            .unwrap();
+
+        order_ids.push(order.id);
+    }
+
+    let distance_to_move_take_profits = dec!(30);
+
+    LevelUtilsImpl::move_take_profits(
+        &mut store,
+        dec!(200),
+        distance_to_move_take_profits,
+        Some(dec!(50)),
+        UniversalTickPrice::Realtime(dec!(1.36799)),
+    )
+    .unwrap();
+
+    let take_profit_of_order = |order_id: &str| {
+        store
+            .get_order_by_id(order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .prices
+            .take_profit
+    };
+
+    assert_eq!(
+        take_profit_of_order(&order_ids[0]),
+        take_profit - points_to_price(distance_to_move_take_profits)
+    );
+    assert_eq!(take_profit_of_order(&order_ids[1]), take_profit);
+    assert_eq!(take_profit_of_order(&order_ids[2]), take_profit);
+    assert!(store.take_profits_of_level_are_moved(&level.id).unwrap());
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn move_take_profits__min_profit_to_trail_and_all_orders_underwater__should_not_move_take_profits()
+{
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let take_profit = dec!(1.36500);
+
+    let level = store
+        .create_working_level(
+            xid::new().to_string(),
+            BacktestingWLProperties {
+                base: BasicWLProperties {
+                    price: dec!(1.37000),
+                    r#type: OrderType::Sell,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    store.move_working_level_to_active(&level.id).unwrap();
+
+    let order = store
+        .create_order(
+            xid::new().to_string(),
+            StepOrderProperties {
+                base: BasicOrderProperties {
+                    r#type: OrderType::Sell,
+                    status: OrderStatus::Opened,
+                    prices: BasicOrderPrices {
+                        open: dec!(1.37100),
+                        take_profit,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                working_level_id: level.id.clone(),
+                creation_time: None,
+            },
+        )
+        .unwrap();
+
+    LevelUtilsImpl::move_take_profits(
+        &mut store,
+        dec!(200),
+        dec!(30),
+        Some(dec!(50)),
+        UniversalTickPrice::Realtime(dec!(1.37201)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&order.id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .prices
+            .take_profit,
+        take_profit
+    );
+    assert!(!store.take_profits_of_level_are_moved(&level.id).unwrap());
+}
+
 // update_tendency_and_get_instruction_to_create_new_working_level cases to test:
 // - tendency is unknown, crossed angle is min, appropriate working level (should set tendency
 //   to down and NOT return instruction to create new working level)
@@ -1319,6 +1940,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -1412,7 +2034,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -1428,6 +2050,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Down);
@@ -1456,6 +2079,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -1549,7 +2173,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -1565,6 +2189,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Up);
@@ -1647,6 +2272,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -1720,7 +2346,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
 
     env::set_var("MODE", "debug");
 
-    assert!(
+    assert_eq!(
         LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
@@ -1736,7 +2362,8 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &current_candle,
             &params,
         )
-        .unwrap()
+        .unwrap(),
+        Some(LevelOrigin::TendencyChange)
     );
 
     assert_eq!(config.tendency, Tendency::Up);
@@ -1833,6 +2460,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -1907,7 +2535,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -1923,6 +2551,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Down);
@@ -2019,6 +2648,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -2093,7 +2723,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -2109,6 +2739,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Up);
@@ -2205,6 +2836,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -2279,7 +2911,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -2295,6 +2927,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Down);
@@ -2415,6 +3048,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -2489,7 +3123,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -2505,6 +3139,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Up);
@@ -2630,6 +3265,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -2704,7 +3340,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -2720,6 +3356,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Down);
@@ -2797,6 +3434,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -2865,7 +3503,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
 
     env::set_var("MODE", "debug");
 
-    assert!(
+    assert_eq!(
         LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
@@ -2881,7 +3519,8 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &current_candle,
             &params,
         )
-        .unwrap()
+        .unwrap(),
+        Some(LevelOrigin::SecondLevelAfterBargainingTendencyChange)
     );
 
     assert_eq!(config.tendency, Tendency::Up);
@@ -2952,6 +3591,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -3020,7 +3660,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
 
     env::set_var("MODE", "debug");
 
-    assert!(
+    assert_eq!(
         LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
@@ -3036,7 +3676,8 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &current_candle,
             &params,
         )
-        .unwrap()
+        .unwrap(),
+        Some(LevelOrigin::SecondLevelAfterBargainingTendencyChange)
     );
 
     assert_eq!(config.tendency, Tendency::Down);
@@ -3113,6 +3754,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -3181,7 +3823,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
 
     env::set_var("MODE", "debug");
 
-    assert!(
+    assert_eq!(
         LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
@@ -3197,7 +3839,8 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &current_candle,
             &params,
         )
-        .unwrap()
+        .unwrap(),
+        Some(LevelOrigin::SecondLevelAfterBargainingTendencyChange)
     );
 
     assert_eq!(config.tendency, Tendency::Up);
@@ -3284,6 +3927,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
         _general_corridor: &[Item<CandleId, C>],
         _angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
         _min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+        _max_candles_to_scan: Option<usize>,
     ) -> Result<bool>
     where
         A: AsRef<BasicAngleProperties> + Debug,
@@ -3353,7 +3997,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
     env::set_var("MODE", "debug");
 
     assert!(
-        !LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
+        LevelUtilsImpl::update_tendency_and_get_instruction_to_create_new_working_level(
             &mut config,
             &mut store,
             UpdateTendencyAndCreateWorkingLevelUtils::new(
@@ -3369,6 +4013,7 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
             &params,
         )
         .unwrap()
+        .is_none()
     );
 
     assert_eq!(config.tendency, Tendency::Down);
@@ -3379,3 +4024,86 @@ fn update_tendency_and_get_instruction_to_create_new_working_level__tendency_is_
 
     assert!(store.get_tendency_change_angle().unwrap().is_none());
 }
+
+fn store_for_scoring_working_levels() -> InMemoryStepBacktestingStore {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    for (id, r#type, price) in [
+        ("1", OrderType::Buy, dec!(1.38000)),
+        ("2", OrderType::Buy, dec!(1.38100)),
+        ("3", OrderType::Buy, dec!(1.38300)),
+        ("4", OrderType::Sell, dec!(1.38050)),
+    ] {
+        store
+            .create_working_level(
+                String::from(id),
+                BacktestingWLProperties {
+                    base: BasicWLProperties {
+                        r#type,
+                        price,
+                        time: Utc::now().naive_utc(),
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+
+    store
+        .update_max_crossing_value_of_working_level("1", dec!(300))
+        .unwrap();
+
+    for candle_id in ["1", "2", "3"] {
+        store
+            .create_candle(String::from(candle_id), Default::default())
+            .unwrap();
+
+        store
+            .add_candle_to_working_level_corridor("1", String::from(candle_id), CorridorType::Small)
+            .unwrap();
+    }
+
+    store
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn score_working_level__crossed_level_with_corridor_and_nearby_level__should_return_weighted_score()
+{
+    let store = store_for_scoring_working_levels();
+    let level = store.get_working_level_by_id("1").unwrap().unwrap();
+
+    // 300 / 150 volatility + 3 candles in the corridor - 1 nearby level
+    assert_eq!(
+        score_working_level(&level, &store, &TestParams, 150, Default::default()).unwrap(),
+        dec!(4)
+    );
+
+    assert_eq!(
+        score_working_level(
+            &level,
+            &store,
+            &TestParams,
+            150,
+            LevelScoreWeights {
+                break_distance: dec!(0.5),
+                corridor_strength: dec!(0),
+                nearby_levels: dec!(2),
+            },
+        )
+        .unwrap(),
+        dec!(-1)
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn score_working_level__level_without_crossing_corridor_and_nearby_levels__should_return_zero() {
+    let store = store_for_scoring_working_levels();
+    let level = store.get_working_level_by_id("3").unwrap().unwrap();
+
+    assert_eq!(
+        score_working_level(&level, &store, &TestParams, 150, Default::default()).unwrap(),
+        dec!(0)
+    );
+}
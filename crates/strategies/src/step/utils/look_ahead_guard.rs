@@ -0,0 +1,225 @@
+use crate::step::utils::entities::angle::{AngleId, BasicAngleProperties, FullAngleProperties};
+use crate::step::utils::entities::candle::StepCandleProperties;
+use anyhow::Result;
+use base::entities::candle::{CandleId, CandleTime};
+use base::entities::Item;
+use base::params::ParamOutputValue;
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
+
+type CrossedAngle<A, C> = Item<AngleId, FullAngleProperties<A, C>>;
+type Corridor<C> = [Item<CandleId, C>];
+type MaxCandlesToScan = Option<usize>;
+
+/// The candle read by the condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConditionRead {
+    pub condition: &'static str,
+    pub candle_time: CandleTime,
+}
+
+/// Checks that the level conditions don't read the candles newer than the current one.
+/// The candles are ordered by their time, so the time of the candle is its index.
+///
+/// The conditions are wrapped with the guard to record the candles passed to them.
+/// The reads are recorded and checked in debug builds only, so the wrapped conditions
+/// work exactly as the original ones in release builds.
+#[derive(Debug, Default)]
+pub struct LookAheadGuard {
+    current_candle_time: Cell<Option<CandleTime>>,
+    reads: RefCell<Vec<ConditionRead>>,
+}
+
+impl LookAheadGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Should be called on every new candle before the conditions are checked.
+    pub fn set_current_candle(&self, candle_time: CandleTime) {
+        self.current_candle_time.set(Some(candle_time));
+    }
+
+    /// Records the candle read by the condition.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, if the candle is newer than the current one.
+    pub fn record_read(&self, condition: &'static str, candle_time: CandleTime) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        if let Some(current_candle_time) = self.current_candle_time.get() {
+            assert!(
+                candle_time <= current_candle_time,
+                "look-ahead in the condition `{}`: it reads the candle of {} \
+                while the current candle is of {}",
+                condition,
+                candle_time,
+                current_candle_time
+            );
+        }
+
+        self.reads.borrow_mut().push(ConditionRead {
+            condition,
+            candle_time,
+        });
+    }
+
+    /// The candles read by the conditions since the last call. Always empty in release builds.
+    pub fn take_reads(&self) -> Vec<ConditionRead> {
+        self.reads.take()
+    }
+
+    pub fn level_comes_out_of_bargaining_corridor<'a, A, C, S, B>(
+        &'a self,
+        condition: &'a B,
+    ) -> impl Fn(
+        &CrossedAngle<A, C>,
+        &Corridor<C>,
+        &S,
+        ParamOutputValue,
+        MaxCandlesToScan,
+    ) -> Result<bool>
+           + 'a
+    where
+        A: AsRef<BasicAngleProperties> + Debug,
+        C: AsRef<StepCandleProperties> + Debug,
+        B: Fn(
+            &CrossedAngle<A, C>,
+            &Corridor<C>,
+            &S,
+            ParamOutputValue,
+            MaxCandlesToScan,
+        ) -> Result<bool>,
+    {
+        move |crossed_angle, general_corridor, store, min_amount_of_candles, max_candles_to_scan| {
+            const CONDITION: &str = "level_comes_out_of_bargaining_corridor";
+
+            self.record_read(CONDITION, candle_time(&crossed_angle.props.candle));
+            for candle in general_corridor {
+                self.record_read(CONDITION, candle_time(candle));
+            }
+
+            condition(
+                crossed_angle,
+                general_corridor,
+                store,
+                min_amount_of_candles,
+                max_candles_to_scan,
+            )
+        }
+    }
+
+    pub fn appropriate_working_level<'a, A, C, S, M, P>(
+        &'a self,
+        condition: &'a P,
+    ) -> impl Fn(&CrossedAngle<A, C>, &Item<CandleId, C>, &S, &M) -> Result<bool> + 'a
+    where
+        A: AsRef<BasicAngleProperties> + Debug,
+        C: AsRef<StepCandleProperties> + Debug,
+        P: Fn(&CrossedAngle<A, C>, &Item<CandleId, C>, &S, &M) -> Result<bool>,
+    {
+        move |crossed_angle, current_candle, store, params| {
+            const CONDITION: &str = "appropriate_working_level";
+
+            self.record_read(CONDITION, candle_time(&crossed_angle.props.candle));
+            self.record_read(CONDITION, candle_time(current_candle));
+
+            condition(crossed_angle, current_candle, store, params)
+        }
+    }
+}
+
+fn candle_time<C: AsRef<StepCandleProperties>>(candle: &Item<CandleId, C>) -> CandleTime {
+    candle.props.as_ref().base.time
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::entities::candle::BasicCandleProperties;
+    use chrono::NaiveDate;
+
+    fn candle(hour: u32) -> Item<CandleId, StepCandleProperties> {
+        Item {
+            id: hour.to_string(),
+            props: StepCandleProperties {
+                base: BasicCandleProperties {
+                    time: NaiveDate::from_ymd(2022, 4, 1).and_hms(hour, 0, 0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    fn angle(
+        hour: u32,
+    ) -> Item<AngleId, FullAngleProperties<BasicAngleProperties, StepCandleProperties>> {
+        Item {
+            id: hour.to_string(),
+            props: FullAngleProperties {
+                base: Default::default(),
+                candle: candle(hour),
+            },
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[allow(non_snake_case)]
+    fn level_comes_out_of_bargaining_corridor__past_candles__should_record_reads() {
+        let guard = LookAheadGuard::new();
+        guard.set_current_candle(NaiveDate::from_ymd(2022, 4, 1).and_hms(12, 0, 0));
+
+        let condition =
+            |_: &Item<AngleId, FullAngleProperties<BasicAngleProperties, StepCandleProperties>>,
+             _: &[Item<CandleId, StepCandleProperties>],
+             _: &(),
+             _: ParamOutputValue,
+             _: Option<usize>| Ok(true);
+        let guarded_condition = guard.level_comes_out_of_bargaining_corridor(&condition);
+
+        assert!(guarded_condition(
+            &angle(10),
+            &[candle(11), candle(12)],
+            &(),
+            Default::default(),
+            None
+        )
+        .unwrap());
+
+        assert_eq!(
+            guard
+                .take_reads()
+                .iter()
+                .map(|read| read.candle_time)
+                .collect::<Vec<_>>(),
+            vec![
+                NaiveDate::from_ymd(2022, 4, 1).and_hms(10, 0, 0),
+                NaiveDate::from_ymd(2022, 4, 1).and_hms(11, 0, 0),
+                NaiveDate::from_ymd(2022, 4, 1).and_hms(12, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "look-ahead in the condition `appropriate_working_level`")]
+    #[allow(non_snake_case)]
+    fn appropriate_working_level__future_candle__should_panic() {
+        let guard = LookAheadGuard::new();
+        guard.set_current_candle(NaiveDate::from_ymd(2022, 4, 1).and_hms(12, 0, 0));
+
+        let condition =
+            |_: &Item<AngleId, FullAngleProperties<BasicAngleProperties, StepCandleProperties>>,
+             _: &Item<CandleId, StepCandleProperties>,
+             _: &(),
+             _: &()| Ok(true);
+        let guarded_condition = guard.appropriate_working_level(&condition);
+
+        let _ = guarded_condition(&angle(10), &candle(13), &(), &());
+    }
+}
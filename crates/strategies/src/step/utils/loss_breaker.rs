@@ -0,0 +1,200 @@
+use backtesting::ClosedTrade;
+use chrono::{Duration, NaiveDateTime};
+use rust_decimal_macros::dec;
+
+pub type NumberOfLosses = u32;
+
+/// Halts opening of new positions after the particular number of consecutive losing trades.
+/// Once tripped, the breaker stays tripped until its cooldown is over or it's reset manually.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct ConsecutiveLossBreaker {
+    /// If it's `None`, the breaker never trips.
+    max_consecutive_losses: Option<NumberOfLosses>,
+    /// The time after the tripping when the breaker is reset automatically.
+    /// If it's `None`, the breaker is reset only manually.
+    cooldown: Option<Duration>,
+    consecutive_losses: NumberOfLosses,
+    tripped_at: Option<NaiveDateTime>,
+}
+
+impl ConsecutiveLossBreaker {
+    pub fn new(max_consecutive_losses: NumberOfLosses) -> Self {
+        Self {
+            max_consecutive_losses: Some(max_consecutive_losses),
+            ..Default::default()
+        }
+    }
+
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    pub fn consecutive_losses(&self) -> NumberOfLosses {
+        self.consecutive_losses
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.tripped_at.is_some()
+    }
+
+    pub fn reset(&mut self) {
+        self.consecutive_losses = 0;
+        self.tripped_at = None;
+    }
+
+    /// Resets the tripped breaker if its cooldown is over at the current time.
+    /// Returns true if the breaker has just been reset.
+    pub fn update_cooldown(&mut self, current_time: NaiveDateTime) -> bool {
+        match (self.tripped_at, self.cooldown) {
+            (Some(tripped_at), Some(cooldown)) if current_time - tripped_at >= cooldown => {
+                self.reset();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records the outcome of the trade closed at the current time. A profitable trade resets
+    /// the counter of consecutive losses. Returns true if the breaker has just been tripped.
+    pub fn record_trade(&mut self, profitable: bool, current_time: NaiveDateTime) -> bool {
+        if profitable {
+            self.consecutive_losses = 0;
+            return false;
+        }
+
+        self.consecutive_losses += 1;
+
+        match self.max_consecutive_losses {
+            Some(max_consecutive_losses)
+                if !self.is_tripped() && self.consecutive_losses >= max_consecutive_losses =>
+            {
+                self.tripped_at = Some(current_time);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records the outcomes of the trades closed since the previous call, which are taken
+    /// from the closing events of the trading engine. The break-even trades are skipped.
+    /// Returns true if the breaker has just been tripped.
+    pub fn record_closed_trades(
+        &mut self,
+        closed_trades: &[ClosedTrade],
+        current_time: NaiveDateTime,
+    ) -> bool {
+        let mut tripped = false;
+
+        for trade in closed_trades {
+            let profit = trade.profit();
+
+            if profit != dec!(0) {
+                tripped |= self.record_trade(profit > dec!(0), current_time);
+            }
+        }
+
+        tripped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backtesting::CloseReason;
+    use base::entities::order::{OrderPrice, OrderType};
+    use chrono::NaiveDate;
+
+    fn closed_trade(close_price: OrderPrice) -> ClosedTrade {
+        ClosedTrade {
+            order_id: String::from("1"),
+            r#type: OrderType::Buy,
+            volume: dec!(0.03),
+            open_price: dec!(1.38000),
+            close_price,
+            open_time: None,
+            close_time: None,
+            open_candle_index: None,
+            close_candle_index: None,
+            excursions: Default::default(),
+            close_reason: CloseReason::TakeProfit,
+            fill_slippage: dec!(0),
+            spread_cost: dec!(0),
+        }
+    }
+
+    fn time(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2022, 4, 1).and_hms(hour, 0, 0)
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn record_closed_trades__max_number_of_consecutive_losses__should_trip_breaker_until_reset() {
+        let mut breaker = ConsecutiveLossBreaker::new(3);
+
+        assert!(!breaker.record_closed_trades(
+            &[
+                closed_trade(dec!(1.37900)),
+                closed_trade(dec!(1.37900)),
+                closed_trade(dec!(1.38100)),
+            ],
+            time(10)
+        ));
+        assert_eq!(breaker.consecutive_losses(), 0);
+        assert!(!breaker.is_tripped());
+
+        assert!(!breaker.record_closed_trades(
+            &[
+                closed_trade(dec!(1.37900)),
+                closed_trade(dec!(1.38000)),
+                closed_trade(dec!(1.37900)),
+            ],
+            time(11)
+        ));
+        assert_eq!(breaker.consecutive_losses(), 2);
+
+        assert!(breaker.record_closed_trades(&[closed_trade(dec!(1.37900))], time(12)));
+        assert_eq!(breaker.consecutive_losses(), 3);
+        assert!(breaker.is_tripped());
+
+        assert!(!breaker.record_closed_trades(&[closed_trade(dec!(1.38100))], time(13)));
+        assert!(breaker.is_tripped());
+
+        // no cooldown is set
+        assert!(!breaker.update_cooldown(time(23)));
+        assert!(breaker.is_tripped());
+
+        breaker.reset();
+
+        assert_eq!(breaker.consecutive_losses(), 0);
+        assert!(!breaker.is_tripped());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn update_cooldown__cooldown_is_over__should_reset_breaker() {
+        let mut breaker = ConsecutiveLossBreaker::new(1).cooldown(Duration::hours(2));
+
+        assert!(breaker.record_trade(false, time(10)));
+
+        assert!(!breaker.update_cooldown(time(11)));
+        assert!(breaker.is_tripped());
+
+        assert!(breaker.update_cooldown(time(12)));
+        assert!(!breaker.is_tripped());
+        assert_eq!(breaker.consecutive_losses(), 0);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn record_trade__no_max_number_of_consecutive_losses__should_never_trip_breaker() {
+        let mut breaker = ConsecutiveLossBreaker::default();
+
+        for _ in 0..10 {
+            assert!(!breaker.record_trade(false, time(10)));
+        }
+
+        assert_eq!(breaker.consecutive_losses(), 10);
+        assert!(!breaker.is_tripped());
+    }
+}
@@ -3,50 +3,80 @@ use crate::step::utils::backtesting_charts::{
 };
 use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
 use crate::step::utils::entities::working_levels::{
-    BacktestingWLProperties, CorridorType, WLStatus,
+    ActiveLevelsCapPolicy, BacktestingWLProperties, CorridorType, WLStatus,
 };
 use crate::step::utils::entities::{Mode, MODE_ENV};
-use crate::step::utils::level_conditions::{LevelConditions, MinAmountOfCandles};
+use crate::step::utils::level_conditions::MinAmountOfCandles;
+use crate::step::utils::level_utils::score_working_level;
 use crate::step::utils::stores::working_level_store::StepWorkingLevelStore;
 use crate::step::utils::stores::{StepBacktestingConfig, StepBacktestingStatistics};
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use backtesting::trading_engine::TradingEngine;
-use backtesting::{BacktestingTradingEngineConfig, Balance, ClosePositionBy, OpenPositionBy};
+use backtesting::{Balance, ClosePositionBy, OpenPositionBy, OrderRejectionReason};
 use base::entities::order::{
     BasicOrderPrices, BasicOrderProperties, OrderPrice, OrderStatus, OrderType, OrderVolume,
 };
 use base::entities::tick::{HistoricalTickPrice, TickPrice, UniversalTickPrice};
-use base::entities::{
-    BasicTickProperties, CANDLE_PRICE_DECIMAL_PLACES, SIGNIFICANT_DECIMAL_PLACES,
-};
+use base::entities::{BasicTickProperties, SIGNIFICANT_DECIMAL_PLACES};
 use base::stores::order_store::BasicOrderStore;
 use base::{
     entities::{candle::CandleVolatility, Item, LOT},
-    helpers::points_to_price,
-    params::StrategyParams,
+    helpers::{points_to_price, LotRounding, PriceRounding},
+    params::{ParamOutputValue, StrategyParams},
 };
+use chrono::{Duration, NaiveDateTime};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use std::marker::PhantomData;
 use std::str::FromStr;
 
 use super::entities::{
-    order::StepOrderProperties,
+    order::{AmbiguousFillPolicy, StepOrderProperties},
     params::{StepPointParam, StepRatioParam},
     working_levels::{BasicWLProperties, WLId},
 };
 
 pub trait OrderUtils {
+    /// Computes the prices of the first order of the chain from the particular level:
+    /// the entry and the stop loss are shifted from the level price against the level type
+    /// by the distances of the ratio params, the take profit is the level price.
+    fn compute_order_prices<W>(
+        level: &Item<WLId, W>,
+        params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        current_volatility: CandleVolatility,
+        price_rounding: PriceRounding,
+    ) -> BasicOrderPrices
+    where
+        W: AsRef<BasicWLProperties>;
+
     /// Creates the chain of orders from the particular level when this level is crossed.
+    /// The prices of the orders are rounded with the particular price rounding
+    /// and the volumes are rounded down to the lot step. If the volume is rounded to zero
+    /// or below the min lot, no orders are created.
     fn get_new_chain_of_orders<W>(
         level: &Item<WLId, W>,
         params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
         current_volatility: CandleVolatility,
         current_balance: Balance,
+        price_rounding: PriceRounding,
+        lot_rounding: LotRounding,
     ) -> Result<Vec<StepOrderProperties>>
     where
         W: AsRef<BasicWLProperties>;
 
+    /// Creates the new chain of orders in the store. Orders exceeding the max amount
+    /// of orders per working level are refused. Orders closer to the existing orders of the level
+    /// than the min distance between orders of a level are skipped.
+    fn create_chain_of_orders<S>(
+        chain_of_orders: Vec<StepOrderProperties>,
+        params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        current_volatility: CandleVolatility,
+        store: &mut S,
+    ) -> Result<()>
+    where
+        S: BasicOrderStore<OrderProperties = StepOrderProperties>
+            + StepWorkingLevelStore<OrderProperties = StepOrderProperties>;
+
     /// Places and closed orders.
     fn update_orders_backtesting<TrEng, C, R, W, P, A>(
         current_tick: &BasicTickProperties<HistoricalTickPrice>,
@@ -119,11 +149,13 @@ impl OrderUtilsImpl {
     }
 
     /// Calculates the volume per order based on the max loss per the chain of orders.
+    /// Returns `None` if the volume is rejected by the lot rounding.
     fn get_volume_per_order(
         params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
         distance_between_orders: DistanceBetweenOrders,
         current_balance: Balance,
-    ) -> Result<OrderVolume> {
+        lot_rounding: LotRounding,
+    ) -> Result<Option<OrderVolume>> {
         let max_loss = Self::get_max_loss_per_chain_of_orders_in_price(params, current_balance)?;
 
         let amount_of_orders = params.get_point_param_value(StepPointParam::AmountOfOrders);
@@ -136,16 +168,267 @@ impl OrderUtilsImpl {
 
         log::debug!("volume per order — {}", volume_per_order);
 
-        Ok(volume_per_order.round_dp(SIGNIFICANT_DECIMAL_PLACES))
+        Ok(lot_rounding.round_volume(volume_per_order))
+    }
+
+    /// Checks whether one more working level can become active without exceeding
+    /// the max number of active working levels. If the max number is reached,
+    /// the cap policy decides whether to block the activation or to remove the weakest active level.
+    /// Zero max number of active working levels disables the cap.
+    #[allow(clippy::too_many_arguments)]
+    fn make_room_for_active_working_level<W, A>(
+        working_level_store: &mut W,
+        level_id: &str,
+        current_tick_price: TickPrice,
+        max_active_working_levels: ParamOutputValue,
+        cap_policy: ActiveLevelsCapPolicy,
+        statistics: &mut StepBacktestingStatistics,
+        level_has_no_active_orders: &A,
+        params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        current_volatility: CandleVolatility,
+    ) -> Result<bool>
+    where
+        W: StepWorkingLevelStore<
+            WorkingLevelProperties = BacktestingWLProperties,
+            OrderProperties = StepOrderProperties,
+        >,
+        A: Fn(&[StepOrderProperties]) -> bool,
+    {
+        if max_active_working_levels == dec!(0) {
+            return Ok(true);
+        }
+
+        let active_working_levels = working_level_store.get_active_working_levels()?;
+
+        if Decimal::from(active_working_levels.len()) < max_active_working_levels {
+            return Ok(true);
+        }
+
+        let mut weakest_level = None;
+
+        match cap_policy {
+            ActiveLevelsCapPolicy::BlockActivation => {}
+            ActiveLevelsCapPolicy::RemoveWeakest => {
+                for active_level in active_working_levels {
+                    let chain_of_orders = working_level_store
+                        .get_working_level_chain_of_orders(&active_level.id)?
+                        .into_iter()
+                        .map(|order| order.props)
+                        .collect::<Vec<_>>();
+
+                    if !level_has_no_active_orders(&chain_of_orders) {
+                        continue;
+                    }
+
+                    let distance = (active_level.props.base.price - current_tick_price).abs();
+
+                    match &weakest_level {
+                        Some((_, max_distance)) if distance <= *max_distance => {}
+                        _ => weakest_level = Some((active_level.id, distance)),
+                    }
+                }
+            }
+            ActiveLevelsCapPolicy::RemoveLowestScored(weights) => {
+                let level = working_level_store
+                    .get_working_level_by_id(level_id)?
+                    .context(format!("no working level with an id {}", level_id))?;
+
+                let level_score = score_working_level(
+                    &level,
+                    working_level_store,
+                    params,
+                    current_volatility,
+                    weights,
+                )?;
+
+                for active_level in active_working_levels {
+                    let chain_of_orders = working_level_store
+                        .get_working_level_chain_of_orders(&active_level.id)?
+                        .into_iter()
+                        .map(|order| order.props)
+                        .collect::<Vec<_>>();
+
+                    if !level_has_no_active_orders(&chain_of_orders) {
+                        continue;
+                    }
+
+                    let score = score_working_level(
+                        &active_level,
+                        working_level_store,
+                        params,
+                        current_volatility,
+                        weights,
+                    )?;
+
+                    if score >= level_score {
+                        continue;
+                    }
+
+                    match &weakest_level {
+                        Some((_, min_score)) if score >= *min_score => {}
+                        _ => weakest_level = Some((active_level.id, score)),
+                    }
+                }
+            }
+        }
+
+        match weakest_level {
+            None => {
+                log::debug!(
+                    "the max number of active working levels ({}) is reached, \
+                    the activation of the working level {} is blocked",
+                    max_active_working_levels,
+                    level_id
+                );
+
+                Ok(false)
+            }
+            Some((weakest_level_id, _)) => {
+                log::debug!(
+                    "the max number of active working levels ({}) is reached, \
+                    the weakest active working level {} is removed to activate the working level {}",
+                    max_active_working_levels,
+                    weakest_level_id,
+                    level_id
+                );
+
+                working_level_store.remove_working_level(&weakest_level_id)?;
+
+                statistics.number_of_working_levels -= 1;
+                statistics.deleted_by_exceeding_max_active_working_levels += 1;
+
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Checks whether the tick has reached the open price of the order.
+fn order_is_crossed_by_tick(
+    order: &BasicOrderProperties,
+    tick_price: &HistoricalTickPrice,
+) -> bool {
+    match order.r#type {
+        OrderType::Buy => tick_price.low <= order.prices.open,
+        OrderType::Sell => tick_price.high >= order.prices.open,
+    }
+}
+
+/// Checks that the pending order has existed longer than the order expiration.
+fn pending_order_is_expired(
+    order: &StepOrderProperties,
+    current_time: NaiveDateTime,
+    order_expiration: Option<Duration>,
+) -> bool {
+    match (order.creation_time, order_expiration) {
+        (Some(creation_time), Some(order_expiration)) => {
+            order.base.status == OrderStatus::Pending
+                && current_time - creation_time > order_expiration
+        }
+        _ => false,
+    }
+}
+
+/// Checks that the order of the working level was closed less than the cooldown ago.
+fn working_level_is_in_cooldown(
+    last_close_time: Option<NaiveDateTime>,
+    current_time: NaiveDateTime,
+    level_cooldown: Option<Duration>,
+) -> bool {
+    match (last_close_time, level_cooldown) {
+        (Some(last_close_time), Some(level_cooldown)) => {
+            current_time - last_close_time < level_cooldown
+        }
+        _ => false,
+    }
+}
+
+/// Returns the way to close the opened order when the tick has reached
+/// both its stop loss and its take profit.
+fn get_close_by_for_ambiguous_fill(
+    order: &BasicOrderProperties,
+    policy: AmbiguousFillPolicy,
+) -> ClosePositionBy {
+    match policy {
+        AmbiguousFillPolicy::StopFirst => ClosePositionBy::StopLoss,
+        AmbiguousFillPolicy::TakeProfitFirst => ClosePositionBy::TakeProfit,
+        AmbiguousFillPolicy::WorstCase => {
+            let profit = |close_price: OrderPrice| match order.r#type {
+                OrderType::Buy => close_price - order.prices.open,
+                OrderType::Sell => order.prices.open - close_price,
+            };
+
+            if profit(order.prices.take_profit) < profit(order.prices.stop_loss) {
+                ClosePositionBy::TakeProfit
+            } else {
+                ClosePositionBy::StopLoss
+            }
+        }
+        AmbiguousFillPolicy::Proportional => {
+            let distance_to_take_profit = (order.prices.take_profit - order.prices.open).abs();
+            let distance_to_stop_loss = (order.prices.stop_loss - order.prices.open).abs();
+            let total_distance = distance_to_take_profit + distance_to_stop_loss;
+
+            if total_distance == dec!(0) {
+                return ClosePositionBy::StopLoss;
+            }
+
+            ClosePositionBy::CurrentTickPrice(
+                (order.prices.take_profit * distance_to_stop_loss
+                    + order.prices.stop_loss * distance_to_take_profit)
+                    / total_distance,
+            )
+        }
     }
 }
 
 impl OrderUtils for OrderUtilsImpl {
+    fn compute_order_prices<W>(
+        level: &Item<WLId, W>,
+        params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        current_volatility: CandleVolatility,
+        price_rounding: PriceRounding,
+    ) -> BasicOrderPrices
+    where
+        W: AsRef<BasicWLProperties>,
+    {
+        let distance_from_level_to_first_order = points_to_price(params.get_ratio_param_value(
+            StepRatioParam::DistanceFromLevelToFirstOrder,
+            current_volatility,
+        ));
+
+        let distance_from_level_to_stop_loss = points_to_price(params.get_ratio_param_value(
+            StepRatioParam::DistanceFromLevelToStopLoss,
+            current_volatility,
+        ));
+
+        let level_price = level.props.as_ref().price;
+
+        let (open, stop_loss) = match level.props.as_ref().r#type {
+            OrderType::Buy => (
+                level_price - distance_from_level_to_first_order,
+                level_price - distance_from_level_to_stop_loss,
+            ),
+            OrderType::Sell => (
+                level_price + distance_from_level_to_first_order,
+                level_price + distance_from_level_to_stop_loss,
+            ),
+        };
+
+        BasicOrderPrices {
+            open: price_rounding.round_price(open),
+            stop_loss: price_rounding.round_price(stop_loss),
+            take_profit: price_rounding.round_price(level_price),
+        }
+    }
+
     fn get_new_chain_of_orders<W>(
         level: &Item<WLId, W>,
         params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
         current_volatility: CandleVolatility,
         current_balance: Balance,
+        price_rounding: PriceRounding,
+        lot_rounding: LotRounding,
     ) -> Result<Vec<StepOrderProperties>>
     where
         W: AsRef<BasicWLProperties>,
@@ -164,35 +447,30 @@ impl OrderUtils for OrderUtilsImpl {
             - distance_from_level_to_first_order)
             / params.get_point_param_value(StepPointParam::AmountOfOrders);
 
-        let volume_per_order =
-            Self::get_volume_per_order(params, distance_between_orders, current_balance)?;
-
-        let (mut price_for_current_order, stop_loss) = match level.props.as_ref().r#type {
-            OrderType::Buy => {
-                let price_for_current_order =
-                    level.props.as_ref().price - distance_from_level_to_first_order;
-                let stop_loss = level.props.as_ref().price - distance_from_level_to_stop_loss;
-                (
-                    price_for_current_order.round_dp(CANDLE_PRICE_DECIMAL_PLACES),
-                    stop_loss.round_dp(CANDLE_PRICE_DECIMAL_PLACES),
-                )
-            }
-            OrderType::Sell => {
-                let price_for_current_order =
-                    level.props.as_ref().price + distance_from_level_to_first_order;
-                let stop_loss = level.props.as_ref().price + distance_from_level_to_stop_loss;
-                (
-                    price_for_current_order.round_dp(CANDLE_PRICE_DECIMAL_PLACES),
-                    stop_loss.round_dp(CANDLE_PRICE_DECIMAL_PLACES),
-                )
+        let volume_per_order = match Self::get_volume_per_order(
+            params,
+            distance_between_orders,
+            current_balance,
+            lot_rounding,
+        )? {
+            Some(volume_per_order) => volume_per_order,
+            None => {
+                log::debug!(
+                    "the volume per order is rejected by the lot rounding {:?}, \
+                    no orders are created for the level {:?}",
+                    lot_rounding,
+                    level.id
+                );
+
+                return Ok(Vec::new());
             }
         };
 
-        let take_profit = level
-            .props
-            .as_ref()
-            .price
-            .round_dp(CANDLE_PRICE_DECIMAL_PLACES);
+        let BasicOrderPrices {
+            open: mut price_for_current_order,
+            stop_loss,
+            take_profit,
+        } = Self::compute_order_prices(level, params, current_volatility, price_rounding);
 
         let mut chain_of_orders = Vec::new();
 
@@ -216,6 +494,7 @@ impl OrderUtils for OrderUtilsImpl {
                     },
                 },
                 working_level_id: level.id.clone(),
+                creation_time: None,
             });
 
             match level.props.as_ref().r#type {
@@ -223,12 +502,69 @@ impl OrderUtils for OrderUtilsImpl {
                 OrderType::Sell => price_for_current_order += distance_between_orders,
             }
 
-            price_for_current_order = price_for_current_order.round_dp(CANDLE_PRICE_DECIMAL_PLACES);
+            price_for_current_order = price_rounding.round_price(price_for_current_order);
         }
 
         Ok(chain_of_orders)
     }
 
+    fn create_chain_of_orders<S>(
+        chain_of_orders: Vec<StepOrderProperties>,
+        params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        current_volatility: CandleVolatility,
+        store: &mut S,
+    ) -> Result<()>
+    where
+        S: BasicOrderStore<OrderProperties = StepOrderProperties>
+            + StepWorkingLevelStore<OrderProperties = StepOrderProperties>,
+    {
+        let max_orders_per_working_level =
+            params.get_point_param_value(StepPointParam::MaxOrdersPerWorkingLevel);
+
+        let min_distance_between_orders = points_to_price(params.get_ratio_param_value(
+            StepRatioParam::MinDistanceBetweenOrdersOfLevel,
+            current_volatility,
+        ));
+
+        for order_props in chain_of_orders {
+            let existing_orders =
+                store.get_working_level_chain_of_orders(&order_props.working_level_id)?;
+
+            let amount_of_orders = existing_orders.len();
+
+            if let Some(too_close_order) = existing_orders.iter().find(|order| {
+                (order.props.base.prices.open - order_props.base.prices.open).abs()
+                    < min_distance_between_orders
+            }) {
+                log::debug!(
+                    "the order {:?} is closer to the existing order {:?} of the working level \
+                    than the min distance between orders ({}), the order is skipped",
+                    order_props,
+                    too_close_order,
+                    min_distance_between_orders
+                );
+
+                continue;
+            }
+
+            if Decimal::from(amount_of_orders) >= max_orders_per_working_level {
+                log::debug!(
+                    "the max amount of orders ({}) for the working level {} is reached, \
+                    the order {:?} is refused",
+                    max_orders_per_working_level,
+                    order_props.working_level_id,
+                    order_props
+                );
+
+                continue;
+            }
+
+            store.create_order(xid::new().to_string(), order_props)?;
+        }
+
+        Ok(())
+    }
+
     fn update_orders_backtesting<TrEng, C, R, W, P, A>(
         current_tick: &BasicTickProperties<HistoricalTickPrice>,
         current_candle: &StepBacktestingCandleProperties,
@@ -250,163 +586,265 @@ impl OrderUtils for OrderUtilsImpl {
         A: Fn(&[StepOrderProperties]) -> bool,
     {
         'level: for level in stores.main.get_all_working_levels()? {
+            let mut chain_of_orders = Vec::new();
+            let mut some_orders_are_expired = false;
+
             for order in stores.main.get_working_level_chain_of_orders(&level.id)? {
+                if pending_order_is_expired(
+                    &order.props,
+                    current_tick.time,
+                    stores.config.trading_engine.order_expiration,
+                ) {
+                    log::debug!("the pending order {:?} is expired, it's cancelled", order);
+
+                    stores.main.cancel_pending_order(&order.id)?;
+                    stores.statistics.cancelled_by_order_expiration += 1;
+                    some_orders_are_expired = true;
+                } else {
+                    chain_of_orders.push(order);
+                }
+            }
+
+            if some_orders_are_expired && chain_of_orders.is_empty() {
+                log::debug!(
+                    "all the orders of the working level {:?} are expired, the level is removed",
+                    level
+                );
+
+                stores.main.remove_working_level(&level.id)?;
+
+                stores.statistics.number_of_working_levels -= 1;
+                stores.statistics.deleted_by_expiration_of_all_orders += 1;
+
+                continue;
+            }
+
+            if !chain_of_orders.iter().any(|order| {
+                order.props.base.status == OrderStatus::Pending
+                    && order_is_crossed_by_tick(&order.props.base, &current_tick.bid)
+            }) {
+                stores
+                    .main
+                    .cancel_pending_activation_of_working_level(&level.id)?;
+            }
+
+            for order in chain_of_orders {
                 match order.props.base.status {
-                    OrderStatus::Pending => {
-                        if (order.props.base.r#type == OrderType::Buy
-                            && current_tick.bid.low <= order.props.base.prices.open)
-                            || (order.props.base.r#type == OrderType::Sell
-                                && current_tick.bid.high >= order.props.base.prices.open)
+                    OrderStatus::Pending
+                        if order_is_crossed_by_tick(&order.props.base, &current_tick.bid) =>
+                    {
+                        let mut remove_working_level = false;
+                        let mut try_to_open_position = false;
+
+                        if stores.main.get_working_level_status(&level.id)?.unwrap()
+                            == WLStatus::Created
                         {
-                            let mut remove_working_level = false;
-                            let mut try_to_open_position = false;
-
-                            if stores.main.get_working_level_status(&level.id)?.unwrap()
-                                == WLStatus::Created
-                            {
+                            if !(utils.level_exceeds_amount_of_candles_in_corridor)(
+                                &order.props.working_level_id,
+                                stores.main,
+                                CorridorType::Small,
+                                params.get_point_param_value(StepPointParam::MinAmountOfCandlesInSmallCorridorBeforeActivationCrossingOfLevel),
+                            )? {
                                 if !(utils.level_exceeds_amount_of_candles_in_corridor)(
                                     &order.props.working_level_id,
                                     stores.main,
-                                    CorridorType::Small,
-                                    params.get_point_param_value(StepPointParam::MinAmountOfCandlesInSmallCorridorBeforeActivationCrossingOfLevel),
+                                    CorridorType::Big,
+                                    params.get_point_param_value(StepPointParam::MinAmountOfCandlesInBigCorridorBeforeActivationCrossingOfLevel),
                                 )? {
-                                    if !(utils.level_exceeds_amount_of_candles_in_corridor)(
-                                        &order.props.working_level_id,
+                                    let number_of_ticks_beyond_level = stores
+                                        .main
+                                        .add_tick_to_pending_activation_of_working_level(
+                                            &level.id,
+                                        )?;
+
+                                    if Decimal::from(number_of_ticks_beyond_level)
+                                        <= params.get_point_param_value(
+                                            StepPointParam::MinAmountOfTicksBeyondLevelBeforeActivation,
+                                        )
+                                    {
+                                        continue 'level;
+                                    }
+
+                                    if !Self::make_room_for_active_working_level(
                                         stores.main,
-                                        CorridorType::Big,
-                                        params.get_point_param_value(StepPointParam::MinAmountOfCandlesInBigCorridorBeforeActivationCrossingOfLevel),
+                                        &level.id,
+                                        current_tick.bid.close,
+                                        params.get_point_param_value(
+                                            StepPointParam::MaxActiveWorkingLevels,
+                                        ),
+                                        stores.config.active_levels_cap_policy,
+                                        stores.statistics,
+                                        utils.level_has_no_active_orders,
+                                        params,
+                                        current_candle.step_common.base.volatility,
                                     )? {
-                                        stores.main.move_working_level_to_active(&level.id)?;
-
-                                        try_to_open_position = true;
-                                    } else {
-                                        stores.statistics.deleted_by_exceeding_amount_of_candles_in_big_corridor_before_activation_crossing += 1;
-                                        remove_working_level = true;
+                                        continue 'level;
                                     }
+
+                                    stores.main.move_working_level_to_active(&level.id)?;
+
+                                    try_to_open_position = true;
                                 } else {
-                                    stores.statistics.deleted_by_exceeding_amount_of_candles_in_small_corridor_before_activation_crossing += 1;
+                                    stores.statistics.deleted_by_exceeding_amount_of_candles_in_big_corridor_before_activation_crossing += 1;
                                     remove_working_level = true;
                                 }
                             } else {
-                                try_to_open_position = true;
+                                stores.statistics.deleted_by_exceeding_amount_of_candles_in_small_corridor_before_activation_crossing += 1;
+                                remove_working_level = true;
                             }
+                        } else {
+                            try_to_open_position = true;
+                        }
+
+                        if try_to_open_position
+                            && working_level_is_in_cooldown(
+                                stores
+                                    .main
+                                    .get_last_close_time_of_working_level(&level.id)?,
+                                current_tick.time,
+                                stores.config.level_cooldown,
+                            )
+                        {
+                            // the order stays pending, so the opening is retried
+                            // on the next tick crossing the order after the cooldown
+                            stores.statistics.suppressed_by_level_cooldown += 1;
+                            continue;
+                        }
 
-                            if try_to_open_position && !no_trading_mode {
-                                let price_is_beyond_stop_loss = (utils.price_is_beyond_stop_loss)(
-                                    UniversalTickPrice::Historical(current_tick.bid),
-                                    order.props.base.prices.stop_loss,
-                                    order.props.base.r#type,
-                                );
+                        if try_to_open_position && !no_trading_mode {
+                            let price_is_beyond_stop_loss = (utils.price_is_beyond_stop_loss)(
+                                UniversalTickPrice::Historical(current_tick.bid),
+                                order.props.base.prices.stop_loss,
+                                order.props.base.r#type,
+                            );
 
-                                let level_has_no_active_orders = (utils.level_has_no_active_orders)(
-                                    &stores
-                                        .main
-                                        .get_working_level_chain_of_orders(&level.id)?
-                                        .into_iter()
-                                        .map(|o| o.props)
-                                        .collect::<Vec<_>>(),
-                                );
-
-                                if price_is_beyond_stop_loss && level_has_no_active_orders {
-                                    stores.statistics.deleted_by_price_being_beyond_stop_loss += 1;
-                                    remove_working_level = true;
-                                } else {
-                                    utils.trading_engine.open_position(
+                            let level_has_no_active_orders = (utils.level_has_no_active_orders)(
+                                &stores
+                                    .main
+                                    .get_working_level_chain_of_orders(&level.id)?
+                                    .into_iter()
+                                    .map(|o| o.props)
+                                    .collect::<Vec<_>>(),
+                            );
+
+                            if price_is_beyond_stop_loss && level_has_no_active_orders {
+                                stores.statistics.deleted_by_price_being_beyond_stop_loss += 1;
+                                remove_working_level = true;
+                            } else {
+                                if let Err(error) = utils.trading_engine.open_position(
+                                    &order,
+                                    OpenPositionBy::OpenPrice,
+                                    stores.main,
+                                    &mut stores.config.trading_engine,
+                                ) {
+                                    // the order stays pending, so the opening is retried
+                                    // on the next tick crossing the order
+                                    match error.downcast_ref::<OrderRejectionReason>() {
+                                        Some(OrderRejectionReason::Requote) => {
+                                            stores.statistics.rejected_openings_by_requote += 1
+                                        }
+                                        Some(OrderRejectionReason::MaxTotalRisk) => {
+                                            stores.statistics.rejected_openings_by_max_total_risk +=
+                                                1
+                                        }
+                                        None => return Err(error),
+                                    }
+
+                                    continue;
+                                }
+
+                                // updated order after opening position for closing position to have actual data
+                                let order = stores.main.get_order_by_id(&order.id)?.unwrap();
+
+                                if price_is_beyond_stop_loss {
+                                    utils.trading_engine.close_position(
                                         &order,
-                                        OpenPositionBy::OpenPrice,
+                                        ClosePositionBy::StopLoss,
                                         stores.main,
                                         &mut stores.config.trading_engine,
                                     )?;
 
-                                    // updated order after opening position for closing position to have actual data
-                                    let order = stores.main.get_order_by_id(&order.id)?.unwrap();
-
-                                    if price_is_beyond_stop_loss {
-                                        utils.trading_engine.close_position(
-                                            &order,
-                                            ClosePositionBy::StopLoss,
-                                            stores.main,
-                                            &mut stores.config.trading_engine,
-                                        )?;
-
-                                        let working_level_chart_index = stores
-                                            .main
-                                            .get_working_level_by_id(&order.props.working_level_id)?
-                                            .unwrap()
-                                            .props
-                                            .chart_index;
-
-                                        if Mode::from_str(&dotenv::var(MODE_ENV).unwrap()).unwrap()
-                                            != Mode::Optimization
-                                        {
-                                            (utils.add_entity_to_chart_traces)(
-                                                ChartTraceEntity::TakeProfit {
-                                                    take_profit_price: order
-                                                        .props
-                                                        .base
-                                                        .prices
-                                                        .take_profit,
-                                                    working_level_chart_index,
-                                                },
-                                                &mut stores.config.chart_traces,
-                                                current_candle.chart_index,
-                                            );
-
-                                            (utils.add_entity_to_chart_traces)(
-                                                ChartTraceEntity::StopLoss {
-                                                    stop_loss_price: order
-                                                        .props
-                                                        .base
-                                                        .prices
-                                                        .stop_loss,
-                                                    working_level_chart_index,
-                                                },
-                                                &mut stores.config.chart_traces,
-                                                current_candle.chart_index,
-                                            );
-                                        }
+                                    let working_level_chart_index = stores
+                                        .main
+                                        .get_working_level_by_id(&order.props.working_level_id)?
+                                        .unwrap()
+                                        .props
+                                        .chart_index;
+
+                                    if Mode::from_str(&dotenv::var(MODE_ENV).unwrap()).unwrap()
+                                        != Mode::Optimization
+                                    {
+                                        (utils.add_entity_to_chart_traces)(
+                                            ChartTraceEntity::TakeProfit {
+                                                take_profit_price: order
+                                                    .props
+                                                    .base
+                                                    .prices
+                                                    .take_profit,
+                                                working_level_chart_index,
+                                            },
+                                            &mut stores.config.chart_traces,
+                                            current_candle.chart_index,
+                                        );
+
+                                        (utils.add_entity_to_chart_traces)(
+                                            ChartTraceEntity::StopLoss {
+                                                stop_loss_price: order.props.base.prices.stop_loss,
+                                                working_level_chart_index,
+                                            },
+                                            &mut stores.config.chart_traces,
+                                            current_candle.chart_index,
+                                        );
                                     }
                                 }
                             }
+                        }
 
-                            if remove_working_level {
-                                stores
-                                    .main
-                                    .remove_working_level(&order.props.working_level_id)?;
+                        if remove_working_level {
+                            stores
+                                .main
+                                .remove_working_level(&order.props.working_level_id)?;
 
-                                stores.statistics.number_of_working_levels -= 1;
+                            stores.statistics.number_of_working_levels -= 1;
 
-                                continue 'level;
-                            }
+                            continue 'level;
                         }
                     }
                     OrderStatus::Opened => {
-                        let mut add_to_chart_traces = false;
-
-                        if (order.props.base.r#type == OrderType::Buy
+                        let take_profit_is_reached = (order.props.base.r#type == OrderType::Buy
                             && current_tick.bid.high >= order.props.base.prices.take_profit)
                             || (order.props.base.r#type == OrderType::Sell
-                                && current_tick.bid.low <= order.props.base.prices.take_profit)
-                        {
-                            add_to_chart_traces = true;
-                            utils.trading_engine.close_position(
-                                &order,
-                                ClosePositionBy::TakeProfit,
-                                stores.main,
-                                &mut stores.config.trading_engine,
-                            )?;
-                        } else if (order.props.base.r#type == OrderType::Buy
+                                && current_tick.bid.low <= order.props.base.prices.take_profit);
+
+                        let stop_loss_is_reached = (order.props.base.r#type == OrderType::Buy
                             && current_tick.bid.low <= order.props.base.prices.stop_loss)
                             || (order.props.base.r#type == OrderType::Sell
-                                && current_tick.bid.high >= order.props.base.prices.stop_loss)
-                        {
-                            add_to_chart_traces = true;
+                                && current_tick.bid.high >= order.props.base.prices.stop_loss);
+
+                        let close_by = match (take_profit_is_reached, stop_loss_is_reached) {
+                            (true, true) => Some(get_close_by_for_ambiguous_fill(
+                                &order.props.base,
+                                stores.config.ambiguous_fill_policy,
+                            )),
+                            (true, false) => Some(ClosePositionBy::TakeProfit),
+                            (false, true) => Some(ClosePositionBy::StopLoss),
+                            (false, false) => None,
+                        };
+
+                        let add_to_chart_traces = close_by.is_some();
+
+                        if let Some(close_by) = close_by {
                             utils.trading_engine.close_position(
                                 &order,
-                                ClosePositionBy::StopLoss,
+                                close_by,
                                 stores.main,
                                 &mut stores.config.trading_engine,
                             )?;
+
+                            stores.main.update_last_close_time_of_working_level(
+                                &order.props.working_level_id,
+                                current_tick.time,
+                            )?;
                         }
 
                         let working_level_chart_index = stores
@@ -1,19 +1,15 @@
 use crate::step::utils::backtesting_charts::StepBacktestingChartTraces;
-use crate::step::utils::entities::working_levels::{
-    LevelTime, WLMaxCrossingValue, WLPrice, WLStatus,
-};
+use crate::step::utils::entities::working_levels::WLStatus;
 use crate::step::utils::level_conditions::MinAmountOfCandles;
 use crate::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
-use backtesting::BacktestingTradingEngineConfig;
-use base::entities::candle::CandleId;
+use backtesting::{BacktestingTradingEngineConfig, Exposure};
 use base::entities::order::{OrderId, OrderPrice};
-use base::entities::tick::{TickPrice, TickTime};
-use base::helpers::{Holiday, NumberOfDaysToExclude};
+use base::entities::tick::TickPrice;
 use base::params::ParamOutputValue;
-use chrono::{NaiveDateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal_macros::dec;
-use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::env;
 
 use super::*;
@@ -44,6 +40,11 @@ impl StrategyParams for StepTestParams {
             }
             StepPointParam::MinAmountOfCandlesInCorridorDefiningEdgeBargaining => unreachable!(),
             StepPointParam::MaxLossPerOneChainOfOrdersPctOfBalance => dec!(10.0),
+            StepPointParam::MaxOrdersPerWorkingLevel => dec!(4),
+            StepPointParam::MaxActiveWorkingLevels => unreachable!(),
+            StepPointParam::VolatilityWindow => unreachable!(),
+            StepPointParam::MinAmountOfTicksBeyondLevelBeforeActivation => unreachable!(),
+            StepPointParam::MaxReentries => unreachable!(),
         }
     }
 
@@ -65,12 +66,75 @@ impl StrategyParams for StepTestParams {
             StepRatioParam::DistanceDefiningNearbyLevelsOfTheSameType => unreachable!(),
             StepRatioParam::MinDistanceOfActivationCrossingOfLevelWhenReturningToLevelForItsDeletion => unreachable!(),
             StepRatioParam::RangeOfBigCorridorNearLevel => unreachable!(),
+            StepRatioParam::MinDistanceBetweenOrdersOfLevel => dec!(0.1),
+            StepRatioParam::MinAngleStrength => unreachable!(),
+            StepRatioParam::MinAngleCandleSize => unreachable!(),
+            StepRatioParam::MinCandleGapForLevelInvalidation => unreachable!(),
+            StepRatioParam::MinProfitToTrail => unreachable!(),
         };
 
         value * Decimal::from(volatility)
     }
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn compute_order_prices__buy_level__should_return_prices_below_level() {
+    let level = Item {
+        id: String::from("1"),
+        props: BasicWLProperties {
+            price: dec!(1.3),
+            r#type: OrderType::Buy,
+            time: Utc::now().naive_utc(),
+        },
+    };
+
+    let order_prices = OrderUtilsImpl::compute_order_prices(
+        &level,
+        &StepTestParams::new(),
+        180,
+        Default::default(),
+    );
+
+    assert_eq!(
+        order_prices,
+        BasicOrderPrices {
+            open: dec!(1.29874),
+            stop_loss: dec!(1.29352),
+            take_profit: dec!(1.3),
+        }
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn compute_order_prices__sell_level__should_return_prices_above_level() {
+    let level = Item {
+        id: String::from("1"),
+        props: BasicWLProperties {
+            price: dec!(1.3),
+            r#type: OrderType::Sell,
+            time: Utc::now().naive_utc(),
+        },
+    };
+
+    let order_prices = OrderUtilsImpl::compute_order_prices(
+        &level,
+        &StepTestParams::new(),
+        180,
+        Default::default(),
+    );
+
+    assert_eq!(
+        order_prices,
+        BasicOrderPrices {
+            open: dec!(1.30126),
+            stop_loss: dec!(1.30648),
+            take_profit: dec!(1.3),
+        }
+    );
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn get_new_chain_of_orders__positive_balance__should_return_correct_chain_of_orders() {
@@ -92,7 +156,7 @@ fn get_new_chain_of_orders__positive_balance__should_return_correct_chain_of_ord
         StepOrderProperties {
             base: BasicOrderProperties {
                 r#type: OrderType::Buy,
-                volume: dec!(0.03),
+                volume: dec!(0.02),
                 status: Default::default(),
                 prices: BasicOrderPrices {
                     open: dec!(1.29874),
@@ -101,11 +165,12 @@ fn get_new_chain_of_orders__positive_balance__should_return_correct_chain_of_ord
                 },
             },
             working_level_id: String::from("1"),
+            creation_time: None,
         },
         StepOrderProperties {
             base: BasicOrderProperties {
                 r#type: OrderType::Buy,
-                volume: dec!(0.03),
+                volume: dec!(0.02),
                 status: Default::default(),
                 prices: BasicOrderPrices {
                     open: dec!(1.29770),
@@ -114,11 +179,12 @@ fn get_new_chain_of_orders__positive_balance__should_return_correct_chain_of_ord
                 },
             },
             working_level_id: String::from("1"),
+            creation_time: None,
         },
         StepOrderProperties {
             base: BasicOrderProperties {
                 r#type: OrderType::Buy,
-                volume: dec!(0.03),
+                volume: dec!(0.02),
                 status: Default::default(),
                 prices: BasicOrderPrices {
                     open: dec!(1.29666),
@@ -127,11 +193,12 @@ fn get_new_chain_of_orders__positive_balance__should_return_correct_chain_of_ord
                 },
             },
             working_level_id: String::from("1"),
+            creation_time: None,
         },
         StepOrderProperties {
             base: BasicOrderProperties {
                 r#type: OrderType::Buy,
-                volume: dec!(0.03),
+                volume: dec!(0.02),
                 status: Default::default(),
                 prices: BasicOrderPrices {
                     open: dec!(1.29562),
@@ -140,11 +207,12 @@ fn get_new_chain_of_orders__positive_balance__should_return_correct_chain_of_ord
                 },
             },
             working_level_id: String::from("1"),
+            creation_time: None,
         },
         StepOrderProperties {
             base: BasicOrderProperties {
                 r#type: OrderType::Buy,
-                volume: dec!(0.03),
+                volume: dec!(0.02),
                 status: Default::default(),
                 prices: BasicOrderPrices {
                     open: dec!(1.29458),
@@ -153,11 +221,19 @@ fn get_new_chain_of_orders__positive_balance__should_return_correct_chain_of_ord
                 },
             },
             working_level_id: String::from("1"),
+            creation_time: None,
         },
     ];
 
-    let chain_of_orders =
-        OrderUtilsImpl::get_new_chain_of_orders(&level, &params, volatility, balance).unwrap();
+    let chain_of_orders = OrderUtilsImpl::get_new_chain_of_orders(
+        &level,
+        &params,
+        volatility,
+        balance,
+        Default::default(),
+        Default::default(),
+    )
+    .unwrap();
 
     assert_eq!(chain_of_orders, expected_chain_of_orders);
 }
@@ -179,8 +255,14 @@ fn get_new_chain_of_orders__zero_balance__should_return_error_result() {
     let volatility = 180;
     let balance = dec!(0);
 
-    let chain_of_orders =
-        OrderUtilsImpl::get_new_chain_of_orders(&level, &params, volatility, balance);
+    let chain_of_orders = OrderUtilsImpl::get_new_chain_of_orders(
+        &level,
+        &params,
+        volatility,
+        balance,
+        Default::default(),
+        Default::default(),
+    );
 
     assert!(chain_of_orders.is_err());
 }
@@ -202,12 +284,106 @@ fn get_new_chain_of_orders__negative_balance__should_return_error_result() {
     let volatility = 180;
     let balance = dec!(-10);
 
-    let chain_of_orders =
-        OrderUtilsImpl::get_new_chain_of_orders(&level, &params, volatility, balance);
+    let chain_of_orders = OrderUtilsImpl::get_new_chain_of_orders(
+        &level,
+        &params,
+        volatility,
+        balance,
+        Default::default(),
+        Default::default(),
+    );
 
     assert!(chain_of_orders.is_err());
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn create_chain_of_orders__chain_exceeds_max_orders_per_working_level__should_refuse_extra_orders()
+{
+    let mut store = InMemoryStepBacktestingStore::default();
+
+    let level = store
+        .create_working_level(String::from("1"), BacktestingWLProperties::default())
+        .unwrap();
+
+    let params = StepTestParams::new();
+
+    let chain_of_orders = (0..5)
+        .map(|i| StepOrderProperties {
+            base: BasicOrderProperties {
+                prices: BasicOrderPrices {
+                    open: dec!(1.3) + Decimal::from(i) / dec!(100),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            working_level_id: level.id.clone(),
+            creation_time: None,
+        })
+        .collect::<Vec<_>>();
+
+    OrderUtilsImpl::create_chain_of_orders(chain_of_orders.clone(), &params, 100, &mut store)
+        .unwrap();
+
+    let created_orders = store.get_working_level_chain_of_orders(&level.id).unwrap();
+
+    assert_eq!(created_orders.len(), 4);
+    assert!(!created_orders
+        .iter()
+        .any(|order| order.props == chain_of_orders[4]));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn create_chain_of_orders__orders_closer_than_min_distance__should_skip_too_close_orders() {
+    let mut store = InMemoryStepBacktestingStore::default();
+
+    let level = store
+        .create_working_level(String::from("1"), BacktestingWLProperties::default())
+        .unwrap();
+
+    let params = StepTestParams::new();
+
+    let order = |open| StepOrderProperties {
+        base: BasicOrderProperties {
+            prices: BasicOrderPrices {
+                open,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        working_level_id: level.id.clone(),
+        creation_time: None,
+    };
+
+    // min distance between orders is 0.1 * 1000 = 100 points
+    OrderUtilsImpl::create_chain_of_orders(
+        vec![order(dec!(1.30000)), order(dec!(1.29950))],
+        &params,
+        1000,
+        &mut store,
+    )
+    .unwrap();
+
+    let created_orders = store.get_working_level_chain_of_orders(&level.id).unwrap();
+
+    assert_eq!(created_orders.len(), 1);
+    assert_eq!(created_orders[0].props.base.prices.open, dec!(1.30000));
+
+    OrderUtilsImpl::create_chain_of_orders(vec![order(dec!(1.29900))], &params, 1000, &mut store)
+        .unwrap();
+
+    let mut created_prices: Vec<_> = store
+        .get_working_level_chain_of_orders(&level.id)
+        .unwrap()
+        .into_iter()
+        .map(|order| order.props.base.prices.open)
+        .collect();
+    created_prices.sort();
+
+    assert_eq!(created_prices, vec![dec!(1.29900), dec!(1.30000)]);
+}
+
 #[derive(Default)]
 struct TestParams;
 
@@ -215,8 +391,12 @@ impl StrategyParams for TestParams {
     type PointParam = StepPointParam;
     type RatioParam = StepRatioParam;
 
-    fn get_point_param_value(&self, _name: Self::PointParam) -> ParamOutputValue {
-        dec!(1)
+    fn get_point_param_value(&self, name: Self::PointParam) -> ParamOutputValue {
+        match name {
+            StepPointParam::MaxActiveWorkingLevels => dec!(10),
+            StepPointParam::MinAmountOfTicksBeyondLevelBeforeActivation => dec!(0),
+            _ => dec!(1),
+        }
     }
 
     fn get_ratio_param_value(
@@ -233,6 +413,7 @@ struct TestTradingEngine {
     opened_orders: RefCell<Vec<String>>,
     closed_orders_by_take_profit: RefCell<Vec<String>>,
     closed_orders_by_stop_loss: RefCell<Vec<String>>,
+    number_of_openings_to_reject: Cell<u32>,
 }
 
 impl TradingEngine for TestTradingEngine {
@@ -246,6 +427,12 @@ impl TradingEngine for TestTradingEngine {
     where
         O: Into<BasicOrderProperties> + Clone,
     {
+        if self.number_of_openings_to_reject.get() > 0 {
+            self.number_of_openings_to_reject
+                .set(self.number_of_openings_to_reject.get() - 1);
+            return Err(OrderRejectionReason::Requote.into());
+        }
+
         self.opened_orders.borrow_mut().push(order.id.clone());
         Ok(())
     }
@@ -274,6 +461,48 @@ impl TradingEngine for TestTradingEngine {
 
         Ok(())
     }
+
+    fn exposure_by_direction<O>(
+        &self,
+        _order_store: &impl BasicOrderStore<OrderProperties = O>,
+    ) -> Result<Exposure>
+    where
+        O: Into<BasicOrderProperties> + Clone,
+    {
+        unimplemented!()
+    }
+
+    fn fill_delayed_orders(
+        &self,
+        _current_tick_price: TickPrice,
+        _trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
+    fn import_position<O>(
+        &self,
+        _order: &Item<OrderId, O>,
+        _open_time: DateTime<Utc>,
+        _trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<()>
+    where
+        O: Into<BasicOrderProperties> + Clone,
+    {
+        unimplemented!()
+    }
+
+    fn close_positions_by_margin_call<O>(
+        &self,
+        _current_tick_price: TickPrice,
+        _order_store: &mut impl BasicOrderStore<OrderProperties = O>,
+        _trading_config: &mut BacktestingTradingEngineConfig,
+    ) -> Result<usize>
+    where
+        O: Into<BasicOrderProperties> + Clone,
+    {
+        unimplemented!()
+    }
 }
 
 // update_orders_backtesting cases to test:
@@ -343,6 +572,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 1,
+                ..Default::default()
             },
         },
         Item {
@@ -353,6 +583,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 2,
+                ..Default::default()
             },
         },
         Item {
@@ -363,6 +594,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 3,
+                ..Default::default()
             },
         },
         Item {
@@ -373,6 +605,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 4,
+                ..Default::default()
             },
         },
         Item {
@@ -383,6 +616,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 5,
+                ..Default::default()
             },
         },
         Item {
@@ -393,6 +627,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 6,
+                ..Default::default()
             },
         },
         Item {
@@ -403,6 +638,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 7,
+                ..Default::default()
             },
         },
         Item {
@@ -413,6 +649,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 8,
+                ..Default::default()
             },
         },
         Item {
@@ -423,6 +660,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 9,
+                ..Default::default()
             },
         },
         Item {
@@ -433,6 +671,7 @@ fn testing_working_levels() -> Vec<Item<WLId, BacktestingWLProperties>> {
                     ..Default::default()
                 },
                 chart_index: 10,
+                ..Default::default()
             },
         },
     ]
@@ -453,6 +692,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "1".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -468,6 +708,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "2".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -484,6 +725,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "3".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -500,6 +742,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "4".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -515,6 +758,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "4".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -530,6 +774,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "4".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -545,6 +790,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "5".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -560,6 +806,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "5".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -576,6 +823,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "5".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -591,6 +839,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "6".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -606,6 +855,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "7".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -622,6 +872,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "8".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -638,6 +889,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "9".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -653,6 +905,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "9".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -668,6 +921,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "9".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -683,6 +937,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "10".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -698,6 +953,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "10".to_string(),
+                creation_time: None,
             },
         },
         Item {
@@ -714,6 +970,7 @@ fn testing_orders() -> Vec<Item<OrderId, StepOrderProperties>> {
                     ..Default::default()
                 },
                 working_level_id: "10".to_string(),
+                creation_time: None,
             },
         },
     ]
@@ -1251,6 +1508,7 @@ fn update_orders_backtesting__no_trading_mode__should_not_place_orders() {
                     ..Default::default()
                 },
                 working_level_id: String::from("1"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1281,6 +1539,7 @@ fn update_orders_backtesting__no_trading_mode__should_not_place_orders() {
                     ..Default::default()
                 },
                 working_level_id: String::from("2"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1424,6 +1683,7 @@ fn update_orders_backtesting__tick_different_low_and_high_prices__should_properl
                     ..Default::default()
                 },
                 working_level_id: String::from("1"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1442,6 +1702,7 @@ fn update_orders_backtesting__tick_different_low_and_high_prices__should_properl
                     ..Default::default()
                 },
                 working_level_id: String::from("1"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1460,6 +1721,7 @@ fn update_orders_backtesting__tick_different_low_and_high_prices__should_properl
                     ..Default::default()
                 },
                 working_level_id: String::from("1"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1478,6 +1740,7 @@ fn update_orders_backtesting__tick_different_low_and_high_prices__should_properl
                     ..Default::default()
                 },
                 working_level_id: String::from("1"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1497,6 +1760,7 @@ fn update_orders_backtesting__tick_different_low_and_high_prices__should_properl
                     ..Default::default()
                 },
                 working_level_id: String::from("1"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1516,6 +1780,7 @@ fn update_orders_backtesting__tick_different_low_and_high_prices__should_properl
                     ..Default::default()
                 },
                 working_level_id: String::from("1"),
+                creation_time: None,
             },
         )
         .unwrap();
@@ -1601,3 +1866,696 @@ fn update_orders_backtesting__tick_different_low_and_high_prices__should_properl
         2
     );
 }
+
+#[derive(Default)]
+struct SingleActiveWorkingLevelTestParams;
+
+impl StrategyParams for SingleActiveWorkingLevelTestParams {
+    type PointParam = StepPointParam;
+    type RatioParam = StepRatioParam;
+
+    fn get_point_param_value(&self, name: Self::PointParam) -> ParamOutputValue {
+        match name {
+            StepPointParam::MinAmountOfTicksBeyondLevelBeforeActivation => dec!(0),
+            _ => dec!(1),
+        }
+    }
+
+    fn get_ratio_param_value(
+        &self,
+        _name: Self::RatioParam,
+        _volatility: CandleVolatility,
+    ) -> ParamOutputValue {
+        dec!(1)
+    }
+}
+
+/// Creates the active working level with the order far from the price
+/// and the created working level with the order crossed by the tick.
+fn store_with_active_and_crossed_created_working_levels() -> InMemoryStepBacktestingStore {
+    let mut store = InMemoryStepBacktestingStore::default();
+
+    for (level_id, open_price) in [("1", dec!(1.20000)), ("2", dec!(1.28000))] {
+        store
+            .create_working_level(
+                String::from(level_id),
+                BacktestingWLProperties {
+                    base: BasicWLProperties {
+                        r#type: OrderType::Buy,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        store
+            .create_order(
+                String::from(level_id),
+                StepOrderProperties {
+                    base: BasicOrderProperties {
+                        r#type: OrderType::Buy,
+                        prices: BasicOrderPrices {
+                            open: open_price,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    working_level_id: String::from(level_id),
+                    creation_time: None,
+                },
+            )
+            .unwrap();
+    }
+
+    store.move_working_level_to_active("1").unwrap();
+
+    store
+}
+
+/// Returns the ids of the opened orders.
+fn update_orders_backtesting_with_single_active_working_level(
+    store: &mut InMemoryStepBacktestingStore,
+    config: &mut StepBacktestingConfig,
+    statistics: &mut StepBacktestingStatistics,
+) -> Vec<OrderId> {
+    update_orders_backtesting_on_tick(
+        store,
+        config,
+        statistics,
+        &SingleActiveWorkingLevelTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    )
+}
+
+/// Returns the ids of the opened orders.
+fn update_orders_backtesting_on_tick(
+    store: &mut InMemoryStepBacktestingStore,
+    config: &mut StepBacktestingConfig,
+    statistics: &mut StepBacktestingStatistics,
+    params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+    trading_engine: &TestTradingEngine,
+    tick_price: TickPrice,
+) -> Vec<OrderId> {
+    update_orders_backtesting_on_historical_tick(
+        store,
+        config,
+        statistics,
+        params,
+        trading_engine,
+        HistoricalTickPrice {
+            low: tick_price,
+            high: tick_price,
+            close: tick_price,
+        },
+    )
+}
+
+/// Returns the ids of the opened orders.
+fn update_orders_backtesting_on_historical_tick(
+    store: &mut InMemoryStepBacktestingStore,
+    config: &mut StepBacktestingConfig,
+    statistics: &mut StepBacktestingStatistics,
+    params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+    trading_engine: &TestTradingEngine,
+    tick_price: HistoricalTickPrice,
+) -> Vec<OrderId> {
+    let current_tick = BasicTickProperties {
+        bid: tick_price,
+        ..Default::default()
+    };
+
+    let level_exceeds_amount_of_candles_in_corridor =
+        |_level_id: &str,
+         _working_level_store: &InMemoryStepBacktestingStore,
+         _corridor_type: CorridorType,
+         _min_amount_of_candles: MinAmountOfCandles| Ok(false);
+
+    let price_is_beyond_stop_loss =
+        |_current_tick_price: UniversalTickPrice,
+         _stop_loss_price: OrderPrice,
+         _working_level_type: OrderType| false;
+
+    let add_entity_to_chart_traces =
+        |_entity: ChartTraceEntity,
+         _chart_traces: &mut StepBacktestingChartTraces,
+         _current_candle_index: ChartIndex| {};
+
+    let level_has_no_active_orders = |_orders: &[StepOrderProperties]| true;
+
+    OrderUtilsImpl::update_orders_backtesting(
+        &current_tick,
+        &StepBacktestingCandleProperties::default(),
+        params,
+        UpdateOrdersBacktestingStores {
+            main: store,
+            config,
+            statistics,
+        },
+        UpdateOrdersBacktestingUtils::new(
+            trading_engine,
+            &add_entity_to_chart_traces,
+            &level_exceeds_amount_of_candles_in_corridor,
+            &price_is_beyond_stop_loss,
+            &level_has_no_active_orders,
+        ),
+        false,
+    )
+    .unwrap();
+
+    trading_engine.opened_orders.take()
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__max_active_working_levels_reached__should_block_activation_until_level_is_removed(
+) {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+    let mut config = StepBacktestingConfig::default(50);
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 2,
+        ..Default::default()
+    };
+
+    let opened_orders = update_orders_backtesting_with_single_active_working_level(
+        &mut store,
+        &mut config,
+        &mut statistics,
+    );
+
+    assert!(opened_orders.is_empty());
+    assert_eq!(
+        store.get_working_level_status("2").unwrap(),
+        Some(WLStatus::Created)
+    );
+
+    store.remove_working_level("1").unwrap();
+
+    let opened_orders = update_orders_backtesting_with_single_active_working_level(
+        &mut store,
+        &mut config,
+        &mut statistics,
+    );
+
+    assert_eq!(opened_orders, vec![String::from("2")]);
+    assert_eq!(
+        store.get_working_level_status("2").unwrap(),
+        Some(WLStatus::Active)
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__max_active_working_levels_reached_and_remove_weakest_policy__should_replace_weakest_level(
+) {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+    let mut config = StepBacktestingConfig::default(50);
+    config.active_levels_cap_policy = ActiveLevelsCapPolicy::RemoveWeakest;
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 2,
+        ..Default::default()
+    };
+
+    let opened_orders = update_orders_backtesting_with_single_active_working_level(
+        &mut store,
+        &mut config,
+        &mut statistics,
+    );
+
+    assert_eq!(opened_orders, vec![String::from("2")]);
+    assert!(store.get_working_level_by_id("1").unwrap().is_none());
+    assert_eq!(
+        store.get_working_level_status("2").unwrap(),
+        Some(WLStatus::Active)
+    );
+    assert_eq!(statistics.number_of_working_levels, 1);
+    assert_eq!(statistics.deleted_by_exceeding_max_active_working_levels, 1);
+}
+
+struct UncappedActiveWorkingLevelsTestParams;
+
+impl StrategyParams for UncappedActiveWorkingLevelsTestParams {
+    type PointParam = StepPointParam;
+    type RatioParam = StepRatioParam;
+
+    fn get_point_param_value(&self, name: Self::PointParam) -> ParamOutputValue {
+        match name {
+            StepPointParam::MinAmountOfTicksBeyondLevelBeforeActivation => dec!(0),
+            StepPointParam::MaxActiveWorkingLevels => dec!(0),
+            _ => dec!(1),
+        }
+    }
+
+    fn get_ratio_param_value(
+        &self,
+        _name: Self::RatioParam,
+        _volatility: CandleVolatility,
+    ) -> ParamOutputValue {
+        dec!(1)
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__zero_max_active_working_levels__should_not_cap_activation() {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+    let mut config = StepBacktestingConfig::default(50);
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 2,
+        ..Default::default()
+    };
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &UncappedActiveWorkingLevelsTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    );
+
+    assert_eq!(opened_orders, vec![String::from("2")]);
+    assert_eq!(
+        store.get_working_level_status("1").unwrap(),
+        Some(WLStatus::Active)
+    );
+    assert_eq!(
+        store.get_working_level_status("2").unwrap(),
+        Some(WLStatus::Active)
+    );
+    assert_eq!(statistics.deleted_by_exceeding_max_active_working_levels, 0);
+}
+
+#[derive(Default)]
+struct ActivationConfirmationTestParams;
+
+impl StrategyParams for ActivationConfirmationTestParams {
+    type PointParam = StepPointParam;
+    type RatioParam = StepRatioParam;
+
+    fn get_point_param_value(&self, name: Self::PointParam) -> ParamOutputValue {
+        match name {
+            StepPointParam::MaxActiveWorkingLevels => dec!(10),
+            _ => dec!(1),
+        }
+    }
+
+    fn get_ratio_param_value(
+        &self,
+        _name: Self::RatioParam,
+        _volatility: CandleVolatility,
+    ) -> ParamOutputValue {
+        dec!(1)
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__one_tick_wick_and_sustained_crossing__should_activate_level_only_on_sustained_crossing(
+) {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+    let mut config = StepBacktestingConfig::default(50);
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 2,
+        ..Default::default()
+    };
+
+    // the price crosses the level for one tick and returns
+    for tick_price in [dec!(1.27000), dec!(1.29000)] {
+        let opened_orders = update_orders_backtesting_on_tick(
+            &mut store,
+            &mut config,
+            &mut statistics,
+            &ActivationConfirmationTestParams,
+            &TestTradingEngine::default(),
+            tick_price,
+        );
+
+        assert!(opened_orders.is_empty());
+        assert_eq!(
+            store.get_working_level_status("2").unwrap(),
+            Some(WLStatus::Created)
+        );
+    }
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &ActivationConfirmationTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    );
+
+    assert!(opened_orders.is_empty());
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &ActivationConfirmationTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    );
+
+    assert_eq!(opened_orders, vec![String::from("2")]);
+    assert_eq!(
+        store.get_working_level_status("2").unwrap(),
+        Some(WLStatus::Active)
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__opening_is_rejected_by_requote__should_keep_order_pending_and_retry_on_next_tick(
+) {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+    store.remove_working_level("1").unwrap();
+
+    let mut config = StepBacktestingConfig::default(50);
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 1,
+        ..Default::default()
+    };
+
+    let trading_engine = TestTradingEngine {
+        number_of_openings_to_reject: Cell::new(1),
+        ..Default::default()
+    };
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &SingleActiveWorkingLevelTestParams,
+        &trading_engine,
+        dec!(1.27000),
+    );
+
+    assert!(opened_orders.is_empty());
+    assert_eq!(statistics.rejected_openings_by_requote, 1);
+    assert_eq!(statistics.number_of_working_levels, 1);
+    assert_eq!(
+        store
+            .get_order_by_id("2")
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .status,
+        OrderStatus::Pending
+    );
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &SingleActiveWorkingLevelTestParams,
+        &trading_engine,
+        dec!(1.27000),
+    );
+
+    assert_eq!(opened_orders, vec![String::from("2")]);
+    assert_eq!(statistics.rejected_openings_by_requote, 1);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__level_in_cooldown__should_suppress_opening_until_cooldown_passes() {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+    store.remove_working_level("1").unwrap();
+
+    let mut config = StepBacktestingConfig {
+        level_cooldown: Some(Duration::hours(1)),
+        ..StepBacktestingConfig::default(50)
+    };
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 1,
+        ..Default::default()
+    };
+
+    store
+        .update_last_close_time_of_working_level(
+            "2",
+            Utc::now().naive_utc() - Duration::minutes(30),
+        )
+        .unwrap();
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &SingleActiveWorkingLevelTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    );
+
+    assert!(opened_orders.is_empty());
+    assert_eq!(statistics.suppressed_by_level_cooldown, 1);
+    assert_eq!(
+        store
+            .get_order_by_id("2")
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .status,
+        OrderStatus::Pending
+    );
+
+    store
+        .update_last_close_time_of_working_level("2", Utc::now().naive_utc() - Duration::hours(2))
+        .unwrap();
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &SingleActiveWorkingLevelTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    );
+
+    assert_eq!(opened_orders, vec![String::from("2")]);
+    assert_eq!(statistics.suppressed_by_level_cooldown, 1);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__pending_order_past_expiration__should_cancel_order_instead_of_filling_it(
+) {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+    store.remove_working_level("1").unwrap();
+
+    store
+        .create_order(
+            String::from("3"),
+            StepOrderProperties {
+                base: BasicOrderProperties {
+                    r#type: OrderType::Buy,
+                    prices: BasicOrderPrices {
+                        open: dec!(1.28000),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                working_level_id: String::from("2"),
+                creation_time: Some(Utc::now().naive_utc() - Duration::hours(2)),
+            },
+        )
+        .unwrap();
+
+    let mut config = StepBacktestingConfig::default(50);
+    config.trading_engine.order_expiration = Some(Duration::hours(1));
+
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 1,
+        ..Default::default()
+    };
+
+    let opened_orders = update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &SingleActiveWorkingLevelTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    );
+
+    // the order without the creation time never expires
+    assert_eq!(opened_orders, vec![String::from("2")]);
+    assert_eq!(statistics.cancelled_by_order_expiration, 1);
+    assert!(store.get_order_by_id("3").unwrap().is_none());
+    assert_eq!(
+        store.get_working_level_chain_of_orders("2").unwrap().len(),
+        1
+    );
+
+    // the level keeps its unexpired order
+    assert_eq!(statistics.number_of_working_levels, 1);
+    assert_eq!(statistics.deleted_by_expiration_of_all_orders, 0);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__all_orders_of_level_past_expiration__should_remove_level_and_count_it(
+) {
+    let mut store = store_with_active_and_crossed_created_working_levels();
+
+    store
+        .create_working_level(
+            String::from("3"),
+            BacktestingWLProperties {
+                base: BasicWLProperties {
+                    r#type: OrderType::Buy,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    store
+        .create_order(
+            String::from("3"),
+            StepOrderProperties {
+                base: BasicOrderProperties {
+                    r#type: OrderType::Buy,
+                    prices: BasicOrderPrices {
+                        open: dec!(1.10000),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                working_level_id: String::from("3"),
+                creation_time: Some(Utc::now().naive_utc() - Duration::hours(2)),
+            },
+        )
+        .unwrap();
+
+    let mut config = StepBacktestingConfig::default(50);
+    config.trading_engine.order_expiration = Some(Duration::hours(1));
+
+    let mut statistics = StepBacktestingStatistics {
+        number_of_working_levels: 3,
+        ..Default::default()
+    };
+
+    update_orders_backtesting_on_tick(
+        &mut store,
+        &mut config,
+        &mut statistics,
+        &UncappedActiveWorkingLevelsTestParams,
+        &TestTradingEngine::default(),
+        dec!(1.27000),
+    );
+
+    assert!(store.get_working_level_by_id("3").unwrap().is_none());
+    assert_eq!(statistics.cancelled_by_order_expiration, 1);
+    assert_eq!(statistics.number_of_working_levels, 2);
+    assert_eq!(statistics.deleted_by_expiration_of_all_orders, 1);
+}
+
+fn order_with_stop_loss_and_take_profit(r#type: OrderType) -> BasicOrderProperties {
+    let (stop_loss, take_profit) = match r#type {
+        OrderType::Buy => (dec!(1.37900), dec!(1.38300)),
+        OrderType::Sell => (dec!(1.38100), dec!(1.37700)),
+    };
+
+    BasicOrderProperties {
+        r#type,
+        status: OrderStatus::Opened,
+        prices: BasicOrderPrices {
+            open: dec!(1.38000),
+            stop_loss,
+            take_profit,
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn get_close_by_for_ambiguous_fill__every_policy__should_return_corresponding_exit() {
+    for r#type in [OrderType::Buy, OrderType::Sell] {
+        let order = order_with_stop_loss_and_take_profit(r#type);
+
+        assert!(matches!(
+            get_close_by_for_ambiguous_fill(&order, AmbiguousFillPolicy::StopFirst),
+            ClosePositionBy::StopLoss
+        ));
+        assert!(matches!(
+            get_close_by_for_ambiguous_fill(&order, AmbiguousFillPolicy::TakeProfitFirst),
+            ClosePositionBy::TakeProfit
+        ));
+        assert!(matches!(
+            get_close_by_for_ambiguous_fill(&order, AmbiguousFillPolicy::WorstCase),
+            ClosePositionBy::StopLoss
+        ));
+
+        // the stop loss is three times closer, so it's three times more probable
+        // to be reached first, and the weighted exit equals the open price
+        match get_close_by_for_ambiguous_fill(&order, AmbiguousFillPolicy::Proportional) {
+            ClosePositionBy::CurrentTickPrice(price) => assert_eq!(price, dec!(1.38000)),
+            close_by => panic!("unexpected close: {:?}", close_by),
+        }
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn update_orders_backtesting__tick_straddles_stop_loss_and_take_profit__should_close_order_by_ambiguous_fill_policy(
+) {
+    env::set_var("MODE", "debug");
+
+    for (policy, closed_by_stop_loss, closed_by_take_profit) in [
+        (AmbiguousFillPolicy::WorstCase, vec!["1"], vec![]),
+        (AmbiguousFillPolicy::StopFirst, vec!["1"], vec![]),
+        (AmbiguousFillPolicy::TakeProfitFirst, vec![], vec!["1"]),
+    ] {
+        let mut store = InMemoryStepBacktestingStore::default();
+
+        store
+            .create_working_level(String::from("1"), Default::default())
+            .unwrap();
+
+        store
+            .create_order(
+                String::from("1"),
+                StepOrderProperties {
+                    base: order_with_stop_loss_and_take_profit(OrderType::Buy),
+                    working_level_id: String::from("1"),
+                    creation_time: None,
+                },
+            )
+            .unwrap();
+
+        let mut config = StepBacktestingConfig::default(50);
+        config.ambiguous_fill_policy = policy;
+
+        let trading_engine = TestTradingEngine::default();
+
+        update_orders_backtesting_on_historical_tick(
+            &mut store,
+            &mut config,
+            &mut Default::default(),
+            &SingleActiveWorkingLevelTestParams,
+            &trading_engine,
+            HistoricalTickPrice {
+                high: dec!(1.38400),
+                low: dec!(1.37800),
+                close: dec!(1.38000),
+            },
+        );
+
+        assert_eq!(
+            trading_engine.closed_orders_by_stop_loss.take(),
+            closed_by_stop_loss
+        );
+        assert_eq!(
+            trading_engine.closed_orders_by_take_profit.take(),
+            closed_by_take_profit
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+
+/// The window of a week day when opening new positions is allowed.
+/// The window includes its start and excludes its end.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TradingSession {
+    pub weekday: Weekday,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+/// Restricts trade entries to the particular sessions defined in the particular timezone.
+///
+/// A `FixedOffset` timezone doesn't follow the daylight saving time, so the sessions
+/// of the markets like London or New York shift by an hour for half of the year.
+/// Such sessions should be defined in a timezone with the daylight saving rules,
+/// e.g. `chrono_tz::Europe::London`.
+#[derive(Debug, Clone)]
+pub struct SessionFilter<Tz: TimeZone = FixedOffset> {
+    pub timezone: Tz,
+    pub sessions: Vec<TradingSession>,
+}
+
+impl Default for SessionFilter {
+    fn default() -> Self {
+        Self {
+            timezone: FixedOffset::east(0),
+            sessions: Vec::new(),
+        }
+    }
+}
+
+impl<Tz: TimeZone> SessionFilter<Tz> {
+    pub fn new(timezone: Tz, sessions: Vec<TradingSession>) -> Self {
+        Self { timezone, sessions }
+    }
+
+    /// Checks whether new positions can be opened at the particular UTC time.
+    /// If no sessions are set, the entries are allowed at any time.
+    pub fn entry_allowed(&self, time: NaiveDateTime) -> bool {
+        if self.sessions.is_empty() {
+            return true;
+        }
+
+        let local_time = DateTime::<Utc>::from_utc(time, Utc).with_timezone(&self.timezone);
+
+        self.sessions.iter().any(|session| {
+            session.weekday == local_time.weekday()
+                && session.start <= local_time.time()
+                && local_time.time() < session.end
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn london_new_york_overlap(timezone: FixedOffset) -> SessionFilter {
+        SessionFilter::new(
+            timezone,
+            [
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ]
+            .into_iter()
+            .map(|weekday| TradingSession {
+                weekday,
+                start: NaiveTime::from_hms(13, 0, 0),
+                end: NaiveTime::from_hms(17, 0, 0),
+            })
+            .collect(),
+        )
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn entry_allowed__time_outside_and_inside_session__should_suppress_and_allow_entry() {
+        let session_filter = london_new_york_overlap(FixedOffset::east(0));
+
+        assert!(!session_filter.entry_allowed(NaiveDate::from_ymd(2022, 5, 25).and_hms(3, 0, 0)));
+        assert!(session_filter.entry_allowed(NaiveDate::from_ymd(2022, 5, 25).and_hms(14, 0, 0)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn entry_allowed__session_in_different_timezone__should_convert_time_to_this_timezone() {
+        // 08:00–12:00 in New York (UTC-5) corresponds to 13:00–17:00 UTC
+        let session_filter = SessionFilter::new(
+            FixedOffset::west(5 * 3600),
+            vec![TradingSession {
+                weekday: Weekday::Wed,
+                start: NaiveTime::from_hms(8, 0, 0),
+                end: NaiveTime::from_hms(12, 0, 0),
+            }],
+        );
+
+        assert!(!session_filter.entry_allowed(NaiveDate::from_ymd(2022, 5, 25).and_hms(3, 0, 0)));
+        assert!(session_filter.entry_allowed(NaiveDate::from_ymd(2022, 5, 25).and_hms(14, 0, 0)));
+        assert!(!session_filter.entry_allowed(NaiveDate::from_ymd(2022, 5, 25).and_hms(17, 0, 0)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn entry_allowed__weekend__should_suppress_entry() {
+        let session_filter = london_new_york_overlap(FixedOffset::east(0));
+
+        assert!(!session_filter.entry_allowed(NaiveDate::from_ymd(2022, 5, 28).and_hms(14, 0, 0)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn entry_allowed__no_sessions__should_allow_entry_at_any_time() {
+        let session_filter = SessionFilter::default();
+
+        assert!(session_filter.entry_allowed(NaiveDate::from_ymd(2022, 5, 25).and_hms(3, 0, 0)));
+    }
+}
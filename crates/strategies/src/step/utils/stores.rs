@@ -1,24 +1,39 @@
 use crate::step::utils::backtesting_charts::{AmountOfCandles, StepBacktestingChartTraces};
 use crate::step::utils::entities::angle::{AngleId, BasicAngleProperties};
 use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
-use crate::step::utils::entities::order::StepOrderProperties;
-use crate::step::utils::entities::working_levels::BacktestingWLProperties;
+use crate::step::utils::entities::order::{AmbiguousFillPolicy, StepOrderProperties};
+use crate::step::utils::entities::working_levels::{
+    ActiveLevelsCapPolicy, BacktestingWLProperties, BasicWLProperties, CrossedLevelTieBreak,
+    TradeDirectionMode, WLId,
+};
 use crate::step::utils::entities::Diff;
+use crate::step::utils::loss_breaker::ConsecutiveLossBreaker;
 use crate::step::utils::stores::angle_store::StepAngleStore;
 use crate::step::utils::stores::candle_store::StepCandleStore;
 use crate::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
 use crate::step::utils::stores::tick_store::StepTickStore;
 use crate::step::utils::stores::working_level_store::StepWorkingLevelStore;
+use anyhow::{bail, Context, Result};
+use backtesting::trading_engine::TradingEngine;
 use backtesting::BacktestingTradingEngineConfig;
-use base::entities::tick::HistoricalTickPrice;
+use base::entities::order::{
+    BasicOrderPrices, BasicOrderProperties, OrderId, OrderStatus, OrderType,
+};
+use base::entities::tick::{HistoricalTickPrice, TickTime};
 use base::entities::{candle::CandleId, tick::TickId, BasicTickProperties, Tendency};
 use base::stores::candle_store::BasicCandleStore;
 use base::stores::order_store::BasicOrderStore;
 use base::stores::tick_store::BasicTickStore;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 pub mod angle_store;
 pub mod candle_store;
 pub mod in_memory_step_backtesting_store;
+pub mod persistent_step_store;
 pub mod tick_store;
 pub mod working_level_store;
 
@@ -31,6 +46,249 @@ where
     pub statistics: StepBacktestingStatistics,
 }
 
+/// The chain of orders of the working level with the statuses, prices and times of the orders.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LevelOrderReport {
+    pub working_level_id: WLId,
+    /// The orders in the order of the chain.
+    pub orders: Vec<LevelOrderReportEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LevelOrderReportEntry {
+    pub id: OrderId,
+    pub r#type: OrderType,
+    pub status: OrderStatus,
+    pub prices: BasicOrderPrices,
+    /// The time of the latest opening of the order in the RFC 3339 format.
+    /// It's `None` if the order is pending or the time of the opening is unknown.
+    pub open_time: Option<String>,
+    /// The time of the closing of the order in the RFC 3339 format.
+    /// It's `None` if the order isn't closed or the time of the closing is unknown.
+    pub close_time: Option<String>,
+}
+
+/// The position opened outside of the current session of the strategy,
+/// e.g. before the restart of the bot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExistingPosition {
+    pub id: OrderId,
+    pub props: BasicOrderProperties,
+    /// The working level the position belongs to. If it's `None`, the position
+    /// is attached to a synthetic active level at its open price.
+    pub working_level_id: Option<WLId>,
+    pub open_time: DateTime<Utc>,
+}
+
+impl ExistingPosition {
+    /// The id of the synthetic working level the position is attached to.
+    pub fn synthetic_working_level_id(&self) -> WLId {
+        format!("imported-{}", self.id)
+    }
+
+    /// Returns an error if the position isn't opened, its volume isn't positive
+    /// or the open price isn't between the stop loss and the take profit.
+    fn validate(&self) -> Result<()> {
+        if self.props.status != OrderStatus::Opened {
+            bail!(
+                "the position {} isn't opened: {:?}",
+                self.id,
+                self.props.status
+            );
+        }
+
+        if self.props.volume <= dec!(0) {
+            bail!(
+                "the volume ({}) of the position {} isn't positive",
+                self.props.volume,
+                self.id
+            );
+        }
+
+        let prices = &self.props.prices;
+
+        let prices_are_valid = match self.props.r#type {
+            OrderType::Buy => prices.stop_loss < prices.open && prices.open < prices.take_profit,
+            OrderType::Sell => prices.take_profit < prices.open && prices.open < prices.stop_loss,
+        };
+
+        if !prices_are_valid {
+            bail!(
+                "the open price of the {:?} position {} isn't between the stop loss \
+                and the take profit: {:?}",
+                self.props.r#type,
+                self.id,
+                prices
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> StepBacktestingStores<T>
+where
+    T: StepBacktestingMainStore,
+{
+    /// Returns the realized profit of the closed trades of every working level
+    /// in the account currency. The trades are linked to the levels via the orders in the store.
+    ///
+    /// The orders removed together with their levels by the `CascadeDelete` removal policy
+    /// can't be linked, so their trades are skipped. The `OrphanOrders` policy keeps them
+    /// for the complete breakdown.
+    pub fn pnl_by_working_level(&self) -> Result<HashMap<WLId, Decimal>> {
+        let orders: HashMap<_, _> = self
+            .main
+            .get_all_orders()?
+            .into_iter()
+            .map(|order| (order.id, order.props))
+            .collect();
+
+        let trading_engine = &self.config.trading_engine;
+
+        let mut pnl_by_working_level = HashMap::new();
+
+        for trade in trading_engine.closed_trades.iter() {
+            if let Some(order) = orders.get(&trade.order_id) {
+                *pnl_by_working_level
+                    .entry(order.working_level_id.clone())
+                    .or_insert(dec!(0)) += trading_engine.trade_pnl(trade);
+            }
+        }
+
+        Ok(pnl_by_working_level)
+    }
+
+    /// Imports the positions opened outside of the current session into the store
+    /// and the trading engine, so the strategy manages them going forward.
+    ///
+    /// The positions are attached to their working levels if these exist,
+    /// otherwise an error is returned. The positions without the working level
+    /// are attached to the synthetic active levels at their open prices.
+    pub fn import_open_positions(
+        &mut self,
+        positions: Vec<ExistingPosition>,
+        trading_engine: &impl TradingEngine,
+    ) -> Result<()> {
+        for position in positions {
+            position.validate()?;
+
+            let working_level_id = match &position.working_level_id {
+                Some(working_level_id) => {
+                    let level = self
+                        .main
+                        .get_working_level_by_id(working_level_id)?
+                        .with_context(|| {
+                            format!(
+                                "a working level with an id {} of the position {} doesn't exist",
+                                working_level_id, position.id
+                            )
+                        })?;
+
+                    if level.props.base.r#type != position.props.r#type {
+                        bail!(
+                            "the type of the position {} ({:?}) differs from the type \
+                            of its working level {} ({:?})",
+                            position.id,
+                            position.props.r#type,
+                            working_level_id,
+                            level.props.base.r#type
+                        );
+                    }
+
+                    working_level_id.clone()
+                }
+                None => {
+                    let level = self.main.create_working_level(
+                        position.synthetic_working_level_id(),
+                        BacktestingWLProperties {
+                            base: BasicWLProperties {
+                                price: position.props.prices.open,
+                                r#type: position.props.r#type,
+                                time: position.open_time.naive_utc(),
+                            },
+                            ..Default::default()
+                        },
+                    )?;
+
+                    self.main.move_working_level_to_active(&level.id)?;
+
+                    level.id
+                }
+            };
+
+            let order = self.main.create_order(
+                position.id,
+                StepOrderProperties {
+                    base: position.props,
+                    working_level_id,
+                    creation_time: Some(position.open_time.naive_utc()),
+                },
+            )?;
+
+            trading_engine.import_position(
+                &order,
+                position.open_time,
+                &mut self.config.trading_engine,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the chain of orders of the working level sorted by the distance from the level.
+    /// The times of the orders are taken from the trades of the trading engine.
+    pub fn export_level_orders(&self, level_id: &str) -> Result<LevelOrderReport> {
+        let level = self
+            .main
+            .get_working_level_by_id(level_id)?
+            .with_context(|| format!("a working level with an id {} doesn't exist", level_id))?;
+
+        let trading_engine = &self.config.trading_engine;
+
+        let mut chain_of_orders = self.main.get_working_level_chain_of_orders(level_id)?;
+        chain_of_orders
+            .sort_by_key(|order| (order.props.base.prices.open - level.props.base.price).abs());
+
+        let orders = chain_of_orders
+            .into_iter()
+            .map(|order| {
+                let (open_time, close_time) = match order.props.base.status {
+                    OrderStatus::Pending => (None, None),
+                    OrderStatus::Opened => (
+                        trading_engine
+                            .open_trades
+                            .get(&order.id)
+                            .and_then(|trade| trade.open_time),
+                        None,
+                    ),
+                    OrderStatus::Closed => trading_engine
+                        .closed_trades
+                        .iter()
+                        .rev()
+                        .find(|trade| trade.order_id == order.id)
+                        .map(|trade| (trade.open_time, trade.close_time))
+                        .unwrap_or_default(),
+                };
+
+                LevelOrderReportEntry {
+                    id: order.id,
+                    r#type: order.props.base.r#type,
+                    status: order.props.base.status,
+                    prices: order.props.base.prices,
+                    open_time: open_time.map(|time| time.to_rfc3339()),
+                    close_time: close_time.map(|time| time.to_rfc3339()),
+                }
+            })
+            .collect();
+
+        Ok(LevelOrderReport {
+            working_level_id: level_id.to_string(),
+            orders,
+        })
+    }
+}
+
 pub trait StepBacktestingMainStore:
     StepTickStore<TickProperties = BasicTickProperties<HistoricalTickPrice>>
     + StepCandleStore<CandleProperties = StepBacktestingCandleProperties>
@@ -50,7 +308,7 @@ pub type Symbol = &'static str;
 
 pub type BacktestingIndex = u32;
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct StepStrategyAngles {
     pub angle_of_second_level_after_bargaining_tendency_change: Option<AngleId>,
     pub tendency_change_angle: Option<AngleId>,
@@ -62,7 +320,7 @@ pub struct StepStrategyAngles {
     pub max_angle_before_bargaining_corridor: Option<AngleId>,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct StepStrategyTicksCandles {
     pub current_tick: Option<TickId>,
     pub previous_tick: Option<TickId>,
@@ -71,19 +329,55 @@ pub struct StepStrategyTicksCandles {
     pub previous_candle: Option<CandleId>,
 }
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
 pub struct StepDiffs {
     pub current: Option<Diff>,
     pub previous: Option<Diff>,
 }
 
-#[derive(Debug, Default)]
+/// The max number of the recent tendency changes kept in the config.
+pub const MAX_NUMBER_OF_RECENT_TENDENCY_CHANGES: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TendencyChange {
+    /// The time of the tick on which the tendency has changed.
+    pub time: TickTime,
+    pub tendency: Tendency,
+    pub crossed_angle_id: AngleId,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct StepConfig {
     pub tendency: Tendency,
     pub tendency_changed_on_crossing_bargaining_corridor: bool,
     pub second_level_after_bargaining_tendency_change_is_created: bool,
     pub skip_creating_new_working_level: bool,
+    /// The max number of the latest candles of the general corridor to scan for the min and max angles
+    /// when checking whether the level comes out of the bargaining corridor. `None` scans the whole corridor.
+    pub max_candles_to_scan_in_general_corridor: Option<usize>,
     pub diffs: StepDiffs,
+    pub(crate) recent_tendency_changes: VecDeque<TendencyChange>,
+}
+
+impl StepConfig {
+    pub fn tendency(&self) -> Tendency {
+        self.tendency
+    }
+
+    /// Returns the recent tendency changes from the oldest to the newest one.
+    pub fn recent_tendency_changes(&self) -> &VecDeque<TendencyChange> {
+        &self.recent_tendency_changes
+    }
+
+    /// Adds the change to the log of the recent tendency changes.
+    /// The oldest change is dropped if the log is full.
+    pub fn record_tendency_change(&mut self, change: TendencyChange) {
+        if self.recent_tendency_changes.len() == MAX_NUMBER_OF_RECENT_TENDENCY_CHANGES {
+            self.recent_tendency_changes.pop_front();
+        }
+
+        self.recent_tendency_changes.push_back(change);
+    }
 }
 
 #[derive(Debug)]
@@ -91,6 +385,17 @@ pub struct StepBacktestingConfig {
     pub base: StepConfig,
     pub trading_engine: BacktestingTradingEngineConfig,
     pub chart_traces: StepBacktestingChartTraces,
+    pub loss_breaker: ConsecutiveLossBreaker,
+    pub active_levels_cap_policy: ActiveLevelsCapPolicy,
+    pub crossed_level_tie_break: CrossedLevelTieBreak,
+    pub ambiguous_fill_policy: AmbiguousFillPolicy,
+    pub trade_direction_mode: TradeDirectionMode,
+    /// The time after the closing of an order of the working level during which
+    /// the level can't open new positions. If it's `None`, there is no cooldown.
+    pub level_cooldown: Option<Duration>,
+    /// The orders closed by stop loss since the previous iteration. The working levels
+    /// of the closed orders are re-armed or removed on the next iteration, and the set is cleared.
+    pub stopped_out_order_ids: HashSet<OrderId>,
 }
 
 impl StepBacktestingConfig {
@@ -99,13 +404,20 @@ impl StepBacktestingConfig {
             base: Default::default(),
             trading_engine: Default::default(),
             chart_traces: StepBacktestingChartTraces::new(total_amount_of_candles),
+            loss_breaker: Default::default(),
+            active_levels_cap_policy: Default::default(),
+            crossed_level_tie_break: Default::default(),
+            ambiguous_fill_policy: Default::default(),
+            trade_direction_mode: Default::default(),
+            level_cooldown: None,
+            stopped_out_order_ids: HashSet::new(),
         }
     }
 }
 
-pub type BacktestingStatisticNumber = u32;
+pub type BacktestingStatisticNumber = u64;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct StepBacktestingStatistics {
     pub number_of_working_levels: BacktestingStatisticNumber,
     pub number_of_tendency_changes: BacktestingStatisticNumber,
@@ -119,4 +431,363 @@ pub struct StepBacktestingStatistics {
     pub deleted_by_exceeding_amount_of_candles_in_big_corridor_before_activation_crossing:
         BacktestingStatisticNumber,
     pub deleted_by_exceeding_activation_crossing_distance: BacktestingStatisticNumber,
+    pub deleted_by_exceeding_max_active_working_levels: BacktestingStatisticNumber,
+    pub deleted_by_candle_gap: BacktestingStatisticNumber,
+    pub deleted_by_expiration_of_all_orders: BacktestingStatisticNumber,
+
+    pub suppressed_signals_during_warmup: BacktestingStatisticNumber,
+    pub suppressed_by_trade_direction: BacktestingStatisticNumber,
+    pub suppressed_by_level_cooldown: BacktestingStatisticNumber,
+    pub suppressed_by_loss_breaker: BacktestingStatisticNumber,
+
+    pub loss_breaker_trips: BacktestingStatisticNumber,
+
+    pub rejected_openings_by_requote: BacktestingStatisticNumber,
+    pub rejected_openings_by_max_total_risk: BacktestingStatisticNumber,
+    pub cancelled_by_order_expiration: BacktestingStatisticNumber,
+
+    pub closed_at_end_of_data: BacktestingStatisticNumber,
+    pub closed_by_margin_call: BacktestingStatisticNumber,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step::utils::entities::working_levels::BasicWLProperties;
+    use backtesting::trading_engine::BacktestingTradingEngine;
+    use backtesting::{CloseReason, ClosedTrade, OpenTrade};
+    use base::entities::order::{BasicOrderProperties, OrderPrice};
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
+    use in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn record_tendency_change__log_is_full__should_drop_oldest_change() {
+        let mut config = StepConfig::default();
+        let start_time = NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0);
+
+        for i in 0..=MAX_NUMBER_OF_RECENT_TENDENCY_CHANGES {
+            config.record_tendency_change(TendencyChange {
+                time: start_time + Duration::hours(i as i64),
+                tendency: if i % 2 == 0 {
+                    Tendency::Up
+                } else {
+                    Tendency::Down
+                },
+                crossed_angle_id: i.to_string(),
+            });
+        }
+
+        let recent_tendency_changes = config.recent_tendency_changes();
+
+        assert_eq!(
+            recent_tendency_changes.len(),
+            MAX_NUMBER_OF_RECENT_TENDENCY_CHANGES
+        );
+        assert_eq!(
+            recent_tendency_changes.front().unwrap().crossed_angle_id,
+            "1"
+        );
+        assert_eq!(
+            recent_tendency_changes.back().unwrap(),
+            &TendencyChange {
+                time: start_time + Duration::hours(MAX_NUMBER_OF_RECENT_TENDENCY_CHANGES as i64),
+                tendency: Tendency::Up,
+                crossed_angle_id: MAX_NUMBER_OF_RECENT_TENDENCY_CHANGES.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn export_level_orders__orders_with_different_statuses__should_return_sorted_chain_with_times()
+    {
+        let mut stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        stores
+            .main
+            .create_working_level(
+                String::from("1"),
+                BacktestingWLProperties {
+                    base: BasicWLProperties {
+                        price: dec!(1.38000),
+                        r#type: OrderType::Buy,
+                        time: NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0),
+                    },
+                    chart_index: 0,
+                    origin: None,
+                },
+            )
+            .unwrap();
+
+        for (id, open_price, status) in [
+            ("3", dec!(1.37700), OrderStatus::Pending),
+            ("1", dec!(1.37900), OrderStatus::Closed),
+            ("2", dec!(1.37800), OrderStatus::Opened),
+        ] {
+            stores
+                .main
+                .create_order(
+                    String::from(id),
+                    StepOrderProperties {
+                        base: BasicOrderProperties {
+                            status,
+                            prices: BasicOrderPrices {
+                                open: open_price,
+                                stop_loss: dec!(1.37500),
+                                take_profit: dec!(1.38500),
+                            },
+                            ..Default::default()
+                        },
+                        working_level_id: String::from("1"),
+                        creation_time: None,
+                    },
+                )
+                .unwrap();
+        }
+
+        let first_open_time = Utc.ymd(2022, 1, 1).and_hms(1, 0, 0);
+        let first_close_time = Utc.ymd(2022, 1, 1).and_hms(2, 0, 0);
+        let second_open_time = Utc.ymd(2022, 1, 1).and_hms(3, 0, 0);
+
+        stores
+            .config
+            .trading_engine
+            .closed_trades
+            .push(ClosedTrade {
+                order_id: String::from("1"),
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                open_price: dec!(1.37900),
+                close_price: dec!(1.38500),
+                open_time: Some(first_open_time),
+                close_time: Some(first_close_time),
+                open_candle_index: None,
+                close_candle_index: None,
+                excursions: Default::default(),
+                close_reason: CloseReason::TakeProfit,
+                fill_slippage: dec!(0),
+                spread_cost: dec!(0),
+            });
+
+        stores.config.trading_engine.open_trades.insert(
+            String::from("2"),
+            OpenTrade {
+                r#type: OrderType::Buy,
+                volume: dec!(0.03),
+                open_price: dec!(1.37800),
+                open_time: Some(second_open_time),
+                open_candle_index: None,
+                excursions: Default::default(),
+                open_fill_slippage: dec!(0),
+                open_spread_cost: dec!(0),
+            },
+        );
+
+        let report = stores.export_level_orders("1").unwrap();
+
+        let orders = report
+            .orders
+            .iter()
+            .map(|order| {
+                (
+                    order.id.as_str(),
+                    order.status,
+                    order.open_time.clone(),
+                    order.close_time.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            orders,
+            vec![
+                (
+                    "1",
+                    OrderStatus::Closed,
+                    Some(first_open_time.to_rfc3339()),
+                    Some(first_close_time.to_rfc3339())
+                ),
+                (
+                    "2",
+                    OrderStatus::Opened,
+                    Some(second_open_time.to_rfc3339()),
+                    None
+                ),
+                ("3", OrderStatus::Pending, None, None),
+            ]
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["working_level_id"], "1");
+        assert_eq!(json["orders"][0]["status"], "Closed");
+        assert_eq!(json["orders"][1]["type"], "Buy");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn export_level_orders__non_existent_level__should_return_error() {
+        let stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        assert!(stores.export_level_orders("1").is_err());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn pnl_by_working_level__closed_trades_of_two_levels__should_sum_pnl_of_orders_of_each_level() {
+        let mut stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        for (id, r#type) in [("1", OrderType::Buy), ("2", OrderType::Sell)] {
+            stores
+                .main
+                .create_working_level(
+                    String::from(id),
+                    BacktestingWLProperties {
+                        base: BasicWLProperties {
+                            price: dec!(1.38000),
+                            r#type,
+                            time: NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0),
+                        },
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+
+        for (id, working_level_id, r#type, volume, close_price) in [
+            ("1", "1", OrderType::Buy, dec!(0.03), dec!(1.38100)),
+            ("2", "1", OrderType::Buy, dec!(0.01), dec!(1.37900)),
+            ("3", "2", OrderType::Sell, dec!(0.02), dec!(1.38050)),
+        ] {
+            stores
+                .main
+                .create_order(
+                    String::from(id),
+                    StepOrderProperties {
+                        base: BasicOrderProperties {
+                            r#type,
+                            volume,
+                            status: OrderStatus::Closed,
+                            ..Default::default()
+                        },
+                        working_level_id: String::from(working_level_id),
+                        creation_time: None,
+                    },
+                )
+                .unwrap();
+
+            stores
+                .config
+                .trading_engine
+                .closed_trades
+                .push(ClosedTrade {
+                    order_id: String::from(id),
+                    r#type,
+                    volume,
+                    open_price: dec!(1.38000),
+                    close_price,
+                    open_time: None,
+                    close_time: None,
+                    open_candle_index: None,
+                    close_candle_index: None,
+                    excursions: Default::default(),
+                    close_reason: CloseReason::Manual,
+                    fill_slippage: dec!(0),
+                    spread_cost: dec!(0),
+                });
+        }
+
+        // the first level: 0.001 * 3000 - 0.001 * 1000, the second level: -0.0005 * 2000
+        assert_eq!(
+            stores.pnl_by_working_level().unwrap(),
+            HashMap::from([(String::from("1"), dec!(2)), (String::from("2"), dec!(-1))])
+        );
+    }
+
+    fn existing_position(open: OrderPrice) -> ExistingPosition {
+        ExistingPosition {
+            id: String::from("1"),
+            props: BasicOrderProperties {
+                status: OrderStatus::Opened,
+                prices: BasicOrderPrices {
+                    open,
+                    stop_loss: dec!(1.37500),
+                    take_profit: dec!(1.38500),
+                },
+                ..Default::default()
+            },
+            working_level_id: None,
+            open_time: Utc.ymd(2022, 1, 1).and_hms(1, 0, 0),
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn import_open_positions__position_without_working_level__should_attach_it_to_synthetic_active_level(
+    ) {
+        let mut stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        let position = existing_position(dec!(1.38000));
+        let synthetic_working_level_id = position.synthetic_working_level_id();
+
+        stores
+            .import_open_positions(vec![position], &BacktestingTradingEngine::new())
+            .unwrap();
+
+        let order = stores.main.get_order_by_id("1").unwrap().unwrap();
+        assert_eq!(order.props.base.status, OrderStatus::Opened);
+        assert_eq!(order.props.working_level_id, synthetic_working_level_id);
+
+        let active_working_levels = stores.main.get_active_working_levels().unwrap();
+        assert_eq!(active_working_levels.len(), 1);
+        assert_eq!(active_working_levels[0].id, synthetic_working_level_id);
+        assert_eq!(active_working_levels[0].props.base.price, dec!(1.38000));
+
+        let trade = &stores.config.trading_engine.open_trades["1"];
+        assert_eq!(trade.open_price, dec!(1.38000));
+        assert_eq!(trade.open_time, Some(Utc.ymd(2022, 1, 1).and_hms(1, 0, 0)));
+
+        // 10_000 - 0.03 * 100_000 * 1.38
+        assert_eq!(
+            stores.config.trading_engine.balances.processing,
+            dec!(5_860)
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn import_open_positions__open_price_beyond_stop_loss__should_return_error() {
+        let mut stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        assert!(stores
+            .import_open_positions(
+                vec![existing_position(dec!(1.37400))],
+                &BacktestingTradingEngine::new()
+            )
+            .is_err());
+
+        assert!(stores.main.get_order_by_id("1").unwrap().is_none());
+        assert!(stores.config.trading_engine.open_trades.is_empty());
+    }
 }
@@ -8,6 +8,12 @@ pub trait StepCandleStore: BasicCandleStore {
         &self,
     ) -> Result<Vec<Item<CandleId, Self::CandleProperties>>>;
 
+    /// Returns at most `number_of_candles` last candles of the general corridor.
+    fn get_last_candles_of_general_corridor(
+        &self,
+        number_of_candles: usize,
+    ) -> Result<Vec<Item<CandleId, Self::CandleProperties>>>;
+
     fn add_candle_to_general_corridor(&mut self, candle_id: CandleId) -> Result<()>;
 
     fn clear_general_corridor(&mut self) -> Result<()>;
@@ -1,26 +1,30 @@
 use std::collections::{HashMap, HashSet};
 
 use anyhow::{bail, Context, Result};
+use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 
-use base::entities::order::{OrderId, OrderStatus, OrderType};
-use base::entities::tick::HistoricalTickPrice;
+use base::entities::candle::{CandleTime, CandleVolatility, PriceValidationMode};
+use base::entities::order::{OrderId, OrderPrice, OrderStatus, OrderType};
+use base::entities::tick::{HistoricalTickPrice, TickTime};
 use base::entities::{
     candle::CandleId, tick::TickId, BasicTickProperties, CANDLE_PRICE_DECIMAL_PLACES,
     SIGNIFICANT_DECIMAL_PLACES,
 };
 use base::entities::{Item, Tendency};
-use base::helpers::{points_to_price, PriceValue};
+use base::helpers::{compute_volatility, points_to_price, PriceValue};
 use base::params::ParamOutputValue;
 use base::stores::candle_store::BasicCandleStore;
 use base::stores::order_store::BasicOrderStore;
 use base::stores::tick_store::BasicTickStore;
 
 use crate::step::utils::entities::angle::FullAngleProperties;
-use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
+use crate::step::utils::entities::candle::{CandleSequenceNumber, StepBacktestingCandleProperties};
 use crate::step::utils::entities::order::StepOrderProperties;
 use crate::step::utils::entities::working_levels::{
-    BacktestingWLProperties, CorridorType, WLMaxCrossingValue, WLStatus,
+    BacktestingWLProperties, CorridorType, NumberOfReentries, NumberOfTicksBeyondLevel,
+    WLMaxCrossingValue, WLStatus,
 };
 use crate::step::utils::entities::{
     angle::{AngleId, BasicAngleProperties},
@@ -34,32 +38,35 @@ use crate::step::utils::stores::{
 
 use super::angle_store::StepAngleStore;
 use super::tick_store::StepTickStore;
-use super::working_level_store::StepWorkingLevelStore;
+use super::working_level_store::{LevelRemovalPolicy, StepWorkingLevelStore};
 
 type RefCount = u64;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct AngleProperties {
     main_props: BasicAngleProperties,
     candle_id: CandleId,
     ref_count: RefCount,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct CandleProperties {
     main_props: StepBacktestingCandleProperties,
     ref_count: RefCount,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct TickProperties {
     main_props: BasicTickProperties<HistoricalTickPrice>,
     ref_count: RefCount,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct InMemoryStepBacktestingStore {
     candles: HashMap<CandleId, Item<CandleId, CandleProperties>>,
+    /// The sequence number of the next created candle. The numbers are never reused,
+    /// so they stay stable when the candles are pruned.
+    next_candle_sequence_number: CandleSequenceNumber,
     ticks: HashMap<TickId, Item<TickId, TickProperties>>,
     angles: HashMap<AngleId, Item<AngleId, AngleProperties>>,
 
@@ -67,9 +74,20 @@ pub struct InMemoryStepBacktestingStore {
 
     working_level_max_crossing_values: HashMap<WLId, WLMaxCrossingValue>,
     working_levels_with_moved_take_profits: HashSet<WLId>,
+    /// The crossed created levels waiting for the confirmation of the crossing to be activated.
+    pending_working_level_activations: HashMap<WLId, NumberOfTicksBeyondLevel>,
+    working_level_reentries: HashMap<WLId, NumberOfReentries>,
+    working_level_last_close_times: HashMap<WLId, TickTime>,
 
     created_working_levels: HashSet<WLId>,
     active_working_levels: HashSet<WLId>,
+    /// Removed levels whose orders may be kept according to the level removal policy.
+    removed_working_levels: HashSet<WLId>,
+    level_removal_policy: LevelRemovalPolicy,
+
+    /// Sequence numbers of the working levels to return them in the order of creation.
+    working_level_creation_numbers: HashMap<WLId, u64>,
+    next_working_level_creation_number: u64,
 
     working_level_small_corridors: HashMap<WLId, Vec<CandleId>>,
     working_level_big_corridors: HashMap<WLId, Vec<CandleId>>,
@@ -86,6 +104,8 @@ pub struct InMemoryStepBacktestingStore {
     second_level_after_bargaining_tendency_change_is_created: bool,
     skip_creating_new_working_level: bool,
     diffs: StepDiffs,
+
+    price_validation_mode: PriceValidationMode,
 }
 
 impl StepBacktestingMainStore for InMemoryStepBacktestingStore {}
@@ -188,12 +208,26 @@ impl BasicCandleStore for InMemoryStepBacktestingStore {
     fn create_candle(
         &mut self,
         id: CandleId,
-        properties: Self::CandleProperties,
+        mut properties: Self::CandleProperties,
     ) -> Result<Item<CandleId, Self::CandleProperties>> {
         if self.candles.contains_key(&id) {
             bail!("a candle with an id {} already exists", id);
         }
 
+        if let Err(err) = properties.step_common.base.prices.validate() {
+            match self.price_validation_mode {
+                PriceValidationMode::Warn => {
+                    log::warn!("a candle with an id {} has invalid prices: {}", id, err)
+                }
+                PriceValidationMode::Error => {
+                    bail!("a candle with an id {} has invalid prices: {}", id, err)
+                }
+            }
+        }
+
+        properties.sequence_number = self.next_candle_sequence_number;
+        self.next_candle_sequence_number += 1;
+
         let new_candle = Item {
             id: id.clone(),
             props: CandleProperties {
@@ -281,6 +315,40 @@ impl BasicCandleStore for InMemoryStepBacktestingStore {
         self.strategy_ticks_candles.previous_candle = Some(new_candle);
         Ok(())
     }
+
+    /// Only the candles that are still stored are taken into account,
+    /// so the candles removed as unused ones are skipped.
+    fn get_candle_offset_from_current(
+        &self,
+        offset: usize,
+    ) -> Result<Option<Item<CandleId, Self::CandleProperties>>> {
+        let current_candle = match self.get_current_candle()? {
+            None => return Ok(None),
+            Some(candle) => candle,
+        };
+
+        let current_candle_time = current_candle.props.step_common.base.time;
+
+        let mut candles = self
+            .candles
+            .values()
+            .filter(|candle| candle.props.main_props.step_common.base.time <= current_candle_time)
+            .collect::<Vec<_>>();
+
+        candles.sort_by(|a, b| {
+            b.props
+                .main_props
+                .step_common
+                .base
+                .time
+                .cmp(&a.props.main_props.step_common.base.time)
+        });
+
+        Ok(candles.get(offset).map(|candle| Item {
+            id: candle.id.clone(),
+            props: candle.props.main_props.clone(),
+        }))
+    }
 }
 
 impl StepCandleStore for InMemoryStepBacktestingStore {
@@ -299,6 +367,26 @@ impl StepCandleStore for InMemoryStepBacktestingStore {
         Ok(candles)
     }
 
+    fn get_last_candles_of_general_corridor(
+        &self,
+        number_of_candles: usize,
+    ) -> Result<Vec<Item<CandleId, Self::CandleProperties>>> {
+        let first_index = self
+            .general_corridor
+            .len()
+            .saturating_sub(number_of_candles);
+
+        let candles = self.general_corridor[first_index..]
+            .iter()
+            .map(|candle_id| {
+                self.get_candle_by_id(candle_id)?
+                    .context(format!("no candle with an id {}", candle_id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(candles)
+    }
+
     fn add_candle_to_general_corridor(&mut self, candle_id: CandleId) -> Result<()> {
         match self.candles.get_mut(&candle_id) {
             None => bail!("a candle with an id {} doesn't exist", candle_id),
@@ -748,6 +836,10 @@ impl StepWorkingLevelStore for InMemoryStepBacktestingStore {
 
         self.created_working_levels.insert(id.clone());
 
+        self.working_level_creation_numbers
+            .insert(id, self.next_working_level_creation_number);
+        self.next_working_level_creation_number += 1;
+
         Ok(new_working_level)
     }
 
@@ -766,6 +858,8 @@ impl StepWorkingLevelStore for InMemoryStepBacktestingStore {
         self.created_working_levels.remove(id);
         self.active_working_levels.insert(id.to_string());
 
+        self.pending_working_level_activations.remove(id);
+
         Ok(())
     }
 
@@ -780,41 +874,41 @@ impl StepWorkingLevelStore for InMemoryStepBacktestingStore {
         self.working_level_max_crossing_values.remove(id);
 
         if let Some(orders) = self.working_level_chain_of_orders.remove(id) {
-            for order in orders.iter() {
-                self.remove_order(order)?;
+            match self.level_removal_policy {
+                LevelRemovalPolicy::CascadeDelete => {
+                    for order in orders.iter() {
+                        self.remove_order(order)?;
+                    }
+                }
+                LevelRemovalPolicy::OrphanOrders => {
+                    self.removed_working_levels.insert(id.to_string());
+                }
             }
         }
 
         self.working_levels_with_moved_take_profits.remove(id);
+        self.pending_working_level_activations.remove(id);
+        self.working_level_reentries.remove(id);
+        self.working_level_last_close_times.remove(id);
 
         self.created_working_levels.remove(id);
         self.active_working_levels.remove(id);
 
+        self.working_level_creation_numbers.remove(id);
+
         Ok(())
     }
 
     fn get_created_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>> {
-        self.created_working_levels
-            .iter()
-            .map(|working_level_id| {
-                self.get_working_level_by_id(working_level_id)?
-                    .context(format!("no working level with an id {}", working_level_id))
-            })
-            .collect::<Result<_, _>>()
+        self.get_working_levels_in_creation_order(self.created_working_levels.iter())
     }
 
     fn get_active_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>> {
-        self.active_working_levels
-            .iter()
-            .map(|working_level_id| {
-                self.get_working_level_by_id(working_level_id)?
-                    .context(format!("no working level with an id {}", working_level_id))
-            })
-            .collect::<Result<_, _>>()
+        self.get_working_levels_in_creation_order(self.active_working_levels.iter())
     }
 
     fn get_all_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>> {
-        Ok(self.working_levels.values().cloned().collect())
+        self.get_working_levels_in_creation_order(self.working_levels.keys())
     }
 
     fn get_working_level_status(&self, id: &str) -> Result<Option<WLStatus>> {
@@ -949,6 +1043,25 @@ impl StepWorkingLevelStore for InMemoryStepBacktestingStore {
         &mut self,
         working_level_id: &str,
         distance_to_move_take_profits: ParamOutputValue,
+    ) -> Result<()> {
+        let order_ids = self
+            .get_working_level_chain_of_orders(working_level_id)?
+            .into_iter()
+            .map(|order| order.id)
+            .collect::<Vec<_>>();
+
+        self.move_take_profits_of_orders_of_level(
+            working_level_id,
+            &order_ids,
+            distance_to_move_take_profits,
+        )
+    }
+
+    fn move_take_profits_of_orders_of_level(
+        &mut self,
+        working_level_id: &str,
+        order_ids: &[OrderId],
+        distance_to_move_take_profits: ParamOutputValue,
     ) -> Result<()> {
         let level = self
             .get_working_level_by_id(working_level_id)?
@@ -978,7 +1091,10 @@ impl StepWorkingLevelStore for InMemoryStepBacktestingStore {
         let distance_to_move_take_profits = points_to_price(distance_to_move_take_profits);
 
         let orders = self.get_working_level_chain_of_orders(working_level_id)?;
-        for order in orders {
+        for order in orders
+            .into_iter()
+            .filter(|order| order_ids.contains(&order.id))
+        {
             let take_profit = &mut self
                 .orders
                 .get_mut(&order.id)
@@ -1001,6 +1117,162 @@ impl StepWorkingLevelStore for InMemoryStepBacktestingStore {
             .contains(working_level_id))
     }
 
+    fn add_tick_to_pending_activation_of_working_level(
+        &mut self,
+        working_level_id: &str,
+    ) -> Result<NumberOfTicksBeyondLevel> {
+        if !self.created_working_levels.contains(working_level_id) {
+            bail!(
+                "a working level with an id {} is not found in created levels",
+                working_level_id
+            );
+        }
+
+        let number_of_ticks = self
+            .pending_working_level_activations
+            .entry(working_level_id.to_string())
+            .or_insert(0);
+
+        *number_of_ticks += 1;
+
+        Ok(*number_of_ticks)
+    }
+
+    fn rearm_working_level(&mut self, working_level_id: &str) -> Result<()> {
+        if self.get_working_level_by_id(working_level_id)?.is_none() {
+            bail!(
+                "a working level with an id {} doesn't exist",
+                working_level_id
+            );
+        }
+
+        for order in self.get_working_level_chain_of_orders(working_level_id)? {
+            if order.props.base.status == OrderStatus::Closed {
+                self.update_order_status(&order.id, OrderStatus::Pending)?;
+            }
+        }
+
+        // the re-armed chain of orders starts a new round of trailing and activation
+        self.working_levels_with_moved_take_profits
+            .remove(working_level_id);
+        self.pending_working_level_activations
+            .remove(working_level_id);
+
+        *self
+            .working_level_reentries
+            .entry(working_level_id.to_string())
+            .or_insert(0) += 1;
+
+        Ok(())
+    }
+
+    fn get_number_of_reentries_of_working_level(
+        &self,
+        working_level_id: &str,
+    ) -> Result<NumberOfReentries> {
+        Ok(self
+            .working_level_reentries
+            .get(working_level_id)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    fn update_last_close_time_of_working_level(
+        &mut self,
+        working_level_id: &str,
+        close_time: TickTime,
+    ) -> Result<()> {
+        if self.get_working_level_by_id(working_level_id)?.is_none() {
+            bail!(
+                "a working level with an id {} doesn't exist",
+                working_level_id
+            );
+        }
+
+        self.working_level_last_close_times
+            .insert(working_level_id.to_string(), close_time);
+
+        Ok(())
+    }
+
+    fn get_last_close_time_of_working_level(
+        &self,
+        working_level_id: &str,
+    ) -> Result<Option<TickTime>> {
+        Ok(self
+            .working_level_last_close_times
+            .get(working_level_id)
+            .copied())
+    }
+
+    fn cancel_pending_activation_of_working_level(&mut self, working_level_id: &str) -> Result<()> {
+        self.pending_working_level_activations
+            .remove(working_level_id);
+
+        Ok(())
+    }
+
+    fn cancel_pending_order(&mut self, order_id: &str) -> Result<()> {
+        let order = self
+            .get_order_by_id(order_id)?
+            .with_context(|| format!("an order with an id {} doesn't exist", order_id))?;
+
+        if order.props.base.status != OrderStatus::Pending {
+            bail!("an order with an id {} is not pending", order_id);
+        }
+
+        if let Some(chain_of_orders) = self
+            .working_level_chain_of_orders
+            .get_mut(&order.props.working_level_id)
+        {
+            chain_of_orders.remove(order_id);
+        }
+
+        self.remove_order(order_id)
+    }
+
+    fn update_stop_loss_of_order(
+        &mut self,
+        order_id: &str,
+        new_stop_loss: OrderPrice,
+    ) -> Result<()> {
+        match self.orders.get_mut(order_id) {
+            None => bail!("an order with an id {} doesn't exist", order_id),
+            Some(order) => order.props.base.prices.stop_loss = new_stop_loss,
+        }
+
+        Ok(())
+    }
+
+    fn validate_order_integrity(&self) -> Result<()> {
+        for order in self.orders.values() {
+            let working_level_id = &order.props.working_level_id;
+
+            if self.working_levels.contains_key(working_level_id) {
+                let order_is_in_chain = self
+                    .working_level_chain_of_orders
+                    .get(working_level_id)
+                    .is_some_and(|chain_of_orders| chain_of_orders.contains(&order.id));
+
+                if !order_is_in_chain {
+                    bail!(
+                        "an order with an id {} is not in the chain of orders of its working level {}",
+                        order.id,
+                        working_level_id
+                    );
+                }
+            } else if !self.removed_working_levels.contains(working_level_id) {
+                bail!(
+                    "an order with an id {} references a non-existent working level {}",
+                    order.id,
+                    working_level_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     fn get_working_level_chain_of_orders(
         &self,
         working_level_id: &str,
@@ -1020,6 +1292,26 @@ impl StepWorkingLevelStore for InMemoryStepBacktestingStore {
 
         Ok(orders)
     }
+
+    fn working_level_is_fully_activated(
+        &self,
+        working_level_id: &str,
+        max_orders_per_working_level: ParamOutputValue,
+    ) -> Result<bool> {
+        if self.get_working_level_by_id(working_level_id)?.is_none() {
+            bail!(
+                "a working level with an id {} doesn't exist",
+                working_level_id
+            );
+        }
+
+        let orders = self.get_working_level_chain_of_orders(working_level_id)?;
+
+        Ok(Decimal::from(orders.len()) >= max_orders_per_working_level
+            && orders
+                .iter()
+                .all(|order| order.props.base.status != OrderStatus::Pending))
+    }
 }
 
 impl InMemoryStepBacktestingStore {
@@ -1027,6 +1319,77 @@ impl InMemoryStepBacktestingStore {
         Default::default()
     }
 
+    /// Defines what to do with the candles having inconsistent prices on their creation.
+    pub fn with_price_validation_mode(
+        mut self,
+        price_validation_mode: PriceValidationMode,
+    ) -> Self {
+        self.price_validation_mode = price_validation_mode;
+        self
+    }
+
+    /// Defines what happens with the orders of a working level when the level is removed.
+    pub fn with_level_removal_policy(mut self, level_removal_policy: LevelRemovalPolicy) -> Self {
+        self.level_removal_policy = level_removal_policy;
+        self
+    }
+
+    fn get_working_levels_in_creation_order<'a>(
+        &self,
+        working_level_ids: impl Iterator<Item = &'a WLId>,
+    ) -> Result<Vec<Item<WLId, BacktestingWLProperties>>> {
+        let mut working_level_ids: Vec<_> = working_level_ids.collect();
+        working_level_ids.sort_by_key(|id| self.working_level_creation_numbers.get(*id));
+
+        working_level_ids
+            .into_iter()
+            .map(|working_level_id| {
+                self.get_working_level_by_id(working_level_id)?
+                    .context(format!("no working level with an id {}", working_level_id))
+            })
+            .collect()
+    }
+
+    /// Clears all the items and resets the strategy state to the default one,
+    /// keeping the allocated memory for the next backtesting run.
+    pub fn reset(&mut self) {
+        self.candles.clear();
+        self.next_candle_sequence_number = 0;
+        self.ticks.clear();
+        self.angles.clear();
+
+        self.working_levels.clear();
+
+        self.working_level_max_crossing_values.clear();
+        self.working_levels_with_moved_take_profits.clear();
+        self.pending_working_level_activations.clear();
+        self.working_level_reentries.clear();
+        self.working_level_last_close_times.clear();
+
+        self.created_working_levels.clear();
+        self.active_working_levels.clear();
+        self.removed_working_levels.clear();
+
+        self.working_level_creation_numbers.clear();
+        self.next_working_level_creation_number = 0;
+
+        self.working_level_small_corridors.clear();
+        self.working_level_big_corridors.clear();
+        self.general_corridor.clear();
+
+        self.working_level_chain_of_orders.clear();
+        self.orders.clear();
+
+        self.strategy_angles = Default::default();
+        self.strategy_ticks_candles = Default::default();
+
+        self.tendency = Default::default();
+        self.tendency_changed_on_crossing_bargaining_corridor = false;
+        self.second_level_after_bargaining_tendency_change_is_created = false;
+        self.skip_creating_new_working_level = false;
+        self.diffs = Default::default();
+    }
+
     pub fn get_all_ticks(&self) -> Result<HashSet<TickId>> {
         Ok(self.ticks.keys().cloned().collect())
     }
@@ -1080,4 +1443,40 @@ impl InMemoryStepBacktestingStore {
 
         Ok(())
     }
+
+    /// Removes the candles older than the cutoff time to bound the memory in long-running sessions.
+    /// The candles still referenced by the current and previous candle pointers, the corridors
+    /// or the angles are skipped.
+    pub fn prune_candles_before(&mut self, cutoff_time: CandleTime) -> Result<()> {
+        self.candles.retain(|_, candle| {
+            candle.props.ref_count > 0
+                || candle.props.main_props.step_common.base.time >= cutoff_time
+        });
+
+        Ok(())
+    }
+
+    /// Returns the volatility of every stored candle in the time order computed over the window
+    /// of the stored candles ending with it. The computation is the same as the one
+    /// of the volatility passed to the strategy. The pruned candles are skipped.
+    pub fn rolling_volatility(&self, window: usize) -> Result<Vec<(CandleId, CandleVolatility)>> {
+        let mut candles = self.candles.values().collect::<Vec<_>>();
+        candles.sort_by_key(|candle| candle.props.main_props.step_common.base.time);
+
+        let candle_props = candles
+            .iter()
+            .map(|candle| candle.props.main_props.step_common.base.clone())
+            .collect::<Vec<_>>();
+
+        Ok(candles
+            .iter()
+            .enumerate()
+            .map(|(i, candle)| {
+                (
+                    candle.id.clone(),
+                    compute_volatility(&candle_props[..=i], window),
+                )
+            })
+            .collect())
+    }
 }
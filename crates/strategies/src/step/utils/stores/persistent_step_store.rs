@@ -0,0 +1,1054 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use redb::{Database, ReadableTable, TableDefinition, TableError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use backtesting::TradingEngineState;
+use base::entities::candle::{CandleId, CandleTime};
+use base::entities::order::{OrderId, OrderPrice, OrderStatus};
+use base::entities::tick::{HistoricalTickPrice, TickId, TickTime};
+use base::entities::{BasicTickProperties, Item};
+use base::params::ParamOutputValue;
+use base::stores::candle_store::BasicCandleStore;
+use base::stores::order_store::BasicOrderStore;
+use base::stores::tick_store::BasicTickStore;
+
+use crate::step::utils::entities::angle::{AngleId, BasicAngleProperties, FullAngleProperties};
+use crate::step::utils::entities::candle::StepBacktestingCandleProperties;
+use crate::step::utils::entities::order::StepOrderProperties;
+use crate::step::utils::entities::working_levels::{
+    BacktestingWLProperties, CorridorType, NumberOfReentries, NumberOfTicksBeyondLevel, WLId,
+    WLMaxCrossingValue, WLStatus,
+};
+use crate::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
+use crate::step::utils::stores::{StepBacktestingMainStore, StepConfig};
+
+use super::angle_store::StepAngleStore;
+use super::candle_store::StepCandleStore;
+use super::tick_store::StepTickStore;
+use super::working_level_store::StepWorkingLevelStore;
+
+/// The key of the snapshot of the whole state of the step store.
+const SNAPSHOT_KEY: &str = "step_store_snapshot";
+const STEP_CONFIG_KEY: &str = "step_config";
+const TRADING_ENGINE_STATE_KEY: &str = "trading_engine_state";
+
+/// The sequence number of the change of the step store. The numbers keep growing
+/// through the compactions, so the changes already included in the snapshot are recognized.
+pub type ChangeNumber = u64;
+
+/// The embedded key-value storage to keep the state of the persistent store in.
+pub trait KeyValueBackend {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<()>;
+    /// Adds the entry under the number to the log of the changes. The entry is either
+    /// fully written or not written at all.
+    fn append_to_log(&mut self, number: ChangeNumber, entry: &[u8]) -> Result<()>;
+    /// Returns the entries of the log of the changes in the order of their numbers.
+    fn get_log(&self) -> Result<Vec<(ChangeNumber, Vec<u8>)>>;
+    fn clear_log(&mut self) -> Result<()>;
+}
+
+const VALUES_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("values");
+const CHANGE_LOG_TABLE: TableDefinition<ChangeNumber, &[u8]> = TableDefinition::new("changes");
+
+/// Keeps the values in the redb database file. Every write is a separate transaction,
+/// which is synced to the disk on the commit, so the written value survives a crash.
+pub struct RedbKeyValueBackend {
+    database: Database,
+}
+
+impl RedbKeyValueBackend {
+    /// Creates the database file if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let database = Database::create(path.as_ref()).context(format!(
+            "failed to open the database of the key-value backend {}",
+            path.as_ref().display()
+        ))?;
+
+        Ok(Self { database })
+    }
+
+    fn write(
+        &self,
+        write_to_transaction: impl FnOnce(&redb::WriteTransaction) -> Result<()>,
+    ) -> Result<()> {
+        let transaction = self.database.begin_write()?;
+        write_to_transaction(&transaction)?;
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
+
+impl KeyValueBackend for RedbKeyValueBackend {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let transaction = self.database.begin_read()?;
+
+        let table = match transaction.open_table(VALUES_TABLE) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(table
+            .get(key)
+            .context(format!("failed to read the value of the key {}", key))?
+            .map(|value| value.value().to_vec()))
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<()> {
+        self.write(|transaction| {
+            transaction
+                .open_table(VALUES_TABLE)?
+                .insert(key, value)
+                .context(format!("failed to write the value of the key {}", key))?;
+            Ok(())
+        })
+    }
+
+    fn append_to_log(&mut self, number: ChangeNumber, entry: &[u8]) -> Result<()> {
+        self.write(|transaction| {
+            transaction
+                .open_table(CHANGE_LOG_TABLE)?
+                .insert(number, entry)
+                .context(format!("failed to log the change #{}", number))?;
+            Ok(())
+        })
+    }
+
+    fn get_log(&self) -> Result<Vec<(ChangeNumber, Vec<u8>)>> {
+        let transaction = self.database.begin_read()?;
+
+        let table = match transaction.open_table(CHANGE_LOG_TABLE) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        table
+            .iter()?
+            .map(|entry| {
+                let (number, entry) = entry.context("failed to read the log of the changes")?;
+                Ok((number.value(), entry.value().to_vec()))
+            })
+            .collect()
+    }
+
+    fn clear_log(&mut self) -> Result<()> {
+        self.write(|transaction| {
+            transaction
+                .delete_table(CHANGE_LOG_TABLE)
+                .context("failed to clear the log of the changes")?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot<S> {
+    store: S,
+    last_change_number: ChangeNumber,
+}
+
+/// The operation changing the step store. The operations are written to the log
+/// of the changes and replayed on the snapshot of the store on the opening.
+#[derive(Serialize, Deserialize)]
+enum StepStoreChange {
+    CreateTick {
+        id: TickId,
+        properties: BasicTickProperties<HistoricalTickPrice>,
+    },
+    UpdateCurrentTick(TickId),
+    UpdatePreviousTick(TickId),
+    CreateCandle {
+        id: CandleId,
+        properties: StepBacktestingCandleProperties,
+    },
+    UpdateCurrentCandle(CandleId),
+    UpdatePreviousCandle(CandleId),
+    AddCandleToGeneralCorridor(CandleId),
+    ClearGeneralCorridor,
+    CreateAngle {
+        id: AngleId,
+        properties: BasicAngleProperties,
+        candle_id: CandleId,
+    },
+    UpdateAngleOfSecondLevelAfterBargainingTendencyChange(Option<AngleId>),
+    UpdateTendencyChangeAngle(AngleId),
+    UpdateMinAngle(AngleId),
+    UpdateVirtualMinAngle(AngleId),
+    UpdateMaxAngle(AngleId),
+    UpdateVirtualMaxAngle(AngleId),
+    UpdateMinAngleBeforeBargainingCorridor(AngleId),
+    UpdateMaxAngleBeforeBargainingCorridor(AngleId),
+    CreateOrder {
+        id: OrderId,
+        properties: StepOrderProperties,
+    },
+    UpdateOrderStatus {
+        order_id: OrderId,
+        new_status: OrderStatus,
+    },
+    CreateWorkingLevel {
+        id: WLId,
+        properties: BacktestingWLProperties,
+    },
+    MoveWorkingLevelToActive(WLId),
+    RemoveWorkingLevel(WLId),
+    ClearWorkingLevelCorridor {
+        working_level_id: WLId,
+        corridor_type: CorridorType,
+    },
+    AddCandleToWorkingLevelCorridor {
+        working_level_id: WLId,
+        candle_id: CandleId,
+        corridor_type: CorridorType,
+    },
+    UpdateMaxCrossingValueOfWorkingLevel {
+        working_level_id: WLId,
+        new_value: WLMaxCrossingValue,
+    },
+    MoveTakeProfitsOfLevel {
+        working_level_id: WLId,
+        distance_to_move_take_profits: ParamOutputValue,
+    },
+    MoveTakeProfitsOfOrdersOfLevel {
+        working_level_id: WLId,
+        order_ids: Vec<OrderId>,
+        distance_to_move_take_profits: ParamOutputValue,
+    },
+    AddTickToPendingActivationOfWorkingLevel(WLId),
+    RearmWorkingLevel(WLId),
+    UpdateLastCloseTimeOfWorkingLevel {
+        working_level_id: WLId,
+        close_time: TickTime,
+    },
+    CancelPendingActivationOfWorkingLevel(WLId),
+    CancelPendingOrder(OrderId),
+    UpdateStopLossOfOrder {
+        order_id: OrderId,
+        new_stop_loss: OrderPrice,
+    },
+    RemoveUnusedItems,
+    PruneCandlesBefore(CandleTime),
+}
+
+impl StepStoreChange {
+    /// Applies the change to the in-memory store. The items returned by the store are dropped,
+    /// because they are needed only when the change is made for the first time.
+    fn apply(self, store: &mut InMemoryStepBacktestingStore) -> Result<()> {
+        match self {
+            Self::CreateTick { id, properties } => store.create_tick(id, properties).map(|_| ()),
+            Self::UpdateCurrentTick(id) => store.update_current_tick(id),
+            Self::UpdatePreviousTick(id) => store.update_previous_tick(id),
+            Self::CreateCandle { id, properties } => {
+                store.create_candle(id, properties).map(|_| ())
+            }
+            Self::UpdateCurrentCandle(id) => store.update_current_candle(id),
+            Self::UpdatePreviousCandle(id) => store.update_previous_candle(id),
+            Self::AddCandleToGeneralCorridor(id) => store.add_candle_to_general_corridor(id),
+            Self::ClearGeneralCorridor => store.clear_general_corridor(),
+            Self::CreateAngle {
+                id,
+                properties,
+                candle_id,
+            } => store.create_angle(id, properties, candle_id).map(|_| ()),
+            Self::UpdateAngleOfSecondLevelAfterBargainingTendencyChange(id) => {
+                store.update_angle_of_second_level_after_bargaining_tendency_change(id)
+            }
+            Self::UpdateTendencyChangeAngle(id) => store.update_tendency_change_angle(id),
+            Self::UpdateMinAngle(id) => store.update_min_angle(id),
+            Self::UpdateVirtualMinAngle(id) => store.update_virtual_min_angle(id),
+            Self::UpdateMaxAngle(id) => store.update_max_angle(id),
+            Self::UpdateVirtualMaxAngle(id) => store.update_virtual_max_angle(id),
+            Self::UpdateMinAngleBeforeBargainingCorridor(id) => {
+                store.update_min_angle_before_bargaining_corridor(id)
+            }
+            Self::UpdateMaxAngleBeforeBargainingCorridor(id) => {
+                store.update_max_angle_before_bargaining_corridor(id)
+            }
+            Self::CreateOrder { id, properties } => store.create_order(id, properties).map(|_| ()),
+            Self::UpdateOrderStatus {
+                order_id,
+                new_status,
+            } => store.update_order_status(&order_id, new_status),
+            Self::CreateWorkingLevel { id, properties } => {
+                store.create_working_level(id, properties).map(|_| ())
+            }
+            Self::MoveWorkingLevelToActive(id) => store.move_working_level_to_active(&id),
+            Self::RemoveWorkingLevel(id) => store.remove_working_level(&id),
+            Self::ClearWorkingLevelCorridor {
+                working_level_id,
+                corridor_type,
+            } => store.clear_working_level_corridor(&working_level_id, corridor_type),
+            Self::AddCandleToWorkingLevelCorridor {
+                working_level_id,
+                candle_id,
+                corridor_type,
+            } => store.add_candle_to_working_level_corridor(
+                &working_level_id,
+                candle_id,
+                corridor_type,
+            ),
+            Self::UpdateMaxCrossingValueOfWorkingLevel {
+                working_level_id,
+                new_value,
+            } => store.update_max_crossing_value_of_working_level(&working_level_id, new_value),
+            Self::MoveTakeProfitsOfLevel {
+                working_level_id,
+                distance_to_move_take_profits,
+            } => store.move_take_profits_of_level(&working_level_id, distance_to_move_take_profits),
+            Self::MoveTakeProfitsOfOrdersOfLevel {
+                working_level_id,
+                order_ids,
+                distance_to_move_take_profits,
+            } => store.move_take_profits_of_orders_of_level(
+                &working_level_id,
+                &order_ids,
+                distance_to_move_take_profits,
+            ),
+            Self::AddTickToPendingActivationOfWorkingLevel(id) => store
+                .add_tick_to_pending_activation_of_working_level(&id)
+                .map(|_| ()),
+            Self::RearmWorkingLevel(id) => store.rearm_working_level(&id),
+            Self::UpdateLastCloseTimeOfWorkingLevel {
+                working_level_id,
+                close_time,
+            } => store.update_last_close_time_of_working_level(&working_level_id, close_time),
+            Self::CancelPendingActivationOfWorkingLevel(id) => {
+                store.cancel_pending_activation_of_working_level(&id)
+            }
+            Self::CancelPendingOrder(id) => store.cancel_pending_order(&id),
+            Self::UpdateStopLossOfOrder {
+                order_id,
+                new_stop_loss,
+            } => store.update_stop_loss_of_order(&order_id, new_stop_loss),
+            Self::RemoveUnusedItems => store.remove_unused_items(),
+            Self::PruneCandlesBefore(cutoff_time) => store.prune_candles_before(cutoff_time),
+        }
+    }
+}
+
+/// The step store surviving the restarts of the strategy. The items are kept
+/// in the in-memory store, and every change is appended to the log of the backend
+/// before it's applied, so the store is restored on the next opening by replaying
+/// the log on the last snapshot. The log is folded into the snapshot by the compaction.
+pub struct PersistentStepStore<B: KeyValueBackend> {
+    store: InMemoryStepBacktestingStore,
+    backend: B,
+    last_change_number: ChangeNumber,
+}
+
+impl<B: KeyValueBackend> PersistentStepStore<B> {
+    /// Restores the state of the store saved in the backend or starts with the empty store.
+    pub fn open(backend: B) -> Result<Self> {
+        let (mut store, mut last_change_number) = match backend.get(SNAPSHOT_KEY)? {
+            Some(snapshot) => {
+                let snapshot: Snapshot<InMemoryStepBacktestingStore> =
+                    serde_json::from_slice(&snapshot)
+                        .context("failed to restore the snapshot of the step store")?;
+                (snapshot.store, snapshot.last_change_number)
+            }
+            None => (InMemoryStepBacktestingStore::new(), 0),
+        };
+
+        for (number, entry) in backend.get_log()? {
+            // the changes of the log left uncleared after the snapshot
+            if number <= last_change_number {
+                continue;
+            }
+
+            let change: StepStoreChange = serde_json::from_slice(&entry)
+                .context("failed to restore the change of the step store")?;
+
+            // the change failed when it was made, so it fails the same way on the replay
+            if let Err(error) = change.apply(&mut store) {
+                log::warn!(
+                    "the change #{} of the step store is skipped: {:?}",
+                    number,
+                    error
+                );
+            }
+
+            last_change_number = number;
+        }
+
+        Ok(Self {
+            store,
+            backend,
+            last_change_number,
+        })
+    }
+
+    /// Returns the in-memory store holding the current state.
+    pub fn inner(&self) -> &InMemoryStepBacktestingStore {
+        &self.store
+    }
+
+    pub fn remove_unused_items(&mut self) -> Result<()> {
+        self.update(StepStoreChange::RemoveUnusedItems, |store| {
+            store.remove_unused_items()
+        })
+    }
+
+    pub fn prune_candles_before(&mut self, cutoff_time: CandleTime) -> Result<()> {
+        self.update(StepStoreChange::PruneCandlesBefore(cutoff_time), |store| {
+            store.prune_candles_before(cutoff_time)
+        })
+    }
+
+    /// Saves the snapshot of the whole state and clears the log of the changes.
+    /// Should be called from time to time, so the log doesn't grow endlessly.
+    pub fn compact(&mut self) -> Result<()> {
+        let snapshot = serde_json::to_vec(&Snapshot {
+            store: &self.store,
+            last_change_number: self.last_change_number,
+        })
+        .context("failed to save the snapshot of the step store")?;
+
+        self.backend.put(SNAPSHOT_KEY, &snapshot)?;
+        self.backend.clear_log()
+    }
+
+    pub fn save_config(&mut self, config: &StepConfig) -> Result<()> {
+        self.save_value(STEP_CONFIG_KEY, config)
+    }
+
+    /// Returns `None` if the config has never been saved.
+    pub fn restore_config(&self) -> Result<Option<StepConfig>> {
+        self.restore_value(STEP_CONFIG_KEY)
+    }
+
+    pub fn save_trading_engine_state(&mut self, state: &TradingEngineState) -> Result<()> {
+        self.save_value(TRADING_ENGINE_STATE_KEY, state)
+    }
+
+    /// Returns `None` if the state of the trading engine has never been saved.
+    pub fn restore_trading_engine_state(&self) -> Result<Option<TradingEngineState>> {
+        self.restore_value(TRADING_ENGINE_STATE_KEY)
+    }
+
+    fn save_value(&mut self, key: &str, value: &impl Serialize) -> Result<()> {
+        let value =
+            serde_json::to_vec(value).context(format!("failed to save the value of {}", key))?;
+        self.backend.put(key, &value)
+    }
+
+    fn restore_value<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.backend
+            .get(key)?
+            .map(|value| {
+                serde_json::from_slice(&value)
+                    .context(format!("failed to restore the value of {}", key))
+            })
+            .transpose()
+    }
+
+    /// Appends the change to the log and then applies it to the in-memory store,
+    /// so the in-memory store is never ahead of the backend. If the change can't be logged,
+    /// it isn't applied, and the error is returned.
+    fn update<T>(
+        &mut self,
+        change: StepStoreChange,
+        apply: impl FnOnce(&mut InMemoryStepBacktestingStore) -> Result<T>,
+    ) -> Result<T> {
+        let number = self.last_change_number + 1;
+
+        let entry =
+            serde_json::to_vec(&change).context("failed to save the change of the step store")?;
+
+        self.backend.append_to_log(number, &entry)?;
+        self.last_change_number = number;
+
+        apply(&mut self.store)
+    }
+}
+
+impl<B: KeyValueBackend> StepBacktestingMainStore for PersistentStepStore<B> {}
+
+impl<B: KeyValueBackend> BasicTickStore for PersistentStepStore<B> {
+    type TickProperties = BasicTickProperties<HistoricalTickPrice>;
+
+    fn create_tick(
+        &mut self,
+        id: TickId,
+        properties: Self::TickProperties,
+    ) -> Result<Item<TickId, Self::TickProperties>> {
+        self.update(
+            StepStoreChange::CreateTick {
+                id: id.clone(),
+                properties: properties.clone(),
+            },
+            |store| store.create_tick(id, properties),
+        )
+    }
+
+    fn get_tick_by_id(&self, tick_id: &str) -> Result<Option<Item<TickId, Self::TickProperties>>> {
+        self.store.get_tick_by_id(tick_id)
+    }
+}
+
+impl<B: KeyValueBackend> StepTickStore for PersistentStepStore<B> {
+    fn get_current_tick(&self) -> Result<Option<Item<TickId, Self::TickProperties>>> {
+        self.store.get_current_tick()
+    }
+
+    fn update_current_tick(&mut self, tick_id: TickId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateCurrentTick(tick_id.clone()),
+            |store| store.update_current_tick(tick_id),
+        )
+    }
+
+    fn get_previous_tick(&self) -> Result<Option<Item<TickId, Self::TickProperties>>> {
+        self.store.get_previous_tick()
+    }
+
+    fn update_previous_tick(&mut self, tick_id: TickId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdatePreviousTick(tick_id.clone()),
+            |store| store.update_previous_tick(tick_id),
+        )
+    }
+}
+
+impl<B: KeyValueBackend> BasicCandleStore for PersistentStepStore<B> {
+    type CandleProperties = StepBacktestingCandleProperties;
+
+    fn create_candle(
+        &mut self,
+        id: CandleId,
+        properties: Self::CandleProperties,
+    ) -> Result<Item<CandleId, Self::CandleProperties>> {
+        self.update(
+            StepStoreChange::CreateCandle {
+                id: id.clone(),
+                properties: properties.clone(),
+            },
+            |store| store.create_candle(id, properties),
+        )
+    }
+
+    fn get_candle_by_id(
+        &self,
+        candle_id: &str,
+    ) -> Result<Option<Item<CandleId, Self::CandleProperties>>> {
+        self.store.get_candle_by_id(candle_id)
+    }
+
+    fn get_current_candle(&self) -> Result<Option<Item<CandleId, Self::CandleProperties>>> {
+        self.store.get_current_candle()
+    }
+
+    fn update_current_candle(&mut self, candle_id: CandleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateCurrentCandle(candle_id.clone()),
+            |store| store.update_current_candle(candle_id),
+        )
+    }
+
+    fn get_previous_candle(&self) -> Result<Option<Item<CandleId, Self::CandleProperties>>> {
+        self.store.get_previous_candle()
+    }
+
+    fn update_previous_candle(&mut self, candle_id: CandleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdatePreviousCandle(candle_id.clone()),
+            |store| store.update_previous_candle(candle_id),
+        )
+    }
+
+    fn get_candle_offset_from_current(
+        &self,
+        offset: usize,
+    ) -> Result<Option<Item<CandleId, Self::CandleProperties>>> {
+        self.store.get_candle_offset_from_current(offset)
+    }
+}
+
+impl<B: KeyValueBackend> StepCandleStore for PersistentStepStore<B> {
+    fn get_candles_of_general_corridor(
+        &self,
+    ) -> Result<Vec<Item<CandleId, Self::CandleProperties>>> {
+        self.store.get_candles_of_general_corridor()
+    }
+
+    fn get_last_candles_of_general_corridor(
+        &self,
+        number_of_candles: usize,
+    ) -> Result<Vec<Item<CandleId, Self::CandleProperties>>> {
+        self.store
+            .get_last_candles_of_general_corridor(number_of_candles)
+    }
+
+    fn add_candle_to_general_corridor(&mut self, candle_id: CandleId) -> Result<()> {
+        self.update(
+            StepStoreChange::AddCandleToGeneralCorridor(candle_id.clone()),
+            |store| store.add_candle_to_general_corridor(candle_id),
+        )
+    }
+
+    fn clear_general_corridor(&mut self) -> Result<()> {
+        self.update(StepStoreChange::ClearGeneralCorridor, |store| {
+            store.clear_general_corridor()
+        })
+    }
+}
+
+impl<B: KeyValueBackend> StepAngleStore for PersistentStepStore<B> {
+    type AngleProperties = BasicAngleProperties;
+    type CandleProperties = StepBacktestingCandleProperties;
+
+    fn create_angle(
+        &mut self,
+        id: AngleId,
+        properties: Self::AngleProperties,
+        candle_id: CandleId,
+    ) -> Result<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>
+    {
+        self.update(
+            StepStoreChange::CreateAngle {
+                id: id.clone(),
+                properties: properties.clone(),
+                candle_id: candle_id.clone(),
+            },
+            |store| store.create_angle(id, properties, candle_id),
+        )
+    }
+
+    fn get_angle_by_id(
+        &self,
+        id: &str,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_angle_by_id(id)
+    }
+
+    fn get_angle_of_second_level_after_bargaining_tendency_change(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store
+            .get_angle_of_second_level_after_bargaining_tendency_change()
+    }
+
+    fn update_angle_of_second_level_after_bargaining_tendency_change(
+        &mut self,
+        new_angle: Option<AngleId>,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateAngleOfSecondLevelAfterBargainingTendencyChange(
+                new_angle.clone(),
+            ),
+            |store| store.update_angle_of_second_level_after_bargaining_tendency_change(new_angle),
+        )
+    }
+
+    fn get_tendency_change_angle(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_tendency_change_angle()
+    }
+
+    fn update_tendency_change_angle(&mut self, new_angle: AngleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateTendencyChangeAngle(new_angle.clone()),
+            |store| store.update_tendency_change_angle(new_angle),
+        )
+    }
+
+    fn get_min_angle(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_min_angle()
+    }
+
+    fn update_min_angle(&mut self, new_angle: AngleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateMinAngle(new_angle.clone()),
+            |store| store.update_min_angle(new_angle),
+        )
+    }
+
+    fn get_virtual_min_angle(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_virtual_min_angle()
+    }
+
+    fn update_virtual_min_angle(&mut self, new_angle: AngleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateVirtualMinAngle(new_angle.clone()),
+            |store| store.update_virtual_min_angle(new_angle),
+        )
+    }
+
+    fn get_max_angle(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_max_angle()
+    }
+
+    fn update_max_angle(&mut self, new_angle: AngleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateMaxAngle(new_angle.clone()),
+            |store| store.update_max_angle(new_angle),
+        )
+    }
+
+    fn get_virtual_max_angle(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_virtual_max_angle()
+    }
+
+    fn update_virtual_max_angle(&mut self, new_angle: AngleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateVirtualMaxAngle(new_angle.clone()),
+            |store| store.update_virtual_max_angle(new_angle),
+        )
+    }
+
+    fn get_min_angle_before_bargaining_corridor(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_min_angle_before_bargaining_corridor()
+    }
+
+    fn update_min_angle_before_bargaining_corridor(&mut self, new_angle: AngleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateMinAngleBeforeBargainingCorridor(new_angle.clone()),
+            |store| store.update_min_angle_before_bargaining_corridor(new_angle),
+        )
+    }
+
+    fn get_max_angle_before_bargaining_corridor(
+        &self,
+    ) -> Result<
+        Option<Item<AngleId, FullAngleProperties<Self::AngleProperties, Self::CandleProperties>>>,
+    > {
+        self.store.get_max_angle_before_bargaining_corridor()
+    }
+
+    fn update_max_angle_before_bargaining_corridor(&mut self, new_angle: AngleId) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateMaxAngleBeforeBargainingCorridor(new_angle.clone()),
+            |store| store.update_max_angle_before_bargaining_corridor(new_angle),
+        )
+    }
+}
+
+impl<B: KeyValueBackend> BasicOrderStore for PersistentStepStore<B> {
+    type OrderProperties = StepOrderProperties;
+
+    fn create_order(
+        &mut self,
+        id: OrderId,
+        properties: Self::OrderProperties,
+    ) -> Result<Item<OrderId, Self::OrderProperties>> {
+        self.update(
+            StepStoreChange::CreateOrder {
+                id: id.clone(),
+                properties: properties.clone(),
+            },
+            |store| store.create_order(id, properties),
+        )
+    }
+
+    fn get_order_by_id(&self, id: &str) -> Result<Option<Item<OrderId, Self::OrderProperties>>> {
+        self.store.get_order_by_id(id)
+    }
+
+    fn get_all_orders(&self) -> Result<Vec<Item<OrderId, Self::OrderProperties>>> {
+        self.store.get_all_orders()
+    }
+
+    fn update_order_status(&mut self, order_id: &str, new_status: OrderStatus) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateOrderStatus {
+                order_id: order_id.to_string(),
+                new_status,
+            },
+            |store| store.update_order_status(order_id, new_status),
+        )
+    }
+}
+
+impl<B: KeyValueBackend> StepWorkingLevelStore for PersistentStepStore<B> {
+    type WorkingLevelProperties = BacktestingWLProperties;
+    type CandleProperties = StepBacktestingCandleProperties;
+    type OrderProperties = StepOrderProperties;
+
+    fn create_working_level(
+        &mut self,
+        id: WLId,
+        properties: Self::WorkingLevelProperties,
+    ) -> Result<Item<WLId, Self::WorkingLevelProperties>> {
+        self.update(
+            StepStoreChange::CreateWorkingLevel {
+                id: id.clone(),
+                properties: properties.clone(),
+            },
+            |store| store.create_working_level(id, properties),
+        )
+    }
+
+    fn get_working_level_by_id(
+        &self,
+        id: &str,
+    ) -> Result<Option<Item<WLId, Self::WorkingLevelProperties>>> {
+        self.store.get_working_level_by_id(id)
+    }
+
+    fn move_working_level_to_active(&mut self, id: &str) -> Result<()> {
+        self.update(
+            StepStoreChange::MoveWorkingLevelToActive(id.to_string()),
+            |store| store.move_working_level_to_active(id),
+        )
+    }
+
+    fn remove_working_level(&mut self, id: &str) -> Result<()> {
+        self.update(
+            StepStoreChange::RemoveWorkingLevel(id.to_string()),
+            |store| store.remove_working_level(id),
+        )
+    }
+
+    fn get_created_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>> {
+        self.store.get_created_working_levels()
+    }
+
+    fn get_active_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>> {
+        self.store.get_active_working_levels()
+    }
+
+    fn get_all_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>> {
+        self.store.get_all_working_levels()
+    }
+
+    fn get_working_level_status(&self, id: &str) -> Result<Option<WLStatus>> {
+        self.store.get_working_level_status(id)
+    }
+
+    fn clear_working_level_corridor(
+        &mut self,
+        working_level_id: &str,
+        corridor_type: CorridorType,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::ClearWorkingLevelCorridor {
+                working_level_id: working_level_id.to_string(),
+                corridor_type,
+            },
+            |store| store.clear_working_level_corridor(working_level_id, corridor_type),
+        )
+    }
+
+    fn add_candle_to_working_level_corridor(
+        &mut self,
+        working_level_id: &str,
+        candle_id: CandleId,
+        corridor_type: CorridorType,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::AddCandleToWorkingLevelCorridor {
+                working_level_id: working_level_id.to_string(),
+                candle_id: candle_id.clone(),
+                corridor_type,
+            },
+            |store| {
+                store.add_candle_to_working_level_corridor(
+                    working_level_id,
+                    candle_id,
+                    corridor_type,
+                )
+            },
+        )
+    }
+
+    fn get_candles_of_working_level_corridor(
+        &self,
+        working_level_id: &str,
+        corridor_type: CorridorType,
+    ) -> Result<Vec<Item<CandleId, Self::CandleProperties>>> {
+        self.store
+            .get_candles_of_working_level_corridor(working_level_id, corridor_type)
+    }
+
+    fn update_max_crossing_value_of_working_level(
+        &mut self,
+        working_level_id: &str,
+        new_value: WLMaxCrossingValue,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateMaxCrossingValueOfWorkingLevel {
+                working_level_id: working_level_id.to_string(),
+                new_value,
+            },
+            |store| store.update_max_crossing_value_of_working_level(working_level_id, new_value),
+        )
+    }
+
+    fn get_max_crossing_value_of_working_level(
+        &self,
+        working_level_id: &str,
+    ) -> Result<Option<WLMaxCrossingValue>> {
+        self.store
+            .get_max_crossing_value_of_working_level(working_level_id)
+    }
+
+    fn move_take_profits_of_level(
+        &mut self,
+        working_level_id: &str,
+        distance_to_move_take_profits: ParamOutputValue,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::MoveTakeProfitsOfLevel {
+                working_level_id: working_level_id.to_string(),
+                distance_to_move_take_profits,
+            },
+            |store| {
+                store.move_take_profits_of_level(working_level_id, distance_to_move_take_profits)
+            },
+        )
+    }
+
+    fn move_take_profits_of_orders_of_level(
+        &mut self,
+        working_level_id: &str,
+        order_ids: &[OrderId],
+        distance_to_move_take_profits: ParamOutputValue,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::MoveTakeProfitsOfOrdersOfLevel {
+                working_level_id: working_level_id.to_string(),
+                order_ids: order_ids.to_vec(),
+                distance_to_move_take_profits,
+            },
+            |store| {
+                store.move_take_profits_of_orders_of_level(
+                    working_level_id,
+                    order_ids,
+                    distance_to_move_take_profits,
+                )
+            },
+        )
+    }
+
+    fn take_profits_of_level_are_moved(&self, working_level_id: &str) -> Result<bool> {
+        self.store.take_profits_of_level_are_moved(working_level_id)
+    }
+
+    fn add_tick_to_pending_activation_of_working_level(
+        &mut self,
+        working_level_id: &str,
+    ) -> Result<NumberOfTicksBeyondLevel> {
+        self.update(
+            StepStoreChange::AddTickToPendingActivationOfWorkingLevel(working_level_id.to_string()),
+            |store| store.add_tick_to_pending_activation_of_working_level(working_level_id),
+        )
+    }
+
+    fn rearm_working_level(&mut self, working_level_id: &str) -> Result<()> {
+        self.update(
+            StepStoreChange::RearmWorkingLevel(working_level_id.to_string()),
+            |store| store.rearm_working_level(working_level_id),
+        )
+    }
+
+    fn get_number_of_reentries_of_working_level(
+        &self,
+        working_level_id: &str,
+    ) -> Result<NumberOfReentries> {
+        self.store
+            .get_number_of_reentries_of_working_level(working_level_id)
+    }
+
+    fn update_last_close_time_of_working_level(
+        &mut self,
+        working_level_id: &str,
+        close_time: TickTime,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateLastCloseTimeOfWorkingLevel {
+                working_level_id: working_level_id.to_string(),
+                close_time,
+            },
+            |store| store.update_last_close_time_of_working_level(working_level_id, close_time),
+        )
+    }
+
+    fn get_last_close_time_of_working_level(
+        &self,
+        working_level_id: &str,
+    ) -> Result<Option<TickTime>> {
+        self.store
+            .get_last_close_time_of_working_level(working_level_id)
+    }
+
+    fn cancel_pending_activation_of_working_level(&mut self, working_level_id: &str) -> Result<()> {
+        self.update(
+            StepStoreChange::CancelPendingActivationOfWorkingLevel(working_level_id.to_string()),
+            |store| store.cancel_pending_activation_of_working_level(working_level_id),
+        )
+    }
+
+    fn cancel_pending_order(&mut self, order_id: &str) -> Result<()> {
+        self.update(
+            StepStoreChange::CancelPendingOrder(order_id.to_string()),
+            |store| store.cancel_pending_order(order_id),
+        )
+    }
+
+    fn update_stop_loss_of_order(
+        &mut self,
+        order_id: &str,
+        new_stop_loss: OrderPrice,
+    ) -> Result<()> {
+        self.update(
+            StepStoreChange::UpdateStopLossOfOrder {
+                order_id: order_id.to_string(),
+                new_stop_loss,
+            },
+            |store| store.update_stop_loss_of_order(order_id, new_stop_loss),
+        )
+    }
+
+    fn validate_order_integrity(&self) -> Result<()> {
+        self.store.validate_order_integrity()
+    }
+
+    fn get_working_level_chain_of_orders(
+        &self,
+        working_level_id: &str,
+    ) -> Result<Vec<Item<OrderId, Self::OrderProperties>>> {
+        self.store
+            .get_working_level_chain_of_orders(working_level_id)
+    }
+
+    fn working_level_is_fully_activated(
+        &self,
+        working_level_id: &str,
+        max_orders_per_working_level: ParamOutputValue,
+    ) -> Result<bool> {
+        self.store
+            .working_level_is_fully_activated(working_level_id, max_orders_per_working_level)
+    }
+}
@@ -1,12 +1,24 @@
 use anyhow::Result;
-use base::entities::order::OrderId;
+use base::entities::order::{OrderId, OrderPrice};
+use base::entities::tick::TickTime;
 use base::entities::{candle::CandleId, Item};
 use base::params::ParamOutputValue;
+use serde::{Deserialize, Serialize};
 
 use crate::step::utils::entities::working_levels::{
-    CorridorType, WLId, WLMaxCrossingValue, WLStatus,
+    CorridorType, NumberOfReentries, NumberOfTicksBeyondLevel, WLId, WLMaxCrossingValue, WLStatus,
 };
 
+/// Defines what happens with the orders of a working level when the level is removed.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LevelRemovalPolicy {
+    /// The orders are removed together with the level.
+    #[default]
+    CascadeDelete,
+    /// The orders are kept in the store, but they're no longer a chain of orders of any level.
+    OrphanOrders,
+}
+
 pub trait StepWorkingLevelStore {
     type WorkingLevelProperties;
     type CandleProperties;
@@ -23,10 +35,14 @@ pub trait StepWorkingLevelStore {
     ) -> Result<Option<Item<WLId, Self::WorkingLevelProperties>>>;
 
     fn move_working_level_to_active(&mut self, id: &str) -> Result<()>;
+    /// The orders of the level are handled according to the level removal policy of the store.
     fn remove_working_level(&mut self, id: &str) -> Result<()>;
 
+    /// Returns the created working levels in the order of their creation.
     fn get_created_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>>;
+    /// Returns the active working levels in the order of their creation.
     fn get_active_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>>;
+    /// Returns all the working levels in the order of their creation.
     fn get_all_working_levels(&self) -> Result<Vec<Item<WLId, Self::WorkingLevelProperties>>>;
 
     fn get_working_level_status(&self, id: &str) -> Result<Option<WLStatus>>;
@@ -44,6 +60,8 @@ pub trait StepWorkingLevelStore {
         corridor_type: CorridorType,
     ) -> Result<()>;
 
+    /// Returns the candles of the particular corridor of the working level
+    /// in the order they were added to the corridor.
     fn get_candles_of_working_level_corridor(
         &self,
         working_level_id: &str,
@@ -67,10 +85,72 @@ pub trait StepWorkingLevelStore {
         distance_to_move_take_profits: ParamOutputValue,
     ) -> Result<()>;
 
+    /// Moves take profits of the particular orders of the working level
+    /// and marks the take profits of the level as moved.
+    fn move_take_profits_of_orders_of_level(
+        &mut self,
+        working_level_id: &str,
+        order_ids: &[OrderId],
+        distance_to_move_take_profits: ParamOutputValue,
+    ) -> Result<()>;
+
     fn take_profits_of_level_are_moved(&self, working_level_id: &str) -> Result<bool>;
 
+    /// Counts one more tick of the price being beyond the crossed created working level
+    /// and returns the number of ticks counted since the level was crossed.
+    fn add_tick_to_pending_activation_of_working_level(
+        &mut self,
+        working_level_id: &str,
+    ) -> Result<NumberOfTicksBeyondLevel>;
+
+    /// Returns the closed orders of the chain of orders of the working level to pending,
+    /// resets the moving of its take profits and its pending activation
+    /// and counts one more re-entry of the level.
+    fn rearm_working_level(&mut self, working_level_id: &str) -> Result<()>;
+
+    fn get_number_of_reentries_of_working_level(
+        &self,
+        working_level_id: &str,
+    ) -> Result<NumberOfReentries>;
+
+    /// Records the time of the closing of the last order of the working level.
+    fn update_last_close_time_of_working_level(
+        &mut self,
+        working_level_id: &str,
+        close_time: TickTime,
+    ) -> Result<()>;
+
+    fn get_last_close_time_of_working_level(
+        &self,
+        working_level_id: &str,
+    ) -> Result<Option<TickTime>>;
+
+    /// Resets the counted ticks of the pending activation of the working level.
+    fn cancel_pending_activation_of_working_level(&mut self, working_level_id: &str) -> Result<()>;
+
+    /// Removes the pending order from the store and from the chain of orders of its working level.
+    fn cancel_pending_order(&mut self, order_id: &str) -> Result<()>;
+
+    fn update_stop_loss_of_order(
+        &mut self,
+        order_id: &str,
+        new_stop_loss: OrderPrice,
+    ) -> Result<()>;
+
+    /// Checks that every order references a known created, active or removed working level
+    /// and the order of an existing level belongs to the chain of orders of this level.
+    fn validate_order_integrity(&self) -> Result<()>;
+
     fn get_working_level_chain_of_orders(
         &self,
         working_level_id: &str,
     ) -> Result<Vec<Item<OrderId, Self::OrderProperties>>>;
+
+    /// Checks that the chain of orders of the working level has reached the max amount of orders
+    /// and none of the orders of the chain is pending anymore.
+    fn working_level_is_fully_activated(
+        &self,
+        working_level_id: &str,
+        max_orders_per_working_level: ParamOutputValue,
+    ) -> Result<bool>;
 }
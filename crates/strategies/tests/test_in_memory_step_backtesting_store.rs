@@ -1,24 +1,28 @@
+use chrono::NaiveDate;
 use rust_decimal_macros::dec;
 use std::collections::HashSet;
 
-use base::entities::candle::CandleId;
+use base::entities::candle::{CandleId, PriceValidationMode};
 use base::entities::order::{BasicOrderPrices, BasicOrderProperties, OrderStatus, OrderType};
 use base::entities::tick::TickId;
-use base::entities::Level;
+use base::entities::{CandlePrices, Item, Level};
 use base::helpers::points_to_price;
 use base::stores::candle_store::BasicCandleStore;
 use base::stores::order_store::BasicOrderStore;
 use base::stores::tick_store::BasicTickStore;
 use strategies::step::utils::entities::angle::{AngleId, AngleState, BasicAngleProperties};
+use strategies::step::utils::entities::candle::StepBacktestingCandleProperties;
 use strategies::step::utils::entities::order::StepOrderProperties;
 use strategies::step::utils::entities::working_levels::{
-    BacktestingWLProperties, BasicWLProperties, CorridorType, WLStatus,
+    BacktestingWLProperties, BasicWLProperties, CorridorType, WLId, WLStatus,
 };
 use strategies::step::utils::stores::angle_store::StepAngleStore;
 use strategies::step::utils::stores::candle_store::StepCandleStore;
 use strategies::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
 use strategies::step::utils::stores::tick_store::StepTickStore;
-use strategies::step::utils::stores::working_level_store::StepWorkingLevelStore;
+use strategies::step::utils::stores::working_level_store::{
+    LevelRemovalPolicy, StepWorkingLevelStore,
+};
 
 #[test]
 fn should_remove_only_unused_items() {
@@ -35,7 +39,7 @@ fn should_remove_only_unused_items() {
     }
 
     assert!(store
-        .update_current_tick(ticks.get(0).unwrap().clone())
+        .update_current_tick(ticks.first().unwrap().clone())
         .is_ok());
     assert!(store
         .update_previous_tick(ticks.get(1).unwrap().clone())
@@ -51,7 +55,7 @@ fn should_remove_only_unused_items() {
     }
 
     assert!(store
-        .update_current_candle(candles.get(0).unwrap().clone())
+        .update_current_candle(candles.first().unwrap().clone())
         .is_ok());
     assert!(store
         .update_previous_candle(candles.get(1).unwrap().clone())
@@ -117,7 +121,7 @@ fn should_remove_only_unused_items() {
 
     assert!(store
         .update_angle_of_second_level_after_bargaining_tendency_change(Some(
-            angles.get(0).unwrap().clone()
+            angles.first().unwrap().clone()
         ))
         .is_ok());
     assert!(store
@@ -294,6 +298,69 @@ fn should_successfully_remove_working_level() {
         .is_empty());
 }
 
+fn create_level_with_order(store: &mut InMemoryStepBacktestingStore) -> (String, String) {
+    let working_level_id = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap()
+        .id;
+
+    let order_id = store
+        .create_order(
+            xid::new().to_string(),
+            StepOrderProperties {
+                working_level_id: working_level_id.clone(),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .id;
+
+    (working_level_id, order_id)
+}
+
+#[test]
+fn should_remove_orders_of_removed_working_level_by_default() {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let (working_level_id, order_id) = create_level_with_order(&mut store);
+    create_level_with_order(&mut store);
+
+    store.remove_working_level(&working_level_id).unwrap();
+
+    assert!(store.get_order_by_id(&order_id).unwrap().is_none());
+    assert_eq!(store.get_all_orders().unwrap().len(), 1);
+    assert!(store.validate_order_integrity().is_ok());
+}
+
+#[test]
+fn should_keep_orders_of_removed_working_level_with_orphan_orders_policy() {
+    let mut store = InMemoryStepBacktestingStore::new()
+        .with_level_removal_policy(LevelRemovalPolicy::OrphanOrders);
+
+    let (working_level_id, order_id) = create_level_with_order(&mut store);
+
+    store.remove_working_level(&working_level_id).unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .working_level_id,
+        working_level_id
+    );
+    assert!(store
+        .get_working_level_chain_of_orders(&working_level_id)
+        .unwrap()
+        .is_empty());
+    assert!(store.validate_order_integrity().is_ok());
+
+    store.reset();
+
+    assert!(store.validate_order_integrity().is_ok());
+}
+
 #[test]
 fn should_successfully_get_all_working_levels() {
     let mut store = InMemoryStepBacktestingStore::default();
@@ -380,6 +447,295 @@ fn should_successfully_add_candle_to_working_level_corridor_and_then_clear_corri
         .is_empty());
 }
 
+#[test]
+fn should_return_working_levels_in_creation_order() {
+    let mut store: InMemoryStepBacktestingStore = Default::default();
+
+    let working_level_ids: Vec<_> = (0..10)
+        .map(|_| {
+            store
+                .create_working_level(xid::new().to_string(), Default::default())
+                .unwrap()
+                .id
+        })
+        .collect();
+
+    for working_level_id in working_level_ids.iter().step_by(2).rev() {
+        store
+            .move_working_level_to_active(working_level_id)
+            .unwrap();
+    }
+
+    let ids = |levels: Vec<Item<WLId, BacktestingWLProperties>>| -> Vec<WLId> {
+        levels.into_iter().map(|level| level.id).collect()
+    };
+
+    assert_eq!(
+        ids(store.get_created_working_levels().unwrap()),
+        working_level_ids
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        ids(store.get_active_working_levels().unwrap()),
+        working_level_ids
+            .iter()
+            .step_by(2)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        ids(store.get_all_working_levels().unwrap()),
+        working_level_ids
+    );
+}
+
+#[test]
+fn should_return_candles_of_working_level_corridor_in_insertion_order() {
+    let mut store: InMemoryStepBacktestingStore = Default::default();
+
+    let working_level_id = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap()
+        .id;
+
+    let mut candle_ids = Vec::new();
+
+    for _ in 0..3 {
+        let candle_id = store
+            .create_candle(xid::new().to_string(), Default::default())
+            .unwrap()
+            .id;
+
+        candle_ids.push(candle_id);
+    }
+
+    for candle_id in candle_ids.iter().rev() {
+        store
+            .add_candle_to_working_level_corridor(
+                &working_level_id,
+                candle_id.clone(),
+                CorridorType::Small,
+            )
+            .unwrap();
+    }
+
+    let corridor_candle_ids: Vec<_> = store
+        .get_candles_of_working_level_corridor(&working_level_id, CorridorType::Small)
+        .unwrap()
+        .into_iter()
+        .map(|candle| candle.id)
+        .collect();
+
+    candle_ids.reverse();
+    assert_eq!(corridor_candle_ids, candle_ids);
+
+    assert!(store
+        .get_candles_of_working_level_corridor(&working_level_id, CorridorType::Big)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn should_reject_candle_with_invalid_prices_only_in_error_price_validation_mode() {
+    let mut candle = StepBacktestingCandleProperties::default();
+    candle.step_common.base.prices = CandlePrices {
+        open: dec!(1.38),
+        high: dec!(1.37),
+        low: dec!(1.39),
+        close: dec!(1.38),
+    };
+
+    let mut store = InMemoryStepBacktestingStore::new();
+    assert!(store
+        .create_candle(xid::new().to_string(), candle.clone())
+        .is_ok());
+
+    let mut store =
+        InMemoryStepBacktestingStore::new().with_price_validation_mode(PriceValidationMode::Error);
+    assert!(store
+        .create_candle(xid::new().to_string(), candle.clone())
+        .is_err());
+    assert!(store.get_all_candles().unwrap().is_empty());
+
+    candle.step_common.base.prices = Default::default();
+    assert!(store.create_candle(xid::new().to_string(), candle).is_ok());
+}
+
+#[test]
+fn should_return_candle_offset_from_current_in_time_order() {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    assert!(store.get_candle_offset_from_current(0).unwrap().is_none());
+
+    let mut candle_ids = Vec::new();
+
+    // the candles are created in the reverse time order to make sure that they're sorted by time
+    for hour in (0..4).rev() {
+        let mut candle = StepBacktestingCandleProperties::default();
+        candle.step_common.base.time = NaiveDate::from_ymd(2022, 4, 1).and_hms(hour, 0, 0);
+
+        let candle_id = xid::new().to_string();
+        store.create_candle(candle_id.clone(), candle).unwrap();
+        candle_ids.insert(0, candle_id);
+    }
+
+    store.update_current_candle(candle_ids[2].clone()).unwrap();
+
+    for (offset, candle_id) in [
+        (0, &candle_ids[2]),
+        (1, &candle_ids[1]),
+        (2, &candle_ids[0]),
+    ] {
+        assert_eq!(
+            &store
+                .get_candle_offset_from_current(offset)
+                .unwrap()
+                .unwrap()
+                .id,
+            candle_id
+        );
+    }
+
+    assert!(store.get_candle_offset_from_current(3).unwrap().is_none());
+}
+
+#[test]
+fn should_prune_only_unreferenced_candles_older_than_cutoff_time() {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let mut candle_ids = Vec::new();
+
+    for hour in 0..6 {
+        let mut candle = StepBacktestingCandleProperties::default();
+        candle.step_common.base.time = NaiveDate::from_ymd(2022, 4, 1).and_hms(hour, 0, 0);
+
+        let candle_id = xid::new().to_string();
+        store.create_candle(candle_id.clone(), candle).unwrap();
+        candle_ids.push(candle_id);
+    }
+
+    let angle_id = store
+        .create_angle(
+            xid::new().to_string(),
+            BasicAngleProperties {
+                r#type: Level::Min,
+                state: AngleState::Real,
+            },
+            candle_ids[0].clone(),
+        )
+        .unwrap()
+        .id;
+    store.update_min_angle(angle_id).unwrap();
+
+    let working_level_id = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap()
+        .id;
+    store
+        .move_working_level_to_active(&working_level_id)
+        .unwrap();
+    store
+        .add_candle_to_working_level_corridor(
+            &working_level_id,
+            candle_ids[1].clone(),
+            CorridorType::Small,
+        )
+        .unwrap();
+
+    store.update_previous_candle(candle_ids[3].clone()).unwrap();
+    store.update_current_candle(candle_ids[4].clone()).unwrap();
+
+    store
+        .prune_candles_before(NaiveDate::from_ymd(2022, 4, 1).and_hms(5, 0, 0))
+        .unwrap();
+
+    let left_candles = [0, 1, 3, 4, 5]
+        .iter()
+        .map(|&i| candle_ids[i].clone())
+        .collect::<HashSet<_>>();
+
+    assert_eq!(store.get_all_candles().unwrap(), left_candles);
+}
+
+#[test]
+fn should_assign_candle_sequence_numbers_that_are_not_reused_after_pruning() {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let mut candle_ids = Vec::new();
+
+    for hour in 0..3 {
+        let mut candle = StepBacktestingCandleProperties::default();
+        candle.step_common.base.time = NaiveDate::from_ymd(2022, 4, 1).and_hms(hour, 0, 0);
+
+        let new_candle = store.create_candle(xid::new().to_string(), candle).unwrap();
+        assert_eq!(new_candle.props.sequence_number, hour as u64);
+
+        candle_ids.push(new_candle.id);
+    }
+
+    store
+        .prune_candles_before(NaiveDate::from_ymd(2022, 4, 1).and_hms(1, 0, 0))
+        .unwrap();
+
+    let mut candle = StepBacktestingCandleProperties::default();
+    candle.step_common.base.time = NaiveDate::from_ymd(2022, 4, 1).and_hms(3, 0, 0);
+
+    let new_candle = store.create_candle(xid::new().to_string(), candle).unwrap();
+    assert_eq!(new_candle.props.sequence_number, 3);
+
+    assert_eq!(
+        store
+            .get_candle_by_id(&candle_ids[1])
+            .unwrap()
+            .unwrap()
+            .props
+            .sequence_number,
+        1
+    );
+}
+
+#[test]
+fn should_return_rolling_volatility_of_candles_in_time_order() {
+    let mut store = InMemoryStepBacktestingStore::new();
+
+    let mut candle_ids = Vec::new();
+
+    for (hour, high, low, close) in [
+        (0, dec!(1.38100), dec!(1.38000), dec!(1.38050)),
+        (1, dec!(1.38200), dec!(1.38000), dec!(1.38100)),
+        (2, dec!(1.38600), dec!(1.38100), dec!(1.38500)),
+    ] {
+        let mut candle = StepBacktestingCandleProperties::default();
+        candle.step_common.base.time = NaiveDate::from_ymd(2022, 4, 1).and_hms(hour, 0, 0);
+        candle.step_common.base.prices = CandlePrices {
+            open: low,
+            high,
+            low,
+            close,
+        };
+
+        let candle_id = xid::new().to_string();
+        store.create_candle(candle_id.clone(), candle).unwrap();
+        candle_ids.push(candle_id);
+    }
+
+    // true ranges 100, 200, 500
+    assert_eq!(
+        store.rolling_volatility(2).unwrap(),
+        vec![
+            (candle_ids[0].clone(), 100),
+            (candle_ids[1].clone(), 150),
+            (candle_ids[2].clone(), 350),
+        ]
+    );
+}
+
 #[test]
 fn should_correctly_update_general_corridor() {
     let mut store = InMemoryStepBacktestingStore::default();
@@ -404,6 +760,38 @@ fn should_correctly_update_general_corridor() {
     assert!(store.get_candles_of_general_corridor().unwrap().is_empty());
 }
 
+#[test]
+fn should_return_only_last_candles_of_general_corridor() {
+    let mut store = InMemoryStepBacktestingStore::default();
+
+    let candle_ids: Vec<_> = (0..3)
+        .map(|_| {
+            store
+                .create_candle(xid::new().to_string(), Default::default())
+                .unwrap()
+                .id
+        })
+        .collect();
+
+    for candle_id in candle_ids.iter() {
+        store
+            .add_candle_to_general_corridor(candle_id.clone())
+            .unwrap();
+    }
+
+    let candles = store.get_last_candles_of_general_corridor(2).unwrap();
+
+    assert_eq!(
+        candles.iter().map(|candle| &candle.id).collect::<Vec<_>>(),
+        candle_ids[1..].iter().collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        store.get_last_candles_of_general_corridor(5).unwrap().len(),
+        3
+    );
+}
+
 #[test]
 fn should_return_error_on_adding_candle_to_working_level_corridor_if_it_is_already_present_there() {
     let mut store: InMemoryStepBacktestingStore = Default::default();
@@ -556,6 +944,7 @@ fn should_successfully_move_take_profits_of_level() {
                         ..Default::default()
                     },
                     working_level_id: buy_working_level_id.clone(),
+                    creation_time: None,
                 },
             )
             .unwrap();
@@ -572,6 +961,7 @@ fn should_successfully_move_take_profits_of_level() {
                         ..Default::default()
                     },
                     working_level_id: sell_working_level_id.clone(),
+                    creation_time: None,
                 },
             )
             .unwrap();
@@ -609,6 +999,125 @@ fn should_successfully_move_take_profits_of_level() {
         });
 }
 
+#[test]
+fn should_reset_moved_take_profits_and_pending_activation_of_rearmed_working_level() {
+    let mut store = InMemoryStepBacktestingStore::default();
+
+    let (working_level_id, order_id) = create_level_with_order(&mut store);
+
+    assert_eq!(
+        store
+            .add_tick_to_pending_activation_of_working_level(&working_level_id)
+            .unwrap(),
+        1
+    );
+
+    store
+        .move_take_profits_of_level(&working_level_id, dec!(100))
+        .unwrap();
+    store
+        .update_order_status(&order_id, OrderStatus::Closed)
+        .unwrap();
+
+    store.rearm_working_level(&working_level_id).unwrap();
+
+    assert_eq!(
+        store
+            .get_order_by_id(&order_id)
+            .unwrap()
+            .unwrap()
+            .props
+            .base
+            .status,
+        OrderStatus::Pending
+    );
+    assert!(!store
+        .take_profits_of_level_are_moved(&working_level_id)
+        .unwrap());
+    assert_eq!(
+        store
+            .add_tick_to_pending_activation_of_working_level(&working_level_id)
+            .unwrap(),
+        1
+    );
+
+    // the take profits of the re-armed level are moved again
+    assert!(store
+        .move_take_profits_of_level(&working_level_id, dec!(100))
+        .is_ok());
+}
+
+#[test]
+fn should_consider_working_level_fully_activated_only_when_all_its_max_orders_are_opened() {
+    let mut store = InMemoryStepBacktestingStore::default();
+
+    let max_orders_per_working_level = dec!(3);
+
+    let partially_activated_level_id = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap()
+        .id;
+
+    let fully_activated_level_id = store
+        .create_working_level(xid::new().to_string(), Default::default())
+        .unwrap()
+        .id;
+
+    for (working_level_id, statuses) in [
+        (
+            &partially_activated_level_id,
+            [
+                OrderStatus::Closed,
+                OrderStatus::Opened,
+                OrderStatus::Pending,
+            ],
+        ),
+        (
+            &fully_activated_level_id,
+            [
+                OrderStatus::Closed,
+                OrderStatus::Opened,
+                OrderStatus::Opened,
+            ],
+        ),
+    ] {
+        for status in statuses {
+            store
+                .create_order(
+                    xid::new().to_string(),
+                    StepOrderProperties {
+                        base: BasicOrderProperties {
+                            status,
+                            ..Default::default()
+                        },
+                        working_level_id: working_level_id.clone(),
+                        creation_time: None,
+                    },
+                )
+                .unwrap();
+        }
+    }
+
+    assert!(!store
+        .working_level_is_fully_activated(
+            &partially_activated_level_id,
+            max_orders_per_working_level
+        )
+        .unwrap());
+    assert!(store
+        .working_level_is_fully_activated(&fully_activated_level_id, max_orders_per_working_level)
+        .unwrap());
+
+    // the chain of orders hasn't reached the max amount of orders yet
+    assert!(!store
+        .working_level_is_fully_activated(&fully_activated_level_id, dec!(4))
+        .unwrap());
+
+    assert!(store
+        .working_level_is_fully_activated("nonexistent", max_orders_per_working_level)
+        .is_err());
+}
+
 #[test]
 fn should_return_error_when_inserting_nonexistent_entity() {
     let mut store: InMemoryStepBacktestingStore = Default::default();
@@ -700,3 +1209,79 @@ fn should_return_error_on_creating_entity_with_existing_id() {
         )
         .is_err());
 }
+
+#[test]
+fn should_behave_like_new_store_after_reset() {
+    let mut store: InMemoryStepBacktestingStore = Default::default();
+
+    let tick_id = store
+        .create_tick(String::from("1"), Default::default())
+        .unwrap()
+        .id;
+    store.update_current_tick(tick_id).unwrap();
+
+    let candle_id = store
+        .create_candle(String::from("1"), Default::default())
+        .unwrap()
+        .id;
+    store.update_current_candle(candle_id.clone()).unwrap();
+    store
+        .add_candle_to_general_corridor(candle_id.clone())
+        .unwrap();
+
+    let angle_id = store
+        .create_angle(String::from("1"), Default::default(), candle_id)
+        .unwrap()
+        .id;
+    store.update_max_angle(angle_id.clone()).unwrap();
+    store.update_tendency_change_angle(angle_id).unwrap();
+
+    let working_level_id = store
+        .create_working_level(String::from("1"), Default::default())
+        .unwrap()
+        .id;
+    store
+        .create_order(
+            String::from("1"),
+            StepOrderProperties {
+                working_level_id,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    store.reset();
+
+    let new_store = InMemoryStepBacktestingStore::new();
+
+    assert_eq!(
+        store.get_all_ticks().unwrap(),
+        new_store.get_all_ticks().unwrap()
+    );
+    assert_eq!(
+        store.get_all_candles().unwrap(),
+        new_store.get_all_candles().unwrap()
+    );
+    assert_eq!(
+        store.get_all_angles().unwrap(),
+        new_store.get_all_angles().unwrap()
+    );
+    assert!(store.get_all_working_levels().unwrap().is_empty());
+    assert!(store.get_all_orders().unwrap().is_empty());
+    assert!(store.get_candles_of_general_corridor().unwrap().is_empty());
+    assert!(store.get_current_tick().unwrap().is_none());
+    assert!(store.get_current_candle().unwrap().is_none());
+    assert!(store.get_max_angle().unwrap().is_none());
+    assert!(store.get_tendency_change_angle().unwrap().is_none());
+
+    // the same ids can be used again after reset
+    assert!(store
+        .create_tick(String::from("1"), Default::default())
+        .is_ok());
+    assert!(store
+        .create_candle(String::from("1"), Default::default())
+        .is_ok());
+    assert!(store
+        .create_working_level(String::from("1"), Default::default())
+        .is_ok());
+}
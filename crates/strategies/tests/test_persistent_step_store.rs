@@ -0,0 +1,291 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use backtesting::trading_engine::{BacktestingTradingEngine, TradingEngine};
+use backtesting::{BacktestingTradingEngineConfig, OpenPositionBy};
+use base::entities::order::{BasicOrderPrices, BasicOrderProperties, OrderStatus};
+use base::entities::{Level, Tendency};
+use base::stores::candle_store::BasicCandleStore;
+use base::stores::order_store::BasicOrderStore;
+use base::stores::tick_store::BasicTickStore;
+use rust_decimal_macros::dec;
+use strategies::step::utils::entities::angle::{AngleState, BasicAngleProperties};
+use strategies::step::utils::entities::order::StepOrderProperties;
+use strategies::step::utils::entities::working_levels::WLStatus;
+use strategies::step::utils::stores::angle_store::StepAngleStore;
+use strategies::step::utils::stores::persistent_step_store::{
+    ChangeNumber, KeyValueBackend, PersistentStepStore, RedbKeyValueBackend,
+};
+use strategies::step::utils::stores::tick_store::StepTickStore;
+use strategies::step::utils::stores::working_level_store::StepWorkingLevelStore;
+use strategies::step::utils::stores::StepConfig;
+
+/// Keeps the values in memory shared between the openings of the store.
+#[derive(Default, Clone)]
+struct InMemoryKeyValueBackend {
+    values: Rc<RefCell<HashMap<String, Vec<u8>>>>,
+    log: Rc<RefCell<BTreeMap<ChangeNumber, Vec<u8>>>>,
+    fail_appends: Rc<Cell<bool>>,
+}
+
+impl KeyValueBackend for InMemoryKeyValueBackend {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.values.borrow().get(key).cloned())
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<()> {
+        self.values
+            .borrow_mut()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn append_to_log(&mut self, number: ChangeNumber, entry: &[u8]) -> Result<()> {
+        if self.fail_appends.get() {
+            bail!("failed to log the change #{}", number);
+        }
+
+        self.log.borrow_mut().insert(number, entry.to_vec());
+        Ok(())
+    }
+
+    fn get_log(&self) -> Result<Vec<(ChangeNumber, Vec<u8>)>> {
+        Ok(self
+            .log
+            .borrow()
+            .iter()
+            .map(|(number, entry)| (*number, entry.clone()))
+            .collect())
+    }
+
+    fn clear_log(&mut self) -> Result<()> {
+        self.log.borrow_mut().clear();
+        Ok(())
+    }
+}
+
+fn order_of_level(working_level_id: &str) -> StepOrderProperties {
+    StepOrderProperties {
+        base: BasicOrderProperties {
+            volume: dec!(0.01),
+            prices: BasicOrderPrices {
+                open: dec!(1.38000),
+                stop_loss: dec!(1.37500),
+                take_profit: dec!(1.38500),
+            },
+            ..Default::default()
+        },
+        working_level_id: working_level_id.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn should_restore_state_of_store_after_reopening() {
+    let directory = tempfile::tempdir().unwrap();
+
+    {
+        let mut store = PersistentStepStore::open(
+            RedbKeyValueBackend::open(directory.path().join("step_store.redb")).unwrap(),
+        )
+        .unwrap();
+
+        store
+            .create_tick(String::from("1"), Default::default())
+            .unwrap();
+        store.update_current_tick(String::from("1")).unwrap();
+
+        store
+            .create_candle(String::from("1"), Default::default())
+            .unwrap();
+        store.update_current_candle(String::from("1")).unwrap();
+
+        store
+            .create_angle(
+                String::from("1"),
+                BasicAngleProperties {
+                    r#type: Level::Max,
+                    state: AngleState::Real,
+                },
+                String::from("1"),
+            )
+            .unwrap();
+        store.update_max_angle(String::from("1")).unwrap();
+
+        store
+            .create_working_level(String::from("1"), Default::default())
+            .unwrap();
+        store.move_working_level_to_active("1").unwrap();
+
+        store
+            .create_order(
+                String::from("1"),
+                StepOrderProperties {
+                    base: BasicOrderProperties::default(),
+                    working_level_id: String::from("1"),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.update_order_status("1", OrderStatus::Opened).unwrap();
+    }
+
+    let store = PersistentStepStore::open(
+        RedbKeyValueBackend::open(directory.path().join("step_store.redb")).unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(store.get_current_tick().unwrap().unwrap().id, "1");
+    assert_eq!(store.get_current_candle().unwrap().unwrap().id, "1");
+    assert_eq!(store.get_max_angle().unwrap().unwrap().id, "1");
+    assert_eq!(
+        store.get_working_level_status("1").unwrap(),
+        Some(WLStatus::Active)
+    );
+
+    let chain_of_orders = store.get_working_level_chain_of_orders("1").unwrap();
+    assert_eq!(chain_of_orders.len(), 1);
+    assert_eq!(chain_of_orders[0].props.base.status, OrderStatus::Opened);
+}
+
+#[test]
+fn should_start_with_empty_store_if_backend_has_no_state() {
+    let directory = tempfile::tempdir().unwrap();
+
+    let store = PersistentStepStore::open(
+        RedbKeyValueBackend::open(directory.path().join("step_store.redb")).unwrap(),
+    )
+    .unwrap();
+
+    assert!(store.get_current_tick().unwrap().is_none());
+    assert!(store.get_all_orders().unwrap().is_empty());
+}
+
+#[test]
+fn should_resume_with_same_tendency_and_open_orders_after_restart() {
+    let directory = tempfile::tempdir().unwrap();
+    let trading_engine = BacktestingTradingEngine::new();
+
+    {
+        let mut store = PersistentStepStore::open(
+            RedbKeyValueBackend::open(directory.path().join("step_store.redb")).unwrap(),
+        )
+        .unwrap();
+
+        let mut config = StepConfig::default();
+        config.tendency = Tendency::Up;
+        config.skip_creating_new_working_level = true;
+
+        let mut trading_engine_config = BacktestingTradingEngineConfig::default();
+
+        store
+            .create_working_level(String::from("1"), Default::default())
+            .unwrap();
+        store.move_working_level_to_active("1").unwrap();
+
+        for id in ["1", "2"] {
+            let order = store
+                .create_order(id.to_string(), order_of_level("1"))
+                .unwrap();
+
+            trading_engine
+                .open_position(
+                    &order,
+                    OpenPositionBy::OpenPrice,
+                    &mut store,
+                    &mut trading_engine_config,
+                )
+                .unwrap();
+        }
+
+        store.save_config(&config).unwrap();
+        store
+            .save_trading_engine_state(&trading_engine_config.state())
+            .unwrap();
+    }
+
+    let store = PersistentStepStore::open(
+        RedbKeyValueBackend::open(directory.path().join("step_store.redb")).unwrap(),
+    )
+    .unwrap();
+
+    let config = store.restore_config().unwrap().unwrap();
+    assert_eq!(config.tendency, Tendency::Up);
+    assert!(config.skip_creating_new_working_level);
+
+    let mut trading_engine_config = BacktestingTradingEngineConfig::default();
+    trading_engine_config.restore_state(store.restore_trading_engine_state().unwrap().unwrap());
+
+    assert_eq!(trading_engine_config.open_trades.len(), 2);
+    assert!(trading_engine_config.open_trades.contains_key("1"));
+    assert!(trading_engine_config.open_trades.contains_key("2"));
+    assert_ne!(
+        trading_engine_config.balances.processing,
+        trading_engine_config.balances.initial
+    );
+
+    let open_orders = store
+        .get_working_level_chain_of_orders("1")
+        .unwrap()
+        .into_iter()
+        .filter(|order| order.props.base.status == OrderStatus::Opened)
+        .count();
+    assert_eq!(open_orders, 2);
+}
+
+#[test]
+fn should_not_apply_change_if_backend_fails_to_log_it() {
+    let backend = InMemoryKeyValueBackend::default();
+
+    let mut store = PersistentStepStore::open(backend.clone()).unwrap();
+
+    store
+        .create_working_level(String::from("1"), Default::default())
+        .unwrap();
+
+    backend.fail_appends.set(true);
+
+    assert!(store
+        .create_order(String::from("1"), order_of_level("1"))
+        .is_err());
+    assert!(store.get_order_by_id("1").unwrap().is_none());
+
+    backend.fail_appends.set(false);
+
+    store
+        .create_order(String::from("2"), order_of_level("1"))
+        .unwrap();
+
+    let store = PersistentStepStore::open(backend).unwrap();
+
+    assert!(store.get_working_level_by_id("1").unwrap().is_some());
+    assert!(store.get_order_by_id("1").unwrap().is_none());
+    assert!(store.get_order_by_id("2").unwrap().is_some());
+}
+
+#[test]
+fn should_restore_changes_made_before_and_after_compaction() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("step_store.redb");
+
+    {
+        let mut store =
+            PersistentStepStore::open(RedbKeyValueBackend::open(&path).unwrap()).unwrap();
+
+        store
+            .create_working_level(String::from("1"), Default::default())
+            .unwrap();
+        store.compact().unwrap();
+
+        store.move_working_level_to_active("1").unwrap();
+    }
+
+    let store = PersistentStepStore::open(RedbKeyValueBackend::open(&path).unwrap()).unwrap();
+
+    assert_eq!(
+        store.get_working_level_status("1").unwrap(),
+        Some(WLStatus::Active)
+    );
+}
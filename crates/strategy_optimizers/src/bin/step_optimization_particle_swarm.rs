@@ -6,6 +6,7 @@ use argmin::solver::simulatedannealing::{Anneal, SATempFunc, SimulatedAnnealing}
 use backtesting::historical_data::get_historical_data;
 use backtesting::historical_data::serialization::HistoricalDataCsvSerialization;
 use backtesting::historical_data::synchronization::sync_candles_and_ticks;
+use backtesting::historical_data::validation::{validate_candles, CandleValidationPolicies};
 use backtesting::trading_engine::BacktestingTradingEngine;
 use backtesting::{HistoricalData, StrategyInitConfig};
 use base::corridor::BasicCorridorUtilsImpl;
@@ -44,7 +45,7 @@ use strategies::step::utils::stores::{StepBacktestingConfig, StepBacktestingStor
 use strategies::step::utils::trading_limiter::TradingLimiterBacktesting;
 use strategies::step::utils::{get_candle_leading_price, StepBacktestingUtils};
 use strategy_runners::step::backtesting_runner;
-use strategy_runners::step::backtesting_runner::StepStrategyRunningConfig;
+use strategy_runners::step::backtesting_runner::{CandleProcessingMode, StepStrategyRunningConfig};
 use trading_apis::metaapi_market_data_api::{
     ApiData, ApiUrls, AUTH_TOKEN_ENV, DEMO_ACCOUNT_ID_ENV, MAIN_API_URL_ENV,
     MARKET_DATA_API_URL_ENV,
@@ -202,6 +203,11 @@ impl CostFunction for StepStrategyOptimization {
             &self.historical_data,
             StepStrategyRunningConfig {
                 timeframes: self.strategy_config.timeframes,
+                candle_processing_mode: CandleProcessingMode::OnClose,
+                warmup_candles: 0,
+                session_filter: Default::default(),
+                candle_quality_filter: None,
+                loss_breaker: Default::default(),
                 stores: &mut step_stores,
                 utils: &utils,
                 params: &step_params,
@@ -209,6 +215,7 @@ impl CostFunction for StepStrategyOptimization {
             &trading_limiter,
             &run_iteration,
         )
+        .map(|backtest_result| backtest_result.performance)
         .unwrap_or(Decimal::MIN);
 
         println!("Performance: {}", performance);
@@ -319,6 +326,41 @@ fn main() -> Result<()> {
             },
             bounds: (15., 15.), // fix single value
         },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Point {
+                name: StepPointParam::MaxOrdersPerWorkingLevel,
+                num_type: NumType::Integer,
+            },
+            bounds: (5., 5.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Point {
+                name: StepPointParam::MaxActiveWorkingLevels,
+                num_type: NumType::Integer,
+            },
+            bounds: (0., 0.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Point {
+                name: StepPointParam::VolatilityWindow,
+                num_type: NumType::Integer,
+            },
+            bounds: (0., 0.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Point {
+                name: StepPointParam::MinAmountOfTicksBeyondLevelBeforeActivation,
+                num_type: NumType::Integer,
+            },
+            bounds: (0., 0.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Point {
+                name: StepPointParam::MaxReentries,
+                num_type: NumType::Integer,
+            },
+            bounds: (0., 0.), // fix single value
+        },
         OptimizationInitialParam {
             descr: OptimizationParamDescr::Ratio(
                 StepRatioParam::MinDistanceBetweenNewAndCurrentMaxMinAngles,
@@ -381,6 +423,26 @@ fn main() -> Result<()> {
             ),
             bounds: (1.2, 3.),
         },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Ratio(StepRatioParam::MinDistanceBetweenOrdersOfLevel),
+            bounds: (0., 0.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Ratio(StepRatioParam::MinAngleStrength),
+            bounds: (0., 0.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Ratio(StepRatioParam::MinAngleCandleSize),
+            bounds: (0., 0.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Ratio(StepRatioParam::MinCandleGapForLevelInvalidation),
+            bounds: (0., 0.), // fix single value
+        },
+        OptimizationInitialParam {
+            descr: OptimizationParamDescr::Ratio(StepRatioParam::MinProfitToTrail),
+            bounds: (0., 0.), // fix single value
+        },
     ];
 
     let api_data = ApiData {
@@ -400,12 +462,23 @@ fn main() -> Result<()> {
 
     let historical_data_csv_serialization = HistoricalDataCsvSerialization::new();
 
+    let candle_validation_policies = CandleValidationPolicies::from_env()?;
+
     let historical_data = get_historical_data(
         step_historical_data_folder,
         &strategy_config,
         &market_data_api,
         &historical_data_csv_serialization,
-        sync_candles_and_ticks,
+        |historical_data| {
+            sync_candles_and_ticks(HistoricalData {
+                candles: validate_candles(
+                    historical_data.candles,
+                    strategy_config.timeframes.candle,
+                    candle_validation_policies,
+                )?,
+                ticks: historical_data.ticks,
+            })
+        },
     )?;
 
     let historical_data = HistoricalData {
@@ -2,6 +2,7 @@ use anyhow::Result;
 use backtesting::historical_data::get_historical_data;
 use backtesting::historical_data::serialization::HistoricalDataCsvSerialization;
 use backtesting::historical_data::synchronization::sync_candles_and_ticks;
+use backtesting::historical_data::validation::{validate_candles, CandleValidationPolicies};
 use backtesting::trading_engine::BacktestingTradingEngine;
 use backtesting::{get_path_name_for_data_config, HistoricalData, StrategyInitConfig};
 use base::corridor::BasicCorridorUtilsImpl;
@@ -10,7 +11,7 @@ use base::entities::{StrategyTimeframes, Timeframe, CANDLE_TIMEFRAME_ENV, TICK_T
 use base::helpers::exclude_weekend_and_holidays;
 use base::requests::ureq::UreqRequestApi;
 use chrono::{DateTime, Duration};
-use plotly::common::{Marker, Title};
+use plotly::common::{Marker, Mode as PlotMode, Title};
 use plotly::layout::{Axis, GridPattern, LayoutGrid};
 use plotly::{Candlestick, Layout, Plot, Scatter};
 use rust_decimal_macros::dec;
@@ -46,7 +47,7 @@ use strategies::step::utils::stores::{StepBacktestingConfig, StepBacktestingStor
 use strategies::step::utils::trading_limiter::TradingLimiterBacktesting;
 use strategies::step::utils::{get_candle_leading_price, StepBacktestingUtils};
 use strategy_runners::step::backtesting_runner;
-use strategy_runners::step::backtesting_runner::StepStrategyRunningConfig;
+use strategy_runners::step::backtesting_runner::{CandleProcessingMode, StepStrategyRunningConfig};
 
 const PLOT_FOLDER_ENV: &str = "PLOT_FOLDER";
 
@@ -99,12 +100,23 @@ fn backtest_step_strategy(strategy_config: StrategyInitConfig) -> Result<()> {
 
     let historical_data_csv_serialization = HistoricalDataCsvSerialization::new();
 
+    let candle_validation_policies = CandleValidationPolicies::from_env()?;
+
     let historical_data = get_historical_data(
         step_historical_data_folder,
         &strategy_config,
         &market_data_api,
         &historical_data_csv_serialization,
-        sync_candles_and_ticks,
+        |historical_data| {
+            sync_candles_and_ticks(HistoricalData {
+                candles: validate_candles(
+                    historical_data.candles,
+                    strategy_config.timeframes.candle,
+                    candle_validation_policies,
+                )?,
+                ticks: historical_data.ticks,
+            })
+        },
     )?;
 
     let historical_data = HistoricalData {
@@ -154,10 +166,15 @@ fn backtest_step_strategy(strategy_config: StrategyInitConfig) -> Result<()> {
         BacktestingTradingEngine::new(),
     );
 
-    let strategy_performance = backtesting_runner::loop_through_historical_data(
+    let backtest_result = backtesting_runner::loop_through_historical_data(
         &historical_data,
         StepStrategyRunningConfig {
             timeframes: strategy_config.timeframes,
+            candle_processing_mode: CandleProcessingMode::OnClose,
+            warmup_candles: 0,
+            session_filter: Default::default(),
+            candle_quality_filter: None,
+            loss_breaker: Default::default(),
             stores: &mut step_stores,
             utils: &utils,
             params: &step_params,
@@ -166,19 +183,10 @@ fn backtest_step_strategy(strategy_config: StrategyInitConfig) -> Result<()> {
         &run_iteration,
     )?;
 
-    println!("Strategy performance: {}", strategy_performance);
-    println!(
-        "Initial balance: {}",
-        step_stores.config.trading_engine.balances.initial
-    );
-    println!(
-        "Final balance: {}",
-        step_stores.config.trading_engine.balances.real
-    );
-    println!("{:#?}", step_stores.statistics);
+    println!("{:#?}", backtest_result);
 
     if Mode::from_str(&dotenv::var(MODE_ENV).unwrap()).unwrap() != Mode::Optimization {
-        let plot_file_name = get_path_name_for_data_config(&strategy_config);
+        let plot_file_name = get_path_name_for_data_config(&strategy_config)?;
         plot_results(
             historical_data.candles,
             step_stores.config.chart_traces,
@@ -332,6 +340,29 @@ fn plot_results(
         );
     }
 
+    let (origin_x, (origin_y, origin_text)): (Vec<_>, (Vec<_>, Vec<_>)) = chart_traces
+        .get_working_level_traces()
+        .iter()
+        .zip(chart_traces.get_working_level_origins())
+        .filter_map(|(trace, origin)| {
+            trace.iter().enumerate().find_map(|(i, value)| {
+                value.map(|value| {
+                    (
+                        x[i].clone(),
+                        (value + dec!(0.00005), format!("{:?}", origin)),
+                    )
+                })
+            })
+        })
+        .unzip();
+
+    let working_level_origins = Scatter::new(origin_x, origin_y)
+        .y_axis("y4")
+        .mode(PlotMode::Markers)
+        .name("working level origin")
+        .text_array(origin_text)
+        .marker(Marker::new().color("orange"));
+
     let mut take_profit_traces = Vec::new();
     for (i, trace) in merge_traces(chart_traces.get_take_profit_traces())
         .iter()
@@ -399,6 +430,8 @@ fn plot_results(
         plot.add_trace(trace);
     }
 
+    plot.add_trace(working_level_origins);
+
     for trace in take_profit_traces {
         plot.add_trace(trace);
     }
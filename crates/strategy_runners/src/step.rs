@@ -1 +1,4 @@
 pub mod backtesting_runner;
+pub mod driver;
+pub mod multi_timeframe;
+pub mod result_diff;
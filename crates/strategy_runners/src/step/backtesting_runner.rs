@@ -1,17 +1,28 @@
 use anyhow::Context;
-use anyhow::Result;
+use anyhow::{bail, Result};
+use backtesting::historical_data::quality::CandleQuality;
+use backtesting::metrics::{Drawdown, DrawdownTracker, EquityReturn, TimeWeightedReturnTracker};
 use backtesting::trading_engine::TradingEngine;
-use backtesting::{BacktestingBalances, HistoricalData};
+use backtesting::{
+    BacktestingBalances, Balance, BalanceIsDepleted, ClosePositionBy, ClosedTrade, EndOfDataPolicy,
+    HistoricalData,
+};
 use base::corridor::BasicCorridorUtils;
-use base::entities::candle::{BasicCandleProperties, CandlePrice};
+use base::entities::candle::{BasicCandleProperties, CandlePrice, CandlePrices, CandleVolatility};
+use base::entities::order::OrderStatus;
 use base::entities::tick::HistoricalTickPrice;
-use base::entities::{BasicTickProperties, StrategyTimeframes, SIGNIFICANT_DECIMAL_PLACES};
-use base::helpers::{Holiday, NumberOfDaysToExclude};
+use base::entities::{
+    BasicTickProperties, StrategyTimeframes, Timeframe, SIGNIFICANT_DECIMAL_PLACES,
+};
+use base::helpers::{compute_volatility, price_to_points, Holiday, NumberOfDaysToExclude};
 use base::params::StrategyParams;
-use base::stores::candle_store::BasicCandleStore;
 use base::stores::order_store::BasicOrderStore;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::Serialize;
+use std::borrow::Cow;
 use std::fmt::Display;
 use std::str::FromStr;
 use strategies::step::utils::angle_utils::AngleUtils;
@@ -30,11 +41,15 @@ use strategies::step::utils::entities::{Mode, StrategyPerformance, StrategySigna
 use strategies::step::utils::helpers::Helpers;
 use strategies::step::utils::level_conditions::LevelConditions;
 use strategies::step::utils::level_utils::LevelUtils;
+use strategies::step::utils::loss_breaker::ConsecutiveLossBreaker;
 use strategies::step::utils::order_utils::OrderUtils;
+use strategies::step::utils::session_filter::SessionFilter;
 use strategies::step::utils::stores::angle_store::StepAngleStore;
 use strategies::step::utils::stores::tick_store::StepTickStore;
 use strategies::step::utils::stores::working_level_store::StepWorkingLevelStore;
-use strategies::step::utils::stores::{StepBacktestingMainStore, StepBacktestingStores};
+use strategies::step::utils::stores::{
+    StepBacktestingMainStore, StepBacktestingStatistics, StepBacktestingStores,
+};
 use strategies::step::utils::trading_limiter::TradingLimiter;
 use strategies::step::utils::{get_candle_leading_price, StepBacktestingUtils};
 
@@ -66,6 +81,262 @@ fn strategy_performance(balances: &BacktestingBalances) -> StrategyPerformance {
         .round_dp(SIGNIFICANT_DECIMAL_PLACES)
 }
 
+/// The results of the backtest gathered in one place for programmatic consumption.
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestResult {
+    pub performance: StrategyPerformance,
+    pub initial_balance: Balance,
+    pub final_balance: Balance,
+    pub number_of_trades: usize,
+    /// Percentage of the closed trades with profit.
+    pub win_rate: Option<Decimal>,
+    /// Average number of candles between the opening and closing of the closed trades.
+    pub average_bars_in_trade: Option<Decimal>,
+    /// Expected profit per trade in the price points.
+    pub expectancy: Option<Decimal>,
+    /// Average difference in points between the requested and the fill prices of the closed trades.
+    pub average_fill_slippage: Option<Decimal>,
+    pub max_drawdown: Drawdown,
+    /// The per-period returns of the real balance linked geometrically.
+    pub time_weighted_return: Option<EquityReturn>,
+    /// The sum of the relative returns of the closed trades.
+    pub trade_weighted_return: Option<Decimal>,
+    pub statistics: StepBacktestingStatistics,
+    /// Whether the backtest has been ended before the end of the data,
+    /// because the real balance has been depleted.
+    pub balance_is_depleted: bool,
+    /// The closed trades in the order of closing.
+    #[serde(skip)]
+    pub trades: Vec<ClosedTrade>,
+}
+
+/// Defines at which moment a candle is passed to the strategy.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum CandleProcessingMode {
+    /// The candle is passed on the first tick after it has been completed,
+    /// so all its fields are final and may be read.
+    #[default]
+    OnClose,
+    /// The candle is passed on the first tick of its period. Only `time` and `prices.open`
+    /// are taken from the historical data. The high, low and close prices come from the ticks
+    /// seen so far within the period, the size, type and leading price are derived from them,
+    /// and the volatility is computed over the closed candles only.
+    OnFormation,
+    /// The candle is passed as in `OnClose`, but not before the first tick at or after
+    /// the end of its period, so the misaligned ticks can't reveal the candle
+    /// before it's completed. Every tick is fed to the strategy.
+    OnCloseStrict,
+}
+
+/// Builds the candle being formed from its time and open price and the range
+/// of the first tick of its period, so that its future prices stay unknown.
+fn build_forming_candle(
+    candle: &StepCandleProperties,
+    current_tick: &BasicTickProperties<HistoricalTickPrice>,
+    volatility: CandleVolatility,
+) -> StepCandleProperties {
+    let open = candle.base.prices.open;
+
+    let prices = CandlePrices {
+        open,
+        high: open.max(current_tick.bid.high),
+        low: open.min(current_tick.bid.low),
+        close: current_tick.bid.close,
+    };
+
+    let base = BasicCandleProperties {
+        time: candle.base.time,
+        r#type: prices.candle_type(),
+        size: price_to_points(prices.high - prices.low),
+        volatility,
+        prices,
+    };
+
+    StepCandleProperties {
+        leading_price: get_candle_leading_price(&base),
+        base,
+    }
+}
+
+/// Returns the candle to pass to the strategy when the `closed_candle` has just been completed.
+fn get_candle_to_process(
+    candles: &[Option<StepCandleProperties>],
+    closed_candle: &Candle<StepCandleProperties>,
+    current_tick: &BasicTickProperties<HistoricalTickPrice>,
+    mode: CandleProcessingMode,
+) -> Option<StepBacktestingCandleProperties> {
+    let (candle, index) = match mode {
+        CandleProcessingMode::OnClose | CandleProcessingMode::OnCloseStrict => {
+            (closed_candle.value.cloned(), closed_candle.index)
+        }
+        CandleProcessingMode::OnFormation => {
+            // the volatility of the closed candle is computed over the closed candles only
+            let volatility = closed_candle
+                .value
+                .map(|candle| candle.base.volatility)
+                .unwrap_or_default();
+
+            (
+                candles
+                    .get(closed_candle.index + 1)?
+                    .as_ref()
+                    .map(|candle| build_forming_candle(candle, current_tick, volatility)),
+                closed_candle.index + 1,
+            )
+        }
+    };
+
+    candle.map(|candle_props| StepBacktestingCandleProperties {
+        step_common: candle_props,
+        chart_index: index,
+        // assigned by the store on the creation of the candle
+        sequence_number: Default::default(),
+    })
+}
+
+/// Treats the candles with the quality below the min one as gaps.
+#[derive(Debug, Copy, Clone)]
+pub struct CandleQualityFilter<'a> {
+    /// The quality scores parallel to the candles. The candles without a score are kept.
+    pub qualities: &'a [Option<CandleQuality>],
+    pub min_quality: CandleQuality,
+}
+
+/// Returns the candles with the low quality ones replaced by gaps.
+/// The candles are cloned only if the filter is set.
+fn skip_low_quality_candles<'a, C: Clone>(
+    candles: &'a [Option<C>],
+    filter: Option<CandleQualityFilter>,
+) -> Cow<'a, [Option<C>]> {
+    let filter = match filter {
+        Some(filter) => filter,
+        None => return Cow::Borrowed(candles),
+    };
+
+    Cow::Owned(
+        candles
+            .iter()
+            .enumerate()
+            .map(|(i, candle)| match filter.qualities.get(i) {
+                Some(Some(quality)) if *quality < filter.min_quality => None,
+                _ => candle.clone(),
+            })
+            .collect(),
+    )
+}
+
+/// Returns the time when the period of the candle ends.
+fn get_candle_end_time(
+    candle: &StepCandleProperties,
+    candle_timeframe: Timeframe,
+) -> NaiveDateTime {
+    candle.base.time + candle_timeframe.duration()
+}
+
+/// Takes the candle waiting for its completion if it's completed by the time of the tick.
+/// The gap is taken at once, because it has no prices to reveal.
+fn take_completed_candle<'a>(
+    waiting_candle: &mut Option<Candle<'a, StepCandleProperties>>,
+    tick: &BasicTickProperties<HistoricalTickPrice>,
+    candle_timeframe: Timeframe,
+) -> Option<Candle<'a, StepCandleProperties>> {
+    let candle_is_completed = waiting_candle
+        .as_ref()?
+        .value
+        .is_none_or(|candle| get_candle_end_time(candle, candle_timeframe) <= tick.time);
+
+    if candle_is_completed {
+        waiting_candle.take()
+    } else {
+        None
+    }
+}
+
+/// Returns the volatility of the candle of the particular index computed over the window
+/// of the last existing candles ending with it. The gaps in the candles are skipped.
+fn get_volatility_over_window(
+    candles: &[Option<StepCandleProperties>],
+    index: ChartIndex,
+    window: usize,
+) -> CandleVolatility {
+    // one more candle is taken to have the previous close for the first true range of the window
+    let mut window_candles: Vec<_> = candles[..=index]
+        .iter()
+        .rev()
+        .flatten()
+        .take(window + 1)
+        .map(|candle| candle.base.clone())
+        .collect();
+
+    window_candles.reverse();
+
+    compute_volatility(&window_candles, window)
+}
+
+/// The number of candles to process before any orders may be opened.
+pub type WarmupCandles = usize;
+
+/// Returns true while the processed candles are not enough to build up the strategy context.
+fn warmup_is_in_progress(
+    number_of_processed_candles: usize,
+    warmup_candles: WarmupCandles,
+) -> bool {
+    number_of_processed_candles < warmup_candles
+}
+
+/// Applies the end of data policy of the trading engine to the orders left open
+/// when the historical data ends.
+fn apply_end_of_data_policy<T>(
+    last_tick: Option<&BasicTickProperties<HistoricalTickPrice>>,
+    stores: &mut StepBacktestingStores<T>,
+    trading_engine: &impl TradingEngine,
+) -> Result<()>
+where
+    T: StepBacktestingMainStore,
+{
+    let open_orders: Vec<_> = stores
+        .main
+        .get_all_orders()?
+        .into_iter()
+        .filter(|order| order.props.base.status == OrderStatus::Opened)
+        .collect();
+
+    if open_orders.is_empty() {
+        return Ok(());
+    }
+
+    match stores.config.trading_engine.end_of_data_policy {
+        EndOfDataPolicy::LeaveOpen => {}
+        EndOfDataPolicy::Error => {
+            bail!("the strategy ended with {} open orders", open_orders.len())
+        }
+        EndOfDataPolicy::CloseAtLastPrice => {
+            let last_tick = last_tick.context("no last tick to close the open orders at")?;
+
+            // there are no ticks left to wait for the fill latency
+            stores
+                .config
+                .trading_engine
+                .without_fill_latency(|trading_config| {
+                    for order in open_orders {
+                        trading_engine.close_position(
+                            &order,
+                            ClosePositionBy::EndOfData(last_tick.bid.close),
+                            &mut stores.main,
+                            trading_config,
+                        )?;
+
+                        stores.statistics.closed_at_end_of_data += 1;
+                    }
+
+                    Ok::<_, anyhow::Error>(())
+                })?;
+        }
+    }
+
+    Ok(())
+}
+
 pub struct StepStrategyRunningConfig<'a, P, T, Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, D, E, X>
 where
     P: StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam> + Display,
@@ -82,6 +353,13 @@ where
     X: Fn(NaiveDateTime, NaiveDateTime, &[Holiday]) -> NumberOfDaysToExclude,
 {
     pub timeframes: StrategyTimeframes,
+    pub candle_processing_mode: CandleProcessingMode,
+    pub warmup_candles: WarmupCandles,
+    pub session_filter: SessionFilter,
+    pub candle_quality_filter: Option<CandleQualityFilter<'a>>,
+    /// Stops creating the new chains of orders after the series of losing trades.
+    /// It replaces the loss breaker of the stores when the run starts.
+    pub loss_breaker: ConsecutiveLossBreaker,
     pub stores: &'a mut StepBacktestingStores<T>,
     pub utils: &'a StepBacktestingUtils<Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, E, D, X>,
     pub params: &'a P,
@@ -108,7 +386,7 @@ pub fn loop_through_historical_data<P, L, T, Hel, LevUt, LevCon, OrUt, BCor, Cor
     >,
     trading_limiter: &L,
     run_iteration: &I,
-) -> Result<StrategyPerformance>
+) -> Result<BacktestResult>
 where
     P: StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam> + Display,
     L: TradingLimiter<TickPrice = HistoricalTickPrice>,
@@ -132,22 +410,25 @@ where
         &P,
     ) -> Result<()>,
 {
+    strategy_config.stores.config.loss_breaker = strategy_config.loss_breaker;
+
+    let candles = skip_low_quality_candles(
+        &historical_data.candles,
+        strategy_config.candle_quality_filter,
+    );
+
     let mut current_tick = Tick {
         index: 0,
         value: historical_data
             .ticks
-            .get(0)
+            .first()
             .context("no first tick")?
             .as_ref(),
     };
 
     let mut current_candle = Candle {
         index: 0,
-        value: historical_data
-            .candles
-            .get(0)
-            .context("no first candle")?
-            .as_ref(),
+        value: candles.first().context("no first candle")?.as_ref(),
     };
 
     let mut first_candle = true;
@@ -157,12 +438,48 @@ where
     let mut no_trading_mode = false;
     let mut cancel_all_orders = false;
 
+    let mut number_of_processed_candles = 0;
+
+    let mut last_tick = None;
+
+    let mut balance_is_depleted = false;
+
+    let strict_mode = strategy_config.candle_processing_mode == CandleProcessingMode::OnCloseStrict;
+    let mut candle_waiting_for_completion = None;
+
+    let volatility_window = strategy_config
+        .params
+        .get_point_param_value(StepPointParam::VolatilityWindow)
+        .to_usize()
+        .context("volatility window is not a valid number of candles")?;
+
+    let mut drawdown_tracker = DrawdownTracker::new();
+    drawdown_tracker.update(strategy_config.stores.config.trading_engine.balances.real);
+
+    let mut time_weighted_return_tracker = TimeWeightedReturnTracker::new();
+
     let number_of_iterations_between_candles =
         strategy_config.timeframes.candle as u32 / strategy_config.timeframes.tick as u32;
     let mut number_of_iterations_to_next_candle = number_of_iterations_between_candles - 1;
 
     loop {
+        if strict_mode && new_candle_appeared {
+            if let Some(Candle { index, .. }) = candle_waiting_for_completion {
+                log::debug!(
+                    "the candle {} isn't completed by the time the next one appears, it's skipped",
+                    index
+                );
+            }
+
+            candle_waiting_for_completion = Some(Candle {
+                index: current_candle.index,
+                value: current_candle.value,
+            });
+        }
+
         if let Some(current_tick) = current_tick.value {
+            last_tick = Some(current_tick);
+
             if no_trading_mode {
                 if trading_limiter.allow_trading(current_tick) {
                     no_trading_mode = false;
@@ -172,27 +489,97 @@ where
                 cancel_all_orders = true;
             }
 
+            strategy_config.stores.config.trading_engine.current_time =
+                Some(DateTime::from_utc(current_tick.time, Utc));
+            strategy_config
+                .stores
+                .config
+                .trading_engine
+                .current_candle_index = Some(current_candle.index);
+
+            let closed_candle = if strict_mode {
+                take_completed_candle(
+                    &mut candle_waiting_for_completion,
+                    current_tick,
+                    strategy_config.timeframes.candle,
+                )
+            } else if new_candle_appeared {
+                Some(Candle {
+                    index: current_candle.index,
+                    value: current_candle.value,
+                })
+            } else {
+                None
+            };
+
+            let candle_to_process = closed_candle.and_then(|closed_candle| {
+                get_candle_to_process(
+                    &candles,
+                    &closed_candle,
+                    current_tick,
+                    strategy_config.candle_processing_mode,
+                )
+                .map(|mut candle| {
+                    // the window ends with the closed candle, so the forming one isn't included
+                    if volatility_window > 0 {
+                        candle.step_common.base.volatility = get_volatility_over_window(
+                            &candles,
+                            closed_candle.index,
+                            volatility_window,
+                        );
+                    }
+
+                    candle
+                })
+            });
+
+            let new_candle_is_processed = candle_to_process.is_some();
+
             // run iteration only if a tick exists
-            run_iteration(
+            let iteration_result = run_iteration(
                 current_tick.clone(),
-                if new_candle_appeared {
-                    current_candle
-                        .value
-                        .map(|candle_props| StepBacktestingCandleProperties {
-                            step_common: candle_props.clone(),
-                            chart_index: current_candle.index,
-                        })
-                } else {
-                    None
-                },
+                candle_to_process,
                 StrategySignals {
                     no_trading_mode,
                     close_all_orders: cancel_all_orders,
+                    warmup: warmup_is_in_progress(
+                        number_of_processed_candles,
+                        strategy_config.warmup_candles,
+                    ),
+                    out_of_session: !strategy_config
+                        .session_filter
+                        .entry_allowed(current_tick.time),
                 },
                 strategy_config.stores,
                 strategy_config.utils,
                 strategy_config.params,
-            )?;
+            );
+
+            if let Err(error) = iteration_result {
+                match error.downcast_ref::<BalanceIsDepleted>() {
+                    Some(depletion) => {
+                        log::warn!(
+                            "the backtest is ended before the end of the data: {}",
+                            depletion
+                        );
+                        balance_is_depleted = true;
+                        break;
+                    }
+                    None => return Err(error),
+                }
+            }
+
+            let trading_engine = &strategy_config.stores.config.trading_engine;
+
+            drawdown_tracker.update(trading_engine.balances.real);
+
+            if let Some(current_time) = trading_engine.current_time {
+                time_weighted_return_tracker.update(current_time, trading_engine.balances.real);
+            }
+
+            if new_candle_is_processed {
+                number_of_processed_candles += 1;
+            }
 
             if cancel_all_orders {
                 cancel_all_orders = false;
@@ -211,7 +598,7 @@ where
         // the moment to update the current candle
         if number_of_iterations_to_next_candle == 0 {
             if !first_candle {
-                let new_candle_value = historical_data.candles.get(current_candle.index + 1);
+                let new_candle_value = candles.get(current_candle.index + 1);
                 match new_candle_value {
                     Some(new_candle) => {
                         current_candle = Candle {
@@ -241,28 +628,61 @@ where
         }
     }
 
-    Ok(strategy_performance(
-        &strategy_config.stores.config.trading_engine.balances,
-    ))
+    apply_end_of_data_policy(
+        last_tick,
+        strategy_config.stores,
+        &strategy_config.utils.trading_engine,
+    )?;
+
+    let trading_engine = &strategy_config.stores.config.trading_engine;
+
+    drawdown_tracker.update(trading_engine.balances.real);
+
+    if let Some(current_time) = trading_engine.current_time {
+        time_weighted_return_tracker.update(current_time, trading_engine.balances.real);
+    }
+
+    Ok(BacktestResult {
+        performance: strategy_performance(&trading_engine.balances),
+        initial_balance: trading_engine.balances.initial,
+        final_balance: trading_engine.balances.real,
+        number_of_trades: trading_engine.streaming_stats.number_of_trades() as usize,
+        win_rate: trading_engine.streaming_stats.win_rate(),
+        average_bars_in_trade: trading_engine.average_bars_in_trade(),
+        expectancy: trading_engine.expectancy(),
+        average_fill_slippage: trading_engine.average_fill_slippage(),
+        max_drawdown: drawdown_tracker.max_drawdown(),
+        time_weighted_return: time_weighted_return_tracker.time_weighted_return(),
+        trade_weighted_return: trading_engine.trade_weighted_return(),
+        statistics: strategy_config.stores.statistics.clone(),
+        balance_is_depleted,
+        trades: trading_engine.closed_trades.clone(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use backtesting::trading_engine::TradingEngine;
-    use backtesting::{BacktestingTradingEngineConfig, Balance, ClosePositionBy, OpenPositionBy};
-    use base::entities::candle::{CandleId, CandleVolatility};
-    use base::entities::order::{BasicOrderProperties, OrderId, OrderPrice, OrderType};
+    use backtesting::trading_engine::{BacktestingTradingEngine, TradingEngine};
+    use backtesting::{
+        BacktestingTradingEngineConfig, Balance, ClosePositionBy, CloseReason, Exposure,
+        OpenPositionBy,
+    };
+    use base::entities::candle::{CandleId, CandlePrices, CandleVolatility};
+    use base::entities::order::{
+        BasicOrderPrices, BasicOrderProperties, OrderId, OrderPrice, OrderType,
+    };
     use base::entities::tick::{TickPrice, TickTime, UniversalTickPrice};
     use base::entities::{Item, Timeframe};
-    use base::helpers::{Holiday, NumberOfDaysToExclude};
+    use base::helpers::{Holiday, LotRounding, NumberOfDaysToExclude, PriceRounding};
     use base::notifier::NotificationQueue;
     use base::params::ParamOutputValue;
     use chrono::{NaiveDateTime, Timelike};
     use float_cmp::approx_eq;
     use rust_decimal_macros::dec;
+    use std::cell::RefCell;
     use std::fmt::{Debug, Formatter};
-    use strategies::step::utils::angle_utils::ExistingDiffs;
+    use strategies::step::utils::angle_utils::{AngleDetector, ExistingDiffs};
     use strategies::step::utils::backtesting_charts::{
         ChartTraceEntity, StepBacktestingChartTraces,
     };
@@ -271,7 +691,8 @@ mod tests {
     };
     use strategies::step::utils::entities::angle::{AngleId, FullAngleProperties};
     use strategies::step::utils::entities::working_levels::{
-        BasicWLProperties, CorridorType, LevelTime, WLId, WLMaxCrossingValue, WLPrice,
+        BasicWLProperties, CorridorType, CrossedLevelTieBreak, LevelOrigin, LevelTime, WLId,
+        WLMaxCrossingValue, WLPrice,
     };
     use strategies::step::utils::entities::{
         Diff, MaxMinAngles, StatisticsChartsNotifier, StatisticsNotifier,
@@ -335,8 +756,11 @@ mod tests {
         type PointParam = StepPointParam;
         type RatioParam = StepRatioParam;
 
-        fn get_point_param_value(&self, _name: Self::PointParam) -> ParamOutputValue {
-            todo!()
+        fn get_point_param_value(&self, name: Self::PointParam) -> ParamOutputValue {
+            match name {
+                StepPointParam::VolatilityWindow => dec!(0),
+                _ => todo!(),
+            }
         }
 
         fn get_ratio_param_value(
@@ -364,8 +788,9 @@ mod tests {
 
     impl LevelUtils for TestLevelUtilsImpl {
         fn get_crossed_level<W>(
-            current_tick_price: UniversalTickPrice,
-            created_working_levels: &[Item<WLId, W>],
+            _current_tick_price: UniversalTickPrice,
+            _created_working_levels: &[Item<WLId, W>],
+            _tie_break: CrossedLevelTieBreak,
         ) -> Option<&Item<WLId, W>>
         where
             W: AsRef<BasicWLProperties>,
@@ -373,11 +798,14 @@ mod tests {
             unimplemented!()
         }
 
-        fn remove_active_working_levels_with_closed_orders<O>(
+        fn remove_active_working_levels_with_closed_orders<O, S>(
             working_level_store: &mut impl StepWorkingLevelStore<OrderProperties = O>,
+            _max_reentries: ParamOutputValue,
+            _order_is_stopped_out: &S,
         ) -> Result<()>
         where
             O: Into<StepOrderProperties>,
+            S: Fn(&str) -> bool,
         {
             unimplemented!()
         }
@@ -413,14 +841,45 @@ mod tests {
             todo!()
         }
 
-        fn move_take_profits<W>(
-            working_level_store: &mut impl StepWorkingLevelStore<WorkingLevelProperties = W>,
+        fn remove_working_levels_jumped_over_by_gap<T, N>(
+            _working_level_store: &mut impl StepWorkingLevelStore<WorkingLevelProperties = T>,
+            _previous_candle: &BasicCandleProperties,
+            _current_candle: &BasicCandleProperties,
+            _min_gap: ParamOutputValue,
+            _entity: StatisticsNotifier<N>,
+        ) -> Result<()>
+        where
+            T: AsRef<BasicWLProperties> + Debug,
+            N: NotificationQueue,
+        {
+            unimplemented!()
+        }
+
+        fn move_stops_to_breakeven<W>(
+            _working_level_store: &mut impl StepWorkingLevelStore<
+                WorkingLevelProperties = W,
+                OrderProperties = StepOrderProperties,
+            >,
+            _trigger_distance: ParamOutputValue,
+            _buffer: Option<ParamOutputValue>,
+            _current_tick_price: UniversalTickPrice,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+
+        fn move_take_profits<W, O>(
+            working_level_store: &mut impl StepWorkingLevelStore<
+                WorkingLevelProperties = W,
+                OrderProperties = O,
+            >,
             distance_from_level_for_signaling_of_moving_take_profits: ParamOutputValue,
             distance_to_move_take_profits: ParamOutputValue,
+            _min_profit_to_trail: Option<ParamOutputValue>,
             current_tick_price: UniversalTickPrice,
         ) -> Result<()>
         where
             W: Into<BasicWLProperties>,
+            O: AsRef<BasicOrderProperties>,
         {
             unimplemented!()
         }
@@ -446,7 +905,7 @@ mod tests {
             crossed_angle: &Item<AngleId, FullAngleProperties<A, C>>,
             current_candle: &Item<CandleId, C>,
             params: &M,
-        ) -> Result<bool>
+        ) -> Result<Option<LevelOrigin>>
         where
             S: StepAngleStore<AngleProperties = A, CandleProperties = C>
                 + StepCandleStore<CandleProperties = C>
@@ -461,6 +920,7 @@ mod tests {
                 &[Item<CandleId, C>],
                 &S,
                 ParamOutputValue,
+                Option<usize>,
             ) -> Result<bool>,
             M: StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
             P: Fn(
@@ -506,6 +966,15 @@ mod tests {
             unimplemented!()
         }
 
+        fn level_expired_by_distance_directional(
+            level_price: WLPrice,
+            level_type: OrderType,
+            current_tick_price: UniversalTickPrice,
+            distance_from_level_for_its_deletion: ParamOutputValue,
+        ) -> bool {
+            unimplemented!()
+        }
+
         fn level_expired_by_time(
             level_time: LevelTime,
             current_tick_time: TickTime,
@@ -546,6 +1015,7 @@ mod tests {
             general_corridor: &[Item<CandleId, C>],
             angle_store: &impl StepAngleStore<AngleProperties = A, CandleProperties = C>,
             min_amount_of_candles_in_corridor_defining_edge_bargaining: ParamOutputValue,
+            _max_candles_to_scan: Option<usize>,
         ) -> Result<bool>
         where
             A: AsRef<BasicAngleProperties> + Debug,
@@ -597,11 +1067,25 @@ mod tests {
     struct TestOrderUtilsImpl;
 
     impl OrderUtils for TestOrderUtilsImpl {
+        fn compute_order_prices<W>(
+            _level: &Item<WLId, W>,
+            _params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+            _current_volatility: CandleVolatility,
+            _price_rounding: PriceRounding,
+        ) -> BasicOrderPrices
+        where
+            W: AsRef<BasicWLProperties>,
+        {
+            unimplemented!()
+        }
+
         fn get_new_chain_of_orders<W>(
             level: &Item<WLId, W>,
             params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
             current_volatility: CandleVolatility,
             current_balance: Balance,
+            _price_rounding: PriceRounding,
+            _lot_rounding: LotRounding,
         ) -> Result<Vec<StepOrderProperties>>
         where
             W: AsRef<BasicWLProperties>,
@@ -609,6 +1093,19 @@ mod tests {
             unimplemented!()
         }
 
+        fn create_chain_of_orders<S>(
+            chain_of_orders: Vec<StepOrderProperties>,
+            params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+            _current_volatility: CandleVolatility,
+            store: &mut S,
+        ) -> Result<()>
+        where
+            S: BasicOrderStore<OrderProperties = StepOrderProperties>
+                + StepWorkingLevelStore<OrderProperties = StepOrderProperties>,
+        {
+            unimplemented!()
+        }
+
         fn update_orders_backtesting<T, C, R, W, P, A>(
             current_tick: &BasicTickProperties<HistoricalTickPrice>,
             current_candle: &StepBacktestingCandleProperties,
@@ -744,7 +1241,7 @@ mod tests {
 
     struct TestAngleUtilsImpl;
 
-    impl AngleUtils for TestAngleUtilsImpl {
+    impl AngleDetector for TestAngleUtilsImpl {
         fn get_diff_between_current_and_previous_candles<C>(
             current_candle_props: &C,
             previous_candle_props: &C,
@@ -761,6 +1258,8 @@ mod tests {
             angles: MaxMinAngles<A, C>,
             min_distance_between_max_min_angles: ParamOutputValue,
             max_distance_between_max_min_angles: ParamOutputValue,
+            _min_angle_strength: ParamOutputValue,
+            _min_angle_candle_size: ParamOutputValue,
         ) -> Option<FullAngleProperties<BasicAngleProperties, C>>
         where
             C: AsRef<StepCandleProperties> + Debug + Clone,
@@ -768,7 +1267,9 @@ mod tests {
         {
             unimplemented!()
         }
+    }
 
+    impl AngleUtils for TestAngleUtilsImpl {
         fn update_angles<A, C>(
             new_angle: Item<AngleId, FullAngleProperties<A, C>>,
             general_corridor: &[Item<CandleId, C>],
@@ -822,6 +1323,48 @@ mod tests {
         {
             unimplemented!()
         }
+
+        fn exposure_by_direction<O>(
+            &self,
+            _order_store: &impl BasicOrderStore<OrderProperties = O>,
+        ) -> Result<Exposure>
+        where
+            O: Into<BasicOrderProperties> + Clone,
+        {
+            unimplemented!()
+        }
+
+        fn fill_delayed_orders(
+            &self,
+            current_tick_price: TickPrice,
+            trading_config: &mut BacktestingTradingEngineConfig,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+
+        fn import_position<O>(
+            &self,
+            _order: &Item<OrderId, O>,
+            _open_time: DateTime<Utc>,
+            _trading_config: &mut BacktestingTradingEngineConfig,
+        ) -> Result<()>
+        where
+            O: Into<BasicOrderProperties> + Clone,
+        {
+            unimplemented!()
+        }
+
+        fn close_positions_by_margin_call<O>(
+            &self,
+            _current_tick_price: TickPrice,
+            _order_store: &mut impl BasicOrderStore<OrderProperties = O>,
+            _trading_config: &mut BacktestingTradingEngineConfig,
+        ) -> Result<usize>
+        where
+            O: Into<BasicOrderProperties> + Clone,
+        {
+            unimplemented!()
+        }
     }
 
     #[test]
@@ -1072,6 +1615,11 @@ mod tests {
                 candle: Timeframe::Hour,
                 tick: Timeframe::ThirtyMin,
             },
+            candle_processing_mode: CandleProcessingMode::OnClose,
+            warmup_candles: 0,
+            session_filter: Default::default(),
+            candle_quality_filter: None,
+            loss_breaker: Default::default(),
             stores: &mut step_stores,
             utils: &utils,
             params: &step_params,
@@ -1113,7 +1661,7 @@ mod tests {
             Ok(())
         }
 
-        let strategy_performance = loop_through_historical_data(
+        let backtest_result = loop_through_historical_data(
             &historical_data,
             strategy_config,
             &trading_limiter,
@@ -1121,10 +1669,778 @@ mod tests {
         )
         .unwrap();
 
-        assert_eq!(strategy_performance, dec!(2.6));
+        assert_eq!(backtest_result.performance, dec!(2.6));
+        assert_eq!(backtest_result.initial_balance, dec!(10_000));
+        assert_eq!(backtest_result.final_balance, dec!(10_260));
+        assert_eq!(backtest_result.max_drawdown.round_dp(4), dec!(0.4931));
         assert_eq!(
             step_stores.config.trading_engine.balances.real,
             dec!(10_260)
         );
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_candle_to_process__different_modes__should_return_closed_or_forming_candle() {
+        let candles = vec![
+            Some(StepCandleProperties {
+                base: BasicCandleProperties {
+                    volatility: 120,
+                    ..Default::default()
+                },
+                leading_price: dec!(1.38000),
+            }),
+            Some(StepCandleProperties {
+                leading_price: dec!(1.39000),
+                ..Default::default()
+            }),
+        ];
+
+        let first_candle = Candle {
+            index: 0,
+            value: candles[0].as_ref(),
+        };
+
+        let last_candle = Candle {
+            index: 1,
+            value: candles[1].as_ref(),
+        };
+
+        let tick = BasicTickProperties::default();
+
+        assert_eq!(
+            get_candle_to_process(
+                &candles,
+                &first_candle,
+                &tick,
+                CandleProcessingMode::OnClose
+            ),
+            Some(StepBacktestingCandleProperties {
+                step_common: candles[0].clone().unwrap(),
+                chart_index: 0,
+                ..Default::default()
+            })
+        );
+
+        let forming_candle = get_candle_to_process(
+            &candles,
+            &first_candle,
+            &tick,
+            CandleProcessingMode::OnFormation,
+        )
+        .unwrap();
+
+        assert_eq!(forming_candle.chart_index, 1);
+        assert_eq!(
+            forming_candle.step_common.base.time,
+            candles[1].as_ref().unwrap().base.time
+        );
+        assert_eq!(forming_candle.step_common.base.volatility, 120);
+
+        assert_eq!(
+            get_candle_to_process(
+                &candles,
+                &last_candle,
+                &tick,
+                CandleProcessingMode::OnFormation
+            ),
+            None
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_candle_to_process__on_formation__should_not_reveal_future_prices_of_forming_candle() {
+        let candles = vec![
+            Some(StepCandleProperties::default()),
+            Some(StepCandleProperties {
+                base: BasicCandleProperties {
+                    size: dec!(150),
+                    prices: CandlePrices {
+                        open: dec!(1.38000),
+                        high: dec!(1.39000),
+                        low: dec!(1.37500),
+                        close: dec!(1.38900),
+                    },
+                    ..Default::default()
+                },
+                leading_price: dec!(1.39000),
+            }),
+        ];
+
+        let tick = BasicTickProperties {
+            bid: HistoricalTickPrice {
+                high: dec!(1.38050),
+                low: dec!(1.37980),
+                close: dec!(1.37990),
+            },
+            ..Default::default()
+        };
+
+        let forming_candle = get_candle_to_process(
+            &candles,
+            &Candle {
+                index: 0,
+                value: candles[0].as_ref(),
+            },
+            &tick,
+            CandleProcessingMode::OnFormation,
+        )
+        .unwrap()
+        .step_common;
+
+        assert_eq!(
+            forming_candle.base.prices,
+            CandlePrices {
+                open: dec!(1.38000),
+                high: dec!(1.38050),
+                low: dec!(1.37980),
+                close: dec!(1.37990),
+            }
+        );
+        assert_eq!(forming_candle.base.size, dec!(70));
+        assert_eq!(
+            forming_candle.base.r#type,
+            forming_candle.base.prices.candle_type()
+        );
+        assert_eq!(forming_candle.leading_price, dec!(1.37980));
+    }
+
+    fn tick_at(time: &str) -> BasicTickProperties<HistoricalTickPrice> {
+        BasicTickProperties {
+            time: NaiveDateTime::parse_from_str(time, "%d-%m-%Y %H:%M").unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn candle_at(time: &str) -> StepCandleProperties {
+        StepCandleProperties {
+            base: BasicCandleProperties {
+                time: NaiveDateTime::parse_from_str(time, "%d-%m-%Y %H:%M").unwrap(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn take_completed_candle__different_ticks__should_take_candle_only_when_it_is_completed() {
+        let candle = candle_at("17-05-2022 18:00");
+
+        let mut waiting_candle = Some(Candle {
+            index: 3,
+            value: Some(&candle),
+        });
+
+        assert!(take_completed_candle(
+            &mut waiting_candle,
+            &tick_at("17-05-2022 18:30"),
+            Timeframe::Hour
+        )
+        .is_none());
+        assert!(waiting_candle.is_some());
+
+        let completed_candle = take_completed_candle(
+            &mut waiting_candle,
+            &tick_at("17-05-2022 19:00"),
+            Timeframe::Hour,
+        )
+        .unwrap();
+
+        assert_eq!(completed_candle.index, 3);
+        assert!(waiting_candle.is_none());
+
+        let mut waiting_gap: Option<Candle<StepCandleProperties>> = Some(Candle {
+            index: 4,
+            value: None,
+        });
+
+        assert!(take_completed_candle(
+            &mut waiting_gap,
+            &tick_at("17-05-2022 18:30"),
+            Timeframe::Hour
+        )
+        .is_some());
+    }
+
+    thread_local! {
+        static PROCESSED_TICKS: RefCell<Vec<(NaiveDateTime, Option<NaiveDateTime>)>> =
+            const { RefCell::new(Vec::new()) };
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn loop_through_historical_data__strict_mode_and_ticks_ahead_of_candles__should_process_every_tick_and_completed_candles_only(
+    ) {
+        let historical_data = HistoricalData {
+            candles: vec![
+                Some(candle_at("17-05-2022 18:00")),
+                Some(candle_at("17-05-2022 19:00")),
+                Some(candle_at("17-05-2022 20:00")),
+            ],
+            ticks: vec![
+                Some(tick_at("17-05-2022 18:15")),
+                Some(tick_at("17-05-2022 18:45")),
+                Some(tick_at("17-05-2022 19:15")),
+                Some(tick_at("17-05-2022 19:45")),
+                Some(tick_at("17-05-2022 20:15")),
+                Some(tick_at("17-05-2022 20:45")),
+            ],
+        };
+
+        let mut step_stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        let step_params = TestStrategyParams::new();
+
+        let exclude_weekend_and_holidays =
+            |_start_time: NaiveDateTime, _end_time: NaiveDateTime, _holidays: &[Holiday]| 0;
+
+        fn add_entity_to_chart_traces(
+            _entity: ChartTraceEntity,
+            _chart_traces: &mut StepBacktestingChartTraces,
+            _current_candle_index: ChartIndex,
+        ) {
+            unimplemented!()
+        }
+
+        let utils: StepBacktestingUtils<
+            TestHelpersImpl,
+            TestLevelUtilsImpl,
+            TestLevelConditionsImpl,
+            TestOrderUtilsImpl,
+            TestBasicCorridorUtilsImpl,
+            TestCorridorsImpl,
+            TestAngleUtilsImpl,
+            _,
+            _,
+            _,
+        > = StepBacktestingUtils::new(
+            add_entity_to_chart_traces,
+            exclude_weekend_and_holidays,
+            BacktestingTradingEngine::new(),
+        );
+
+        // records the processed tick with the passed candle and fills an order on it
+        fn run_iteration<T, Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, D, E, X>(
+            new_tick_props: BasicTickProperties<HistoricalTickPrice>,
+            new_candle_props: Option<StepBacktestingCandleProperties>,
+            _signals: StrategySignals,
+            stores: &mut StepBacktestingStores<T>,
+            utils: &StepBacktestingUtils<Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, E, D, X>,
+            _params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        ) -> Result<()>
+        where
+            T: StepBacktestingMainStore,
+            Hel: Helpers,
+            LevUt: LevelUtils,
+            LevCon: LevelConditions,
+            OrUt: OrderUtils,
+            BCor: BasicCorridorUtils,
+            Cor: Corridors,
+            Ang: AngleUtils,
+            D: Fn(ChartTraceEntity, &mut StepBacktestingChartTraces, ChartIndex),
+            E: TradingEngine,
+            X: Fn(NaiveDateTime, NaiveDateTime, &[Holiday]) -> NumberOfDaysToExclude,
+        {
+            PROCESSED_TICKS.with(|ticks| {
+                ticks.borrow_mut().push((
+                    new_tick_props.time,
+                    new_candle_props.map(|candle| candle.step_common.base.time),
+                ))
+            });
+
+            let id = new_tick_props.time.to_string();
+
+            stores
+                .main
+                .create_working_level(id.clone(), Default::default())?;
+
+            let order = stores.main.create_order(
+                id.clone(),
+                StepOrderProperties {
+                    base: BasicOrderProperties {
+                        volume: dec!(0.001),
+                        prices: BasicOrderPrices {
+                            open: dec!(1.38000),
+                            stop_loss: dec!(1.37500),
+                            take_profit: dec!(1.38500),
+                        },
+                        ..Default::default()
+                    },
+                    working_level_id: id,
+                    creation_time: None,
+                },
+            )?;
+
+            utils.trading_engine.open_position(
+                &order,
+                OpenPositionBy::OpenPrice,
+                &mut stores.main,
+                &mut stores.config.trading_engine,
+            )
+        }
+
+        loop_through_historical_data(
+            &historical_data,
+            StepStrategyRunningConfig {
+                timeframes: StrategyTimeframes {
+                    candle: Timeframe::Hour,
+                    tick: Timeframe::ThirtyMin,
+                },
+                candle_processing_mode: CandleProcessingMode::OnCloseStrict,
+                warmup_candles: 0,
+                session_filter: Default::default(),
+                candle_quality_filter: None,
+                loss_breaker: Default::default(),
+                stores: &mut step_stores,
+                utils: &utils,
+                params: &step_params,
+            },
+            &TestTradingLimiter::new(),
+            &run_iteration,
+        )
+        .unwrap();
+
+        let time = |time| NaiveDateTime::parse_from_str(time, "%d-%m-%Y %H:%M").unwrap();
+
+        // the candles are passed on the first ticks after their completion,
+        // and the last candle isn't completed by the last tick
+        assert_eq!(
+            PROCESSED_TICKS.with(|ticks| ticks.take()),
+            vec![
+                (time("17-05-2022 18:15"), None),
+                (time("17-05-2022 18:45"), None),
+                (time("17-05-2022 19:15"), Some(time("17-05-2022 18:00"))),
+                (time("17-05-2022 19:45"), None),
+                (time("17-05-2022 20:15"), Some(time("17-05-2022 19:00"))),
+                (time("17-05-2022 20:45"), None),
+            ]
+        );
+
+        assert_eq!(step_stores.config.trading_engine.open_trades.len(), 6);
+    }
+
+    thread_local! {
+        static ITERATION_TIMES: RefCell<Vec<NaiveDateTime>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn loop_through_historical_data__balance_is_depleted__should_end_backtest_without_error() {
+        let historical_data = HistoricalData {
+            candles: vec![
+                Some(candle_at("17-05-2022 18:00")),
+                Some(candle_at("17-05-2022 19:00")),
+            ],
+            ticks: vec![
+                Some(tick_at("17-05-2022 18:00")),
+                Some(tick_at("17-05-2022 18:30")),
+                Some(tick_at("17-05-2022 19:00")),
+                Some(tick_at("17-05-2022 19:30")),
+            ],
+        };
+
+        let mut step_stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        let step_params = TestStrategyParams::new();
+
+        let exclude_weekend_and_holidays =
+            |_start_time: NaiveDateTime, _end_time: NaiveDateTime, _holidays: &[Holiday]| 0;
+
+        fn add_entity_to_chart_traces(
+            _entity: ChartTraceEntity,
+            _chart_traces: &mut StepBacktestingChartTraces,
+            _current_candle_index: ChartIndex,
+        ) {
+            unimplemented!()
+        }
+
+        let utils: StepBacktestingUtils<
+            TestHelpersImpl,
+            TestLevelUtilsImpl,
+            TestLevelConditionsImpl,
+            TestOrderUtilsImpl,
+            TestBasicCorridorUtilsImpl,
+            TestCorridorsImpl,
+            TestAngleUtilsImpl,
+            _,
+            _,
+            _,
+        > = StepBacktestingUtils::new(
+            add_entity_to_chart_traces,
+            exclude_weekend_and_holidays,
+            BacktestingTradingEngine::new(),
+        );
+
+        // the balance is depleted on the third tick
+        fn run_iteration<T, Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, D, E, X>(
+            new_tick_props: BasicTickProperties<HistoricalTickPrice>,
+            _new_candle_props: Option<StepBacktestingCandleProperties>,
+            _signals: StrategySignals,
+            _stores: &mut StepBacktestingStores<T>,
+            _utils: &StepBacktestingUtils<Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, E, D, X>,
+            _params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        ) -> Result<()>
+        where
+            T: StepBacktestingMainStore,
+            Hel: Helpers,
+            LevUt: LevelUtils,
+            LevCon: LevelConditions,
+            OrUt: OrderUtils,
+            BCor: BasicCorridorUtils,
+            Cor: Corridors,
+            Ang: AngleUtils,
+            D: Fn(ChartTraceEntity, &mut StepBacktestingChartTraces, ChartIndex),
+            E: TradingEngine,
+            X: Fn(NaiveDateTime, NaiveDateTime, &[Holiday]) -> NumberOfDaysToExclude,
+        {
+            let number_of_iterations = ITERATION_TIMES.with(|times| {
+                let mut times = times.borrow_mut();
+                times.push(new_tick_props.time);
+                times.len()
+            });
+
+            if number_of_iterations == 3 {
+                return Err(BalanceIsDepleted(dec!(-10)).into());
+            }
+
+            Ok(())
+        }
+
+        let result = loop_through_historical_data(
+            &historical_data,
+            StepStrategyRunningConfig {
+                timeframes: StrategyTimeframes {
+                    candle: Timeframe::Hour,
+                    tick: Timeframe::ThirtyMin,
+                },
+                candle_processing_mode: Default::default(),
+                warmup_candles: 0,
+                session_filter: Default::default(),
+                candle_quality_filter: None,
+                loss_breaker: Default::default(),
+                stores: &mut step_stores,
+                utils: &utils,
+                params: &step_params,
+            },
+            &TestTradingLimiter::new(),
+            &run_iteration,
+        )
+        .unwrap();
+
+        assert!(result.balance_is_depleted);
+        assert_eq!(ITERATION_TIMES.with(|times| times.take().len()), 3);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn loop_through_historical_data__closed_trades_are_not_kept__should_count_trades_and_win_rate()
+    {
+        let historical_data = HistoricalData {
+            candles: vec![Some(candle_at("17-05-2022 18:00"))],
+            ticks: vec![
+                Some(tick_at("17-05-2022 18:00")),
+                Some(tick_at("17-05-2022 18:30")),
+            ],
+        };
+
+        let mut step_stores = store_with_open_order();
+        step_stores.config.trading_engine.keep_closed_trades = false;
+
+        let step_params = TestStrategyParams::new();
+
+        let exclude_weekend_and_holidays =
+            |_start_time: NaiveDateTime, _end_time: NaiveDateTime, _holidays: &[Holiday]| 0;
+
+        fn add_entity_to_chart_traces(
+            _entity: ChartTraceEntity,
+            _chart_traces: &mut StepBacktestingChartTraces,
+            _current_candle_index: ChartIndex,
+        ) {
+            unimplemented!()
+        }
+
+        let utils: StepBacktestingUtils<
+            TestHelpersImpl,
+            TestLevelUtilsImpl,
+            TestLevelConditionsImpl,
+            TestOrderUtilsImpl,
+            TestBasicCorridorUtilsImpl,
+            TestCorridorsImpl,
+            TestAngleUtilsImpl,
+            _,
+            _,
+            _,
+        > = StepBacktestingUtils::new(
+            add_entity_to_chart_traces,
+            exclude_weekend_and_holidays,
+            BacktestingTradingEngine::new(),
+        );
+
+        // the opened order is closed by take profit on the first tick
+        fn run_iteration<T, Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, D, E, X>(
+            _new_tick_props: BasicTickProperties<HistoricalTickPrice>,
+            _new_candle_props: Option<StepBacktestingCandleProperties>,
+            _signals: StrategySignals,
+            stores: &mut StepBacktestingStores<T>,
+            _utils: &StepBacktestingUtils<Hel, LevUt, LevCon, OrUt, BCor, Cor, Ang, E, D, X>,
+            _params: &impl StrategyParams<PointParam = StepPointParam, RatioParam = StepRatioParam>,
+        ) -> Result<()>
+        where
+            T: StepBacktestingMainStore,
+            Hel: Helpers,
+            LevUt: LevelUtils,
+            LevCon: LevelConditions,
+            OrUt: OrderUtils,
+            BCor: BasicCorridorUtils,
+            Cor: Corridors,
+            Ang: AngleUtils,
+            D: Fn(ChartTraceEntity, &mut StepBacktestingChartTraces, ChartIndex),
+            E: TradingEngine,
+            X: Fn(NaiveDateTime, NaiveDateTime, &[Holiday]) -> NumberOfDaysToExclude,
+        {
+            let order = stores.main.get_order_by_id("1")?.unwrap();
+
+            if order.props.base.status == OrderStatus::Opened {
+                BacktestingTradingEngine::new().close_position(
+                    &order,
+                    ClosePositionBy::TakeProfit,
+                    &mut stores.main,
+                    &mut stores.config.trading_engine,
+                )?;
+            }
+
+            Ok(())
+        }
+
+        let result = loop_through_historical_data(
+            &historical_data,
+            StepStrategyRunningConfig {
+                timeframes: StrategyTimeframes {
+                    candle: Timeframe::Hour,
+                    tick: Timeframe::ThirtyMin,
+                },
+                candle_processing_mode: Default::default(),
+                warmup_candles: 0,
+                session_filter: Default::default(),
+                candle_quality_filter: None,
+                loss_breaker: Default::default(),
+                stores: &mut step_stores,
+                utils: &utils,
+                params: &step_params,
+            },
+            &TestTradingLimiter::new(),
+            &run_iteration,
+        )
+        .unwrap();
+
+        assert!(result.trades.is_empty());
+        assert_eq!(result.number_of_trades, 1);
+        assert_eq!(result.win_rate, Some(dec!(100)));
+    }
+
+    fn step_candle(
+        high: CandlePrice,
+        low: CandlePrice,
+        close: CandlePrice,
+    ) -> StepCandleProperties {
+        StepCandleProperties {
+            base: BasicCandleProperties {
+                prices: CandlePrices {
+                    open: low,
+                    high,
+                    low,
+                    close,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_volatility_over_window__different_windows__should_return_different_volatility() {
+        let candles = vec![
+            Some(step_candle(dec!(1.38100), dec!(1.38000), dec!(1.38050))),
+            None,
+            Some(step_candle(dec!(1.38200), dec!(1.38000), dec!(1.38100))),
+            Some(step_candle(dec!(1.38600), dec!(1.38100), dec!(1.38500))),
+        ];
+
+        assert_eq!(get_volatility_over_window(&candles, 3, 1), 500);
+        // true ranges 100, 200, 500, the gap is skipped
+        assert_eq!(get_volatility_over_window(&candles, 3, 3), 267);
+        assert_eq!(get_volatility_over_window(&candles, 2, 3), 150);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn warmup_is_in_progress__different_processed_candles__should_return_correct_result() {
+        assert!(!warmup_is_in_progress(0, 0));
+        assert!(warmup_is_in_progress(0, 2));
+        assert!(warmup_is_in_progress(1, 2));
+        assert!(!warmup_is_in_progress(2, 2));
+        assert!(!warmup_is_in_progress(3, 2));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn skip_low_quality_candles__different_qualities__should_replace_low_quality_candles_with_gaps()
+    {
+        let candles = vec![Some(1), Some(2), None, Some(4), Some(5)];
+
+        assert_eq!(skip_low_quality_candles(&candles, None).as_ref(), &candles);
+
+        let qualities = vec![Some(90), Some(40), Some(10), None];
+
+        assert_eq!(
+            skip_low_quality_candles(
+                &candles,
+                Some(CandleQualityFilter {
+                    qualities: &qualities,
+                    min_quality: 50,
+                })
+            )
+            .as_ref(),
+            &[Some(1), None, None, Some(4), Some(5)]
+        );
+    }
+
+    fn store_with_open_order() -> StepBacktestingStores<InMemoryStepBacktestingStore> {
+        let mut stores = StepBacktestingStores {
+            main: InMemoryStepBacktestingStore::new(),
+            config: StepBacktestingConfig::default(10),
+            statistics: Default::default(),
+        };
+
+        let level = stores
+            .main
+            .create_working_level(String::from("1"), Default::default())
+            .unwrap();
+
+        let order = stores
+            .main
+            .create_order(
+                String::from("1"),
+                StepOrderProperties {
+                    base: BasicOrderProperties {
+                        r#type: OrderType::Buy,
+                        volume: dec!(0.1),
+                        prices: BasicOrderPrices {
+                            open: dec!(1.3),
+                            stop_loss: dec!(1.29),
+                            take_profit: dec!(1.31),
+                        },
+                        ..Default::default()
+                    },
+                    working_level_id: level.id,
+                    creation_time: None,
+                },
+            )
+            .unwrap();
+
+        BacktestingTradingEngine::new()
+            .open_position(
+                &order,
+                OpenPositionBy::OpenPrice,
+                &mut stores.main,
+                &mut stores.config.trading_engine,
+            )
+            .unwrap();
+
+        stores
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn apply_end_of_data_policy__close_at_last_price__should_close_orders_at_last_tick() {
+        let mut stores = store_with_open_order();
+        stores.config.trading_engine.end_of_data_policy = EndOfDataPolicy::CloseAtLastPrice;
+
+        let last_tick = BasicTickProperties {
+            bid: HistoricalTickPrice {
+                close: dec!(1.305),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        apply_end_of_data_policy(
+            Some(&last_tick),
+            &mut stores,
+            &BacktestingTradingEngine::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            stores
+                .main
+                .get_order_by_id("1")
+                .unwrap()
+                .unwrap()
+                .props
+                .base
+                .status,
+            OrderStatus::Closed
+        );
+        assert_eq!(
+            stores.config.trading_engine.closed_trades[0].close_price,
+            dec!(1.305)
+        );
+        assert_eq!(
+            stores.config.trading_engine.closed_trades[0].close_reason,
+            CloseReason::EndOfData
+        );
+        assert_eq!(stores.statistics.closed_at_end_of_data, 1);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn apply_end_of_data_policy__error_with_open_orders__should_return_error() {
+        let mut stores = store_with_open_order();
+        stores.config.trading_engine.end_of_data_policy = EndOfDataPolicy::Error;
+
+        assert!(apply_end_of_data_policy(
+            Some(&Default::default()),
+            &mut stores,
+            &BacktestingTradingEngine::new(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn apply_end_of_data_policy__leave_open__should_keep_orders_open() {
+        let mut stores = store_with_open_order();
+
+        apply_end_of_data_policy(
+            Some(&Default::default()),
+            &mut stores,
+            &BacktestingTradingEngine::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            stores
+                .main
+                .get_order_by_id("1")
+                .unwrap()
+                .unwrap()
+                .props
+                .base
+                .status,
+            OrderStatus::Opened
+        );
+        assert_eq!(stores.statistics.closed_at_end_of_data, 0);
+    }
 }
@@ -0,0 +1,270 @@
+use anyhow::Result;
+use backtesting::replay::ReplayEvent;
+use base::entities::tick::{HistoricalTickPrice, TickPriceMode, UniversalTickPrice};
+use base::entities::BasicTickProperties;
+use chrono::{DateTime, Utc};
+use strategies::step::utils::decision_sink::{CollectingDecisionSink, DecisionEvent};
+use strategies::step::utils::entities::candle::StepBacktestingCandleProperties;
+use strategies::step::utils::entities::StrategySignals;
+use strategies::step::utils::loss_breaker::ConsecutiveLossBreaker;
+use strategies::step::utils::stores::{StepBacktestingMainStore, StepBacktestingStores};
+
+pub type StrategyEvent = DecisionEvent;
+
+pub type StepReplayEvent<'a> =
+    ReplayEvent<'a, StepBacktestingCandleProperties, BasicTickProperties<HistoricalTickPrice>>;
+
+/// Drives the step strategy one replay event at a time. Used to step through
+/// the backtest interactively instead of looping through the whole historical data.
+///
+/// The decisions are taken from the `decisions` sink, so its clone should be set
+/// as the decision sink of the utils.
+pub struct StepStrategyDriver<T, U, P, I>
+where
+    T: StepBacktestingMainStore,
+{
+    pub stores: StepBacktestingStores<T>,
+    pub signals: StrategySignals,
+    /// The kind of the tick prices the strategy is run on. The ticks
+    /// of the other kind are rejected.
+    pub tick_price_mode: TickPriceMode,
+    utils: U,
+    params: P,
+    run_iteration: I,
+    decisions: CollectingDecisionSink,
+    /// The completed candle waiting for the next tick to be processed together with it.
+    pending_candle: Option<StepBacktestingCandleProperties>,
+}
+
+impl<T, U, P, I> StepStrategyDriver<T, U, P, I>
+where
+    T: StepBacktestingMainStore,
+    I: Fn(
+        BasicTickProperties<HistoricalTickPrice>,
+        Option<StepBacktestingCandleProperties>,
+        StrategySignals,
+        &mut StepBacktestingStores<T>,
+        &U,
+        &P,
+    ) -> Result<()>,
+{
+    pub fn new(
+        stores: StepBacktestingStores<T>,
+        utils: U,
+        params: P,
+        run_iteration: I,
+        decisions: CollectingDecisionSink,
+    ) -> Self {
+        Self {
+            stores,
+            signals: StrategySignals {
+                no_trading_mode: false,
+                close_all_orders: false,
+                warmup: false,
+                out_of_session: false,
+            },
+            tick_price_mode: TickPriceMode::Historical,
+            utils,
+            params,
+            run_iteration,
+            decisions,
+            pending_candle: None,
+        }
+    }
+
+    /// Stops creating the new chains of orders after the series of losing trades.
+    pub fn loss_breaker(mut self, loss_breaker: ConsecutiveLossBreaker) -> Self {
+        self.stores.config.loss_breaker = loss_breaker;
+        self
+    }
+
+    /// Processes exactly one event and returns the decisions made on it.
+    ///
+    /// The strategy iterates on ticks only, so the completed candle is kept until
+    /// the next tick and processed together with it, and no decisions are returned for the candle.
+    /// The time of the trading engine is moved only to the time of the processed tick.
+    /// Returns an error if the tick price doesn't match the tick price mode.
+    pub fn process_one(&mut self, event: StepReplayEvent) -> Result<Vec<StrategyEvent>> {
+        match event {
+            ReplayEvent::Candle(candle) => {
+                self.pending_candle = Some(candle.clone());
+                Ok(Vec::new())
+            }
+            ReplayEvent::Tick(tick) => {
+                self.tick_price_mode
+                    .ensure_matches(&UniversalTickPrice::from(tick.bid))?;
+
+                let candle = self.pending_candle.take();
+
+                let trading_engine = &mut self.stores.config.trading_engine;
+                trading_engine.current_time = Some(DateTime::from_utc(tick.time, Utc));
+
+                if let Some(candle) = &candle {
+                    trading_engine.current_candle_index = Some(candle.chart_index);
+                }
+
+                // the decisions left from the previous failed event don't belong to this one
+                self.decisions.take_decisions();
+
+                (self.run_iteration)(
+                    tick.clone(),
+                    candle,
+                    self.signals,
+                    &mut self.stores,
+                    &self.utils,
+                    &self.params,
+                )?;
+
+                Ok(self.decisions.take_decisions())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::entities::{BasicTickProperties, Tendency};
+    use chrono::NaiveDate;
+    use strategies::step::utils::decision_sink::DecisionSink;
+    use strategies::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
+    use strategies::step::utils::stores::StepBacktestingConfig;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn process_one__candle_and_ticks__should_return_decisions_of_each_tick_only() {
+        let decisions = CollectingDecisionSink::new();
+
+        // every passed candle changes the tendency
+        let run_iteration = |tick: BasicTickProperties<HistoricalTickPrice>,
+                             new_candle: Option<StepBacktestingCandleProperties>,
+                             _signals: StrategySignals,
+                             stores: &mut StepBacktestingStores<InMemoryStepBacktestingStore>,
+                             decisions: &CollectingDecisionSink,
+                             _params: &()|
+         -> Result<()> {
+            if new_candle.is_some() {
+                stores.statistics.number_of_tendency_changes += 1;
+
+                decisions.record(
+                    DecisionEvent::TendencyChanged {
+                        previous: Tendency::Unknown,
+                        current: Tendency::Up,
+                    },
+                    &tick,
+                    new_candle.as_ref(),
+                );
+            }
+
+            Ok(())
+        };
+
+        let mut driver = StepStrategyDriver::new(
+            StepBacktestingStores {
+                main: InMemoryStepBacktestingStore::new(),
+                config: StepBacktestingConfig::default(10),
+                statistics: Default::default(),
+            },
+            decisions.clone(),
+            (),
+            run_iteration,
+            decisions,
+        );
+
+        let first_tick = BasicTickProperties {
+            time: NaiveDate::from_ymd(2022, 4, 1).and_hms(18, 0, 0),
+            ..Default::default()
+        };
+
+        let second_tick = BasicTickProperties {
+            time: NaiveDate::from_ymd(2022, 4, 1).and_hms(19, 0, 0),
+            ..Default::default()
+        };
+
+        let candle = StepBacktestingCandleProperties {
+            chart_index: 5,
+            ..Default::default()
+        };
+
+        assert!(driver
+            .process_one(ReplayEvent::Tick(&first_tick))
+            .unwrap()
+            .is_empty());
+
+        assert!(driver
+            .process_one(ReplayEvent::Candle(&candle))
+            .unwrap()
+            .is_empty());
+
+        // the candle doesn't move the time
+        assert_eq!(
+            driver.stores.config.trading_engine.current_time,
+            Some(DateTime::from_utc(first_tick.time, Utc))
+        );
+        assert_eq!(driver.stores.statistics.number_of_tendency_changes, 0);
+
+        assert_eq!(
+            driver.process_one(ReplayEvent::Tick(&second_tick)).unwrap(),
+            vec![DecisionEvent::TendencyChanged {
+                previous: Tendency::Unknown,
+                current: Tendency::Up,
+            }]
+        );
+
+        assert_eq!(
+            driver.stores.config.trading_engine.current_time,
+            Some(DateTime::from_utc(second_tick.time, Utc))
+        );
+        assert_eq!(
+            driver.stores.config.trading_engine.current_candle_index,
+            Some(5)
+        );
+        assert_eq!(driver.stores.statistics.number_of_tendency_changes, 1);
+
+        assert!(driver
+            .process_one(ReplayEvent::Tick(&second_tick))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn process_one__tick_price_mismatching_mode__should_return_error() {
+        let decisions = CollectingDecisionSink::new();
+
+        let run_iteration = |_tick: BasicTickProperties<HistoricalTickPrice>,
+                             _new_candle: Option<StepBacktestingCandleProperties>,
+                             _signals: StrategySignals,
+                             _stores: &mut StepBacktestingStores<InMemoryStepBacktestingStore>,
+                             _decisions: &CollectingDecisionSink,
+                             _params: &()|
+         -> Result<()> { unreachable!() };
+
+        let mut driver = StepStrategyDriver::new(
+            StepBacktestingStores {
+                main: InMemoryStepBacktestingStore::new(),
+                config: StepBacktestingConfig::default(10),
+                statistics: Default::default(),
+            },
+            decisions.clone(),
+            (),
+            run_iteration,
+            decisions,
+        );
+        driver.tick_price_mode = TickPriceMode::Realtime;
+
+        let tick = BasicTickProperties {
+            time: NaiveDate::from_ymd(2022, 4, 1).and_hms(18, 0, 0),
+            ..Default::default()
+        };
+
+        assert!(driver
+            .process_one(ReplayEvent::Tick(&tick))
+            .unwrap_err()
+            .to_string()
+            .contains("doesn't match the Realtime tick price mode"));
+
+        // the rejected tick doesn't move the time
+        assert_eq!(driver.stores.config.trading_engine.current_time, None);
+    }
+}
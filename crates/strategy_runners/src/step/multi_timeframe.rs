@@ -0,0 +1,209 @@
+use anyhow::{bail, Result};
+use base::entities::tick::HistoricalTickPrice;
+use base::entities::{BasicTickProperties, Timeframe};
+use strategies::step::utils::entities::candle::StepBacktestingCandleProperties;
+use strategies::step::utils::entities::StrategySignals;
+use strategies::step::utils::stores::{StepBacktestingMainStore, StepBacktestingStores};
+
+/// The instance of the step strategy with its own config and stores
+/// working on the candles of the particular timeframe.
+pub struct StrategyInstance<T>
+where
+    T: StepBacktestingMainStore,
+{
+    pub timeframe: Timeframe,
+    pub stores: StepBacktestingStores<T>,
+}
+
+impl<T> StrategyInstance<T>
+where
+    T: StepBacktestingMainStore,
+{
+    pub fn new(timeframe: Timeframe, stores: StepBacktestingStores<T>) -> Self {
+        Self { timeframe, stores }
+    }
+}
+
+/// Drives several strategy instances of different timeframes from one feed of ticks and candles.
+pub struct StrategyInstances<T>
+where
+    T: StepBacktestingMainStore,
+{
+    instances: Vec<StrategyInstance<T>>,
+}
+
+impl<T> Default for StrategyInstances<T>
+where
+    T: StepBacktestingMainStore,
+{
+    fn default() -> Self {
+        Self {
+            instances: Vec::new(),
+        }
+    }
+}
+
+impl<T> StrategyInstances<T>
+where
+    T: StepBacktestingMainStore,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the instance. Only one instance per timeframe is allowed.
+    pub fn add(&mut self, instance: StrategyInstance<T>) -> Result<()> {
+        if self.get(instance.timeframe).is_some() {
+            bail!(
+                "strategy instance of the timeframe {} already exists",
+                instance.timeframe
+            );
+        }
+
+        self.instances.push(instance);
+
+        Ok(())
+    }
+
+    pub fn get(&self, timeframe: Timeframe) -> Option<&StrategyInstance<T>> {
+        self.instances
+            .iter()
+            .find(|instance| instance.timeframe == timeframe)
+    }
+
+    /// Passes the tick to every instance. The new candles are passed only
+    /// to the instances of the same timeframe.
+    pub fn run_iteration<U, P, I>(
+        &mut self,
+        tick: &BasicTickProperties<HistoricalTickPrice>,
+        new_candles: &[(Timeframe, StepBacktestingCandleProperties)],
+        signals: StrategySignals,
+        utils: &U,
+        params: &P,
+        run_iteration: &I,
+    ) -> Result<()>
+    where
+        I: Fn(
+            BasicTickProperties<HistoricalTickPrice>,
+            Option<StepBacktestingCandleProperties>,
+            StrategySignals,
+            &mut StepBacktestingStores<T>,
+            &U,
+            &P,
+        ) -> Result<()>,
+    {
+        for instance in self.instances.iter_mut() {
+            let new_candle = new_candles
+                .iter()
+                .find(|(timeframe, _)| *timeframe == instance.timeframe)
+                .map(|(_, candle)| candle.clone());
+
+            run_iteration(
+                tick.clone(),
+                new_candle,
+                signals,
+                &mut instance.stores,
+                utils,
+                params,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strategies::step::utils::stores::in_memory_step_backtesting_store::InMemoryStepBacktestingStore;
+    use strategies::step::utils::stores::StepBacktestingConfig;
+
+    fn new_instance(timeframe: Timeframe) -> StrategyInstance<InMemoryStepBacktestingStore> {
+        StrategyInstance::new(
+            timeframe,
+            StepBacktestingStores {
+                main: InMemoryStepBacktestingStore::new(),
+                config: StepBacktestingConfig::default(10),
+                statistics: Default::default(),
+            },
+        )
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn run_iteration__candles_of_different_timeframes__should_pass_them_to_same_timeframe() {
+        let mut instances = StrategyInstances::new();
+        instances.add(new_instance(Timeframe::Hour)).unwrap();
+        instances.add(new_instance(Timeframe::FourHours)).unwrap();
+
+        // the statistics of the instance are used as the counters of iterations and passed candles
+        let run_iteration = |_tick: BasicTickProperties<HistoricalTickPrice>,
+                             new_candle: Option<StepBacktestingCandleProperties>,
+                             _signals: StrategySignals,
+                             stores: &mut StepBacktestingStores<InMemoryStepBacktestingStore>,
+                             _utils: &(),
+                             _params: &()|
+         -> Result<()> {
+            stores.statistics.number_of_tendency_changes += 1;
+
+            if new_candle.is_some() {
+                stores.statistics.number_of_working_levels += 1;
+            }
+
+            Ok(())
+        };
+
+        let signals = StrategySignals {
+            no_trading_mode: false,
+            close_all_orders: false,
+            warmup: false,
+            out_of_session: false,
+        };
+
+        let candle = StepBacktestingCandleProperties::default();
+
+        let feed = vec![
+            vec![
+                (Timeframe::Hour, candle.clone()),
+                (Timeframe::FourHours, candle.clone()),
+            ],
+            vec![(Timeframe::Hour, candle.clone())],
+            vec![],
+            vec![(Timeframe::Hour, candle)],
+        ];
+
+        for new_candles in feed {
+            instances
+                .run_iteration(
+                    &Default::default(),
+                    &new_candles,
+                    signals,
+                    &(),
+                    &(),
+                    &run_iteration,
+                )
+                .unwrap();
+        }
+
+        let hour_statistics = &instances.get(Timeframe::Hour).unwrap().stores.statistics;
+        assert_eq!(hour_statistics.number_of_tendency_changes, 4);
+        assert_eq!(hour_statistics.number_of_working_levels, 3);
+
+        let four_hours_statistics = &instances
+            .get(Timeframe::FourHours)
+            .unwrap()
+            .stores
+            .statistics;
+        assert_eq!(four_hours_statistics.number_of_tendency_changes, 4);
+        assert_eq!(four_hours_statistics.number_of_working_levels, 1);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn add__instance_of_existing_timeframe__should_return_error() {
+        let mut instances = StrategyInstances::new();
+        instances.add(new_instance(Timeframe::Hour)).unwrap();
+
+        assert!(instances.add(new_instance(Timeframe::Hour)).is_err());
+    }
+}
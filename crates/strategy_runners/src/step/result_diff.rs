@@ -0,0 +1,240 @@
+use backtesting::{Balance, ClosedTrade};
+use strategies::step::utils::stores::{BacktestingStatisticNumber, StepBacktestingStatistics};
+
+use crate::step::backtesting_runner::BacktestResult;
+
+pub type CounterDelta = i64;
+
+/// The trades of the baseline and candidate results closed at the same position
+/// that differ from each other. A missing trade means that one of the results has less trades.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeDiff {
+    pub index: usize,
+    pub baseline: Option<ClosedTrade>,
+    pub candidate: Option<ClosedTrade>,
+}
+
+/// The deltas of the working level deletion reasons of the candidate result relative to the baseline.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeletionReasonDeltas {
+    pub deleted_by_being_close_to_another_one: CounterDelta,
+    pub deleted_by_expiration_by_distance: CounterDelta,
+    pub deleted_by_expiration_by_time: CounterDelta,
+    pub deleted_by_price_being_beyond_stop_loss: CounterDelta,
+    pub deleted_by_exceeding_amount_of_candles_in_small_corridor_before_activation_crossing:
+        CounterDelta,
+    pub deleted_by_exceeding_amount_of_candles_in_big_corridor_before_activation_crossing:
+        CounterDelta,
+    pub deleted_by_exceeding_activation_crossing_distance: CounterDelta,
+    pub deleted_by_exceeding_max_active_working_levels: CounterDelta,
+}
+
+/// The changes of the candidate result relative to the baseline one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResultDiff {
+    pub final_balance: Balance,
+    pub number_of_trades: CounterDelta,
+    pub deletion_reasons: DeletionReasonDeltas,
+    pub differing_trades: Vec<TradeDiff>,
+}
+
+impl ResultDiff {
+    /// Returns true if the candidate result is the same as the baseline one.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+fn counter_delta(
+    baseline: BacktestingStatisticNumber,
+    candidate: BacktestingStatisticNumber,
+) -> CounterDelta {
+    candidate as CounterDelta - baseline as CounterDelta
+}
+
+fn get_deletion_reason_deltas(
+    baseline: &StepBacktestingStatistics,
+    candidate: &StepBacktestingStatistics,
+) -> DeletionReasonDeltas {
+    DeletionReasonDeltas {
+        deleted_by_being_close_to_another_one: counter_delta(
+            baseline.deleted_by_being_close_to_another_one,
+            candidate.deleted_by_being_close_to_another_one,
+        ),
+        deleted_by_expiration_by_distance: counter_delta(
+            baseline.deleted_by_expiration_by_distance,
+            candidate.deleted_by_expiration_by_distance,
+        ),
+        deleted_by_expiration_by_time: counter_delta(
+            baseline.deleted_by_expiration_by_time,
+            candidate.deleted_by_expiration_by_time,
+        ),
+        deleted_by_price_being_beyond_stop_loss: counter_delta(
+            baseline.deleted_by_price_being_beyond_stop_loss,
+            candidate.deleted_by_price_being_beyond_stop_loss,
+        ),
+        deleted_by_exceeding_amount_of_candles_in_small_corridor_before_activation_crossing:
+            counter_delta(
+                baseline
+                    .deleted_by_exceeding_amount_of_candles_in_small_corridor_before_activation_crossing,
+                candidate
+                    .deleted_by_exceeding_amount_of_candles_in_small_corridor_before_activation_crossing,
+            ),
+        deleted_by_exceeding_amount_of_candles_in_big_corridor_before_activation_crossing:
+            counter_delta(
+                baseline
+                    .deleted_by_exceeding_amount_of_candles_in_big_corridor_before_activation_crossing,
+                candidate
+                    .deleted_by_exceeding_amount_of_candles_in_big_corridor_before_activation_crossing,
+            ),
+        deleted_by_exceeding_activation_crossing_distance: counter_delta(
+            baseline.deleted_by_exceeding_activation_crossing_distance,
+            candidate.deleted_by_exceeding_activation_crossing_distance,
+        ),
+        deleted_by_exceeding_max_active_working_levels: counter_delta(
+            baseline.deleted_by_exceeding_max_active_working_levels,
+            candidate.deleted_by_exceeding_max_active_working_levels,
+        ),
+    }
+}
+
+/// Compares the trades of the results position by position.
+fn get_differing_trades(baseline: &[ClosedTrade], candidate: &[ClosedTrade]) -> Vec<TradeDiff> {
+    (0..baseline.len().max(candidate.len()))
+        .filter_map(|index| {
+            let baseline_trade = baseline.get(index);
+            let candidate_trade = candidate.get(index);
+
+            if baseline_trade == candidate_trade {
+                return None;
+            }
+
+            Some(TradeDiff {
+                index,
+                baseline: baseline_trade.cloned(),
+                candidate: candidate_trade.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Reports the changes of the candidate backtest result relative to the baseline one
+/// to detect the regressions of the strategy.
+pub fn diff_results(baseline: &BacktestResult, candidate: &BacktestResult) -> ResultDiff {
+    ResultDiff {
+        final_balance: candidate.final_balance - baseline.final_balance,
+        number_of_trades: candidate.number_of_trades as CounterDelta
+            - baseline.number_of_trades as CounterDelta,
+        deletion_reasons: get_deletion_reason_deltas(&baseline.statistics, &candidate.statistics),
+        differing_trades: get_differing_trades(&baseline.trades, &candidate.trades),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backtesting::CloseReason;
+    use base::entities::order::{OrderPrice, OrderType};
+    use rust_decimal_macros::dec;
+
+    fn closed_trade(order_id: &str, close_price: OrderPrice) -> ClosedTrade {
+        ClosedTrade {
+            order_id: String::from(order_id),
+            r#type: OrderType::Buy,
+            volume: dec!(0.03),
+            open_price: dec!(1.38000),
+            close_price,
+            open_time: None,
+            close_time: None,
+            open_candle_index: None,
+            close_candle_index: None,
+            excursions: Default::default(),
+            close_reason: CloseReason::TakeProfit,
+            fill_slippage: dec!(0),
+            spread_cost: dec!(0),
+        }
+    }
+
+    fn result(final_balance: Balance, trades: Vec<ClosedTrade>) -> BacktestResult {
+        BacktestResult {
+            performance: dec!(0),
+            initial_balance: dec!(10_000),
+            final_balance,
+            number_of_trades: trades.len(),
+            win_rate: None,
+            average_bars_in_trade: None,
+            expectancy: None,
+            average_fill_slippage: None,
+            max_drawdown: dec!(0),
+            time_weighted_return: None,
+            trade_weighted_return: None,
+            statistics: Default::default(),
+            balance_is_depleted: false,
+            trades,
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn diff_results__same_results__should_return_empty_diff() {
+        let baseline = result(dec!(10_100), vec![closed_trade("1", dec!(1.38100))]);
+        let candidate = result(dec!(10_100), vec![closed_trade("1", dec!(1.38100))]);
+
+        assert!(diff_results(&baseline, &candidate).is_empty());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn diff_results__different_results__should_return_deltas_and_differing_trades() {
+        let mut baseline = result(
+            dec!(10_100),
+            vec![
+                closed_trade("1", dec!(1.38100)),
+                closed_trade("2", dec!(1.38200)),
+            ],
+        );
+        baseline.statistics.deleted_by_expiration_by_time = 3;
+        baseline.statistics.deleted_by_being_close_to_another_one = 1;
+
+        let mut candidate = result(
+            dec!(10_050),
+            vec![
+                closed_trade("1", dec!(1.38100)),
+                closed_trade("2", dec!(1.37900)),
+                closed_trade("3", dec!(1.38000)),
+            ],
+        );
+        candidate.statistics.deleted_by_expiration_by_time = 1;
+        candidate.statistics.deleted_by_being_close_to_another_one = 1;
+        candidate.statistics.deleted_by_expiration_by_distance = 2;
+
+        let diff = diff_results(&baseline, &candidate);
+
+        assert!(!diff.is_empty());
+        assert_eq!(diff.final_balance, dec!(-50));
+        assert_eq!(diff.number_of_trades, 1);
+        assert_eq!(
+            diff.deletion_reasons,
+            DeletionReasonDeltas {
+                deleted_by_expiration_by_time: -2,
+                deleted_by_expiration_by_distance: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            diff.differing_trades,
+            vec![
+                TradeDiff {
+                    index: 1,
+                    baseline: Some(closed_trade("2", dec!(1.38200))),
+                    candidate: Some(closed_trade("2", dec!(1.37900))),
+                },
+                TradeDiff {
+                    index: 2,
+                    baseline: None,
+                    candidate: Some(closed_trade("3", dec!(1.38000))),
+                },
+            ]
+        );
+    }
+}
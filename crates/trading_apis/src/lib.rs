@@ -13,6 +13,9 @@ pub trait MarketDataApi {
     type HistoricalTickProperties;
     type CandleProperties;
 
+    /// Lists the symbols available at the broker.
+    fn get_symbols(&self) -> Result<Vec<String>>;
+
     fn get_current_tick(&self, symbol: &str) -> Result<Self::RealTickProperties>;
 
     fn get_current_candle(
@@ -29,6 +32,15 @@ pub trait MarketDataApi {
         duration: Duration,
     ) -> Result<Vec<Option<Self::CandleProperties>>>;
 
+    /// Gets the candles from the particular time up to now. The candle of the `since` time
+    /// is included, so it should be deduped if it's already known.
+    fn get_historical_candles_since(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Option<Self::CandleProperties>>>;
+
     fn get_historical_ticks(
         &self,
         symbol: &str,
@@ -3,16 +3,13 @@ use std::{thread, time};
 
 use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
-use polars::prelude::RollingOptions;
-use polars::series::Series;
-use rust_decimal::Decimal;
 use serde::Deserialize;
 use ureq::serde_json;
 
 use base::entities::candle::{BasicCandleProperties, CandlePrice, CandleVolatility};
 use base::entities::tick::{HistoricalTickPrice, TickPrice};
 use base::entities::{BasicTickProperties, CandlePrices, CandleType, Timeframe};
-use base::helpers::{mean, price_to_points};
+use base::helpers::{compute_volatility, price_to_points};
 use base::requests::api::SyncHttpRequest;
 use base::requests::entities::{HttpRequestData, HttpRequestMethod, HttpRequestWithRetriesParams};
 use base::requests::http_request_with_retries;
@@ -24,6 +21,8 @@ pub const AUTH_TOKEN_ENV: &str = "AUTH_TOKEN";
 pub const DEMO_ACCOUNT_ID_ENV: &str = "DEMO_ACCOUNT_ID";
 pub const MAIN_API_URL_ENV: &str = "MAIN_API_URL";
 pub const MARKET_DATA_API_URL_ENV: &str = "MARKET_DATA_API_URL";
+pub const NUMBER_OF_REQUEST_RETRIES_ENV: &str = "NUMBER_OF_REQUEST_RETRIES";
+pub const SECONDS_TO_SLEEP_BEFORE_REQUEST_RETRY_ENV: &str = "SECONDS_TO_SLEEP_BEFORE_REQUEST_RETRY";
 
 pub const HOURS_IN_DAY: u8 = 24;
 pub const DAYS_FOR_VOLATILITY: u8 = 7;
@@ -44,6 +43,7 @@ type MetatraderTime = String;
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct MetatraderTickJson {
+    time: MetatraderTime,
     broker_time: MetatraderTime,
     ask: TickPrice,
     bid: TickPrice,
@@ -60,6 +60,26 @@ struct MetatraderCandleJson {
     close: CandlePrice,
 }
 
+impl From<&MetatraderCandleJson> for CandlePrices {
+    fn from(candle: &MetatraderCandleJson) -> Self {
+        Self {
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+        }
+    }
+}
+
+impl From<&MetatraderCandleJson> for BasicCandleProperties {
+    fn from(candle: &MetatraderCandleJson) -> Self {
+        Self {
+            prices: candle.into(),
+            ..Default::default()
+        }
+    }
+}
+
 pub struct RetrySettings {
     pub number_of_request_retries: NumberOfRequestRetries,
     pub seconds_to_sleep_before_request_retry: SecondsToSleepBeforeRequestRetry,
@@ -84,6 +104,73 @@ pub struct ApiData {
     pub urls: ApiUrls,
 }
 
+impl ApiData {
+    /// Reads the api data from the `AUTH_TOKEN`, `DEMO_ACCOUNT_ID`, `MAIN_API_URL`
+    /// and `MARKET_DATA_API_URL` environment variables.
+    /// Returns an error listing all the missing variables.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| dotenv::var(name).ok())
+    }
+
+    fn from_vars(get_var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut missing_vars = Vec::new();
+
+        let mut get_required_var = |name| {
+            get_var(name).unwrap_or_else(|| {
+                missing_vars.push(name);
+                String::new()
+            })
+        };
+
+        let api_data = Self {
+            auth_token: get_required_var(AUTH_TOKEN_ENV),
+            account_id: get_required_var(DEMO_ACCOUNT_ID_ENV),
+            urls: ApiUrls {
+                main: get_required_var(MAIN_API_URL_ENV),
+                market_data: get_required_var(MARKET_DATA_API_URL_ENV),
+            },
+        };
+
+        if !missing_vars.is_empty() {
+            bail!("missing environment variables: {}", missing_vars.join(", "));
+        }
+
+        Ok(api_data)
+    }
+}
+
+impl RetrySettings {
+    /// Reads the retry settings from the `NUMBER_OF_REQUEST_RETRIES`
+    /// and `SECONDS_TO_SLEEP_BEFORE_REQUEST_RETRY` environment variables.
+    /// The missing variables are replaced with the default settings.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| dotenv::var(name).ok())
+    }
+
+    fn from_vars(get_var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let default = Self::default();
+
+        Ok(Self {
+            number_of_request_retries: match get_var(NUMBER_OF_REQUEST_RETRIES_ENV) {
+                Some(value) => value.parse().context(format!(
+                    "invalid {} environment variable: {}",
+                    NUMBER_OF_REQUEST_RETRIES_ENV, value
+                ))?,
+                None => default.number_of_request_retries,
+            },
+            seconds_to_sleep_before_request_retry: match get_var(
+                SECONDS_TO_SLEEP_BEFORE_REQUEST_RETRY_ENV,
+            ) {
+                Some(value) => value.parse().context(format!(
+                    "invalid {} environment variable: {}",
+                    SECONDS_TO_SLEEP_BEFORE_REQUEST_RETRY_ENV, value
+                ))?,
+                None => default.seconds_to_sleep_before_request_retry,
+            },
+        })
+    }
+}
+
 impl Default for RetrySettings {
     fn default() -> Self {
         Self {
@@ -101,6 +188,7 @@ where
     api_data: ApiData,
     retry_settings: RetrySettings,
     request_api: R,
+    include_forming_candle: bool,
 }
 
 impl<R: SyncHttpRequest> MetaapiMarketDataApi<R> {
@@ -113,9 +201,79 @@ impl<R: SyncHttpRequest> MetaapiMarketDataApi<R> {
             api_data,
             retry_settings,
             request_api,
+            include_forming_candle: false,
         }
     }
 
+    /// Creates the api with the api data and retry settings read from the environment variables.
+    /// See [`ApiData::from_env`] and [`RetrySettings::from_env`].
+    pub fn from_env(request_api: R) -> Result<MetaapiMarketDataApi<R>> {
+        Ok(Self::new(
+            ApiData::from_env()?,
+            RetrySettings::from_env()?,
+            request_api,
+        ))
+    }
+
+    /// Defines whether the last historical candle that is still being formed
+    /// should be returned. It's excluded by default to avoid the lookahead bias.
+    pub fn include_forming_candle(mut self, include_forming_candle: bool) -> Self {
+        self.include_forming_candle = include_forming_candle;
+        self
+    }
+
+    /// Removes the last candle if its close time is in the future relative to the current server time.
+    fn remove_forming_candle(
+        candles: &mut Vec<MetatraderCandleJson>,
+        timeframe: Timeframe,
+        current_time: DateTime<Utc>,
+    ) -> Result<()> {
+        if let Some(last_candle) = candles.last() {
+            let close_time =
+                from_iso_utc_str_to_utc_datetime(&last_candle.time)? + timeframe.duration();
+
+            if close_time > current_time {
+                log::debug!(
+                    "the last candle {:?} is still being formed, it's removed",
+                    last_candle
+                );
+
+                candles.pop();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_current_price(&self, symbol: &str) -> Result<MetatraderTickJson> {
+        let get_current_price_url = format!(
+            "{}/users/current/accounts/{}/symbols/{}/current-price",
+            self.api_data.urls.main, self.api_data.account_id, symbol
+        );
+
+        let req_data = HttpRequestData::new(HttpRequestMethod::Get, get_current_price_url)
+            .add_header("auth-token", &self.api_data.auth_token)
+            .add_query("keepSubscription", "true");
+
+        let req_params = HttpRequestWithRetriesParams {
+            req_entity_name: "the current tick",
+            number_of_retries: self.retry_settings.number_of_request_retries,
+            seconds_to_sleep: self.retry_settings.seconds_to_sleep_before_request_retry,
+        };
+
+        Ok(serde_json::from_str(&http_request_with_retries(
+            req_data,
+            req_params,
+            &self.request_api,
+        )?)?)
+    }
+
+    /// Returns the current time of the server taken from the current price of the symbol,
+    /// so the skew of the local clock doesn't matter.
+    fn get_server_time(&self, symbol: &str) -> Result<DateTime<Utc>> {
+        from_iso_utc_str_to_utc_datetime(&self.get_current_price(symbol)?.time)
+    }
+
     fn get_current_volatility(
         &self,
         symbol: &str,
@@ -147,59 +305,33 @@ impl<R: SyncHttpRequest> MetaapiMarketDataApi<R> {
             &http_request_with_retries(req_data, req_params, &self.request_api)?,
         )?;
 
-        let sizes_of_candles: Vec<_> = last_n_candles
+        let last_n_candles: Vec<BasicCandleProperties> = last_n_candles
             .iter()
-            .map(|candle| price_to_points(candle.high - candle.low))
+            .map(BasicCandleProperties::from)
             .collect();
 
-        Ok(mean(&sizes_of_candles)
-            .round()
-            .to_string()
-            .parse::<CandleVolatility>()
-            .unwrap())
+        Ok(compute_volatility(&last_n_candles, last_n_candles.len()))
     }
 
+    /// Returns the volatility of every candle computed over the window of candles
+    /// ending with it. The candles without the full window have no volatility.
     fn get_all_volatilities(
         &self,
         candles: &[MetatraderCandleJson],
         window: usize,
-    ) -> Result<Vec<Option<CandleVolatility>>> {
-        let candle_sizes: Series = candles
-            .iter()
-            .map(|candle| {
-                price_to_points(candle.high - candle.low)
-                    .to_string()
-                    .parse::<f32>()
-                    .unwrap()
-            })
-            .collect();
-
-        let all_candle_volatilities = candle_sizes
-            .rolling_mean(RollingOptions {
-                window_size: window,
-                min_periods: window,
-                weights: None,
-                center: false,
-            })
-            .context("error on rolling candle volatilities")?;
-
-        let candle_volatilities = all_candle_volatilities
-            .f32()
-            .context("error on casting rolling volatilities to f32 ChunkedArray")?
-            .into_iter()
-            .map(|volatility| {
-                volatility.map(|value| {
-                    Decimal::try_from(value)
-                        .unwrap()
-                        .round()
-                        .to_string()
-                        .parse::<CandleVolatility>()
-                        .unwrap()
-                })
+    ) -> Vec<Option<CandleVolatility>> {
+        let candles: Vec<BasicCandleProperties> =
+            candles.iter().map(BasicCandleProperties::from).collect();
+
+        (0..candles.len())
+            .map(|i| {
+                if i + 1 < window {
+                    None
+                } else {
+                    Some(compute_volatility(&candles[..=i], window))
+                }
             })
-            .collect();
-
-        Ok(candle_volatilities)
+            .collect()
     }
 
     fn tune_candle(
@@ -207,12 +339,7 @@ impl<R: SyncHttpRequest> MetaapiMarketDataApi<R> {
         candle_json: &MetatraderCandleJson,
         current_volatility: CandleVolatility,
     ) -> Result<BasicCandleProperties> {
-        let candle_edge_prices = CandlePrices {
-            open: candle_json.open,
-            high: candle_json.high,
-            low: candle_json.low,
-            close: candle_json.close,
-        };
+        let candle_edge_prices = CandlePrices::from(candle_json);
 
         let candle_size = price_to_points(candle_json.high - candle_json.low);
 
@@ -287,7 +414,17 @@ impl<R: SyncHttpRequest> MetaapiMarketDataApi<R> {
             }
         }
 
-        Ok(all_candles.into_iter().collect())
+        let mut all_candles = all_candles.into_iter().collect();
+
+        if !self.include_forming_candle {
+            Self::remove_forming_candle(
+                &mut all_candles,
+                timeframe,
+                self.get_server_time(symbol)?,
+            )?;
+        }
+
+        Ok(all_candles)
     }
 
     fn get_items_with_filled_gaps<T, F>(
@@ -304,13 +441,7 @@ impl<R: SyncHttpRequest> MetaapiMarketDataApi<R> {
             _ => (),
         }
 
-        let number_of_minutes_between_adjacent_items = match timeframe {
-            Timeframe::Hour => 60,
-            Timeframe::ThirtyMin => 30,
-            Timeframe::FifteenMin => 15,
-            Timeframe::OneMin => 1,
-            Timeframe::FiveMin => 5,
-        };
+        let number_of_minutes_between_adjacent_items = timeframe.duration().num_minutes();
 
         let mut all_items_with_filled_gaps: Vec<Option<T>> = Vec::new();
         let mut previous_item_time =
@@ -361,28 +492,33 @@ impl<R: SyncHttpRequest> MarketDataApi for MetaapiMarketDataApi<R> {
     type HistoricalTickProperties = BasicTickProperties<HistoricalTickPrice>;
     type CandleProperties = BasicCandleProperties;
 
-    fn get_current_tick(&self, symbol: &str) -> Result<Self::RealTickProperties> {
-        let get_current_tick_url = format!(
-            "{}/users/current/accounts/{}/symbols/{}/current-price",
-            self.api_data.urls.main, self.api_data.account_id, symbol
+    fn get_symbols(&self) -> Result<Vec<String>> {
+        let get_symbols_url = format!(
+            "{}/users/current/accounts/{}/symbols",
+            self.api_data.urls.main, self.api_data.account_id
         );
 
-        let req_data = HttpRequestData::new(HttpRequestMethod::Get, get_current_tick_url)
-            .add_header("auth-token", &self.api_data.auth_token)
-            .add_query("keepSubscription", "true");
+        let req_data = HttpRequestData::new(HttpRequestMethod::Get, get_symbols_url)
+            .add_header("auth-token", &self.api_data.auth_token);
 
         let req_params = HttpRequestWithRetriesParams {
-            req_entity_name: "the current tick",
+            req_entity_name: "the symbols",
             number_of_retries: self.retry_settings.number_of_request_retries,
             seconds_to_sleep: self.retry_settings.seconds_to_sleep_before_request_retry,
         };
 
-        let tick_json: MetatraderTickJson = serde_json::from_str(&http_request_with_retries(
+        let symbols: Vec<Symbol> = serde_json::from_str(&http_request_with_retries(
             req_data,
             req_params,
             &self.request_api,
         )?)?;
 
+        Ok(symbols)
+    }
+
+    fn get_current_tick(&self, symbol: &str) -> Result<Self::RealTickProperties> {
+        let tick_json = self.get_current_price(symbol)?;
+
         let time = from_naive_str_to_naive_datetime(&tick_json.broker_time)?;
 
         let tick = BasicTickProperties {
@@ -433,28 +569,10 @@ impl<R: SyncHttpRequest> MarketDataApi for MetaapiMarketDataApi<R> {
     ) -> Result<Vec<Option<Self::CandleProperties>>> {
         let days_for_volatility = Duration::days(DAYS_FOR_VOLATILITY as i64);
 
-        let (total_amount_of_candles, volatility_window) = match timeframe {
-            Timeframe::Hour => (
-                duration.num_hours() as u64,
-                days_for_volatility.num_hours() as usize,
-            ),
-            Timeframe::ThirtyMin => (
-                (duration.num_hours() * 2) as u64,
-                (days_for_volatility.num_hours() * 2) as usize,
-            ),
-            Timeframe::FifteenMin => (
-                (duration.num_hours() * 4) as u64,
-                (days_for_volatility.num_hours() * 4) as usize,
-            ),
-            Timeframe::OneMin => (
-                duration.num_minutes() as u64,
-                days_for_volatility.num_minutes() as usize,
-            ),
-            Timeframe::FiveMin => (
-                (duration.num_minutes() / 5) as u64,
-                (days_for_volatility.num_minutes() / 5) as usize,
-            ),
-        };
+        let (total_amount_of_candles, volatility_window) = (
+            (duration.num_minutes() / timeframe.duration().num_minutes()) as u64,
+            (days_for_volatility.num_minutes() / timeframe.duration().num_minutes()) as usize,
+        );
 
         let all_candles = self.get_blocks_of_historical_candles(
             symbol,
@@ -463,28 +581,33 @@ impl<R: SyncHttpRequest> MarketDataApi for MetaapiMarketDataApi<R> {
             end_time,
         )?;
 
-        let all_candle_volatilities = self.get_all_volatilities(&all_candles, volatility_window)?;
+        let all_candle_volatilities = self.get_all_volatilities(&all_candles, volatility_window);
 
         let all_candles = all_candles
             .iter()
-            .zip(all_candle_volatilities.into_iter())
-            .filter(|(_, volatility)| volatility.is_some())
-            .map(|(candle, volatility)| {
-                self.tune_candle(
-                    candle,
-                    Decimal::try_from(volatility.unwrap())
-                        .unwrap()
-                        .round()
-                        .to_string()
-                        .parse::<u32>()
-                        .unwrap(),
-                )
+            .zip(all_candle_volatilities)
+            .filter_map(|(candle, volatility)| {
+                volatility.map(|volatility| self.tune_candle(candle, volatility))
             })
             .collect::<Result<Vec<_>>>()?;
 
         Self::get_items_with_filled_gaps(all_candles, timeframe, |candle| candle.time)
     }
 
+    fn get_historical_candles_since(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Option<Self::CandleProperties>>> {
+        let end_time = Utc::now();
+
+        // the first candles are used only to determine the volatility
+        let duration = end_time - since + Duration::days(DAYS_FOR_VOLATILITY as i64);
+
+        self.get_historical_candles(symbol, timeframe, end_time, duration)
+    }
+
     fn get_historical_ticks(
         &self,
         symbol: &str,
@@ -494,21 +617,9 @@ impl<R: SyncHttpRequest> MarketDataApi for MetaapiMarketDataApi<R> {
     ) -> Result<Vec<Option<Self::HistoricalTickProperties>>> {
         let days_for_volatility = Duration::days(DAYS_FOR_VOLATILITY as i64);
 
-        let total_amount_of_candles = match timeframe {
-            Timeframe::Hour => (duration.num_hours() - days_for_volatility.num_hours()) as u64,
-            Timeframe::ThirtyMin => {
-                ((duration.num_hours() * 2) - (days_for_volatility.num_hours() * 2)) as u64
-            }
-            Timeframe::FifteenMin => {
-                ((duration.num_hours() * 4) - (days_for_volatility.num_hours() * 4)) as u64
-            }
-            Timeframe::OneMin => {
-                (duration.num_minutes() - days_for_volatility.num_minutes()) as u64
-            }
-            Timeframe::FiveMin => {
-                ((duration.num_minutes() / 5) - (days_for_volatility.num_minutes() / 5)) as u64
-            }
-        } + 1;
+        let total_amount_of_candles = ((duration.num_minutes() - days_for_volatility.num_minutes())
+            / timeframe.duration().num_minutes()) as u64
+            + 1;
 
         let all_candles = self.get_blocks_of_historical_candles(
             symbol,
@@ -541,6 +652,7 @@ impl<R: SyncHttpRequest> MarketDataApi for MetaapiMarketDataApi<R> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
     use rust_decimal_macros::dec;
 
     struct TestRequestApi;
@@ -602,6 +714,36 @@ mod tests {
         assert_eq!(volatility, 271);
     }
 
+    struct TestCurrentPriceRequestApi;
+
+    impl SyncHttpRequest for TestCurrentPriceRequestApi {
+        fn call(&self, _req: HttpRequestData) -> Result<String> {
+            Ok(r#"{
+  "symbol": "GBPUSD",
+  "bid": 1.22662,
+  "ask": 1.22672,
+  "time": "2022-06-21T12:30:00.000Z",
+  "brokerTime": "2022-06-21 15:30:00.000"
+}"#
+            .to_string())
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_server_time__current_price_response__should_return_its_utc_time() {
+        let metaapi = MetaapiMarketDataApi::new(
+            Default::default(),
+            Default::default(),
+            TestCurrentPriceRequestApi,
+        );
+
+        assert_eq!(
+            metaapi.get_server_time("GBPUSD").unwrap(),
+            Utc.ymd(2022, 6, 21).and_hms(12, 30, 0)
+        );
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn tune_candle__should_return_properly_tuned_candle() {
@@ -681,8 +823,131 @@ mod tests {
             },
         ];
 
-        let volatilities = metaapi.get_all_volatilities(&candles, 2).unwrap();
+        let volatilities = metaapi.get_all_volatilities(&candles, 2);
 
         assert_eq!(volatilities, vec![None, Some(290), Some(286), Some(252)]);
     }
+
+    struct TestSymbolsRequestApi;
+
+    impl SyncHttpRequest for TestSymbolsRequestApi {
+        fn call(&self, req: HttpRequestData) -> Result<String> {
+            assert!(req.url.ends_with("/users/current/accounts/smth/symbols"));
+
+            Ok(r#"["EURUSD", "GBPUSD", "USDJPY"]"#.to_string())
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn get_symbols__should_return_all_symbols() {
+        let metaapi = MetaapiMarketDataApi::new(
+            ApiData {
+                account_id: "smth".to_string(),
+                ..Default::default()
+            },
+            Default::default(),
+            TestSymbolsRequestApi {},
+        );
+
+        assert_eq!(
+            metaapi.get_symbols().unwrap(),
+            vec![
+                "EURUSD".to_string(),
+                "GBPUSD".to_string(),
+                "USDJPY".to_string()
+            ]
+        );
+    }
+
+    fn candles_for_forming_check() -> Vec<MetatraderCandleJson> {
+        vec![
+            MetatraderCandleJson {
+                time: "2022-06-21T10:00:00.000Z".to_string(),
+                open: dec!(1.22958),
+                high: dec!(1.23006),
+                low: dec!(1.22781),
+                close: dec!(1.22806),
+                broker_time: "2022-06-21 13:00:00.000".to_string(),
+            },
+            MetatraderCandleJson {
+                time: "2022-06-21T11:00:00.000Z".to_string(),
+                open: dec!(1.22805),
+                high: dec!(1.22863),
+                low: dec!(1.22507),
+                close: dec!(1.22685),
+                broker_time: "2022-06-21 14:00:00.000".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn remove_forming_candle__last_candle_closes_in_future__should_remove_last_candle() {
+        let mut candles = candles_for_forming_check();
+
+        MetaapiMarketDataApi::<TestRequestApi>::remove_forming_candle(
+            &mut candles,
+            Timeframe::Hour,
+            Utc.ymd(2022, 6, 21).and_hms(11, 30, 0),
+        )
+        .unwrap();
+
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].time, "2022-06-21T10:00:00.000Z");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn remove_forming_candle__last_candle_is_closed__should_keep_all_candles() {
+        let mut candles = candles_for_forming_check();
+
+        MetaapiMarketDataApi::<TestRequestApi>::remove_forming_candle(
+            &mut candles,
+            Timeframe::Hour,
+            Utc.ymd(2022, 6, 21).and_hms(12, 0, 0),
+        )
+        .unwrap();
+
+        assert_eq!(candles.len(), 2);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn api_data_from_vars__some_vars_are_missing__should_return_error_listing_them() {
+        let error = ApiData::from_vars(|name| match name {
+            AUTH_TOKEN_ENV => Some(String::from("token")),
+            MAIN_API_URL_ENV => Some(String::from("https://main")),
+            _ => None,
+        })
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "missing environment variables: DEMO_ACCOUNT_ID, MARKET_DATA_API_URL"
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn retry_settings_from_vars__only_number_of_retries_is_set__should_take_default_sleep() {
+        let retry_settings = RetrySettings::from_vars(|name| match name {
+            NUMBER_OF_REQUEST_RETRIES_ENV => Some(String::from("3")),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(retry_settings.number_of_request_retries, 3);
+        assert_eq!(
+            retry_settings.seconds_to_sleep_before_request_retry,
+            DEFAULT_NUMBER_OF_SECONDS_TO_SLEEP_BEFORE_REQUEST_RETRY
+        );
+
+        assert!(RetrySettings::from_vars(|name| match name {
+            SECONDS_TO_SLEEP_BEFORE_REQUEST_RETRY_ENV => Some(String::from("soon")),
+            _ => None,
+        })
+        .is_err());
+    }
 }